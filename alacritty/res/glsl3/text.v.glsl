@@ -26,6 +26,7 @@ uniform vec4 projection;
 uniform int renderingPass;
 
 #define WIDE_CHAR 2
+#define DOUBLE_WIDTH 4
 
 void main() {
     vec2 projectionOffset = projection.xy;
@@ -43,9 +44,15 @@ void main() {
     bg = backgroundColor / 255.0;
 
     float occupiedCells = 1;
+    if ((int(fg.a) >= DOUBLE_WIDTH)) {
+        // Cells of double-width lines cover twice as many columns.
+        occupiedCells = 2;
+        fg.a = round(fg.a - DOUBLE_WIDTH);
+    }
+
     if ((int(fg.a) >= WIDE_CHAR)) {
         // Update wide char x dimension so it'll cover the following spacer.
-        occupiedCells = 2;
+        occupiedCells *= 2;
 
         // Since we don't perform bitwise operations due to limitations of
         // the GLES2 renderer,we subtract wide char bits keeping only colored.
@@ -16,6 +16,8 @@ use crate::config::UiConfig;
 use crate::config::ui_config::Program;
 use crate::config::window::{Class, Identity};
 use crate::logging::LOG_TARGET_IPC_CONFIG;
+#[cfg(unix)]
+use crate::scrollback::DumpFormat;
 
 /// CLI options for the main Alacritty executable.
 #[derive(Parser, Default, Debug)]
@@ -163,6 +165,11 @@ pub struct TerminalOptions {
     #[clap(long)]
     pub hold: bool,
 
+    /// Fill the terminal with content saved by `msg save-scrollback` or the `SaveScrollback`
+    /// action.
+    #[clap(long, value_hint = ValueHint::FilePath)]
+    pub restore_scrollback: Option<PathBuf>,
+
     /// Command and args to execute (must be last argument).
     #[clap(short = 'e', long, allow_hyphen_values = true, num_args = 1..)]
     command: Vec<String>,
@@ -234,7 +241,7 @@ impl WindowIdentity {
 #[derive(Subcommand, Debug)]
 pub enum Subcommands {
     #[cfg(unix)]
-    Msg(MessageOptions),
+    Msg(Box<MessageOptions>),
     Migrate(MigrateOptions),
 }
 
@@ -263,6 +270,12 @@ pub enum SocketMessage {
 
     /// Read runtime Alacritty configuration.
     GetConfig(IpcGetConfig),
+
+    /// Read the working directory of a window's active terminal.
+    GetWorkingDirectory(IpcGetWorkingDirectory),
+
+    /// Save the scrollback history and screen of a window's active terminal to a file.
+    SaveScrollback(IpcSaveScrollback),
 }
 
 /// Migrate the configuration file.
@@ -305,11 +318,6 @@ pub struct WindowOptions {
     /// The window tabbing identifier to use when building a window.
     pub window_tabbing_id: Option<String>,
 
-    #[clap(skip)]
-    #[cfg(target_os = "linux")]
-    /// The window tabbing identifier to use when building a window.
-    pub window_tabbing_id: Option<String>,
-
     #[clap(skip)]
     #[cfg(not(any(target_os = "macos", windows)))]
     /// `ActivationToken` that we pass to winit.
@@ -357,6 +365,32 @@ pub struct IpcGetConfig {
     pub window_id: Option<i128>,
 }
 
+/// Parameters to the `get-working-directory` IPC subcommand.
+#[cfg(unix)]
+#[derive(Args, Serialize, Deserialize, Default, Debug, Clone, PartialEq, Eq)]
+pub struct IpcGetWorkingDirectory {
+    /// Window ID for the working directory request.
+    #[clap(short, long, env = "ALACRITTY_WINDOW_ID")]
+    pub window_id: Option<i128>,
+}
+
+/// Parameters to the `save-scrollback` IPC subcommand.
+#[cfg(unix)]
+#[derive(Args, Serialize, Deserialize, Debug, Clone, PartialEq, Eq)]
+pub struct IpcSaveScrollback {
+    /// Path of the created file.
+    #[clap(value_hint = ValueHint::FilePath)]
+    pub path: PathBuf,
+
+    /// Format of the saved content.
+    #[clap(short, long, value_enum, default_value_t)]
+    pub format: DumpFormat,
+
+    /// Window ID of the saved terminal.
+    #[clap(short, long, env = "ALACRITTY_WINDOW_ID")]
+    pub window_id: Option<i128>,
+}
+
 /// Parsed CLI config overrides.
 #[derive(Debug, Default)]
 pub struct ParsedOptions {
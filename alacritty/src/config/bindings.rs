@@ -160,9 +160,24 @@ pub enum Action {
     /// Scroll all the way to the bottom.
     ScrollToBottom,
 
+    /// Scroll to the previous shell prompt.
+    ScrollToPreviousPrompt,
+
+    /// Scroll to the next shell prompt.
+    ScrollToNextPrompt,
+
+    /// Select the output of the last command.
+    SelectLastCommandOutput,
+
+    /// Store the output of the last command into clipboard.
+    CopyLastCommandOutput,
+
     /// Clear the display buffer(s) to remove history.
     ClearHistory,
 
+    /// Save the scrollback history and screen to a file in the working directory.
+    SaveScrollback,
+
     /// Hide the Alacritty window.
     Hide,
 
@@ -338,6 +353,12 @@ pub enum ViAction {
     SemanticSearchForward,
     /// Search backward for selection or word under the cursor.
     SemanticSearchBackward,
+    /// Set a named mark on the vi cursor line.
+    SetMark,
+    /// Jump to the line of a named mark.
+    JumpToMark,
+    /// Show all named marks in the message bar.
+    ListMarks,
 }
 
 /// Search mode specific actions.
@@ -494,6 +515,8 @@ pub fn default_key_bindings() -> Vec<KeyBinding> {
         ",",                                +BindingMode::VI, ~BindingMode::SEARCH; ViAction::InlineSearchPrevious;
         "*",      ModifiersState::SHIFT,    +BindingMode::VI, ~BindingMode::SEARCH; ViAction::SemanticSearchForward;
         "#",      ModifiersState::SHIFT,    +BindingMode::VI, ~BindingMode::SEARCH; ViAction::SemanticSearchBackward;
+        "m",                                +BindingMode::VI, ~BindingMode::SEARCH; ViAction::SetMark;
+        "'",                                +BindingMode::VI, ~BindingMode::SEARCH; ViAction::JumpToMark;
         "k",                                +BindingMode::VI, ~BindingMode::SEARCH; ViMotion::Up;
         "j",                                +BindingMode::VI, ~BindingMode::SEARCH; ViMotion::Down;
         "h",                                +BindingMode::VI, ~BindingMode::SEARCH; ViMotion::Left;
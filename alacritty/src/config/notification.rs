@@ -0,0 +1,14 @@
+use serde::Serialize;
+
+use alacritty_config_derive::ConfigDeserialize;
+
+use crate::config::ui_config::Program;
+
+#[derive(ConfigDeserialize, Serialize, Default, Clone, Debug, PartialEq, Eq)]
+pub struct NotificationConfig {
+    /// Command to run for desktop notifications.
+    pub command: Option<Program>,
+
+    /// Ignore notifications while the window is focused.
+    pub ignore_focused: bool,
+}
@@ -3,8 +3,10 @@ use serde::{Deserialize, Deserializer, Serialize};
 
 use alacritty_config_derive::{ConfigDeserialize, SerdeReplace};
 
+use crate::scrollback::DumpFormat;
+
 /// Maximum scrollback amount configurable.
-pub const MAX_SCROLLBACK_LINES: u32 = 100_000;
+pub const MAX_SCROLLBACK_LINES: u32 = 10_000_000;
 
 /// Struct for scrolling related settings.
 #[derive(ConfigDeserialize, Serialize, Copy, Clone, Debug, PartialEq, Eq)]
@@ -12,11 +14,26 @@ pub struct Scrolling {
     pub multiplier: u8,
 
     history: ScrollingHistory,
+
+    /// Memory for compressed history in MiB, before it is moved to disk.
+    memory_limit: Option<u32>,
+
+    /// Show the time at which lines were written.
+    pub timestamps: Timestamps,
+
+    /// File format used by the `SaveScrollback` action.
+    pub save_format: DumpFormat,
 }
 
 impl Default for Scrolling {
     fn default() -> Self {
-        Self { multiplier: 3, history: Default::default() }
+        Self {
+            multiplier: 3,
+            history: Default::default(),
+            memory_limit: Default::default(),
+            timestamps: Default::default(),
+            save_format: Default::default(),
+        }
     }
 }
 
@@ -24,6 +41,22 @@ impl Scrolling {
     pub fn history(self) -> u32 {
         self.history.0
     }
+
+    /// Memory for compressed history in bytes.
+    pub fn memory_limit(self) -> Option<usize> {
+        self.memory_limit.map(|limit| limit as usize * 1024 * 1024)
+    }
+}
+
+/// Format of the line timestamps shown next to the terminal content.
+#[derive(ConfigDeserialize, Serialize, Default, Debug, Copy, Clone, PartialEq, Eq)]
+pub enum Timestamps {
+    #[default]
+    None,
+    /// Time elapsed since the line was written.
+    Relative,
+    /// Local time at which the line was written.
+    Absolute,
 }
 
 #[derive(SerdeReplace, Serialize, Copy, Clone, Debug, PartialEq, Eq)]
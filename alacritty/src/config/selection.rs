@@ -7,6 +7,9 @@ use alacritty_terminal::term::SEMANTIC_ESCAPE_CHARS;
 pub struct Selection {
     pub semantic_escape_chars: String,
     pub save_to_clipboard: bool,
+
+    /// Prefix copied lines with the time at which they were written.
+    pub copy_timestamps: bool,
 }
 
 impl Default for Selection {
@@ -14,6 +17,7 @@ impl Default for Selection {
         Self {
             semantic_escape_chars: SEMANTIC_ESCAPE_CHARS.to_owned(),
             save_to_clipboard: Default::default(),
+            copy_timestamps: Default::default(),
         }
     }
 }
@@ -1,3 +1,6 @@
+use std::cmp;
+use std::time::Duration;
+
 use serde::{Deserialize, Deserializer, Serialize, de};
 use toml::Value;
 
@@ -6,12 +9,43 @@ use alacritty_terminal::term::Osc52;
 
 use crate::config::ui_config::{Program, StringVisitor};
 
-#[derive(ConfigDeserialize, Serialize, Default, Clone, Debug, PartialEq)]
+/// Minimum blinking text interval in milliseconds.
+const MIN_TEXT_BLINK_INTERVAL: u64 = 10;
+
+#[derive(ConfigDeserialize, Serialize, Clone, Debug, PartialEq)]
 pub struct Terminal {
     /// OSC52 support mode.
     pub osc52: SerdeOsc52,
     /// Path to a shell program to run on startup.
     pub shell: Option<Program>,
+
+    /// Display right-to-left text in visual order.
+    pub bidi: bool,
+
+    /// Blinking text interval in milliseconds, `0` disables blinking.
+    text_blink_interval: u64,
+}
+
+impl Default for Terminal {
+    fn default() -> Self {
+        Self {
+            text_blink_interval: 500,
+            osc52: Default::default(),
+            shell: Default::default(),
+            bidi: Default::default(),
+        }
+    }
+}
+
+impl Terminal {
+    /// Interval between visibility changes of blinking text.
+    #[inline]
+    pub fn text_blink_interval(&self) -> Option<Duration> {
+        match self.text_blink_interval {
+            0 => None,
+            interval => Some(Duration::from_millis(cmp::max(interval, MIN_TEXT_BLINK_INTERVAL))),
+        }
+    }
 }
 
 #[derive(SerdeReplace, Serialize, Default, Copy, Clone, Debug, PartialEq)]
@@ -30,6 +30,7 @@ use crate::config::debug::Debug;
 use crate::config::font::Font;
 use crate::config::general::General;
 use crate::config::mouse::Mouse;
+use crate::config::notification::NotificationConfig;
 use crate::config::scrolling::Scrolling;
 use crate::config::selection::Selection;
 use crate::config::terminal::Terminal;
@@ -72,6 +73,9 @@ pub struct UiConfig {
     /// Bell configuration.
     pub bell: BellConfig,
 
+    /// Desktop notification configuration.
+    pub notification: NotificationConfig,
+
     /// RGB values for colors.
     pub colors: Colors,
 
@@ -120,10 +124,13 @@ impl UiConfig {
         TermConfig {
             semantic_escape_chars: self.selection.semantic_escape_chars.clone(),
             scrolling_history: self.scrolling.history() as usize,
+            scrolling_memory_limit: self.scrolling.memory_limit(),
             vi_mode_cursor_style: self.cursor.vi_mode_style(),
             default_cursor_style: self.cursor.style(),
             osc52: self.terminal.osc52.0,
             kitty_keyboard: true,
+            // Image placements are not drawn, so applications are told that they aren't shown.
+            graphics: false,
         }
     }
 
@@ -4,6 +4,7 @@ use std::fs;
 use std::io;
 #[cfg(windows)]
 use std::os::windows::process::CommandExt;
+use std::path::{Path, PathBuf};
 use std::process::{Command, Stdio};
 
 #[rustfmt::skip]
@@ -13,7 +14,6 @@ use {
     std::error::Error,
     std::os::unix::process::CommandExt,
     std::os::unix::io::RawFd,
-    std::path::PathBuf,
 };
 
 #[cfg(not(windows))]
@@ -26,16 +26,25 @@ use crate::macos;
 
 /// Start a new process in the background.
 #[cfg(windows)]
-pub fn spawn_daemon<I, S>(program: &str, args: I) -> io::Result<()>
+pub fn spawn_daemon<I, S>(
+    program: &str,
+    args: I,
+    working_directory: Option<PathBuf>,
+) -> io::Result<()>
 where
     I: IntoIterator<Item = S> + Copy,
     S: AsRef<OsStr>,
 {
+    let mut command = Command::new(program);
+    if let Some(working_directory) = working_directory {
+        command.current_dir(working_directory);
+    }
+
     // Setting all the I/O handles to null and setting the
     // CREATE_NEW_PROCESS_GROUP and CREATE_NO_WINDOW has the effect
     // that console applications will run without opening a new
     // console window.
-    Command::new(program)
+    command
         .args(args)
         .stdin(Stdio::null())
         .stdout(Stdio::null())
@@ -50,8 +59,7 @@ where
 pub fn spawn_daemon<I, S>(
     program: &str,
     args: I,
-    master_fd: RawFd,
-    shell_pid: u32,
+    working_directory: Option<PathBuf>,
 ) -> io::Result<()>
 where
     I: IntoIterator<Item = S> + Copy,
@@ -60,7 +68,6 @@ where
     let mut command = Command::new(program);
     command.args(args).stdin(Stdio::null()).stdout(Stdio::null()).stderr(Stdio::null());
 
-    let working_directory = foreground_process_path(master_fd, shell_pid).ok();
     unsafe {
         command
             .pre_exec(move || {
@@ -70,7 +77,7 @@ where
                     _ => libc::_exit(0),
                 }
 
-                // Copy foreground process' working directory, ignoring invalid paths.
+                // Copy the terminal's working directory, ignoring invalid paths.
                 if let Some(working_directory) = working_directory.as_ref() {
                     let _ = env::set_current_dir(working_directory);
                 }
@@ -87,6 +94,24 @@ where
     }
 }
 
+/// Working directory for processes started from a terminal.
+///
+/// The directory last reported by the shell is preferred over the working directory of the
+/// foreground process, since the latter is wrong inside of ssh, tmux or subshells.
+pub fn working_directory(
+    reported_directory: Option<&Path>,
+    #[cfg(not(windows))] master_fd: RawFd,
+    #[cfg(not(windows))] shell_pid: u32,
+) -> Option<PathBuf> {
+    match reported_directory.filter(|path| path.is_dir()) {
+        Some(path) => Some(path.to_owned()),
+        #[cfg(not(windows))]
+        None => foreground_process_path(master_fd, shell_pid).ok(),
+        #[cfg(windows)]
+        None => None,
+    }
+}
+
 /// Get working directory of controlling process.
 #[cfg(not(windows))]
 pub fn foreground_process_path(
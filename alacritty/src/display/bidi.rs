@@ -0,0 +1,151 @@
+//! Bidirectional text reordering.
+//!
+//! This maps the logical columns stored in the grid to the visual columns they're drawn at,
+//! treating every terminal line as a separate paragraph.
+
+use unicode_bidi::ParagraphBidiInfo;
+
+use alacritty_terminal::index::Column;
+use alacritty_terminal::term::cell::{Cell, Flags};
+
+/// Visual order of a single terminal line.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct BidiLine {
+    /// Visual column of every logical column.
+    visual: Vec<usize>,
+    /// Logical column of every visual column.
+    logical: Vec<usize>,
+    /// Whether a logical column is part of right-to-left text.
+    rtl: Vec<bool>,
+}
+
+impl BidiLine {
+    /// Reorder the cells of a line.
+    ///
+    /// Returns [`None`] when the line has no right-to-left text, since its visual and logical
+    /// order are identical.
+    pub fn new(cells: &[Cell]) -> Option<Self> {
+        // Fullwidth characters are reordered together with their spacer.
+        let mut units = Vec::with_capacity(cells.len());
+        let mut text = String::with_capacity(cells.len());
+        for (column, cell) in cells.iter().enumerate() {
+            if cell.flags.contains(Flags::WIDE_CHAR_SPACER) {
+                continue;
+            }
+
+            let width = if cell.flags.contains(Flags::WIDE_CHAR) { 2 } else { 1 };
+            units.push((column, width.min(cells.len() - column)));
+            text.push(cell.c);
+        }
+
+        let info = ParagraphBidiInfo::new(&text, None);
+        if !info.has_rtl() {
+            return None;
+        }
+
+        let levels = info.reordered_levels_per_char(0..text.len());
+
+        let mut line = Self {
+            visual: vec![0; cells.len()],
+            logical: vec![0; cells.len()],
+            rtl: vec![false; cells.len()],
+        };
+
+        let mut visual_column = 0;
+        for index in ParagraphBidiInfo::reorder_visual(&levels) {
+            let (column, width) = units[index];
+            for offset in 0..width {
+                line.visual[column + offset] = visual_column + offset;
+                line.logical[visual_column + offset] = column + offset;
+                line.rtl[column + offset] = levels[index].is_rtl();
+            }
+            visual_column += width;
+        }
+
+        Some(line)
+    }
+
+    /// Column a logical column is displayed at.
+    pub fn visual_column(&self, column: Column) -> Column {
+        Column(self.visual.get(column.0).copied().unwrap_or(column.0))
+    }
+
+    /// Logical column of a column on the screen.
+    pub fn logical_column(&self, column: Column) -> Column {
+        Column(self.logical.get(column.0).copied().unwrap_or(column.0))
+    }
+
+    /// Check if a logical column is part of right-to-left text.
+    pub fn is_rtl(&self, column: Column) -> bool {
+        self.rtl.get(column.0).copied().unwrap_or(false)
+    }
+}
+
+/// Mirrored glyph of a character displayed in right-to-left text.
+pub fn mirror(c: char) -> char {
+    unicode_bidi_mirroring::get_mirrored(c).unwrap_or(c)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn line(text: &str) -> Vec<Cell> {
+        text.chars().map(|c| Cell { c, ..Cell::default() }).collect()
+    }
+
+    fn visual(text: &str) -> String {
+        let cells = line(text);
+        let Some(bidi) = BidiLine::new(&cells) else { return text.into() };
+
+        (0..cells.len()).map(|column| cells[bidi.logical_column(Column(column)).0].c).collect()
+    }
+
+    #[test]
+    fn left_to_right() {
+        assert_eq!(BidiLine::new(&line("hello 123")), None);
+    }
+
+    #[test]
+    fn right_to_left() {
+        assert_eq!(visual("אבג דה  "), "  הד גבא");
+        assert_eq!(visual("abc אבג def"), "abc גבא def");
+    }
+
+    #[test]
+    fn numbers() {
+        assert_eq!(visual("אבג 123 דה"), "הד 123 גבא");
+        assert_eq!(visual("אב 1.5%"), "1.5% בא");
+        assert_eq!(visual("\u{627}\u{628} 12"), "12 \u{628}\u{627}");
+    }
+
+    #[test]
+    fn mirroring() {
+        assert_eq!(mirror('('), ')');
+        assert_eq!(mirror('\u{2264}'), '\u{2265}');
+        assert_eq!(mirror('a'), 'a');
+    }
+
+    #[test]
+    fn mapping() {
+        let cells = line("ab אבג");
+        let bidi = BidiLine::new(&cells).unwrap();
+
+        assert_eq!(bidi.visual_column(Column(3)), Column(5));
+        assert_eq!(bidi.logical_column(Column(5)), Column(3));
+        assert!(bidi.is_rtl(Column(4)));
+        assert!(!bidi.is_rtl(Column(1)));
+    }
+
+    #[test]
+    fn wide_chars() {
+        let mut cells = line("א界  ");
+        cells[1].flags.insert(Flags::WIDE_CHAR);
+        cells[2].flags.insert(Flags::WIDE_CHAR_SPACER);
+        let bidi = BidiLine::new(&cells).unwrap();
+
+        assert_eq!(bidi.visual_column(Column(0)), Column(3));
+        assert_eq!(bidi.visual_column(Column(1)), Column(1));
+        assert_eq!(bidi.visual_column(Column(2)), Column(2));
+    }
+}
@@ -4,7 +4,7 @@ use std::ops::Deref;
 use std::{cmp, mem};
 
 use alacritty_terminal::event::EventListener;
-use alacritty_terminal::grid::{Dimensions, Indexed};
+use alacritty_terminal::grid::{Dimensions, Grid, Indexed, LineSize};
 use alacritty_terminal::index::{Column, Line, Point};
 use alacritty_terminal::selection::SelectionRange;
 use alacritty_terminal::term::cell::{Cell, Flags, Hyperlink};
@@ -13,6 +13,7 @@ use alacritty_terminal::term::{self, RenderableContent as TerminalContent, Term,
 use alacritty_terminal::vte::ansi::{Color, CursorShape, NamedColor};
 
 use crate::config::UiConfig;
+use crate::display::bidi::{self, BidiLine};
 use crate::display::color::{CellRgb, DIM_FACTOR, List, Rgb};
 use crate::display::hint::{self, HintState};
 use crate::display::{Display, SizeInfo};
@@ -26,6 +27,7 @@ pub const MIN_CURSOR_CONTRAST: f64 = 1.5;
 /// This provides the terminal cursor and an iterator over all non-empty cells.
 pub struct RenderableContent<'a> {
     terminal_content: TerminalContent<'a>,
+    grid: &'a Grid<Cell>,
     cursor: RenderableCursor,
     cursor_shape: CursorShape,
     cursor_point: Point<usize>,
@@ -35,6 +37,8 @@ pub struct RenderableContent<'a> {
     colors: &'a List,
     focused_match: Option<&'a Match>,
     size: &'a SizeInfo,
+    text_blink_hidden: bool,
+    bidi_line: Option<(Line, Option<BidiLine>)>,
 }
 
 impl<'a> RenderableContent<'a> {
@@ -43,6 +47,7 @@ impl<'a> RenderableContent<'a> {
         display: &'a mut Display,
         term: &'a Term<T>,
         search_state: &'a mut SearchState,
+        focused: bool,
     ) -> Self {
         let search = search_state.dfas().map(|dfas| HintMatches::visible_regex_matches(term, dfas));
         let focused_match = search_state.focused_match();
@@ -50,9 +55,9 @@ impl<'a> RenderableContent<'a> {
 
         // Find terminal cursor shape.
         let cursor_shape = if terminal_content.cursor.shape == CursorShape::Hidden
-            || display.cursor_hidden
+            || (focused && display.cursor_hidden)
             || search_state.regex().is_some()
-            || display.ime.preedit().is_some()
+            || (focused && display.ime.preedit().is_some())
         {
             CursorShape::Hidden
         } else if !term.is_focused && config.cursor.unfocused_hollow {
@@ -66,7 +71,7 @@ impl<'a> RenderableContent<'a> {
         let display_offset = terminal_content.display_offset;
         let cursor_point = term::point_to_viewport(display_offset, cursor_point).unwrap();
 
-        let hint = if display.hint_state.active() {
+        let hint = if focused && display.hint_state.active() {
             display.hint_state.update_matches(term);
             Some(Hint::from(&display.hint_state))
         } else {
@@ -78,12 +83,15 @@ impl<'a> RenderableContent<'a> {
             size: &display.size_info,
             cursor: RenderableCursor::new_hidden(),
             terminal_content,
+            grid: term.grid(),
             focused_match,
             cursor_shape,
             cursor_point,
             search,
             config,
             hint,
+            text_blink_hidden: display.text_blink_hidden,
+            bidi_line: None,
         }
     }
 
@@ -109,6 +117,25 @@ impl<'a> RenderableContent<'a> {
         self.terminal_content.selection
     }
 
+    /// Move a cell to its column in the visual order of its line.
+    fn reorder(&mut self, cell: &mut RenderableCell, line: Line) {
+        if !self.config.terminal.bidi || cell.line_size.is_double_width() {
+            return;
+        }
+
+        // Lines are only reordered once, since cells are iterated line by line.
+        if self.bidi_line.as_ref().is_none_or(|(bidi_line, _)| *bidi_line != line) {
+            self.bidi_line = Some((line, BidiLine::new(&self.grid[line][..])));
+        }
+
+        if let Some((_, Some(bidi))) = &self.bidi_line {
+            if bidi.is_rtl(cell.point.column) {
+                cell.character = bidi::mirror(cell.character);
+            }
+            cell.point.column = bidi.visual_column(cell.point.column);
+        }
+    }
+
     /// Assemble the information required to render the terminal cursor.
     fn renderable_cursor(&mut self, cell: &RenderableCell) -> RenderableCursor {
         // Cursor colors.
@@ -135,18 +162,9 @@ impl<'a> RenderableContent<'a> {
             text_color = self.config.colors.primary.background;
         }
 
-        let width = if cell.flags.contains(Flags::WIDE_CHAR) {
-            NonZeroU32::new(2).unwrap()
-        } else {
-            NonZeroU32::new(1).unwrap()
-        };
-        RenderableCursor {
-            width,
-            shape: self.cursor_shape,
-            point: self.cursor_point,
-            cursor_color,
-            text_color,
-        }
+        let width = NonZeroU32::new(cell.columns() as u32).unwrap();
+        let point = Point::new(self.cursor_point.line, Column(cell.display_column()));
+        RenderableCursor { width, point, shape: self.cursor_shape, cursor_color, text_color }
     }
 }
 
@@ -161,9 +179,20 @@ impl Iterator for RenderableContent<'_> {
     fn next(&mut self) -> Option<Self::Item> {
         loop {
             let cell = self.terminal_content.display_iter.next()?;
-            let mut cell = RenderableCell::new(self, cell);
 
-            if self.cursor_point == cell.point {
+            // Skip cells past the end of double-width lines.
+            let line_size = self.grid[cell.point.line].line_size();
+            if line_size.is_double_width() && cell.point.column >= self.grid.columns() / 2 {
+                continue;
+            }
+
+            let line = cell.point.line;
+            let mut cell = RenderableCell::new(self, cell, line_size);
+            let is_cursor = self.cursor_point == cell.point;
+
+            self.reorder(&mut cell, line);
+
+            if is_cursor {
                 // Store the cursor which should be rendered.
                 self.cursor = self.renderable_cursor(&cell);
                 if self.cursor.shape == CursorShape::Block {
@@ -194,6 +223,7 @@ pub struct RenderableCell {
     pub bg_alpha: f32,
     pub underline: Rgb,
     pub flags: Flags,
+    pub line_size: LineSize,
     pub extra: Option<Box<RenderableCellExtra>>,
 }
 
@@ -206,7 +236,7 @@ pub struct RenderableCellExtra {
 }
 
 impl RenderableCell {
-    fn new(content: &mut RenderableContent<'_>, cell: Indexed<&Cell>) -> Self {
+    fn new(content: &mut RenderableContent<'_>, cell: Indexed<&Cell>, line_size: LineSize) -> Self {
         // Lookup RGB values.
         let mut fg = Self::compute_fg_rgb(content, cell.fg, cell.flags);
         let mut bg = Self::compute_bg_rgb(content, cell.bg);
@@ -232,6 +262,12 @@ impl RenderableCell {
         let mut character = cell.c;
         let mut flags = cell.flags;
 
+        // Hide blinking text during the invisible half of the blink cycle.
+        if content.text_blink_hidden && flags.contains(Flags::BLINK) {
+            flags.insert(Flags::HIDDEN);
+            flags.remove(Flags::ALL_UNDERLINES | Flags::STRIKEOUT | Flags::OVERLINE);
+        }
+
         let num_cols = content.size.columns();
         if let Some((c, is_first)) = content
             .hint
@@ -295,7 +331,21 @@ impl RenderableCell {
             })
         });
 
-        RenderableCell { flags, character, bg_alpha, point, fg, bg, underline, extra }
+        RenderableCell { flags, character, bg_alpha, point, fg, bg, underline, line_size, extra }
+    }
+
+    /// Column of the cell's left edge on the screen.
+    ///
+    /// This differs from the cell's column for double-width lines, since every cell in them is
+    /// displayed at twice its width.
+    pub fn display_column(&self) -> usize {
+        if self.line_size.is_double_width() { self.point.column.0 * 2 } else { self.point.column.0 }
+    }
+
+    /// Number of columns occupied by the cell on the screen.
+    pub fn columns(&self) -> usize {
+        let columns = if self.flags.contains(Flags::WIDE_CHAR) { 2 } else { 1 };
+        if self.line_size.is_double_width() { columns * 2 } else { columns }
     }
 
     /// Check if cell contains any renderable content.
@@ -303,7 +353,7 @@ impl RenderableCell {
         self.bg_alpha == 0.
             && self.character == ' '
             && self.extra.is_none()
-            && !self.flags.intersects(Flags::ALL_UNDERLINES | Flags::STRIKEOUT)
+            && !self.flags.intersects(Flags::ALL_UNDERLINES | Flags::STRIKEOUT | Flags::OVERLINE)
     }
 
     /// Apply [`CellRgb`] colors to the cell's colors.
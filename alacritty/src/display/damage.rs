@@ -89,6 +89,17 @@ impl DamageTracker {
         }
     }
 
+    /// Extend the damage of every damaged line to its full width.
+    ///
+    /// This is necessary when cells aren't drawn at their own column, like with reordered
+    /// bidirectional text.
+    pub fn damage_full_lines(&mut self) {
+        let columns = self.columns;
+        for line in self.frame().lines.iter_mut().filter(|line| line.is_damaged()) {
+            line.expand(0, columns - 1);
+        }
+    }
+
     /// Get shaped frame damage for the active frame.
     pub fn shape_frame_damage(&self, size_info: SizeInfo<u32>) -> Vec<Rect> {
         if self.frames[0].full {
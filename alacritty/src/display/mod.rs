@@ -1,12 +1,12 @@
 //! The display subsystem including window management, font rasterization, and
 //! GPU drawing.
 
-use std::cmp;
 use std::fmt::{self, Formatter};
 use std::mem::{self, ManuallyDrop};
 use std::num::NonZeroU32;
 use std::ops::Deref;
-use std::time::{Duration, Instant};
+use std::time::{Duration, Instant, SystemTime};
+use std::{cmp, iter};
 
 use glutin::config::GetGlConfig;
 use glutin::context::{NotCurrentContext, PossiblyCurrentContext};
@@ -26,7 +26,7 @@ use winit::window::CursorIcon;
 use crossfont::{Rasterize, Rasterizer, Size as FontSize};
 use unicode_width::UnicodeWidthChar;
 
-use alacritty_terminal::event::{EventListener, OnResize, WindowSize};
+use alacritty_terminal::event::{EventListener, WindowSize};
 use alacritty_terminal::grid::Dimensions as TermDimensions;
 use alacritty_terminal::index::{Column, Direction, Line, Point};
 use alacritty_terminal::selection::Selection;
@@ -39,6 +39,7 @@ use alacritty_terminal::vte::ansi::{CursorShape, NamedColor};
 use crate::config::UiConfig;
 use crate::config::debug::RendererPreference;
 use crate::config::font::Font;
+use crate::config::scrolling::Timestamps;
 use crate::config::window::Dimensions;
 #[cfg(not(windows))]
 use crate::config::window::StartupMode;
@@ -52,15 +53,19 @@ use crate::display::meter::Meter;
 use crate::display::window::Window;
 use crate::event::{Event, EventType, Mouse, SearchState};
 use crate::message_bar::{MessageBuffer, MessageType};
+use crate::pane::PaneRect;
 use crate::renderer::rects::{RenderLine, RenderLines, RenderRect};
 use crate::renderer::{self, GlyphCache, Renderer, platform};
 use crate::scheduler::{Scheduler, TimerId, Topic};
 use crate::string::{ShortenDirection, StrShortener};
+use crate::tabs::TabBar;
 
+pub mod bidi;
 pub mod color;
 pub mod content;
 pub mod cursor;
 pub mod hint;
+pub mod timestamp;
 pub mod window;
 
 mod bell;
@@ -268,6 +273,11 @@ impl SizeInfo<f32> {
         self.screen_lines = cmp::max(self.screen_lines.saturating_sub(count), MIN_SCREEN_LINES);
     }
 
+    #[inline]
+    pub fn reserve_columns(&mut self, count: usize) {
+        self.columns = cmp::max(self.columns.saturating_sub(count), MIN_COLUMNS);
+    }
+
     /// Check if coordinates are inside the terminal grid.
     ///
     /// The padding, message bar or search are not counted as part of the grid.
@@ -303,6 +313,20 @@ impl TermDimensions for SizeInfo {
     }
 }
 
+/// Terminal pane which should be drawn.
+pub struct PaneFrame<'a, T> {
+    pub terminal: MutexGuard<'a, Term<T>>,
+
+    /// Dimensions of the pane's terminal.
+    pub size_info: SizeInfo,
+
+    /// Position of the pane inside the window.
+    pub rect: PaneRect,
+
+    /// Whether keyboard input is directed at this pane.
+    pub focused: bool,
+}
+
 #[derive(Default, Clone, Debug, PartialEq, Eq)]
 pub struct DisplayUpdate {
     pub dirty: bool,
@@ -362,8 +386,20 @@ pub struct Display {
     /// UI cursor visibility for blinking.
     pub cursor_hidden: bool,
 
+    /// Visibility of text with the blink attribute.
+    pub text_blink_hidden: bool,
+
+    /// Whether the last frame contained text with the blink attribute.
+    pub text_blinking: bool,
+
+    /// Time until the relative timestamps of the last frame change.
+    timestamp_refresh: Option<Duration>,
+
     pub visual_bell: VisualBell,
 
+    /// Tabs shown at the top of the window.
+    pub tab_bar: TabBar,
+
     /// Mapped RGB values for each terminal color.
     pub colors: List,
 
@@ -451,7 +487,7 @@ impl Display {
         let viewport_size = window.inner_size();
 
         // Create new size with at least one column and row.
-        let size_info = SizeInfo::new(
+        let mut size_info = SizeInfo::new(
             viewport_size.width as f32,
             viewport_size.height as f32,
             cell_width,
@@ -460,6 +496,7 @@ impl Display {
             padding.1,
             config.window.dynamic_padding && config.window.dimensions().is_none(),
         );
+        size_info.reserve_columns(timestamp::gutter_width(config.scrolling.timestamps));
 
         info!("Cell size: {cell_width} x {cell_height}");
         info!("Padding: {} x {}", size_info.padding_x(), size_info.padding_y());
@@ -540,6 +577,10 @@ impl Display {
             hint_mouse_point: Default::default(),
             pending_update: Default::default(),
             cursor_hidden: Default::default(),
+            text_blink_hidden: Default::default(),
+            text_blinking: Default::default(),
+            timestamp_refresh: Default::default(),
+            tab_bar: Default::default(),
             meter: Default::default(),
             ime: Default::default(),
         })
@@ -651,16 +692,14 @@ impl Display {
     // performed in [`Self::process_renderer_update`] right before drawing.
     //
     /// Process update events.
-    pub fn handle_update<T>(
+    ///
+    /// Terminals are not resized here, they need to be updated to the new `size_info` afterwards.
+    pub fn handle_update(
         &mut self,
-        terminal: &mut Term<T>,
-        pty_resize_handle: &mut dyn OnResize,
         message_buffer: &MessageBuffer,
         search_state: &mut SearchState,
         config: &UiConfig,
-    ) where
-        T: EventListener,
-    {
+    ) {
         let pending_update = mem::take(&mut self.pending_update);
 
         let (mut cell_width, mut cell_height) =
@@ -707,23 +746,17 @@ impl Display {
         let message_bar_lines = message_buffer.message().map_or(0, |m| m.text(&new_size).len());
         let search_lines = usize::from(search_active);
         new_size.reserve_lines(message_bar_lines + search_lines);
+        new_size.reserve_columns(timestamp::gutter_width(config.scrolling.timestamps));
 
         // Update resize increments.
         if config.window.resize_increments {
             self.window.set_resize_increments(PhysicalSize::new(cell_width, cell_height));
         }
 
-        // Resize when terminal when its dimensions have changed.
+        // Resize damage tracking when the terminal dimensions have changed.
         if self.size_info.screen_lines() != new_size.screen_lines
             || self.size_info.columns() != new_size.columns()
         {
-            // Resize PTY.
-            pty_resize_handle.on_resize(new_size.into());
-
-            // Resize terminal.
-            terminal.resize(new_size);
-
-            // Resize damage tracking.
             self.damage_tracker.resize(new_size.screen_lines(), new_size.columns());
         }
 
@@ -772,132 +805,64 @@ impl Display {
 
     /// Draw the screen.
     ///
-    /// A reference to Term whose state is being drawn must be provided.
+    /// The state of every terminal pane which should be drawn must be provided, together with the
+    /// borders separating them.
     ///
     /// This call may block if vsync is enabled.
     pub fn draw<T: EventListener>(
         &mut self,
-        mut terminal: MutexGuard<'_, Term<T>>,
+        panes: Vec<PaneFrame<'_, T>>,
+        borders: &[PaneRect],
         scheduler: &mut Scheduler,
         message_buffer: &MessageBuffer,
         config: &UiConfig,
         search_state: &mut SearchState,
     ) {
-        // Collect renderable content before the terminal is dropped.
-        let mut content = RenderableContent::new(config, self, &terminal, search_state);
-        let mut grid_cells = Vec::new();
-        for cell in &mut content {
-            grid_cells.push(cell);
-        }
-        let selection_range = content.selection_range();
-        let foreground_color = content.color(NamedColor::Foreground as usize);
-        let background_color = content.color(NamedColor::Background as usize);
-        let display_offset = content.display_offset();
-        let cursor = content.cursor();
-
-        let cursor_point = terminal.grid().cursor.point;
-        let total_lines = terminal.grid().total_lines();
         let metrics = self.glyph_cache.font_metrics();
         let size_info = self.size_info;
+        let split = panes.iter().any(|pane| pane.size_info != size_info);
 
-        let vi_mode = terminal.mode().contains(TermMode::VI);
-        let vi_cursor_point = if vi_mode { Some(terminal.vi_mode_cursor.point) } else { None };
+        // Make sure this window's OpenGL context is active.
+        self.make_current();
 
-        // Add damage from the terminal.
-        match terminal.damage() {
-            TermDamage::Full => self.damage_tracker.frame().mark_fully_damaged(),
-            TermDamage::Partial(damaged_lines) => {
-                for damage in damaged_lines {
-                    self.damage_tracker.frame().damage_line(damage);
-                }
-            },
+        // Clear the borders and window-wide bars, panes clear their own area.
+        if split {
+            self.renderer.clear(config.colors.primary.background, config.window_opacity());
         }
-        terminal.reset_damage();
 
-        // Drop terminal as early as possible to free lock.
-        drop(terminal);
+        self.text_blinking = false;
+        self.timestamp_refresh = None;
+        for pane in panes {
+            if split {
+                // Move the renderer into the pane, with OpenGL's origin at the bottom left.
+                let y = size_info.height() - pane.rect.y - pane.rect.height;
+                self.renderer.set_offset(pane.rect.x as i32, y as i32);
+                self.renderer.resize(&pane.size_info);
+                self.size_info = pane.size_info;
+            }
 
-        // Invalidate highlighted hints if grid has changed.
-        self.validate_hint_highlights(display_offset);
+            self.draw_pane(pane, config, search_state, split);
+        }
 
-        // Add damage from alacritty's UI elements overlapping terminal.
+        if split {
+            self.size_info = size_info;
+            self.renderer.set_offset(0, 0);
+            self.renderer.resize(&size_info);
 
-        let requires_full_damage = self.visual_bell.intensity() != 0.
-            || self.hint_state.active()
-            || search_state.regex().is_some();
-        if requires_full_damage {
+            // Damage tracking only covers a single grid, so always redraw split windows entirely.
             self.damage_tracker.frame().mark_fully_damaged();
-            self.damage_tracker.next_frame().mark_fully_damaged();
         }
 
-        let vi_cursor_viewport_point =
-            vi_cursor_point.and_then(|cursor| term::point_to_viewport(display_offset, cursor));
-        self.damage_tracker.damage_vi_cursor(vi_cursor_viewport_point);
-        self.damage_tracker.damage_selection(selection_range, display_offset);
-
-        // Make sure this window's OpenGL context is active.
-        self.make_current();
-
-        self.renderer.clear(background_color, config.window_opacity());
-        let mut lines = RenderLines::new();
-
-        // Optimize loop hint comparator.
-        let has_highlighted_hint =
-            self.highlighted_hint.is_some() || self.vi_highlighted_hint.is_some();
-
-        // Draw grid.
-        {
-            let _sampler = self.meter.sampler();
-
-            // Ensure macOS hasn't reset our viewport.
-            #[cfg(target_os = "macos")]
-            self.renderer.set_viewport(&size_info);
-
-            let glyph_cache = &mut self.glyph_cache;
-            let highlighted_hint = &self.highlighted_hint;
-            let vi_highlighted_hint = &self.vi_highlighted_hint;
-            let damage_tracker = &mut self.damage_tracker;
-
-            let cells = grid_cells.into_iter().map(|mut cell| {
-                // Underline hints hovered by mouse or vi mode cursor.
-                if has_highlighted_hint {
-                    let point = term::viewport_to_point(display_offset, cell.point);
-                    let hyperlink = cell.extra.as_ref().and_then(|extra| extra.hyperlink.as_ref());
-
-                    let should_highlight = |hint: &Option<HintMatch>| {
-                        hint.as_ref().is_some_and(|hint| hint.should_highlight(point, hyperlink))
-                    };
-                    if should_highlight(highlighted_hint) || should_highlight(vi_highlighted_hint) {
-                        damage_tracker.frame().damage_point(cell.point);
-                        cell.flags.insert(Flags::UNDERLINE);
-                    }
-                }
-
-                // Update underline/strikeout.
-                lines.update(&cell);
+        let mut rects = Vec::new();
 
-                cell
-            });
-            self.renderer.draw_cells(&size_info, glyph_cache, cells);
+        // Draw borders between panes.
+        let border_color = config.colors.primary.foreground;
+        for border in borders {
+            let rect =
+                RenderRect::new(border.x, border.y, border.width, border.height, border_color, 0.5);
+            rects.push(rect);
         }
 
-        let mut rects = lines.rects(&metrics, &size_info);
-
-        if let Some(vi_cursor_point) = vi_cursor_point {
-            // Indicate vi mode by showing the cursor's position in the top right corner.
-            let line = (-vi_cursor_point.line.0 + size_info.bottommost_line().0) as usize;
-            let obstructed_column = Some(vi_cursor_point)
-                .filter(|point| point.line == -(display_offset as i32))
-                .map(|point| point.column);
-            self.draw_line_indicator(config, total_lines, obstructed_column, line);
-        } else if search_state.regex().is_some() {
-            // Show current display offset in vi-less search to indicate match position.
-            self.draw_line_indicator(config, total_lines, None, display_offset);
-        };
-
-        // Draw cursor.
-        rects.extend(cursor.rects(&size_info, config.cursor.thickness()));
-
         // Push visual bell after url/underline/strikeout rects.
         let visual_bell_intensity = self.visual_bell.intensity();
         if visual_bell_intensity != 0. {
@@ -912,55 +877,38 @@ impl Display {
             rects.push(visual_bell_rect);
         }
 
-        // Handle IME positioning and search bar rendering.
-        let ime_position = match search_state.regex() {
-            Some(regex) => {
-                let search_label = match search_state.direction() {
-                    Direction::Right => FORWARD_SEARCH_LABEL,
-                    Direction::Left => BACKWARD_SEARCH_LABEL,
-                };
-
-                let search_text = Self::format_search(regex, search_label, size_info.columns());
-
-                // Render the search bar.
-                self.draw_search(config, &search_text);
+        // Handle search bar rendering.
+        if let Some(regex) = search_state.regex() {
+            let search_label = match search_state.direction() {
+                Direction::Right => FORWARD_SEARCH_LABEL,
+                Direction::Left => BACKWARD_SEARCH_LABEL,
+            };
 
-                // Draw search bar cursor.
-                let line = size_info.screen_lines();
-                let column = Column(search_text.chars().count() - 1);
+            let search_text = Self::format_search(regex, search_label, size_info.columns());
 
-                // Add cursor to search bar if IME is not active.
-                if self.ime.preedit().is_none() {
-                    let fg = config.colors.footer_bar_foreground();
-                    let shape = CursorShape::Underline;
-                    let cursor_width = NonZeroU32::new(1).unwrap();
-                    let cursor =
-                        RenderableCursor::new(Point::new(line, column), shape, fg, cursor_width);
-                    rects.extend(cursor.rects(&size_info, config.cursor.thickness()));
-                }
+            // Render the search bar.
+            self.draw_search(config, &search_text);
 
-                Some(Point::new(line, column))
-            },
-            None => {
-                let num_lines = self.size_info.screen_lines();
-                match vi_cursor_viewport_point {
-                    None => term::point_to_viewport(display_offset, cursor_point)
-                        .filter(|point| point.line < num_lines),
-                    point => point,
-                }
-            },
-        };
+            // Draw search bar cursor.
+            let line = size_info.screen_lines();
+            let column = Column(search_text.chars().count() - 1);
 
-        // Handle IME.
-        if self.ime.is_enabled() {
-            if let Some(point) = ime_position {
-                let (fg, bg) = if search_state.regex().is_some() {
-                    (config.colors.footer_bar_foreground(), config.colors.footer_bar_background())
-                } else {
-                    (foreground_color, background_color)
-                };
+            // Add cursor to search bar if IME is not active.
+            if self.ime.preedit().is_none() {
+                let fg = config.colors.footer_bar_foreground();
+                let shape = CursorShape::Underline;
+                let cursor_width = NonZeroU32::new(1).unwrap();
+                let cursor =
+                    RenderableCursor::new(Point::new(line, column), shape, fg, cursor_width);
+                rects.extend(cursor.rects(&size_info, config.cursor.thickness()));
+            }
 
-                self.draw_ime_preview(point, fg, bg, &mut rects, config);
+            // Handle IME inside the search bar.
+            if self.ime.is_enabled() {
+                let fg = config.colors.footer_bar_foreground();
+                let bg = config.colors.footer_bar_background();
+                let point = Point::new(line, column);
+                self.draw_ime_preview(point, fg, bg, &mut rects, config, PaneRect::default());
             }
         }
 
@@ -975,6 +923,7 @@ impl Display {
             let bg = match message.ty() {
                 MessageType::Error => config.colors.normal.red,
                 MessageType::Warning => config.colors.normal.yellow,
+                MessageType::Info => config.colors.normal.cyan,
             };
 
             let x = 0;
@@ -989,10 +938,6 @@ impl Display {
             // Always damage message bar, since it could have messages of the same size in it.
             self.damage_tracker.frame().add_viewport_rect(&size_info, x, y as i32, width, height);
 
-            // Draw tab indicators if we have tabs
-            #[cfg(target_os = "linux")]
-            self.draw_tabs(&mut rects, config);
-            
             // Draw rectangles.
             self.renderer.draw_rects(&size_info, &metrics, rects);
 
@@ -1015,13 +960,9 @@ impl Display {
             self.renderer.draw_rects(&size_info, &metrics, rects);
         }
 
-        self.draw_render_timer(config);
+        self.draw_tab_bar(config);
 
-        // Draw hyperlink uri preview.
-        if has_highlighted_hint {
-            let cursor_point = vi_cursor_point.or(Some(cursor_point));
-            self.draw_hyperlink_preview(config, cursor_point, display_offset);
-        }
+        self.draw_render_timer(config);
 
         // Notify winit that we're about to present.
         self.window.pre_present_notify();
@@ -1044,6 +985,15 @@ impl Display {
             self.renderer.finish();
         }
 
+        // Redraw once relative timestamps change.
+        if let Some(refresh) = self.timestamp_refresh {
+            let window_id = self.window.id();
+            let timer_id = TimerId::new(Topic::TimestampRefresh, window_id);
+            let event = Event::new(EventType::TimestampRefresh, window_id);
+            scheduler.unschedule(timer_id);
+            scheduler.schedule(event, refresh, false, timer_id);
+        }
+
         // XXX: Request the new frame after swapping buffers, so the
         // time to finish OpenGL operations is accounted for in the timeout.
         if !matches!(self.raw_window_handle, RawWindowHandle::Wayland(_)) {
@@ -1053,6 +1003,219 @@ impl Display {
         self.damage_tracker.swap_damage();
     }
 
+    /// Draw the content of a single terminal pane.
+    ///
+    /// The renderer and `size_info` must already be set up for the pane's area.
+    fn draw_pane<T: EventListener>(
+        &mut self,
+        pane: PaneFrame<'_, T>,
+        config: &UiConfig,
+        search_state: &mut SearchState,
+        split: bool,
+    ) {
+        let PaneFrame { mut terminal, rect, focused, .. } = pane;
+
+        // Search and hints only apply to the focused pane.
+        let mut unfocused_search_state = SearchState::default();
+        let search_state = if focused { search_state } else { &mut unfocused_search_state };
+
+        // Collect renderable content before the terminal is dropped.
+        let mut content = RenderableContent::new(config, self, &terminal, search_state, focused);
+        let mut grid_cells = Vec::new();
+        for cell in &mut content {
+            grid_cells.push(cell);
+        }
+        let selection_range = content.selection_range();
+        let foreground_color = content.color(NamedColor::Foreground as usize);
+        let background_color = content.color(NamedColor::Background as usize);
+        let dim_foreground_color = content.color(NamedColor::DimForeground as usize);
+        let display_offset = content.display_offset();
+        let cursor = content.cursor();
+
+        self.text_blinking |= grid_cells.iter().any(|cell| cell.flags.contains(Flags::BLINK));
+
+        let cursor_point = terminal.grid().cursor.point;
+        let total_lines = terminal.grid().total_lines();
+        let metrics = self.glyph_cache.font_metrics();
+        let size_info = self.size_info;
+
+        let vi_mode = terminal.mode().contains(TermMode::VI);
+        let vi_cursor_point = if vi_mode { Some(terminal.vi_mode_cursor.point) } else { None };
+
+        // Add damage from the terminal.
+        //
+        // The damage of unfocused panes is irrelevant, since split windows are always fully
+        // redrawn.
+        if focused {
+            match terminal.damage() {
+                TermDamage::Full => self.damage_tracker.frame().mark_fully_damaged(),
+                TermDamage::Partial(damaged_lines) => {
+                    for damage in damaged_lines {
+                        self.damage_tracker.frame().damage_line(damage);
+                    }
+                },
+            }
+        }
+        terminal.reset_damage();
+
+        // Collect timestamps of visible lines, skipping rows continuing a wrapped line.
+        let mut timestamps = Vec::new();
+        if config.scrolling.timestamps != Timestamps::None {
+            let grid = terminal.grid();
+            let topmost_line = grid.topmost_line();
+            let last_column = grid.last_column();
+            for viewport_line in 0..grid.screen_lines() {
+                let line = Line(viewport_line as i32 - display_offset as i32);
+                let wrapped = line > topmost_line
+                    && grid[line - 1i32][last_column].flags.contains(Flags::WRAPLINE);
+                if let Some(time) = grid.timestamp(line).filter(|_| !wrapped) {
+                    timestamps.push((viewport_line, time));
+                }
+            }
+        }
+
+        // Collect vi mode marks of visible lines.
+        let mut vi_marks = Vec::new();
+        for viewport_line in 0..terminal.screen_lines() {
+            let line = Line(viewport_line as i32 - display_offset as i32);
+            let marks = terminal.grid()[line].vi_marks();
+            if !marks.is_empty() {
+                let text = iter::once('\'').chain(marks.iter()).collect::<String>();
+                vi_marks.push((viewport_line, text));
+            }
+        }
+
+        // Drop terminal as early as possible to free lock.
+        drop(terminal);
+
+        let vi_cursor_viewport_point =
+            vi_cursor_point.and_then(|cursor| term::point_to_viewport(display_offset, cursor));
+
+        if focused {
+            // Invalidate highlighted hints if grid has changed.
+            self.validate_hint_highlights(display_offset);
+
+            // Add damage from alacritty's UI elements overlapping terminal.
+
+            let requires_full_damage = self.visual_bell.intensity() != 0.
+                || self.hint_state.active()
+                || search_state.regex().is_some();
+            if requires_full_damage {
+                self.damage_tracker.frame().mark_fully_damaged();
+                self.damage_tracker.next_frame().mark_fully_damaged();
+            }
+
+            self.damage_tracker.damage_vi_cursor(vi_cursor_viewport_point);
+            self.damage_tracker.damage_selection(selection_range, display_offset);
+
+            if config.terminal.bidi {
+                self.damage_tracker.damage_full_lines();
+            }
+        }
+
+        if split {
+            self.renderer.clear_area(&size_info, background_color, config.window_opacity());
+        } else {
+            self.renderer.clear(background_color, config.window_opacity());
+        }
+        let mut lines = RenderLines::new();
+
+        // Optimize loop hint comparator.
+        let has_highlighted_hint =
+            focused && (self.highlighted_hint.is_some() || self.vi_highlighted_hint.is_some());
+
+        // Draw grid.
+        {
+            let _sampler = self.meter.sampler();
+
+            // Ensure macOS hasn't reset our viewport.
+            #[cfg(target_os = "macos")]
+            self.renderer.set_viewport(&size_info);
+
+            let glyph_cache = &mut self.glyph_cache;
+            let highlighted_hint = &self.highlighted_hint;
+            let vi_highlighted_hint = &self.vi_highlighted_hint;
+            let damage_tracker = &mut self.damage_tracker;
+
+            let cells = grid_cells.into_iter().map(|mut cell| {
+                // Underline hints hovered by mouse or vi mode cursor.
+                if has_highlighted_hint {
+                    let point = term::viewport_to_point(display_offset, cell.point);
+                    let hyperlink = cell.extra.as_ref().and_then(|extra| extra.hyperlink.as_ref());
+
+                    let should_highlight = |hint: &Option<HintMatch>| {
+                        hint.as_ref().is_some_and(|hint| hint.should_highlight(point, hyperlink))
+                    };
+                    if should_highlight(highlighted_hint) || should_highlight(vi_highlighted_hint) {
+                        damage_tracker.frame().damage_point(cell.point);
+                        cell.flags.insert(Flags::UNDERLINE);
+                    }
+                }
+
+                // Update underline/strikeout.
+                lines.update(&cell);
+
+                cell
+            });
+            self.renderer.draw_cells(&size_info, glyph_cache, cells);
+        }
+
+        let mut rects = lines.rects(&metrics, &size_info);
+
+        let line_indicator_width = if let Some(vi_cursor_point) = vi_cursor_point {
+            // Indicate vi mode by showing the cursor's position in the top right corner.
+            let line = (-vi_cursor_point.line.0 + size_info.bottommost_line().0) as usize;
+            let obstructed_column = Some(vi_cursor_point)
+                .filter(|point| point.line == -(display_offset as i32))
+                .map(|point| point.column);
+            self.draw_line_indicator(config, total_lines, obstructed_column, line)
+        } else if search_state.regex().is_some() {
+            // Show current display offset in vi-less search to indicate match position.
+            self.draw_line_indicator(config, total_lines, None, display_offset)
+        } else {
+            0
+        };
+
+        let cursor_viewport_point = term::point_to_viewport(display_offset, cursor_point);
+        let obstructions = [cursor_viewport_point, vi_cursor_viewport_point];
+
+        if !vi_marks.is_empty() {
+            self.draw_vi_marks(config, &vi_marks, line_indicator_width, obstructions);
+        }
+
+        if config.scrolling.timestamps != Timestamps::None {
+            let colors = (dim_foreground_color, background_color);
+            self.draw_timestamps(config, timestamps, colors);
+        }
+
+        // Draw cursor.
+        rects.extend(cursor.rects(&size_info, config.cursor.thickness()));
+
+        // Handle IME positioning, unless it is inside the search bar.
+        if focused && self.ime.is_enabled() && search_state.regex().is_none() {
+            let num_lines = self.size_info.screen_lines();
+            let ime_position = match vi_cursor_viewport_point {
+                None => term::point_to_viewport(display_offset, cursor_point)
+                    .filter(|point| point.line < num_lines),
+                point => point,
+            };
+
+            if let Some(point) = ime_position {
+                let (fg, bg) = (foreground_color, background_color);
+                self.draw_ime_preview(point, fg, bg, &mut rects, config, rect);
+            }
+        }
+
+        // Draw rectangles.
+        self.renderer.draw_rects(&size_info, &metrics, rects);
+
+        // Draw hyperlink uri preview.
+        if has_highlighted_hint {
+            let cursor_point = vi_cursor_point.or(Some(cursor_point));
+            self.draw_hyperlink_preview(config, cursor_point, display_offset);
+        }
+    }
+
     /// Update to a new configuration.
     pub fn update_config(&mut self, config: &UiConfig) {
         self.damage_tracker.debug = config.debug.highlight_damage;
@@ -1063,11 +1226,15 @@ impl Display {
     /// Update the mouse/vi mode cursor hint highlighting.
     ///
     /// This will return whether the highlighted hints changed.
+    ///
+    /// The `mouse` is `None` when it is not above the terminal's pane, with `size_info` being
+    /// the dimensions of that pane.
     pub fn update_highlighted_hints<T>(
         &mut self,
         term: &Term<T>,
         config: &UiConfig,
-        mouse: &Mouse,
+        mouse: Option<&Mouse>,
+        size_info: &SizeInfo,
         modifiers: ModifiersState,
     ) -> bool {
         // Update vi mode cursor hint.
@@ -1088,16 +1255,19 @@ impl Display {
         }
 
         // Abort if mouse highlighting conditions are not met.
-        if !mouse.inside_text_area || !term.selection.as_ref().is_none_or(Selection::is_empty) {
-            if self.highlighted_hint.take().is_some() {
-                self.damage_tracker.frame().mark_fully_damaged();
-                dirty = true;
-            }
-            return dirty;
-        }
+        let mouse = match mouse.filter(|mouse| mouse.inside_text_area) {
+            Some(mouse) if term.selection.as_ref().is_none_or(Selection::is_empty) => mouse,
+            _ => {
+                if self.highlighted_hint.take().is_some() {
+                    self.damage_tracker.frame().mark_fully_damaged();
+                    dirty = true;
+                }
+                return dirty;
+            },
+        };
 
         // Find highlighted hint at mouse position.
-        let point = mouse.point(&self.size_info, term.grid().display_offset());
+        let point = mouse.point(size_info, term);
         let highlighted_hint = hint::highlighted_at(term, config, point, modifiers);
 
         // Update cursor shape.
@@ -1129,6 +1299,26 @@ impl Display {
         dirty
     }
 
+    /// Damage all visible lines containing blinking text.
+    ///
+    /// This will return whether any blinking text is visible.
+    pub fn damage_blinking_text<T>(&mut self, term: &Term<T>) -> bool {
+        let display_offset = term.grid().display_offset();
+        let columns = term.columns();
+
+        let mut blinking = false;
+        for line in 0..term.screen_lines() {
+            let row = &term.grid()[Line(line as i32 - display_offset as i32)];
+            if row[..].iter().any(|cell| cell.flags.contains(Flags::BLINK)) {
+                let damage = LineDamageBounds::new(line, 0, columns - 1);
+                self.damage_tracker.frame().damage_line(damage);
+                blinking = true;
+            }
+        }
+
+        blinking
+    }
+
     #[inline(never)]
     fn draw_ime_preview(
         &mut self,
@@ -1137,12 +1327,13 @@ impl Display {
         bg: Rgb,
         rects: &mut Vec<RenderRect>,
         config: &UiConfig,
+        pane: PaneRect,
     ) {
         let preedit = match self.ime.preedit() {
             Some(preedit) => preedit,
             None => {
                 // In case we don't have preedit, just set the popup point.
-                self.window.update_ime_position(point, &self.size_info);
+                self.window.update_ime_position(point, &self.size_info, pane);
                 return;
             },
         };
@@ -1216,7 +1407,7 @@ impl Display {
             _ => end,
         };
 
-        self.window.update_ime_position(ime_popup_point, &self.size_info);
+        self.window.update_ime_position(ime_popup_point, &self.size_info, pane);
     }
 
     /// Format search regex to account for the cursor and fullwidth characters.
@@ -1351,6 +1542,8 @@ impl Display {
     }
 
     /// Draw an indicator for the position of a line in history.
+    ///
+    /// Returns the number of columns reserved for the indicator.
     #[inline(never)]
     fn draw_line_indicator(
         &mut self,
@@ -1358,7 +1551,7 @@ impl Display {
         total_lines: usize,
         obstructed_column: Option<Column>,
         line: usize,
-    ) {
+    ) -> usize {
         let columns = self.size_info.columns();
         let text = format!("[{}/{}]", line, total_lines - 1);
         let column = Column(self.size_info.columns().saturating_sub(text.len()));
@@ -1378,45 +1571,136 @@ impl Display {
             let glyph_cache = &mut self.glyph_cache;
             self.renderer.draw_string(point, fg, bg, text.chars(), &self.size_info, glyph_cache);
         }
+
+        text.len()
+    }
+
+    /// Draw the vi mode marks of visible lines at the right edge of the terminal.
+    #[inline(never)]
+    fn draw_vi_marks(
+        &mut self,
+        config: &UiConfig,
+        vi_marks: &[(usize, String)],
+        line_indicator_width: usize,
+        obstructions: [Option<Point<usize>>; 2],
+    ) {
+        let columns = self.size_info.columns();
+
+        let colors = &config.colors;
+        let fg = colors.line_indicator.foreground.unwrap_or(colors.primary.background);
+        let bg = colors.line_indicator.background.unwrap_or(colors.primary.foreground);
+
+        for (line, text) in vi_marks {
+            // Place marks of the first line next to the line indicator.
+            let margin = if *line == 0 { line_indicator_width } else { 0 };
+            let column = Column(columns.saturating_sub(text.len() + margin));
+            let point = Point::new(*line, column);
+
+            // Damage the marks for current and next frame.
+            let damage = LineDamageBounds::new(point.line, point.column.0, columns - 1);
+            self.damage_tracker.frame().damage_line(damage);
+            self.damage_tracker.next_frame().damage_line(damage);
+
+            // Do not render anything if it would obscure the cursor.
+            let obstructed = obstructions
+                .iter()
+                .flatten()
+                .any(|cursor| cursor.line == *line && cursor.column >= column);
+            if !obstructed {
+                let glyph_cache = &mut self.glyph_cache;
+                self.renderer.draw_string(
+                    point,
+                    fg,
+                    bg,
+                    text.chars(),
+                    &self.size_info,
+                    glyph_cache,
+                );
+            }
+        }
+    }
+
+    /// Draw the timestamps of visible lines into the gutter right of the terminal.
+    #[inline(never)]
+    fn draw_timestamps(
+        &mut self,
+        config: &UiConfig,
+        timestamps: Vec<(usize, SystemTime)>,
+        (fg, bg): (Rgb, Rgb),
+    ) {
+        let format = config.scrolling.timestamps;
+        let gutter_width = timestamp::gutter_width(format);
+        let size_info = self.size_info;
+        let columns = size_info.columns();
+        let now = SystemTime::now();
+
+        // Damage the gutter for current and next frame, since it's outside of the terminal.
+        let x = size_info.padding_x() + columns as f32 * size_info.cell_width();
+        let width = gutter_width as f32 * size_info.cell_width();
+        let height = size_info.screen_lines() as f32 * size_info.cell_height();
+        let rect = (x as i32, size_info.padding_y() as i32, width as i32, height as i32);
+        self.damage_tracker.frame().add_viewport_rect(&size_info, rect.0, rect.1, rect.2, rect.3);
+        let next_frame = self.damage_tracker.next_frame();
+        next_frame.add_viewport_rect(&size_info, rect.0, rect.1, rect.2, rect.3);
+
+        for (line, time) in timestamps {
+            let text = match timestamp::gutter_text(format, time, now) {
+                Some(text) => text,
+                None => continue,
+            };
+
+            // Right-align timestamps within the gutter.
+            let column = Column(columns + gutter_width.saturating_sub(text.len()));
+            let glyph_cache = &mut self.glyph_cache;
+            let point = Point::new(line, column);
+            self.renderer.draw_string(point, fg, bg, text.chars(), &size_info, glyph_cache);
+
+            // Schedule a redraw for the earliest change of a relative timestamp.
+            if let Some(change) = timestamp::next_change(format, time, now) {
+                let refresh = self.timestamp_refresh.get_or_insert(change);
+                *refresh = cmp::min(*refresh, change);
+            }
+        }
     }
 
-    /// Draw tab indicators if we have tabs
-    #[cfg(target_os = "linux")]
-    fn draw_tabs(&self, rects: &mut Vec<RenderRect>, config: &UiConfig) {
-        // For now, we'll just draw a simple tab indicator
-        // A full implementation would require more complex UI elements
-        let num_tabs = self.window.num_tabs();
-        if num_tabs <= 1 {
+    /// Draw the titles of all tabs.
+    #[inline(never)]
+    fn draw_tab_bar(&mut self, config: &UiConfig) {
+        if !self.tab_bar.is_visible() {
             return;
         }
 
-        let tab_width = self.size_info.width() / num_tabs as f32;
-        let tab_height = 20.0; // Height of tab bar
-        let active_tab = self.window.active_tab_index();
+        let columns = self.size_info.columns();
+        let tab_columns = self.tab_bar.tab_columns(&self.size_info);
+        let num_tabs = self.tab_bar.titles.len();
+
+        for (i, title) in self.tab_bar.titles.iter().enumerate() {
+            let column = i * tab_columns;
+            if column >= columns {
+                break;
+            }
 
-        // Draw background for tab bar
-        let bg = config.colors.primary.background;
-        let tab_bar_rect = RenderRect::new(0., 0., self.size_info.width(), tab_height, bg, 1.);
-        rects.push(tab_bar_rect);
+            // Extend the last tab to the end of the line.
+            let width = if i + 1 == num_tabs { columns - column } else { tab_columns };
 
-        // Draw individual tabs
-        for i in 0..num_tabs {
-            let x = i as f32 * tab_width;
-            let color = if i == active_tab {
-                config.colors.normal.blue // Highlight active tab
+            let (fg, bg) = if i == self.tab_bar.active {
+                (config.colors.footer_bar_foreground(), config.colors.footer_bar_background())
             } else {
-                config.colors.primary.foreground
+                (config.colors.primary.foreground, config.colors.primary.background)
             };
-            
-            // Draw tab background
-            let tab_rect = RenderRect::new(x, 0., tab_width, tab_height, color, 0.3);
-            rects.push(tab_rect);
-            
-            // Draw tab separator
-            if i < num_tabs - 1 {
-                let separator_rect = RenderRect::new(x + tab_width - 1., 0., 1., tab_height, bg, 1.);
-                rects.push(separator_rect);
-            }
+
+            // Keep one column as separator between tabs.
+            let label = format!(" {}: {title}", i + 1);
+            let max_width = width.saturating_sub(1);
+            let mut text: String =
+                StrShortener::new(&label, max_width, ShortenDirection::Right, Some(SHORTENER))
+                    .collect();
+            let text_width = text.chars().count();
+            text.extend(iter::repeat_n(' ', width.saturating_sub(text_width)));
+
+            let point = Point::new(0, Column(column));
+            let glyph_cache = &mut self.glyph_cache;
+            self.renderer.draw_string(point, fg, bg, text.chars(), &self.size_info, glyph_cache);
         }
     }
 
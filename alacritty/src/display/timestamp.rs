@@ -0,0 +1,180 @@
+//! Formatting of line timestamps.
+
+use std::fmt::{self, Display, Formatter};
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+use std::{cmp, mem};
+
+use crate::config::scrolling::Timestamps;
+
+/// Maximum number of days shown for relative timestamps.
+const MAX_RELATIVE_DAYS: u64 = 999;
+
+/// Number of columns reserved for the timestamp gutter.
+///
+/// This includes one column separating the timestamps from the terminal content.
+pub fn gutter_width(format: Timestamps) -> usize {
+    match format {
+        Timestamps::None => 0,
+        // Up to `999d`.
+        Timestamps::Relative => 5,
+        // Up to `MM-DD HH:MM`.
+        Timestamps::Absolute => 12,
+    }
+}
+
+/// Format a line's timestamp for the timestamp gutter.
+pub fn gutter_text(format: Timestamps, time: SystemTime, now: SystemTime) -> Option<String> {
+    match format {
+        Timestamps::None => None,
+        Timestamps::Relative => Some(relative(time, now)),
+        Timestamps::Absolute => {
+            let local_time = LocalTime::new(time)?;
+
+            // Omit the date for lines written today.
+            if LocalTime::new(now).is_some_and(|now| now.date() == local_time.date()) {
+                Some(local_time.time())
+            } else {
+                Some(local_time.short())
+            }
+        },
+    }
+}
+
+/// Time until the gutter text of a timestamp changes.
+pub fn next_change(format: Timestamps, time: SystemTime, now: SystemTime) -> Option<Duration> {
+    if format != Timestamps::Relative {
+        return None;
+    }
+
+    let elapsed = now.duration_since(time).unwrap_or_default().as_millis() as u64;
+    let unit = match elapsed / 1000 {
+        0..=59 => 1,
+        60..=3599 => 60,
+        3600..=86399 => 3600,
+        secs if secs / 86400 < MAX_RELATIVE_DAYS => 86400,
+        _ => return None,
+    };
+
+    let unit = unit * 1000;
+    Some(Duration::from_millis(unit - elapsed % unit))
+}
+
+/// Format the time elapsed since `time` using its largest unit.
+pub fn relative(time: SystemTime, now: SystemTime) -> String {
+    let secs = now.duration_since(time).unwrap_or_default().as_secs();
+    match secs {
+        0 => String::from("now"),
+        1..=59 => format!("{secs}s"),
+        60..=3599 => format!("{}m", secs / 60),
+        3600..=86399 => format!("{}h", secs / 3600),
+        _ => format!("{}d", cmp::min(secs / 86400, MAX_RELATIVE_DAYS)),
+    }
+}
+
+/// Date and time in the local timezone.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub struct LocalTime {
+    year: i32,
+    month: u8,
+    day: u8,
+    hour: u8,
+    minute: u8,
+    second: u8,
+}
+
+impl LocalTime {
+    pub fn new(time: SystemTime) -> Option<Self> {
+        let secs = time.duration_since(UNIX_EPOCH).ok()?.as_secs();
+        let secs = libc::time_t::try_from(secs).ok()?;
+
+        // SAFETY: All-zero is a valid value for every field of `tm`.
+        let mut tm: libc::tm = unsafe { mem::zeroed() };
+
+        #[cfg(not(windows))]
+        let success = unsafe { !libc::localtime_r(&secs, &mut tm).is_null() };
+        #[cfg(windows)]
+        let success = unsafe { libc::localtime_s(&mut tm, &secs) == 0 };
+
+        if !success {
+            return None;
+        }
+
+        Some(Self {
+            year: tm.tm_year + 1900,
+            month: (tm.tm_mon + 1) as u8,
+            day: tm.tm_mday as u8,
+            hour: tm.tm_hour as u8,
+            minute: tm.tm_min as u8,
+            second: tm.tm_sec as u8,
+        })
+    }
+
+    /// Time of the day, formatted as `HH:MM:SS`.
+    pub fn time(&self) -> String {
+        format!("{:02}:{:02}:{:02}", self.hour, self.minute, self.second)
+    }
+
+    /// Date and time without the year and seconds, formatted as `MM-DD HH:MM`.
+    pub fn short(&self) -> String {
+        format!("{:02}-{:02} {:02}:{:02}", self.month, self.day, self.hour, self.minute)
+    }
+
+    fn date(&self) -> (i32, u8, u8) {
+        (self.year, self.month, self.day)
+    }
+}
+
+impl Display for LocalTime {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        write!(f, "{:04}-{:02}-{:02} {}", self.year, self.month, self.day, self.time())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use std::time::Duration;
+
+    #[test]
+    fn relative_units() {
+        let now = UNIX_EPOCH + Duration::from_secs(1_000_000);
+        let ago = |secs| relative(now - Duration::from_secs(secs), now);
+
+        assert_eq!(ago(0), "now");
+        assert_eq!(ago(59), "59s");
+        assert_eq!(ago(60), "1m");
+        assert_eq!(ago(3599), "59m");
+        assert_eq!(ago(7200), "2h");
+        assert_eq!(ago(86400 * 3 + 5), "3d");
+        assert_eq!(ago(86400 * 5000), "999d");
+
+        // Clock changes must not panic.
+        assert_eq!(relative(now + Duration::from_secs(10), now), "now");
+    }
+
+    #[test]
+    fn local_time_format() {
+        let time = LocalTime { year: 2024, month: 3, day: 7, hour: 9, minute: 5, second: 0 };
+        assert_eq!(time.time(), "09:05:00");
+        assert_eq!(time.to_string(), "2024-03-07 09:05:00");
+        assert_eq!(time.short(), "03-07 09:05");
+
+        let now = SystemTime::now();
+        assert!(LocalTime::new(now).is_some());
+        let text = gutter_text(Timestamps::Absolute, now, now).unwrap();
+        assert_eq!(text.len(), 8);
+    }
+
+    #[test]
+    fn refresh_relative() {
+        let now = UNIX_EPOCH + Duration::from_secs(1_000_000);
+        let next = |ago| next_change(Timestamps::Relative, now - Duration::from_millis(ago), now);
+
+        assert_eq!(next(500), Some(Duration::from_millis(500)));
+        assert_eq!(next(90_000), Some(Duration::from_secs(30)));
+        assert_eq!(next(3_600_000), Some(Duration::from_secs(3600)));
+        assert_eq!(next(86_400_000 * 1000), None);
+        assert_eq!(next_change(Timestamps::Absolute, now, now), None);
+    }
+}
@@ -44,6 +44,7 @@ use crate::cli::WindowOptions;
 use crate::config::UiConfig;
 use crate::config::window::{Decorations, Identity, WindowConfig};
 use crate::display::SizeInfo;
+use crate::pane::PaneRect;
 
 /// Window icon for `_NET_WM_ICON` property.
 #[cfg(all(feature = "x11", not(any(target_os = "macos", windows))))]
@@ -117,18 +118,6 @@ pub struct Window {
     /// Current window title.
     title: String,
 
-    /// Tabbing identifier for grouping windows as tabs.
-    #[cfg(target_os = "linux")]
-    tabbing_id: String,
-
-    /// List of tabs in this window.
-    #[cfg(target_os = "linux")]
-    tabs: Vec<String>,
-
-    /// Index of the currently active tab.
-    #[cfg(target_os = "linux")]
-    active_tab: usize,
-
     is_x11: bool,
     current_mouse_cursor: CursorIcon,
     mouse_visible: bool,
@@ -213,7 +202,6 @@ impl Window {
         log::info!("Window scale factor: {scale_factor}");
         let is_x11 = matches!(window.window_handle().unwrap().as_raw(), RawWindowHandle::Xlib(_));
 
-        let title = identity.title.clone();
         Ok(Self {
             hold: options.terminal_options.hold,
             requested_redraw: false,
@@ -221,15 +209,9 @@ impl Window {
             scale_factor,
             window,
             is_x11,
-            title: title.clone(),
+            title: identity.title,
             current_mouse_cursor,
             mouse_visible: true,
-            #[cfg(target_os = "linux")]
-            tabbing_id: options.window_tabbing_id.clone().unwrap_or_else(|| "default".to_string()),
-            #[cfg(target_os = "linux")]
-            tabs: vec![title.clone()],
-            #[cfg(target_os = "linux")]
-            active_tab: 0,
         })
     }
 
@@ -455,13 +437,17 @@ impl Window {
     }
 
     /// Adjust the IME editor position according to the new location of the cursor.
-    pub fn update_ime_position(&self, point: Point<usize>, size: &SizeInfo) {
+    ///
+    /// The `pane` is the area of the window the cursor's terminal is drawn in.
+    pub fn update_ime_position(&self, point: Point<usize>, size: &SizeInfo, pane: PaneRect) {
         // NOTE: X11 doesn't support cursor area, so we need to offset manually to not obscure
         // the text.
         let offset = if self.is_x11 { 1 } else { 0 };
-        let nspot_x = f64::from(size.padding_x() + point.column.0 as f32 * size.cell_width());
-        let nspot_y =
-            f64::from(size.padding_y() + (point.line + offset) as f32 * size.cell_height());
+        let nspot_x =
+            f64::from(pane.x + size.padding_x() + point.column.0 as f32 * size.cell_width());
+        let nspot_y = f64::from(
+            pane.y + size.padding_y() + (point.line + offset) as f32 * size.cell_height(),
+        );
 
         // NOTE: some compositors don't like excluding too much and try to render popup at the
         // bottom right corner of the provided area, so exclude just the full-width char to not
@@ -519,105 +505,6 @@ impl Window {
     pub fn tabbing_id(&self) -> String {
         self.window.tabbing_identifier()
     }
-
-    /// Select tab at the given `index`.
-    #[cfg(target_os = "linux")]
-    pub fn select_tab_at_index(&mut self, index: usize) {
-        if index < self.tabs.len() {
-            self.active_tab = index;
-            // Update window title to reflect active tab
-            self.window.set_title(&self.tabs[self.active_tab]);
-        }
-    }
-
-    /// Select the last tab.
-    #[cfg(target_os = "linux")]
-    pub fn select_last_tab(&mut self) {
-        if !self.tabs.is_empty() {
-            self.select_tab_at_index(self.tabs.len() - 1);
-        }
-    }
-
-    /// Select next tab.
-    #[cfg(target_os = "linux")]
-    pub fn select_next_tab(&mut self) {
-        if self.tabs.len() > 1 {
-            let next_index = (self.active_tab + 1) % self.tabs.len();
-            self.select_tab_at_index(next_index);
-        }
-    }
-
-    /// Select previous tab.
-    #[cfg(target_os = "linux")]
-    pub fn select_previous_tab(&mut self) {
-        if self.tabs.len() > 1 {
-            let prev_index = if self.active_tab == 0 {
-                self.tabs.len() - 1
-            } else {
-                self.active_tab - 1
-            };
-            self.select_tab_at_index(prev_index);
-        }
-    }
-
-    #[cfg(target_os = "linux")]
-    pub fn tabbing_id(&self) -> String {
-        self.tabbing_id.clone()
-    }
-
-    /// Add a new tab with the given title.
-    #[cfg(target_os = "linux")]
-    pub fn add_tab(&mut self, title: String) {
-        self.tabs.push(title);
-    }
-
-    /// Get the number of tabs.
-    #[cfg(target_os = "linux")]
-    pub fn num_tabs(&self) -> usize {
-        self.tabs.len()
-    }
-
-    /// Get the index of the active tab.
-    #[cfg(target_os = "linux")]
-    pub fn active_tab_index(&self) -> usize {
-        self.active_tab
-    }
-
-    /// Get the title of the active tab.
-    #[cfg(target_os = "linux")]
-    pub fn active_tab_title(&self) -> &str {
-        &self.tabs[self.active_tab]
-    }
-
-    /// Select tab at the given `index`.
-    #[cfg(not(any(target_os = "macos", target_os = "linux")))]
-    pub fn select_tab_at_index(&self, _index: usize) {
-        // No-op on non-macOS, non-Linux platforms
-    }
-
-    /// Select the last tab.
-    #[cfg(not(any(target_os = "macos", target_os = "linux")))]
-    pub fn select_last_tab(&self) {
-        // No-op on non-macOS, non-Linux platforms
-    }
-
-    /// Select next tab.
-    #[cfg(not(any(target_os = "macos", target_os = "linux")))]
-    pub fn select_next_tab(&self) {
-        // No-op on non-macOS, non-Linux platforms
-    }
-
-    /// Select previous tab.
-    #[cfg(not(any(target_os = "macos", target_os = "linux")))]
-    pub fn select_previous_tab(&self) {
-        // No-op on non-macOS, non-Linux platforms
-    }
-
-    #[cfg(not(any(target_os = "macos", target_os = "linux")))]
-    pub fn tabbing_id(&self) -> String {
-        // Return a default tabbing ID for non-macOS, non-Linux platforms
-        "default".to_string()
-    }
 }
 
 #[cfg(target_os = "macos")]
@@ -13,11 +13,11 @@ use std::fmt::Debug;
 use std::os::unix::io::RawFd;
 #[cfg(unix)]
 use std::os::unix::net::UnixStream;
-use std::path::PathBuf;
+use std::path::{Path, PathBuf};
 use std::rc::Rc;
 #[cfg(unix)]
 use std::sync::Arc;
-use std::time::{Duration, Instant};
+use std::time::{Duration, Instant, SystemTime, UNIX_EPOCH};
 use std::{env, f32, mem};
 
 use ahash::RandomState;
@@ -32,7 +32,7 @@ use winit::event::{
 };
 use winit::event_loop::{ActiveEventLoop, ControlFlow, DeviceEvents, EventLoop, EventLoopProxy};
 use winit::raw_window_handle::HasDisplayHandle;
-use winit::window::WindowId;
+use winit::window::{CursorIcon, WindowId};
 
 use alacritty_terminal::event::{Event as TerminalEvent, EventListener, Notify};
 use alacritty_terminal::event_loop::Notifier;
@@ -42,27 +42,31 @@ use alacritty_terminal::selection::{Selection, SelectionType};
 use alacritty_terminal::term::cell::Flags;
 use alacritty_terminal::term::search::{Match, RegexSearch};
 use alacritty_terminal::term::{self, ClipboardType, Term, TermMode};
+use alacritty_terminal::vi_mode::ViMotion;
 use alacritty_terminal::vte::ansi::NamedColor;
 
 #[cfg(unix)]
-use crate::cli::{IpcConfig, ParsedOptions};
+use crate::cli::{IpcConfig, IpcSaveScrollback, ParsedOptions};
 use crate::cli::{Options as CliOptions, WindowOptions};
 use crate::clipboard::Clipboard;
 use crate::config::ui_config::{HintAction, HintInternalAction};
 use crate::config::{self, UiConfig};
-#[cfg(not(windows))]
-use crate::daemon::foreground_process_path;
-use crate::daemon::spawn_daemon;
+use crate::daemon::{self, spawn_daemon};
+use crate::display::bidi::BidiLine;
 use crate::display::color::Rgb;
 use crate::display::hint::HintMatch;
+use crate::display::timestamp::LocalTime;
 use crate::display::window::Window;
 use crate::display::{Display, Preedit, SizeInfo};
 use crate::input::{self, ActionContext as _, FONT_SIZE_STEP};
 #[cfg(unix)]
 use crate::ipc::{self, SocketReply};
 use crate::logging::{LOG_TARGET_CONFIG, LOG_TARGET_WINIT};
-use crate::message_bar::{Message, MessageBuffer};
+use crate::message_bar::{Message, MessageBuffer, MessageType};
+use crate::pane::{PaneId, SplitDirection};
 use crate::scheduler::{Scheduler, TimerId, Topic};
+use crate::scrollback;
+use crate::tabs::TabSelection;
 use crate::window_context::WindowContext;
 
 /// Duration after the last user input until an unlimited search is performed.
@@ -80,6 +84,14 @@ const TOUCH_ZOOM_FACTOR: f32 = 0.01;
 /// Cooldown between invocations of the bell command.
 const BELL_CMD_COOLDOWN: Duration = Duration::from_millis(100);
 
+/// Cooldown between invocations of the notification command.
+///
+/// Notifications received during the cooldown are dropped.
+const NOTIFICATION_CMD_COOLDOWN: Duration = Duration::from_secs(1);
+
+/// Message bar target of the vi mark list.
+const MESSAGE_TARGET_VI_MARKS: &str = "vi_marks";
+
 /// The event processor.
 ///
 /// Stores some state from received events and dispatches actions when they are
@@ -288,6 +300,7 @@ impl ApplicationHandler<Event> for Processor {
         }
 
         // Handle events which don't mandate the WindowId.
+        let pane_id = event.pane_id;
         match (event.payload, event.window_id.as_ref()) {
             // Process IPC config update.
             #[cfg(unix)]
@@ -340,6 +353,42 @@ impl ApplicationHandler<Event> for Processor {
                     ipc::send_reply(&mut stream, SocketReply::GetConfig(config_json));
                 }
             },
+            // Process IPC working directory requests.
+            #[cfg(unix)]
+            (EventType::IpcGetWorkingDirectory(stream), window_id) => {
+                let path = window_id
+                    .and_then(|window_id| self.windows.get(window_id))
+                    .and_then(WindowContext::working_directory);
+
+                if let Ok(mut stream) = stream.try_clone() {
+                    ipc::send_reply(&mut stream, SocketReply::GetWorkingDirectory(path));
+                }
+            },
+            // Process IPC scrollback save requests.
+            #[cfg(unix)]
+            (EventType::IpcSaveScrollback(stream, request), window_id) => {
+                let mut stream = match stream.try_clone() {
+                    Ok(stream) => stream,
+                    Err(_) => return,
+                };
+
+                // The reply is sent once the file is written on a background thread.
+                match window_id.and_then(|window_id| self.windows.get(window_id)) {
+                    Some(window_context) => {
+                        let path = request.path.clone();
+                        window_context.save_scrollback(request.format, path, move |result| {
+                            let result = result.map_err(|err| {
+                                format!("could not save to {}: {err}", request.path.display())
+                            });
+                            ipc::send_reply(&mut stream, SocketReply::SaveScrollback(result));
+                        });
+                    },
+                    None => {
+                        let result = Err(String::from("no window found"));
+                        ipc::send_reply(&mut stream, SocketReply::SaveScrollback(result));
+                    },
+                }
+            },
             (EventType::ConfigReload(path), _) => {
                 // Clear config logs from message bar for all terminals.
                 for window_context in self.windows.values_mut() {
@@ -412,6 +461,15 @@ impl ApplicationHandler<Event> for Processor {
                 }
             },
             (EventType::Terminal(TerminalEvent::Exit), Some(window_id)) => {
+                // Only close the exited pane while the window has others left.
+                if let Some((window_context, pane_id)) =
+                    self.windows.get_mut(window_id).zip(pane_id)
+                {
+                    if window_context.close_pane(pane_id) {
+                        return;
+                    }
+                }
+
                 // Remove the closed terminal.
                 let window_context = match self.windows.entry(*window_id) {
                     // Don't exit when terminal exits if user asked to hold the window.
@@ -436,6 +494,25 @@ impl ApplicationHandler<Event> for Processor {
                     event_loop.exit();
                 }
             },
+            (EventType::SplitPane(direction), Some(window_id)) => {
+                if let Some(window_context) = self.windows.get_mut(window_id) {
+                    if let Err(err) = window_context.split_pane(direction) {
+                        error!("Could not split pane: {err}");
+                    }
+                }
+            },
+            (EventType::CreateTab, Some(window_id)) => {
+                if let Some(window_context) = self.windows.get_mut(window_id) {
+                    if let Err(err) = window_context.create_tab() {
+                        error!("Could not create tab: {err}");
+                    }
+                }
+            },
+            (EventType::SelectTab(selection), Some(window_id)) => {
+                if let Some(window_context) = self.windows.get_mut(window_id) {
+                    window_context.select_tab(selection);
+                }
+            },
             // NOTE: This event bypasses batching to minimize input latency.
             (EventType::Frame, Some(window_id)) => {
                 if let Some(window_context) = self.windows.get_mut(window_id) {
@@ -447,13 +524,14 @@ impl ApplicationHandler<Event> for Processor {
             },
             (payload, Some(window_id)) => {
                 if let Some(window_context) = self.windows.get_mut(window_id) {
+                    let event = Event { window_id: Some(*window_id), pane_id, payload };
                     window_context.handle_event(
                         #[cfg(target_os = "macos")]
                         event_loop,
                         &self.proxy,
                         &mut self.clipboard,
                         &mut self.scheduler,
-                        WinitEvent::UserEvent(Event::new(payload, *window_id)),
+                        WinitEvent::UserEvent(event),
                     );
                 }
             },
@@ -519,13 +597,31 @@ pub struct Event {
     /// Limit event to a specific window.
     window_id: Option<WindowId>,
 
+    /// Limit event to a specific pane inside the window.
+    pane_id: Option<PaneId>,
+
     /// Event payload.
     payload: EventType,
 }
 
 impl Event {
     pub fn new<I: Into<Option<WindowId>>>(payload: EventType, window_id: I) -> Self {
-        Self { window_id: window_id.into(), payload }
+        Self { window_id: window_id.into(), pane_id: None, payload }
+    }
+
+    /// Pane this event is limited to.
+    pub fn pane_id(&self) -> Option<PaneId> {
+        self.pane_id
+    }
+
+    /// Event payload.
+    pub fn payload(&self) -> &EventType {
+        &self.payload
+    }
+
+    /// Redirect the event to a different pane.
+    pub fn set_pane_id(&mut self, pane_id: PaneId) {
+        self.pane_id = Some(pane_id);
     }
 }
 
@@ -543,12 +639,22 @@ pub enum EventType {
     Message(Message),
     Scroll(Scroll),
     CreateWindow(WindowOptions),
+    SplitPane(SplitDirection),
+    CreateTab,
+    SelectTab(TabSelection),
     #[cfg(unix)]
     IpcConfig(IpcConfig),
     #[cfg(unix)]
     IpcGetConfig(Arc<UnixStream>),
+    #[cfg(unix)]
+    IpcGetWorkingDirectory(Arc<UnixStream>),
+    #[cfg(unix)]
+    IpcSaveScrollback(Arc<UnixStream>, IpcSaveScrollback),
     BlinkCursor,
     BlinkCursorTimeout,
+    BlinkText,
+    TextBlinkingChange,
+    TimestampRefresh,
     SearchNext,
     Frame,
 }
@@ -655,6 +761,15 @@ impl Default for InlineSearchState {
     }
 }
 
+/// Vi mark command waiting for the name of the mark.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum PendingViMark {
+    /// Set the mark on the vi cursor line.
+    Set,
+    /// Jump to the line of the mark.
+    Jump,
+}
+
 pub struct ActionContext<'a, N, T> {
     pub notifier: &'a mut N,
     pub terminal: &'a mut Term<T>,
@@ -663,16 +778,19 @@ pub struct ActionContext<'a, N, T> {
     pub touch: &'a mut TouchPurpose,
     pub modifiers: &'a mut Modifiers,
     pub display: &'a mut Display,
+    pub size_info: SizeInfo,
     pub message_buffer: &'a mut MessageBuffer,
     pub config: &'a UiConfig,
     pub cursor_blink_timed_out: &'a mut bool,
     pub prev_bell_cmd: &'a mut Option<Instant>,
+    pub prev_notification_cmd: &'a mut Option<Instant>,
     #[cfg(target_os = "macos")]
     pub event_loop: &'a ActiveEventLoop,
     pub event_proxy: &'a EventLoopProxy<Event>,
     pub scheduler: &'a mut Scheduler,
     pub search_state: &'a mut SearchState,
     pub inline_search_state: &'a mut InlineSearchState,
+    pub pending_vi_mark: &'a mut Option<PendingViMark>,
     pub dirty: &'a mut bool,
     pub occluded: &'a mut bool,
     pub preserve_title: bool,
@@ -680,6 +798,8 @@ pub struct ActionContext<'a, N, T> {
     pub master_fd: RawFd,
     #[cfg(not(windows))]
     pub shell_pid: u32,
+    pub reported_directory: Option<&'a Path>,
+    pub pointer_shape: Option<CursorIcon>,
 }
 
 impl<'a, N: Notify + 'a, T: EventListener> input::ActionContext<T> for ActionContext<'a, N, T> {
@@ -696,7 +816,7 @@ impl<'a, N: Notify + 'a, T: EventListener> input::ActionContext<T> for ActionCon
 
     #[inline]
     fn size_info(&self) -> SizeInfo {
-        self.display.size_info
+        self.size_info
     }
 
     fn scroll(&mut self, scroll: Scroll) {
@@ -720,8 +840,7 @@ impl<'a, N: Notify + 'a, T: EventListener> input::ActionContext<T> for ActionCon
         } else if self.mouse.left_button_state == ElementState::Pressed
             || self.mouse.right_button_state == ElementState::Pressed
         {
-            let display_offset = self.terminal.grid().display_offset();
-            let point = self.mouse.point(&self.size_info(), display_offset);
+            let point = self.mouse.point(&self.size_info(), self.terminal);
             self.update_selection(point, self.mouse.cell_side);
         }
 
@@ -737,7 +856,16 @@ impl<'a, N: Notify + 'a, T: EventListener> input::ActionContext<T> for ActionCon
 
     // Copy text selection.
     fn copy_selection(&mut self, ty: ClipboardType) {
-        let text = match self.terminal.selection_to_string().filter(|s| !s.is_empty()) {
+        let text = if self.config.selection.copy_timestamps {
+            self.terminal.selection_to_string_with_timestamps(|time| match LocalTime::new(time) {
+                Some(time) => format!("[{time}] "),
+                None => String::new(),
+            })
+        } else {
+            self.terminal.selection_to_string()
+        };
+
+        let text = match text.filter(|s| !s.is_empty()) {
             Some(text) => text,
             None => return,
         };
@@ -809,6 +937,11 @@ impl<'a, N: Notify + 'a, T: EventListener> input::ActionContext<T> for ActionCon
             && !self.terminal.mode().contains(TermMode::VI)
     }
 
+    #[inline]
+    fn pointer_shape(&self) -> Option<CursorIcon> {
+        self.pointer_shape
+    }
+
     #[inline]
     fn mouse_mut(&mut self) -> &mut Mouse {
         self.mouse
@@ -849,6 +982,20 @@ impl<'a, N: Notify + 'a, T: EventListener> input::ActionContext<T> for ActionCon
         self.terminal
     }
 
+    fn save_scrollback(&mut self) {
+        let format = self.config.scrolling.save_format;
+        let secs = SystemTime::now().duration_since(UNIX_EPOCH).unwrap_or_default().as_secs();
+        let directory = self.working_directory().or_else(home::home_dir).unwrap_or_default();
+        let path = directory.join(format!("alacritty-scrollback-{secs}.{}", format.extension()));
+
+        scrollback::save_in_background(self.terminal, format, path.clone(), move |result| {
+            match result {
+                Ok(()) => info!("Saved scrollback to {}", path.display()),
+                Err(err) => error!("Unable to save scrollback to {}: {err}", path.display()),
+            }
+        });
+    }
+
     fn spawn_new_instance(&mut self) {
         let mut env_args = env::args();
         let alacritty = env_args.next().unwrap();
@@ -878,33 +1025,34 @@ impl<'a, N: Notify + 'a, T: EventListener> input::ActionContext<T> for ActionCon
 
     fn create_new_window(&mut self, #[cfg(target_os = "macos")] tabbing_id: Option<String>) {
         let mut options = WindowOptions::default();
-        options.terminal_options.working_directory =
-            foreground_process_path(self.master_fd, self.shell_pid).ok();
+        options.terminal_options.working_directory = self.working_directory();
 
         #[cfg(target_os = "macos")]
         {
             options.window_tabbing_id = tabbing_id;
         }
 
-        #[cfg(target_os = "linux")]
-        {
-            // Use the same tabbing ID as the current window to create a new tab
-            options.window_tabbing_id = Some(self.display.window.tabbing_id());
-        }
-
         let _ = self.event_proxy.send_event(Event::new(EventType::CreateWindow(options), None));
     }
 
     fn split_terminal_horizontal(&mut self) {
-        // For now, we'll create a new window as a simple implementation
-        // A full implementation would require UI changes to support splits
-        self.create_new_window(#[cfg(target_os = "macos")] None);
+        let payload = EventType::SplitPane(SplitDirection::Horizontal);
+        let _ = self.event_proxy.send_event(Event::new(payload, self.display.window.id()));
     }
 
     fn split_terminal_vertical(&mut self) {
-        // For now, we'll create a new window as a simple implementation
-        // A full implementation would require UI changes to support splits
-        self.create_new_window(#[cfg(target_os = "macos")] None);
+        let payload = EventType::SplitPane(SplitDirection::Vertical);
+        let _ = self.event_proxy.send_event(Event::new(payload, self.display.window.id()));
+    }
+
+    fn create_new_tab(&mut self) {
+        let _ =
+            self.event_proxy.send_event(Event::new(EventType::CreateTab, self.display.window.id()));
+    }
+
+    fn select_tab(&mut self, selection: TabSelection) {
+        let payload = EventType::SelectTab(selection);
+        let _ = self.event_proxy.send_event(Event::new(payload, self.display.window.id()));
     }
 
     fn spawn_daemon<I, S>(&self, program: &str, args: I)
@@ -912,10 +1060,7 @@ impl<'a, N: Notify + 'a, T: EventListener> input::ActionContext<T> for ActionCon
         I: IntoIterator<Item = S> + Debug + Copy,
         S: AsRef<OsStr>,
     {
-        #[cfg(not(windows))]
-        let result = spawn_daemon(program, args, self.master_fd, self.shell_pid);
-        #[cfg(windows)]
-        let result = spawn_daemon(program, args);
+        let result = spawn_daemon(program, args, self.working_directory());
 
         match result {
             Ok(_) => debug!("Launched {program} with args {args:?}"),
@@ -1289,8 +1434,7 @@ impl<'a, N: Notify + 'a, T: EventListener> input::ActionContext<T> for ActionCon
         };
 
         // Load mouse point, treating message bar and padding as the closest cell.
-        let display_offset = self.terminal().grid().display_offset();
-        let point = self.mouse().point(&self.size_info(), display_offset);
+        let point = self.mouse().point(&self.size_info(), self.terminal());
 
         let cell_side = self.mouse().cell_side;
 
@@ -1479,6 +1623,70 @@ impl<'a, N: Notify + 'a, T: EventListener> input::ActionContext<T> for ActionCon
         self.inline_search_next();
     }
 
+    /// Check if a vi mark command is waiting for the mark's name.
+    #[inline]
+    fn vi_mark_pending(&self) -> bool {
+        self.pending_vi_mark.is_some()
+    }
+
+    /// Start a vi mark command, capturing the next character as the mark's name.
+    #[inline]
+    fn start_vi_mark(&mut self, mark: PendingViMark) {
+        *self.pending_vi_mark = Some(mark);
+    }
+
+    /// Process the name of a pending vi mark command.
+    fn vi_mark_input(&mut self, text: &str) {
+        // Ignore input with empty text, like modifier keys.
+        let name = match text.chars().next() {
+            Some(name) => name,
+            None => return,
+        };
+
+        match self.pending_vi_mark.take() {
+            Some(PendingViMark::Set) => {
+                let line = self.terminal.vi_mode_cursor.point.line;
+                self.terminal.set_vi_mark(name, line);
+            },
+            Some(PendingViMark::Jump) => {
+                if let Some(line) = self.terminal.vi_mark_line(name) {
+                    self.terminal.vi_goto_point(Point::new(line, Column(0)));
+                    self.terminal.vi_motion(ViMotion::FirstOccupied);
+                }
+            },
+            None => return,
+        }
+
+        self.mark_dirty();
+    }
+
+    /// Show all vi marks in the message bar.
+    fn list_vi_marks(&mut self) {
+        let marks = self.terminal.vi_marks();
+        let text = if marks.is_empty() {
+            String::from("No marks set")
+        } else {
+            // Show line numbers matching the vi mode line indicator.
+            let bottommost_line = self.terminal.bottommost_line();
+            let last_column = self.terminal.last_column();
+            let lines: Vec<_> = marks
+                .into_iter()
+                .map(|(name, line)| {
+                    let start = Point::new(line, Column(0));
+                    let text = self.terminal.bounds_to_string(start, Point::new(line, last_column));
+                    format!("'{name} {:>6}  {}", bottommost_line.0 - line.0, text.trim())
+                })
+                .collect();
+            lines.join("\n")
+        };
+
+        let mut message = Message::new(text, MessageType::Info);
+        message.set_target(MESSAGE_TARGET_VI_MARKS.into());
+        self.message_buffer.remove_target(MESSAGE_TARGET_VI_MARKS);
+        self.message_buffer.push(message);
+        self.display.pending_update.dirty = true;
+    }
+
     fn message(&self) -> Option<&Message> {
         self.message_buffer.message()
     }
@@ -1502,6 +1710,17 @@ impl<'a, N: Notify + 'a, T: EventListener> input::ActionContext<T> for ActionCon
 }
 
 impl<'a, N: Notify + 'a, T: EventListener> ActionContext<'a, N, T> {
+    /// Working directory for processes started from this terminal.
+    fn working_directory(&self) -> Option<PathBuf> {
+        daemon::working_directory(
+            self.reported_directory,
+            #[cfg(not(windows))]
+            self.master_fd,
+            #[cfg(not(windows))]
+            self.shell_pid,
+        )
+    }
+
     fn update_search(&mut self) {
         let regex = match self.search_state.regex() {
             Some(regex) => regex,
@@ -1651,6 +1870,26 @@ impl<'a, N: Notify + 'a, T: EventListener> ActionContext<'a, N, T> {
         }
     }
 
+    /// Update the blinking text state.
+    fn update_text_blinking(&mut self) {
+        let window_id = self.display.window.id();
+        let timer_id = TimerId::new(Topic::BlinkText, window_id);
+        self.scheduler.unschedule(timer_id);
+
+        match self.config.terminal.text_blink_interval() {
+            Some(interval) if self.terminal.is_focused && self.display.text_blinking => {
+                let event = Event::new(EventType::BlinkText, window_id);
+                self.scheduler.schedule(event, interval, true, timer_id);
+            },
+            // Keep blinking text visible while it isn't blinking, or there is none.
+            _ => {
+                if mem::take(&mut self.display.text_blink_hidden) {
+                    *self.dirty |= self.display.damage_blinking_text(self.terminal);
+                }
+            },
+        }
+    }
+
     fn schedule_blinking(&mut self) {
         let window_id = self.display.window.id();
         let timer_id = TimerId::new(Topic::BlinkCursor, window_id);
@@ -1784,6 +2023,7 @@ pub struct Mouse {
     pub block_hint_launcher: bool,
     pub hint_highlight_dirty: bool,
     pub inside_text_area: bool,
+    pub bidi: bool,
     pub x: usize,
     pub y: usize,
 }
@@ -1802,6 +2042,7 @@ impl Default for Mouse {
             block_hint_launcher: Default::default(),
             inside_text_area: Default::default(),
             accumulated_scroll: Default::default(),
+            bidi: Default::default(),
             x: Default::default(),
             y: Default::default(),
         }
@@ -1814,14 +2055,35 @@ impl Mouse {
     /// If the coordinates are outside of the terminal grid, like positions inside the padding, the
     /// coordinates will be clamped to the closest grid coordinates.
     #[inline]
-    pub fn point(&self, size: &SizeInfo, display_offset: usize) -> Point {
+    pub fn point<T>(&self, size: &SizeInfo, terminal: &Term<T>) -> Point {
         let col = self.x.saturating_sub(size.padding_x() as usize) / (size.cell_width() as usize);
         let col = min(Column(col), size.last_column());
 
         let line = self.y.saturating_sub(size.padding_y() as usize) / (size.cell_height() as usize);
         let line = min(line, size.bottommost_line().0 as usize);
 
-        term::viewport_to_point(display_offset, Point::new(line, col))
+        let display_offset = terminal.grid().display_offset();
+        let mut point = term::viewport_to_point(display_offset, Point::new(line, col));
+
+        // Cells of double-width lines are displayed at twice their width.
+        let row = &terminal.grid()[point.line];
+        if row.line_size().is_double_width() {
+            point.column = Column(point.column.0 / 2);
+        } else if let Some(bidi) = self.bidi_line(terminal, point.line) {
+            point.column = bidi.logical_column(point.column);
+        }
+
+        point
+    }
+
+    /// Visual order of a line, if it is reordered for display.
+    pub fn bidi_line<T>(&self, terminal: &Term<T>, line: Line) -> Option<BidiLine> {
+        let row = &terminal.grid()[line];
+        if !self.bidi || row.line_size().is_double_width() {
+            return None;
+        }
+
+        BidiLine::new(&row[..])
     }
 }
 
@@ -1866,6 +2128,7 @@ impl input::Processor<EventProxy, ActionContext<'_, Notifier, EventProxy>> {
                     self.ctx.display.cursor_hidden = false;
                     *self.ctx.dirty = true;
                 },
+                EventType::TextBlinkingChange => self.ctx.update_text_blinking(),
                 // Add message only if it's not already queued.
                 EventType::Message(message) if !self.ctx.message_buffer.is_queued(&message) => {
                     self.ctx.message_buffer.push(message);
@@ -1906,6 +2169,34 @@ impl input::Processor<EventProxy, ActionContext<'_, Notifier, EventProxy>> {
                             }
                         }
                     },
+                    TerminalEvent::Notification { title, body } => {
+                        let cooldown = self
+                            .ctx
+                            .prev_notification_cmd
+                            .is_some_and(|i| i.elapsed() < NOTIFICATION_CMD_COOLDOWN);
+                        if cooldown {
+                            return;
+                        }
+
+                        if let Some(command) = &self.ctx.config.notification.command {
+                            // Fall back to the window title for notifications without one.
+                            let title = if title.is_empty() {
+                                self.ctx.display.window.title()
+                            } else {
+                                &title
+                            };
+
+                            let args: Vec<&str> = command
+                                .args()
+                                .iter()
+                                .map(String::as_str)
+                                .chain([title, body.as_str()])
+                                .collect();
+                            self.ctx.spawn_daemon(command.program(), &args);
+
+                            *self.ctx.prev_notification_cmd = Some(Instant::now());
+                        }
+                    },
                     TerminalEvent::ClipboardStore(clipboard_type, content) => {
                         if self.ctx.terminal.is_focused {
                             self.ctx.clipboard.store(clipboard_type, content);
@@ -1931,15 +2222,28 @@ impl input::Processor<EventProxy, ActionContext<'_, Notifier, EventProxy>> {
                         self.ctx.write_to_pty(text.into_bytes());
                     },
                     TerminalEvent::PtyWrite(text) => self.ctx.write_to_pty(text.into_bytes()),
-                    TerminalEvent::MouseCursorDirty => self.reset_mouse_cursor(),
+                    TerminalEvent::MouseCursorDirty | TerminalEvent::PointerShape(_) => {
+                        self.reset_mouse_cursor()
+                    },
                     TerminalEvent::CursorBlinkingChange => self.ctx.update_cursor_blinking(),
-                    TerminalEvent::Exit | TerminalEvent::ChildExit(_) | TerminalEvent::Wakeup => (),
+                    TerminalEvent::Exit
+                    | TerminalEvent::ChildExit(_)
+                    | TerminalEvent::WorkingDirectory(_)
+                    | TerminalEvent::Wakeup => (),
                 },
                 #[cfg(unix)]
-                EventType::IpcConfig(_) | EventType::IpcGetConfig(..) => (),
+                EventType::IpcConfig(_)
+                | EventType::IpcGetConfig(..)
+                | EventType::IpcGetWorkingDirectory(..)
+                | EventType::IpcSaveScrollback(..) => (),
                 EventType::Message(_)
                 | EventType::ConfigReload(_)
                 | EventType::CreateWindow(_)
+                | EventType::SplitPane(_)
+                | EventType::CreateTab
+                | EventType::SelectTab(_)
+                | EventType::BlinkText
+                | EventType::TimestampRefresh
                 | EventType::Frame => (),
             },
             WinitEvent::WindowEvent { event, .. } => {
@@ -2003,6 +2307,7 @@ impl input::Processor<EventProxy, ActionContext<'_, Notifier, EventProxy>> {
                         }
 
                         self.ctx.update_cursor_blinking();
+                        self.ctx.update_text_blinking();
                         self.on_focus_change(is_focused);
                     },
                     WindowEvent::Occluded(occluded) => {
@@ -2077,21 +2382,24 @@ impl input::Processor<EventProxy, ActionContext<'_, Notifier, EventProxy>> {
 pub struct EventProxy {
     proxy: EventLoopProxy<Event>,
     window_id: WindowId,
+    pane_id: PaneId,
 }
 
 impl EventProxy {
-    pub fn new(proxy: EventLoopProxy<Event>, window_id: WindowId) -> Self {
-        Self { proxy, window_id }
+    pub fn new(proxy: EventLoopProxy<Event>, window_id: WindowId, pane_id: PaneId) -> Self {
+        Self { proxy, window_id, pane_id }
     }
 
     /// Send an event to the event loop.
     pub fn send_event(&self, event: EventType) {
-        let _ = self.proxy.send_event(Event::new(event, self.window_id));
+        let mut event = Event::new(event, self.window_id);
+        event.set_pane_id(self.pane_id);
+        let _ = self.proxy.send_event(event);
     }
 }
 
 impl EventListener for EventProxy {
     fn send_event(&self, event: TerminalEvent) {
-        let _ = self.proxy.send_event(Event::new(event.into(), self.window_id));
+        EventProxy::send_event(self, event.into());
     }
 }
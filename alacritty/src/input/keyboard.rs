@@ -52,6 +52,12 @@ impl<T: EventListener, A: ActionContext<T>> Processor<T, A> {
             return;
         }
 
+        // First key after a vi mark command is the mark's name.
+        if self.ctx.vi_mark_pending() {
+            self.ctx.vi_mark_input(text);
+            return;
+        }
+
         // Reset search delay when the user is still typing.
         self.reset_search_delay();
 
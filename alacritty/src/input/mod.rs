@@ -28,7 +28,7 @@ use winit::window::CursorIcon;
 
 use alacritty_terminal::event::EventListener;
 use alacritty_terminal::grid::{Dimensions, Scroll};
-use alacritty_terminal::index::{Boundary, Column, Direction, Point, Side};
+use alacritty_terminal::index::{Boundary, Column, Direction, Line, Point, Side};
 use alacritty_terminal::selection::SelectionType;
 use alacritty_terminal::term::search::Match;
 use alacritty_terminal::term::{ClipboardType, Term, TermMode};
@@ -43,10 +43,11 @@ use crate::display::hint::HintMatch;
 use crate::display::window::Window;
 use crate::display::{Display, SizeInfo};
 use crate::event::{
-    ClickState, Event, EventType, InlineSearchState, Mouse, TouchPurpose, TouchZoom,
+    ClickState, Event, EventType, InlineSearchState, Mouse, PendingViMark, TouchPurpose, TouchZoom,
 };
 use crate::message_bar::{self, Message};
 use crate::scheduler::{Scheduler, TimerId, Topic};
+use crate::tabs::TabSelection;
 
 pub mod keyboard;
 
@@ -68,6 +69,28 @@ const MAX_TAP_DISTANCE: f64 = 20.;
 /// Threshold used for double_click/triple_click.
 const CLICK_THRESHOLD: Duration = Duration::from_millis(400);
 
+/// Convert an OSC 22 pointer shape request to a cursor icon.
+///
+/// The request is a comma-separated list of names, the first one which is recognized is used.
+/// Besides the CSS cursor names, common X11 cursor font names are supported.
+pub fn pointer_shape(names: &str) -> Option<CursorIcon> {
+    names.split(',').map(str::trim).find_map(|name| {
+        name.parse().ok().or(match name {
+            "left_ptr" | "arrow" | "top_left_arrow" => Some(CursorIcon::Default),
+            "xterm" | "ibeam" => Some(CursorIcon::Text),
+            "hand" | "hand1" | "hand2" => Some(CursorIcon::Pointer),
+            "left_ptr_watch" => Some(CursorIcon::Progress),
+            "watch" => Some(CursorIcon::Wait),
+            "question_arrow" => Some(CursorIcon::Help),
+            "cross" | "tcross" => Some(CursorIcon::Crosshair),
+            "fleur" => Some(CursorIcon::Move),
+            "sb_h_double_arrow" => Some(CursorIcon::EwResize),
+            "sb_v_double_arrow" => Some(CursorIcon::NsResize),
+            _ => None,
+        })
+    })
+}
+
 /// Processes input from winit.
 ///
 /// An escape sequence may be emitted in case specific keys or key combinations
@@ -97,12 +120,13 @@ pub trait ActionContext<T: EventListener> {
     fn terminal(&self) -> &Term<T>;
     fn terminal_mut(&mut self) -> &mut Term<T>;
     fn spawn_new_instance(&mut self) {}
+    fn save_scrollback(&mut self) {}
     fn create_new_window(&mut self, #[cfg(target_os = "macos")] _tabbing_id: Option<String>) {
         #[cfg(target_os = "macos")]
         {
             // On macOS, we would use the tabbing_id parameter
         }
-        
+
         // Implementation will be provided by the concrete type
     }
 
@@ -113,6 +137,9 @@ pub trait ActionContext<T: EventListener> {
     fn split_terminal_vertical(&mut self) {
         // Implementation will be provided by the concrete type
     }
+
+    fn create_new_tab(&mut self) {}
+    fn select_tab(&mut self, _selection: TabSelection) {}
     fn change_font_size(&mut self, _delta: f32) {}
     fn reset_font_size(&mut self) {}
     fn pop_message(&mut self) {}
@@ -121,6 +148,9 @@ pub trait ActionContext<T: EventListener> {
     #[cfg(target_os = "macos")]
     fn event_loop(&self) -> &ActiveEventLoop;
     fn mouse_mode(&self) -> bool;
+    fn pointer_shape(&self) -> Option<CursorIcon> {
+        None
+    }
     fn clipboard_mut(&mut self) -> &mut Clipboard;
     fn scheduler_mut(&mut self) -> &mut Scheduler;
     fn start_search(&mut self, _direction: Direction) {}
@@ -142,6 +172,12 @@ pub trait ActionContext<T: EventListener> {
     fn inline_search_next(&mut self) {}
     fn inline_search_input(&mut self, _text: &str) {}
     fn inline_search_previous(&mut self) {}
+    fn vi_mark_pending(&self) -> bool {
+        false
+    }
+    fn start_vi_mark(&mut self, _mark: PendingViMark) {}
+    fn vi_mark_input(&mut self, _text: &str) {}
+    fn list_vi_marks(&mut self) {}
     fn hint_input(&mut self, _character: char) {}
     fn trigger_hint(&mut self, _hint: &HintMatch) {}
     fn expand_selection(&mut self) {}
@@ -292,6 +328,9 @@ impl<T: EventListener> Execute<T> for Action {
             },
             Action::Vi(ViAction::InlineSearchNext) => ctx.inline_search_next(),
             Action::Vi(ViAction::InlineSearchPrevious) => ctx.inline_search_previous(),
+            Action::Vi(ViAction::SetMark) => ctx.start_vi_mark(PendingViMark::Set),
+            Action::Vi(ViAction::JumpToMark) => ctx.start_vi_mark(PendingViMark::Jump),
+            Action::Vi(ViAction::ListMarks) => ctx.list_vi_marks(),
             Action::Vi(ViAction::SemanticSearchForward | ViAction::SemanticSearchBackward) => {
                 let seed_text = match ctx.terminal().selection_to_string() {
                     Some(selection) if !selection.is_empty() => selection,
@@ -411,51 +450,67 @@ impl<T: EventListener> Execute<T> for Action {
                 term.vi_motion(ViMotion::FirstOccupied);
                 ctx.mark_dirty();
             },
+            Action::ScrollToPreviousPrompt | Action::ScrollToNextPrompt => {
+                let direction = match self {
+                    Action::ScrollToPreviousPrompt => Direction::Left,
+                    _ => Direction::Right,
+                };
+
+                // Search relative to the vi cursor or the top of the viewport.
+                let term = ctx.terminal();
+                let display_offset = term.grid().display_offset() as i32;
+                let origin = if term.mode().contains(TermMode::VI) {
+                    term.vi_mode_cursor.point.line
+                } else {
+                    Line(-display_offset)
+                };
+
+                let line = match term.prompt_line(origin, direction) {
+                    Some(line) => line,
+                    None => return,
+                };
+
+                // Move the prompt to the top of the viewport.
+                ctx.scroll(Scroll::Delta(-line.0 - display_offset));
+
+                // Move vi mode cursor.
+                ctx.terminal_mut().vi_mode_cursor.point = Point::new(line, Column(0));
+                ctx.mark_dirty();
+            },
+            Action::SelectLastCommandOutput => {
+                if let Some((start, end)) = ctx.terminal().last_command_output() {
+                    ctx.start_selection(SelectionType::Lines, start, Side::Left);
+                    ctx.update_selection(end, Side::Right);
+                    ctx.copy_selection(ClipboardType::Selection);
+                }
+            },
+            Action::CopyLastCommandOutput => {
+                let term = ctx.terminal();
+                if let Some((start, end)) = term.last_command_output() {
+                    let text = term.bounds_to_string(start, end);
+                    ctx.clipboard_mut().store(ClipboardType::Clipboard, text);
+                }
+            },
             Action::ClearHistory => ctx.terminal_mut().clear_screen(ClearMode::Saved),
+            Action::SaveScrollback => ctx.save_scrollback(),
             Action::ClearLogNotice => ctx.pop_message(),
             #[cfg(not(target_os = "macos"))]
             Action::CreateNewWindow => ctx.create_new_window(),
             Action::SpawnNewInstance => ctx.spawn_new_instance(),
             #[cfg(target_os = "macos")]
             Action::CreateNewWindow => ctx.create_new_window(None),
+            #[cfg(target_os = "macos")]
             Action::CreateNewTab => {
-                #[cfg(target_os = "macos")]
-                {
-                    // Tabs on macOS are not possible without decorations.
-                    if ctx.config().window.decorations != Decorations::None {
-                        let tabbing_id = Some(ctx.window().tabbing_id());
-                        ctx.create_new_window(tabbing_id);
-                    } else {
-                        ctx.create_new_window();
-                    }
-                }
-                
-                #[cfg(target_os = "linux")]
-                {
-                    // On Linux, first check if we have a supported desktop environment with tools available
-                    if let Some(desktop) = crate::linux_tabs::detect_desktop_environment() {
-                        if desktop.is_available() {
-                            match desktop.create_tab() {
-                                Ok(()) => {
-                                    // Successfully created a native tab
-                                    return;
-                                }
-                                Err(err) => {
-                                    // If tab creation failed, log the error and fall back to creating a new window
-                                    log::warn!("Failed to create native tab: {}, falling back to new window", err);
-                                }
-                            }
-                        } else {
-                            log::debug!("Desktop environment tools not available, using internal tabbing");
-                        }
-                    }
-                    // Fallback to creating a new window (current behavior)
-                    ctx.create_new_window();
+                // Tabs on macOS are not possible without decorations.
+                if ctx.config().window.decorations != Decorations::None {
+                    let tabbing_id = Some(ctx.window().tabbing_id());
+                    ctx.create_new_window(tabbing_id);
+                } else {
+                    ctx.create_new_window(None);
                 }
-                
-                #[cfg(not(any(target_os = "macos", target_os = "linux")))]
-                ctx.create_new_window();
             },
+            #[cfg(not(target_os = "macos"))]
+            Action::CreateNewTab => ctx.create_new_tab(),
             Action::SplitTerminalHorizontal => ctx.split_terminal_horizontal(),
             Action::SplitTerminalVertical => ctx.split_terminal_vertical(),
             #[cfg(target_os = "macos")]
@@ -482,48 +537,30 @@ impl<T: EventListener> Execute<T> for Action {
             Action::SelectTab9 => ctx.window().select_tab_at_index(8),
             #[cfg(target_os = "macos")]
             Action::SelectLastTab => ctx.window().select_last_tab(),
-            #[cfg(target_os = "linux")]
-            Action::SelectLastTab => {
-                // On Linux, try to use native tab navigation if available
-                if let Some(desktop) = crate::linux_tabs::detect_desktop_environment() {
-                    if desktop.is_available() {
-                        match desktop.select_next_tab() {
-                            Ok(()) => return, // Successfully used native tab navigation
-                            Err(err) => log::warn!("Failed to select last tab: {}", err),
-                        }
-                    }
-                }
-                // Fallback to window's tab navigation methods
-                ctx.window().select_last_tab();
-            },
-            #[cfg(target_os = "linux")]
-            Action::SelectNextTab => {
-                // On Linux, try to use native tab navigation if available
-                if let Some(desktop) = crate::linux_tabs::detect_desktop_environment() {
-                    if desktop.is_available() {
-                        match desktop.select_next_tab() {
-                            Ok(()) => return, // Successfully used native tab navigation
-                            Err(err) => log::warn!("Failed to select next tab: {}", err),
-                        }
-                    }
-                }
-                // Fallback to window's tab navigation methods
-                ctx.window().select_next_tab();
-            },
-            #[cfg(target_os = "linux")]
-            Action::SelectPreviousTab => {
-                // On Linux, try to use native tab navigation if available
-                if let Some(desktop) = crate::linux_tabs::detect_desktop_environment() {
-                    if desktop.is_available() {
-                        match desktop.select_previous_tab() {
-                            Ok(()) => return, // Successfully used native tab navigation
-                            Err(err) => log::warn!("Failed to select previous tab: {}", err),
-                        }
-                    }
-                }
-                // Fallback to window's tab navigation methods
-                ctx.window().select_previous_tab();
-            },
+            #[cfg(not(target_os = "macos"))]
+            Action::SelectNextTab => ctx.select_tab(TabSelection::Next),
+            #[cfg(not(target_os = "macos"))]
+            Action::SelectPreviousTab => ctx.select_tab(TabSelection::Previous),
+            #[cfg(not(target_os = "macos"))]
+            Action::SelectTab1 => ctx.select_tab(TabSelection::Index(0)),
+            #[cfg(not(target_os = "macos"))]
+            Action::SelectTab2 => ctx.select_tab(TabSelection::Index(1)),
+            #[cfg(not(target_os = "macos"))]
+            Action::SelectTab3 => ctx.select_tab(TabSelection::Index(2)),
+            #[cfg(not(target_os = "macos"))]
+            Action::SelectTab4 => ctx.select_tab(TabSelection::Index(3)),
+            #[cfg(not(target_os = "macos"))]
+            Action::SelectTab5 => ctx.select_tab(TabSelection::Index(4)),
+            #[cfg(not(target_os = "macos"))]
+            Action::SelectTab6 => ctx.select_tab(TabSelection::Index(5)),
+            #[cfg(not(target_os = "macos"))]
+            Action::SelectTab7 => ctx.select_tab(TabSelection::Index(6)),
+            #[cfg(not(target_os = "macos"))]
+            Action::SelectTab8 => ctx.select_tab(TabSelection::Index(7)),
+            #[cfg(not(target_os = "macos"))]
+            Action::SelectTab9 => ctx.select_tab(TabSelection::Index(8)),
+            #[cfg(not(target_os = "macos"))]
+            Action::SelectLastTab => ctx.select_tab(TabSelection::Last),
             _ => (),
         }
     }
@@ -546,8 +583,8 @@ impl<T: EventListener, A: ActionContext<T>> Processor<T, A> {
             self.update_selection_scrolling(y);
         }
 
-        let display_offset = self.ctx.terminal().grid().display_offset();
-        let old_point = self.ctx.mouse().point(&size_info, display_offset);
+        let old_point = self.ctx.mouse().point(&size_info, self.ctx.terminal());
+        let old_position = (self.ctx.mouse().x, self.ctx.mouse().y);
 
         let x = x.clamp(0, size_info.width() as i32 - 1) as usize;
         let y = y.clamp(0, size_info.height() as i32 - 1) as usize;
@@ -555,13 +592,23 @@ impl<T: EventListener, A: ActionContext<T>> Processor<T, A> {
         self.ctx.mouse_mut().y = y;
 
         let inside_text_area = size_info.contains_point(x, y);
-        let cell_side = self.cell_side(x);
+        let mut cell_side = self.cell_side(x);
 
-        let point = self.ctx.mouse().point(&size_info, display_offset);
+        let point = self.ctx.mouse().point(&size_info, self.ctx.terminal());
+
+        // Cell sides are mirrored for right-to-left text.
+        let bidi = self.ctx.mouse().bidi_line(self.ctx.terminal(), point.line);
+        if bidi.is_some_and(|bidi| bidi.is_rtl(point.column)) {
+            cell_side = cell_side.opposite();
+        }
         let cell_changed = old_point != point;
 
-        // If the mouse hasn't changed cells, do nothing.
-        if !cell_changed
+        // Pixel mouse reporting needs every movement, not just cell changes.
+        let pixel_mouse = self.ctx.terminal().mode().contains(TermMode::SGR_PIXEL_MOUSE);
+        let moved = cell_changed || (pixel_mouse && old_position != (x, y));
+
+        // If the mouse hasn't moved, do nothing.
+        if !moved
             && self.ctx.mouse().cell_side == cell_side
             && self.ctx.mouse().inside_text_area == inside_text_area
         {
@@ -585,7 +632,7 @@ impl<T: EventListener, A: ActionContext<T>> Processor<T, A> {
             && (self.ctx.modifiers().state().shift_key() || !self.ctx.mouse_mode())
         {
             self.ctx.update_selection(point, cell_side);
-        } else if cell_changed
+        } else if moved
             && self.ctx.terminal().mode().intersects(TermMode::MOUSE_MOTION | TermMode::MOUSE_DRAG)
         {
             if lmb_pressed {
@@ -623,37 +670,63 @@ impl<T: EventListener, A: ActionContext<T>> Processor<T, A> {
     }
 
     fn mouse_report(&mut self, button: u8, state: ElementState) {
-        let display_offset = self.ctx.terminal().grid().display_offset();
-        let point = self.ctx.mouse().point(&self.ctx.size_info(), display_offset);
+        let point = self.ctx.mouse().point(&self.ctx.size_info(), self.ctx.terminal());
 
         // Assure the mouse point is not in the scrollback.
         if point.line < 0 {
             return;
         }
 
+        // X10 compatibility mode only reports button presses, without modifiers.
+        let mode = *self.ctx.terminal().mode();
+        let x10 = mode.contains(TermMode::X10_MOUSE);
+        if x10 && state == ElementState::Released {
+            return;
+        }
+
         // Calculate modifiers value.
         let mut mods = 0;
         let modifiers = self.ctx.modifiers().state();
-        if modifiers.shift_key() {
+        if modifiers.shift_key() && !x10 {
             mods += 4;
         }
-        if modifiers.alt_key() {
+        if modifiers.alt_key() && !x10 {
             mods += 8;
         }
-        if modifiers.control_key() {
+        if modifiers.control_key() && !x10 {
             mods += 16;
         }
 
         // Report mouse events.
-        if self.ctx.terminal().mode().contains(TermMode::SGR_MOUSE) {
-            self.sgr_mouse_report(point, button + mods, state);
-        } else if let ElementState::Released = state {
-            self.normal_mouse_report(point, 3 + mods);
+        if mode.contains(TermMode::SGR_PIXEL_MOUSE) {
+            let (x, y) = self.pixel_position();
+            self.sgr_mouse_report(x, y, button + mods, state);
+        } else if mode.contains(TermMode::SGR_MOUSE) {
+            let (column, line) = (point.column.0 + 1, point.line.0 as usize + 1);
+            self.sgr_mouse_report(column, line, button + mods, state);
         } else {
-            self.normal_mouse_report(point, button + mods);
+            let button = if state == ElementState::Released { 3 + mods } else { button + mods };
+            if mode.contains(TermMode::URXVT_MOUSE) {
+                self.urxvt_mouse_report(point, button);
+            } else {
+                self.normal_mouse_report(point, button);
+            }
         }
     }
 
+    /// One-based mouse position in pixels, relative to the text area.
+    fn pixel_position(&self) -> (usize, usize) {
+        let size_info = self.ctx.size_info();
+        let mouse = self.ctx.mouse();
+
+        let max_x = (size_info.columns() as f32 * size_info.cell_width()) as usize - 1;
+        let max_y = (size_info.screen_lines() as f32 * size_info.cell_height()) as usize - 1;
+        let x = mouse.x.saturating_sub(size_info.padding_x() as usize).min(max_x);
+        let y = mouse.y.saturating_sub(size_info.padding_y() as usize).min(max_y);
+
+        (x + 1, y + 1)
+    }
+
     fn normal_mouse_report(&mut self, point: Point, button: u8) {
         let Point { line, column } = point;
         let utf8 = self.ctx.terminal().mode().contains(TermMode::UTF8_MOUSE);
@@ -688,13 +761,20 @@ impl<T: EventListener, A: ActionContext<T>> Processor<T, A> {
         self.ctx.write_to_pty(msg);
     }
 
-    fn sgr_mouse_report(&mut self, point: Point, button: u8, state: ElementState) {
+    fn urxvt_mouse_report(&mut self, point: Point, button: u8) {
+        let Point { line, column } = point;
+        let msg = format!("\x1b[{};{};{}M", 32 + button, column + 1, line + 1);
+        self.ctx.write_to_pty(msg.into_bytes());
+    }
+
+    /// Report a mouse event at one-based coordinates, which are either cells or pixels.
+    fn sgr_mouse_report(&mut self, x: usize, y: usize, button: u8, state: ElementState) {
         let c = match state {
             ElementState::Pressed => 'M',
             ElementState::Released => 'm',
         };
 
-        let msg = format!("\x1b[<{};{};{}{}", button, point.column + 1, point.line + 1, c);
+        let msg = format!("\x1b[<{button};{x};{y}{c}");
         self.ctx.write_to_pty(msg.into_bytes());
     }
 
@@ -731,8 +811,7 @@ impl<T: EventListener, A: ActionContext<T>> Processor<T, A> {
             };
 
             // Load mouse point, treating message bar and padding as the closest cell.
-            let display_offset = self.ctx.terminal().grid().display_offset();
-            let point = self.ctx.mouse().point(&self.ctx.size_info(), display_offset);
+            let point = self.ctx.mouse().point(&self.ctx.size_info(), self.ctx.terminal());
 
             if let MouseButton::Left = button {
                 self.on_left_click(point)
@@ -1154,8 +1233,7 @@ impl<T: EventListener, A: ActionContext<T>> Processor<T, A> {
             + size.cell_height() as usize * (size.screen_lines() + search_height);
 
         let mouse = self.ctx.mouse();
-        let display_offset = self.ctx.terminal().grid().display_offset();
-        let point = self.ctx.mouse().point(&self.ctx.size_info(), display_offset);
+        let point = self.ctx.mouse().point(&self.ctx.size_info(), self.ctx.terminal());
 
         if self.ctx.message().is_none() || (mouse.y <= terminal_end) {
             None
@@ -1170,8 +1248,7 @@ impl<T: EventListener, A: ActionContext<T>> Processor<T, A> {
 
     /// Icon state of the cursor.
     fn cursor_state(&mut self) -> CursorIcon {
-        let display_offset = self.ctx.terminal().grid().display_offset();
-        let point = self.ctx.mouse().point(&self.ctx.size_info(), display_offset);
+        let point = self.ctx.mouse().point(&self.ctx.size_info(), self.ctx.terminal());
         let hyperlink = self.ctx.terminal().grid()[point].hyperlink();
 
         // Function to check if mouse is on top of a hint.
@@ -1181,6 +1258,8 @@ impl<T: EventListener, A: ActionContext<T>> Processor<T, A> {
             mouse_state
         } else if self.ctx.display().highlighted_hint.as_ref().is_some_and(hint_highlighted) {
             CursorIcon::Pointer
+        } else if let Some(icon) = self.ctx.pointer_shape() {
+            icon
         } else if !self.ctx.modifiers().state().shift_key() && self.ctx.mouse_mode() {
             CursorIcon::Default
         } else {
@@ -1625,6 +1704,15 @@ mod tests {
         mods: ModifiersState::empty(),
     }
 
+    #[test]
+    fn pointer_shape_names() {
+        assert_eq!(pointer_shape("pointer"), Some(CursorIcon::Pointer));
+        assert_eq!(pointer_shape("bogus, ew-resize"), Some(CursorIcon::EwResize));
+        assert_eq!(pointer_shape("hand2"), Some(CursorIcon::Pointer));
+        assert_eq!(pointer_shape("xterm,default"), Some(CursorIcon::Text));
+        assert_eq!(pointer_shape("bogus"), None);
+    }
+
     test_process_binding! {
         name: process_binding_fail_with_extra_mods,
         binding: Binding { trigger: KEY, mods: ModifiersState::SUPER, action: Action::from("arst"), mode: BindingMode::empty(), notmode: BindingMode::empty() },
@@ -83,6 +83,21 @@ pub fn spawn_ipc_socket(
                     let event = Event::new(EventType::IpcGetConfig(Arc::new(stream)), window_id);
                     let _ = event_proxy.send_event(event);
                 },
+                SocketMessage::GetWorkingDirectory(request) => {
+                    let window_id =
+                        request.window_id.and_then(|id| u64::try_from(id).ok()).map(WindowId::from);
+                    let stream = Arc::new(stream);
+                    let event = Event::new(EventType::IpcGetWorkingDirectory(stream), window_id);
+                    let _ = event_proxy.send_event(event);
+                },
+                SocketMessage::SaveScrollback(request) => {
+                    let window_id =
+                        request.window_id.and_then(|id| u64::try_from(id).ok()).map(WindowId::from);
+                    let stream = Arc::new(stream);
+                    let event =
+                        Event::new(EventType::IpcSaveScrollback(stream, request), window_id);
+                    let _ = event_proxy.send_event(event);
+                },
             }
         }
     });
@@ -128,6 +143,20 @@ fn handle_reply(stream: &UnixStream, message: &SocketMessage) -> IoResult<()> {
             println!("{config}");
             Ok(())
         },
+        // Write requested working directory to STDOUT.
+        (SocketMessage::GetWorkingDirectory(..), SocketReply::GetWorkingDirectory(path)) => {
+            match path {
+                Some(path) => {
+                    println!("{}", path.display());
+                    Ok(())
+                },
+                None => Err(IoError::new(ErrorKind::NotFound, "no working directory found")),
+            }
+        },
+        // Report failure to save the scrollback.
+        (SocketMessage::SaveScrollback(..), SocketReply::SaveScrollback(result)) => {
+            result.clone().map_err(IoError::other)
+        },
         // Ignore requests without reply.
         _ => Ok(()),
     }
@@ -234,4 +263,6 @@ fn socket_prefix() -> String {
 #[derive(Serialize, Deserialize, Debug)]
 pub enum SocketReply {
     GetConfig(String),
+    GetWorkingDirectory(Option<PathBuf>),
+    SaveScrollback(Result<(), String>),
 }
@@ -39,15 +39,16 @@ mod ipc;
 mod logging;
 #[cfg(target_os = "macos")]
 mod macos;
-#[cfg(target_os = "linux")]
-mod linux_tabs;
 mod message_bar;
 mod migrate;
+mod pane;
 #[cfg(windows)]
 mod panic;
 mod renderer;
 mod scheduler;
+mod scrollback;
 mod string;
+mod tabs;
 mod window_context;
 
 mod gl {
@@ -83,7 +84,7 @@ fn main() -> Result<(), Box<dyn Error>> {
 
     match options.subcommands {
         #[cfg(unix)]
-        Some(Subcommands::Msg(options)) => msg(options)?,
+        Some(Subcommands::Msg(options)) => msg(*options)?,
         Some(Subcommands::Migrate(options)) => migrate::migrate(options),
         None => alacritty(options)?,
     }
@@ -103,6 +104,20 @@ fn msg(mut options: MessageOptions) -> Result<(), Box<dyn Error>> {
                 env::var("XDG_ACTIVATION_TOKEN").or_else(|_| env::var("DESKTOP_STARTUP_ID")).ok();
         }
     }
+
+    // Resolve paths relative to the client's working directory.
+    match &mut options.message {
+        SocketMessage::CreateWindow(window_options) => {
+            if let Some(path) = &mut window_options.terminal_options.restore_scrollback {
+                *path = std::path::absolute(&path)?;
+            }
+        },
+        SocketMessage::SaveScrollback(request) => {
+            request.path = std::path::absolute(&request.path)?;
+        },
+        _ => (),
+    }
+
     ipc::send_message(options.socket, options.message).map_err(|err| err.into())
 }
 
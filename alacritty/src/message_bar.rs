@@ -27,6 +27,9 @@ pub enum MessageType {
 
     /// A message represents a warning.
     Warning,
+
+    /// A message represents information requested by the user.
+    Info,
 }
 
 impl Message {
@@ -0,0 +1,441 @@
+//! Terminal panes inside a single window.
+
+use std::error::Error;
+use std::mem;
+#[cfg(not(windows))]
+use std::os::unix::io::{AsRawFd, RawFd};
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+use std::sync::atomic::{AtomicUsize, Ordering};
+
+use log::{error, info};
+use winit::window::{CursorIcon, WindowId};
+
+use alacritty_terminal::event::{Event as TerminalEvent, Notify, OnResize};
+use alacritty_terminal::event_loop::{EventLoop as PtyEventLoop, Msg, Notifier};
+use alacritty_terminal::grid::Dimensions;
+use alacritty_terminal::sync::FairMutex;
+use alacritty_terminal::term::{Term, TermMode};
+use alacritty_terminal::tty::{self, Options as PtyOptions};
+
+use crate::config::UiConfig;
+use crate::display::SizeInfo;
+use crate::event::{EventProxy, EventType};
+use crate::{daemon, scrollback};
+
+/// Unique identifier of a pane.
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub struct PaneId(usize);
+
+impl PaneId {
+    /// Allocate a new unique pane ID.
+    pub fn next() -> Self {
+        static NEXT_ID: AtomicUsize = AtomicUsize::new(0);
+        Self(NEXT_ID.fetch_add(1, Ordering::Relaxed))
+    }
+}
+
+/// Orientation of the divider between two panes.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum SplitDirection {
+    /// Horizontal divider, with the new pane below the existing one.
+    Horizontal,
+
+    /// Vertical divider, with the new pane right of the existing one.
+    Vertical,
+}
+
+/// Pixel rectangle inside the window.
+#[derive(Debug, Default, Copy, Clone, PartialEq)]
+pub struct PaneRect {
+    pub x: f32,
+    pub y: f32,
+    pub width: f32,
+    pub height: f32,
+}
+
+impl PaneRect {
+    pub fn new(x: f32, y: f32, width: f32, height: f32) -> Self {
+        Self { x, y, width, height }
+    }
+
+    /// Check if the pixel coordinates are inside the rectangle.
+    #[inline]
+    pub fn contains(&self, x: f64, y: f64) -> bool {
+        let (x, y) = (x as f32, y as f32);
+        x >= self.x && x < self.x + self.width && y >= self.y && y < self.y + self.height
+    }
+
+    /// Split the rectangle in two, leaving space for a border of `border` pixels between them.
+    fn split(&self, direction: SplitDirection, border: f32) -> (Self, Self, Self) {
+        match direction {
+            SplitDirection::Horizontal => {
+                let first_height = ((self.height - border) / 2.).floor().max(0.);
+                let second_y = self.y + first_height + border;
+                let first = Self::new(self.x, self.y, self.width, first_height);
+                let border = Self::new(self.x, self.y + first_height, self.width, border);
+                let second_height = (self.y + self.height - second_y).max(0.);
+                let second = Self::new(self.x, second_y, self.width, second_height);
+                (first, border, second)
+            },
+            SplitDirection::Vertical => {
+                let first_width = ((self.width - border) / 2.).floor().max(0.);
+                let second_x = self.x + first_width + border;
+                let first = Self::new(self.x, self.y, first_width, self.height);
+                let border = Self::new(self.x + first_width, self.y, border, self.height);
+                let second_width = (self.x + self.width - second_x).max(0.);
+                let second = Self::new(second_x, self.y, second_width, self.height);
+                (first, border, second)
+            },
+        }
+    }
+}
+
+/// Binary tree describing the arrangement of panes.
+#[derive(Debug, Clone, PartialEq, Eq)]
+enum LayoutNode {
+    Pane(PaneId),
+    Split { direction: SplitDirection, first: Box<LayoutNode>, second: Box<LayoutNode> },
+}
+
+impl LayoutNode {
+    fn split(&mut self, target: PaneId, new: PaneId, direction: SplitDirection) -> bool {
+        match self {
+            Self::Pane(id) if *id == target => {
+                let first = Box::new(Self::Pane(target));
+                let second = Box::new(Self::Pane(new));
+                *self = Self::Split { direction, first, second };
+                true
+            },
+            Self::Pane(_) => false,
+            Self::Split { first, second, .. } => {
+                first.split(target, new, direction) || second.split(target, new, direction)
+            },
+        }
+    }
+
+    fn remove(&mut self, target: PaneId) -> bool {
+        let (first, second) = match self {
+            Self::Pane(_) => return false,
+            Self::Split { first, second, .. } => (first, second),
+        };
+
+        // Promote the sibling of the removed pane to take its parent's place.
+        let sibling = match (&**first, &**second) {
+            (Self::Pane(id), _) if *id == target => second,
+            (_, Self::Pane(id)) if *id == target => first,
+            _ => return first.remove(target) || second.remove(target),
+        };
+        let sibling = mem::replace(&mut **sibling, Self::Pane(target));
+        *self = sibling;
+
+        true
+    }
+
+    fn visit(&self, rect: PaneRect, border: f32, visitor: &mut impl FnMut(LayoutItem)) {
+        match self {
+            Self::Pane(id) => visitor(LayoutItem::Pane(*id, rect)),
+            Self::Split { direction, first, second } => {
+                let (first_rect, border_rect, second_rect) = rect.split(*direction, border);
+                first.visit(first_rect, border, visitor);
+                visitor(LayoutItem::Border(border_rect));
+                second.visit(second_rect, border, visitor);
+            },
+        }
+    }
+}
+
+/// Element of a resolved pane layout.
+enum LayoutItem {
+    Pane(PaneId, PaneRect),
+    Border(PaneRect),
+}
+
+/// Arrangement of all panes inside a window.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct PaneLayout {
+    root: LayoutNode,
+}
+
+impl PaneLayout {
+    /// Create a layout with a single pane.
+    pub fn new(pane: PaneId) -> Self {
+        Self { root: LayoutNode::Pane(pane) }
+    }
+
+    /// Split the `target` pane in two, placing `new` after it.
+    ///
+    /// Returns `false` if `target` is not part of the layout.
+    pub fn split(&mut self, target: PaneId, new: PaneId, direction: SplitDirection) -> bool {
+        self.root.split(target, new, direction)
+    }
+
+    /// Remove a pane, handing its space to its sibling.
+    ///
+    /// Returns `false` if `target` is not part of the layout, or is the only pane in it.
+    pub fn remove(&mut self, target: PaneId) -> bool {
+        self.root.remove(target)
+    }
+
+    /// Whether the layout consists of only a single pane.
+    pub fn is_single(&self) -> bool {
+        matches!(self.root, LayoutNode::Pane(_))
+    }
+
+    /// All panes in the layout, from top left to bottom right.
+    pub fn panes(&self) -> Vec<PaneId> {
+        let mut panes = Vec::new();
+        self.root.visit(PaneRect::default(), 0., &mut |item| {
+            if let LayoutItem::Pane(id, _) = item {
+                panes.push(id);
+            }
+        });
+        panes
+    }
+
+    /// Pixel rectangles of every pane inside `area`.
+    pub fn rects(&self, area: PaneRect, border: f32) -> Vec<(PaneId, PaneRect)> {
+        let mut rects = Vec::new();
+        self.root.visit(area, border, &mut |item| {
+            if let LayoutItem::Pane(id, rect) = item {
+                rects.push((id, rect));
+            }
+        });
+        rects
+    }
+
+    /// Pixel rectangles of the borders between panes inside `area`.
+    pub fn borders(&self, area: PaneRect, border: f32) -> Vec<PaneRect> {
+        let mut borders = Vec::new();
+        self.root.visit(area, border, &mut |item| {
+            if let LayoutItem::Border(rect) = item {
+                borders.push(rect);
+            }
+        });
+        borders
+    }
+}
+
+/// A terminal running inside a part of a window.
+pub struct Pane {
+    pub terminal: Arc<FairMutex<Term<EventProxy>>>,
+    pub notifier: Notifier,
+    #[cfg(not(windows))]
+    pub master_fd: RawFd,
+    #[cfg(not(windows))]
+    pub shell_pid: u32,
+
+    /// Dimensions of the pane's terminal.
+    pub size_info: SizeInfo,
+
+    /// Position of the pane inside the window.
+    pub rect: PaneRect,
+
+    /// Last title requested by the pane's terminal.
+    pub title: Option<String>,
+
+    /// Last working directory reported by the pane's shell.
+    pub reported_directory: Option<PathBuf>,
+
+    /// Mouse pointer shape requested by the pane's terminal.
+    pub pointer_shape: Option<CursorIcon>,
+}
+
+impl Pane {
+    /// Spawn a new terminal with its own PTY.
+    pub fn new(
+        config: &UiConfig,
+        pty_config: &PtyOptions,
+        size_info: SizeInfo,
+        rect: PaneRect,
+        event_proxy: EventProxy,
+        window_id: WindowId,
+        restore: Option<&Path>,
+    ) -> Result<Self, Box<dyn Error>> {
+        info!("PTY dimensions: {:?} x {:?}", size_info.screen_lines(), size_info.columns());
+
+        // Create the terminal.
+        //
+        // This object contains all of the state about what's being displayed. It's
+        // wrapped in a clonable mutex since both the I/O loop and display need to
+        // access it.
+        let mut terminal = Term::new(config.term_options(), &size_info, event_proxy.clone());
+        terminal.set_cell_size(size_info.cell_width() as usize, size_info.cell_height() as usize);
+
+        // Fill the terminal with saved content before the shell starts writing to it.
+        if let Some(path) = restore {
+            match scrollback::restore(config.term_options(), &size_info, path) {
+                Ok(grid) => terminal.restore_grid(grid),
+                Err(err) => error!("Unable to restore scrollback from {}: {err}", path.display()),
+            }
+        }
+        let terminal = Arc::new(FairMutex::new(terminal));
+
+        // Create the PTY.
+        //
+        // The PTY forks a process to run the shell on the slave side of the
+        // pseudoterminal. A file descriptor for the master side is retained for
+        // reading/writing to the shell.
+        let pty = tty::new(pty_config, size_info.into(), window_id.into())?;
+
+        #[cfg(not(windows))]
+        let master_fd = pty.file().as_raw_fd();
+        #[cfg(not(windows))]
+        let shell_pid = pty.child().id();
+
+        // Create the pseudoterminal I/O loop.
+        //
+        // PTY I/O is ran on another thread as to not occupy cycles used by the
+        // renderer and input processing. Note that access to the terminal state is
+        // synchronized since the I/O loop updates the state, and the display
+        // consumes it periodically.
+        let event_loop = PtyEventLoop::new(
+            Arc::clone(&terminal),
+            event_proxy.clone(),
+            pty,
+            pty_config.drain_on_exit,
+            config.debug.ref_test,
+        )?;
+
+        // The event loop channel allows write requests from the event processor
+        // to be sent to the pty loop and ultimately written to the pty.
+        let loop_tx = event_loop.channel();
+
+        // Kick off the I/O thread.
+        let _io_thread = event_loop.spawn();
+
+        // Start cursor blinking, in case `Focused` isn't sent on startup.
+        if config.cursor.style().blinking {
+            event_proxy.send_event(EventType::Terminal(TerminalEvent::CursorBlinkingChange));
+        }
+
+        Ok(Self {
+            terminal,
+            notifier: Notifier(loop_tx),
+            #[cfg(not(windows))]
+            master_fd,
+            #[cfg(not(windows))]
+            shell_pid,
+            size_info,
+            rect,
+            title: None,
+            reported_directory: None,
+            pointer_shape: None,
+        })
+    }
+
+    /// Working directory for processes started from this pane.
+    pub fn working_directory(&self) -> Option<PathBuf> {
+        daemon::working_directory(
+            self.reported_directory.as_deref(),
+            #[cfg(not(windows))]
+            self.master_fd,
+            #[cfg(not(windows))]
+            self.shell_pid,
+        )
+    }
+
+    /// Move the pane, resizing its terminal and PTY when the grid dimensions changed.
+    pub fn resize(&mut self, size_info: SizeInfo, rect: PaneRect) {
+        self.rect = rect;
+
+        if self.size_info.screen_lines() != size_info.screen_lines()
+            || self.size_info.columns() != size_info.columns()
+        {
+            // Resize PTY.
+            self.notifier.on_resize(size_info.into());
+
+            // Resize terminal.
+            self.terminal.lock().resize(size_info);
+        }
+
+        if self.size_info.cell_width() != size_info.cell_width()
+            || self.size_info.cell_height() != size_info.cell_height()
+        {
+            let (width, height) = (size_info.cell_width(), size_info.cell_height());
+            self.terminal.lock().set_cell_size(width as usize, height as usize);
+        }
+
+        self.size_info = size_info;
+    }
+
+    /// Update the terminal's focus, reporting the change to the shell if it requested it.
+    ///
+    /// Returns the previous focus state.
+    pub fn set_focused(&self, is_focused: bool) -> bool {
+        let mut terminal = self.terminal.lock();
+        let was_focused = mem::replace(&mut terminal.is_focused, is_focused);
+
+        if was_focused != is_focused && terminal.mode().contains(TermMode::FOCUS_IN_OUT) {
+            let chr = if is_focused { "I" } else { "O" };
+            self.notifier.notify(format!("\x1b[{chr}").into_bytes());
+        }
+
+        was_focused
+    }
+}
+
+impl Drop for Pane {
+    fn drop(&mut self) {
+        // Shutdown the terminal's PTY.
+        let _ = self.notifier.0.send(Msg::Shutdown);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn split_and_remove() {
+        let (a, b, c) = (PaneId(0), PaneId(1), PaneId(2));
+        let mut layout = PaneLayout::new(a);
+        assert!(layout.is_single());
+
+        assert!(layout.split(a, b, SplitDirection::Vertical));
+        assert!(layout.split(b, c, SplitDirection::Horizontal));
+        assert!(!layout.split(PaneId(3), a, SplitDirection::Vertical));
+        assert_eq!(layout.panes(), vec![a, b, c]);
+
+        assert!(layout.remove(b));
+        assert_eq!(layout, {
+            let mut expected = PaneLayout::new(a);
+            expected.split(a, c, SplitDirection::Vertical);
+            expected
+        });
+
+        assert!(layout.remove(a));
+        assert_eq!(layout, PaneLayout::new(c));
+
+        // The last pane can never be removed.
+        assert!(!layout.remove(c));
+    }
+
+    #[test]
+    fn layout_rects() {
+        let (a, b, c) = (PaneId(0), PaneId(1), PaneId(2));
+        let mut layout = PaneLayout::new(a);
+        layout.split(a, b, SplitDirection::Vertical);
+        layout.split(b, c, SplitDirection::Horizontal);
+
+        let area = PaneRect::new(0., 0., 101., 51.);
+        assert_eq!(layout.rects(area, 1.), vec![
+            (a, PaneRect::new(0., 0., 50., 51.)),
+            (b, PaneRect::new(51., 0., 50., 25.)),
+            (c, PaneRect::new(51., 26., 50., 25.)),
+        ]);
+        assert_eq!(layout.borders(area, 1.), vec![
+            PaneRect::new(50., 0., 1., 51.),
+            PaneRect::new(51., 25., 50., 1.),
+        ]);
+    }
+
+    #[test]
+    fn rect_contains() {
+        let rect = PaneRect::new(10., 10., 10., 10.);
+        assert!(rect.contains(10., 10.));
+        assert!(rect.contains(19.5, 19.5));
+        assert!(!rect.contains(20., 15.));
+        assert!(!rect.contains(9.9, 15.));
+    }
+}
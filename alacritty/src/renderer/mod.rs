@@ -12,6 +12,7 @@ use glutin::display::{GetGlDisplay, GlDisplay};
 use log::{LevelFilter, debug, info};
 use unicode_width::UnicodeWidthChar;
 
+use alacritty_terminal::grid::LineSize;
 use alacritty_terminal::index::Point;
 use alacritty_terminal::term::cell::Flags;
 
@@ -90,6 +91,9 @@ pub struct Renderer {
     text_renderer: TextRendererProvider,
     rect_renderer: RectRenderer,
     robustness: bool,
+
+    /// Offset of the drawing area from the bottom left corner of the window.
+    offset: (i32, i32),
 }
 
 /// Wrapper around gl::GetString with error checking and reporting.
@@ -171,7 +175,7 @@ impl Renderer {
             }
         }
 
-        Ok(Self { text_renderer, rect_renderer, robustness })
+        Ok(Self { text_renderer, rect_renderer, robustness, offset: (0, 0) })
     }
 
     pub fn draw_cells<I: Iterator<Item = RenderableCell>>(
@@ -223,6 +227,7 @@ impl Renderer {
                 fg,
                 bg,
                 underline: fg,
+                line_size: LineSize::Single,
             })
         });
 
@@ -248,11 +253,12 @@ impl Renderer {
         // Prepare rect rendering state.
         unsafe {
             // Remove padding from viewport.
-            gl::Viewport(0, 0, size_info.width() as i32, size_info.height() as i32);
+            let (x, y) = self.offset;
+            gl::Viewport(x, y, size_info.width() as i32, size_info.height() as i32);
             gl::BlendFuncSeparate(gl::SRC_ALPHA, gl::ONE_MINUS_SRC_ALPHA, gl::SRC_ALPHA, gl::ONE);
         }
 
-        self.rect_renderer.draw(size_info, metrics, rects);
+        self.rect_renderer.draw(size_info, metrics, rects, self.offset);
 
         // Activate regular state again.
         unsafe {
@@ -277,6 +283,21 @@ impl Renderer {
         }
     }
 
+    /// Fill the drawing area of `size_info` with `color` and `alpha`.
+    pub fn clear_area(&self, size_info: &SizeInfo, color: Rgb, alpha: f32) {
+        unsafe {
+            let (x, y) = self.offset;
+            gl::Enable(gl::SCISSOR_TEST);
+            gl::Scissor(x, y, size_info.width() as i32, size_info.height() as i32);
+        }
+
+        self.clear(color, alpha);
+
+        unsafe {
+            gl::Disable(gl::SCISSOR_TEST);
+        }
+    }
+
     /// Get the context reset status.
     pub fn was_context_reset(&self) -> bool {
         // If robustness is not supported, don't use its functions.
@@ -326,13 +347,21 @@ impl Renderer {
         }
     }
 
+    /// Move the drawing area away from the bottom left corner of the window.
+    ///
+    /// This only takes effect once the renderer is resized to the dimensions of the new area.
+    pub fn set_offset(&mut self, x: i32, y: i32) {
+        self.offset = (x, y);
+    }
+
     /// Set the viewport for cell rendering.
     #[inline]
     pub fn set_viewport(&self, size: &SizeInfo) {
+        let (x, y) = self.offset;
         unsafe {
             gl::Viewport(
-                size.padding_x() as i32,
-                size.padding_y() as i32,
+                x + size.padding_x() as i32,
+                y + size.padding_y() as i32,
                 size.width() as i32 - 2 * size.padding_x() as i32,
                 size.height() as i32 - 2 * size.padding_y() as i32,
             );
@@ -109,6 +109,12 @@ impl RenderLine {
             Flags::STRIKEOUT => {
                 (metrics.strikeout_position, metrics.strikeout_thickness, RectKind::Normal)
             },
+            // Align overline with the top of the cell.
+            Flags::OVERLINE => {
+                let thickness = metrics.underline_thickness.max(1.);
+                let position = size.cell_height() + metrics.descent - thickness / 2.;
+                (position, metrics.underline_thickness, RectKind::Normal)
+            },
             _ => unimplemented!("Invalid flag for cell line drawing specified"),
         };
 
@@ -155,7 +161,7 @@ impl RenderLine {
     }
 }
 
-/// Lines for underline and strikeout.
+/// Lines for underline, strikeout and overline.
 #[derive(Default)]
 pub struct RenderLines {
     inner: HashMap<Flags, Vec<RenderLine>, RandomState>,
@@ -183,6 +189,7 @@ impl RenderLines {
         self.update_flag(cell, Flags::UNDERLINE);
         self.update_flag(cell, Flags::DOUBLE_UNDERLINE);
         self.update_flag(cell, Flags::STRIKEOUT);
+        self.update_flag(cell, Flags::OVERLINE);
         self.update_flag(cell, Flags::UNDERCURL);
         self.update_flag(cell, Flags::DOTTED_UNDERLINE);
         self.update_flag(cell, Flags::DASHED_UNDERLINE);
@@ -197,17 +204,15 @@ impl RenderLines {
         // The underline color escape does not apply to strikeout.
         let color = if flag.contains(Flags::STRIKEOUT) { cell.fg } else { cell.underline };
 
-        // Include wide char spacer if the current cell is a wide char.
-        let mut end = cell.point;
-        if cell.flags.contains(Flags::WIDE_CHAR) {
-            end.column += 1;
-        }
+        // Include wide char spacer and the stretched part of double-width cells.
+        let start = Point::new(cell.point.line, Column(cell.display_column()));
+        let end = Point::new(start.line, start.column + cell.columns() - 1);
 
         // Check if there's an active line.
         if let Some(line) = self.inner.get_mut(&flag).and_then(|lines| lines.last_mut()) {
             if color == line.color
-                && cell.point.column == line.end.column + 1
-                && cell.point.line == line.end.line
+                && start.column == line.end.column + 1
+                && start.line == line.end.line
             {
                 // Update the length of the line.
                 line.end = end;
@@ -216,7 +221,7 @@ impl RenderLines {
         }
 
         // Start new line if there currently is none.
-        let line = RenderLine { start: cell.point, end, color };
+        let line = RenderLine { start, end, color };
         match self.inner.get_mut(&flag) {
             Some(lines) => lines.push(line),
             None => {
@@ -317,7 +322,13 @@ impl RectRenderer {
         Ok(Self { vao, vbo, programs, vertices: Default::default() })
     }
 
-    pub fn draw(&mut self, size_info: &SizeInfo, metrics: &Metrics, rects: Vec<RenderRect>) {
+    pub fn draw(
+        &mut self,
+        size_info: &SizeInfo,
+        metrics: &Metrics,
+        rects: Vec<RenderRect>,
+        offset: (i32, i32),
+    ) {
         unsafe {
             // Bind VAO to enable vertex attribute slots.
             gl::BindVertexArray(self.vao);
@@ -346,7 +357,7 @@ impl RectRenderer {
 
                 let program = &self.programs[rect_kind as usize];
                 gl::UseProgram(program.id());
-                program.update_uniforms(size_info, metrics);
+                program.update_uniforms(size_info, metrics, offset);
 
                 // Upload accumulated undercurl vertices.
                 gl::BufferData(
@@ -461,7 +472,7 @@ impl RectShaderProgram {
         self.program.id()
     }
 
-    pub fn update_uniforms(&self, size_info: &SizeInfo, metrics: &Metrics) {
+    pub fn update_uniforms(&self, size_info: &SizeInfo, metrics: &Metrics, offset: (i32, i32)) {
         let position = (0.5 * metrics.descent).abs();
         let underline_position = metrics.descent.abs() - metrics.underline_position.abs();
 
@@ -477,10 +488,10 @@ impl RectShaderProgram {
                 gl::Uniform1f(u_cell_height, size_info.cell_height());
             }
             if let Some(u_padding_y) = self.u_padding_y {
-                gl::Uniform1f(u_padding_y, padding_y);
+                gl::Uniform1f(u_padding_y, padding_y + offset.1 as f32);
             }
             if let Some(u_padding_x) = self.u_padding_x {
-                gl::Uniform1f(u_padding_x, size_info.padding_x());
+                gl::Uniform1f(u_padding_x, size_info.padding_x() + offset.0 as f32);
             }
             if let Some(u_underline_position) = self.u_underline_position {
                 gl::Uniform1f(u_underline_position, underline_position);
@@ -4,8 +4,6 @@ use std::ptr;
 use crossfont::RasterizedGlyph;
 use log::info;
 
-use alacritty_terminal::term::cell::Flags;
-
 use crate::display::SizeInfo;
 use crate::display::content::RenderableCell;
 use crate::gl;
@@ -278,11 +276,11 @@ impl TextRenderBatch for Batch {
         }
 
         // Calculate the cell position.
-        let x = cell.point.column.0 as i16 * size_info.cell_width() as i16;
+        let x = cell.display_column() as i16 * size_info.cell_width() as i16;
         let y = cell.point.line as i16 * size_info.cell_height() as i16;
 
         // Calculate the glyph position.
-        let glyph_x = cell.point.column.0 as i16 * size_info.cell_width() as i16 + glyph.left;
+        let glyph_x = x + glyph.left;
         let glyph_y = (cell.point.line + 1) as i16 * size_info.cell_height() as i16 - glyph.top;
 
         let colored = if glyph.multicolor {
@@ -291,7 +289,7 @@ impl TextRenderBatch for Batch {
             RenderingGlyphFlags::empty()
         };
 
-        let is_wide = if cell.flags.contains(Flags::WIDE_CHAR) { 2 } else { 1 };
+        let columns = cell.columns() as i16;
 
         let mut vertex = TextVertex {
             x,
@@ -320,13 +318,13 @@ impl TextRenderBatch for Batch {
         vertex.v = glyph.uv_bot;
         self.vertices.push(vertex);
 
-        vertex.x = x + is_wide * size_info.cell_width() as i16;
+        vertex.x = x + columns * size_info.cell_width() as i16;
         vertex.glyph_x = glyph_x + glyph.width;
         vertex.u = glyph.uv_left + glyph.uv_width;
         vertex.v = glyph.uv_bot;
         self.vertices.push(vertex);
 
-        vertex.x = x + is_wide * size_info.cell_width() as i16;
+        vertex.x = x + columns * size_info.cell_width() as i16;
         vertex.y = y + size_info.cell_height() as i16;
         vertex.glyph_x = glyph_x + glyph.width;
         vertex.glyph_y = glyph_y + glyph.height;
@@ -347,9 +347,10 @@ impl TextRenderBatch for Batch {
         let mut cell_flags = RenderingGlyphFlags::empty();
         cell_flags.set(RenderingGlyphFlags::COLORED, glyph.multicolor);
         cell_flags.set(RenderingGlyphFlags::WIDE_CHAR, cell.flags.contains(Flags::WIDE_CHAR));
+        cell_flags.set(RenderingGlyphFlags::DOUBLE_WIDTH, cell.line_size.is_double_width());
 
         self.instances.push(InstanceData {
-            col: cell.point.column.0 as u16,
+            col: cell.display_column() as u16,
             row: cell.point.line as u16,
 
             top: glyph.top,
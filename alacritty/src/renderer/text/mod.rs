@@ -1,6 +1,7 @@
 use bitflags::bitflags;
 use crossfont::{GlyphKey, RasterizedGlyph};
 
+use alacritty_terminal::grid::LineSize;
 use alacritty_terminal::term::cell::Flags;
 
 use crate::display::SizeInfo;
@@ -25,8 +26,9 @@ bitflags! {
     #[repr(C)]
     #[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
     struct RenderingGlyphFlags: u8 {
-        const COLORED   = 0b0000_0001;
-        const WIDE_CHAR = 0b0000_0010;
+        const COLORED      = 0b0000_0001;
+        const WIDE_CHAR    = 0b0000_0010;
+        const DOUBLE_WIDTH = 0b0000_0100;
     }
 }
 
@@ -123,7 +125,10 @@ pub trait TextRenderApi<T: TextRenderBatch>: LoadGlyph {
             self.render_batch();
         }
 
-        self.batch().add_item(cell, glyph, size_info);
+        // Stretch glyphs of double-width and double-height lines.
+        let glyph = scale_glyph(glyph, cell.line_size, size_info.cell_height() as i16);
+
+        self.batch().add_item(cell, &glyph, size_info);
 
         // Render batch and clear if it's full.
         if self.batch().full() {
@@ -196,6 +201,42 @@ impl LoadGlyph for LoaderApi<'_> {
     }
 }
 
+/// Stretch a glyph to the size of the cells in its line.
+///
+/// Cells of double-height lines only display one half of the glyph, so it is clipped to the cell.
+fn scale_glyph(glyph: &Glyph, line_size: LineSize, cell_height: i16) -> Glyph {
+    let mut glyph = *glyph;
+    if !line_size.is_double_width() {
+        return glyph;
+    }
+
+    glyph.left *= 2;
+    glyph.width *= 2;
+
+    // Distance from the top of the cell to the top of the stretched glyph.
+    let top = match line_size {
+        LineSize::DoubleHeightTop => 2 * (cell_height - glyph.top),
+        LineSize::DoubleHeightBottom => 2 * (cell_height - glyph.top) - cell_height,
+        _ => return glyph,
+    };
+    let bottom = top + 2 * glyph.height;
+
+    let clipped_top = top.clamp(0, cell_height);
+    let clipped_bottom = bottom.clamp(0, cell_height);
+
+    // Only sample the visible part of the glyph from the atlas.
+    if bottom > top {
+        let uv_per_pixel = glyph.uv_height / (bottom - top) as f32;
+        glyph.uv_bot += (clipped_top - top) as f32 * uv_per_pixel;
+        glyph.uv_height = (clipped_bottom - clipped_top) as f32 * uv_per_pixel;
+    }
+
+    glyph.top = cell_height - clipped_top;
+    glyph.height = clipped_bottom - clipped_top;
+
+    glyph
+}
+
 fn update_projection(u_projection: GLint, size: &SizeInfo) {
     let width = size.width();
     let height = size.height();
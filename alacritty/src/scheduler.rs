@@ -28,6 +28,8 @@ pub enum Topic {
     DelayedSearch,
     BlinkCursor,
     BlinkTimeout,
+    BlinkText,
+    TimestampRefresh,
     Frame,
 }
 
@@ -0,0 +1,206 @@
+//! Saving terminal content to files and restoring it in new terminals.
+
+use std::fs::{self, File};
+use std::io::{self, BufWriter, Write};
+use std::path::{Path, PathBuf};
+
+use clap::ValueEnum;
+use serde::Serialize;
+
+use alacritty_config_derive::ConfigDeserialize;
+use alacritty_terminal::event::VoidListener;
+use alacritty_terminal::grid::{Dimensions, Grid};
+use alacritty_terminal::parser::{ExtendedHandler, Processor};
+use alacritty_terminal::term::cell::Cell;
+use alacritty_terminal::term::{Config, Term};
+use alacritty_terminal::thread;
+use alacritty_terminal::vte::ansi::{Attr, Handler, Hyperlink};
+
+/// File format of saved scrollback.
+#[derive(ConfigDeserialize, ValueEnum, Serialize, Default, Debug, Copy, Clone, PartialEq, Eq)]
+pub enum DumpFormat {
+    /// Plain text.
+    #[default]
+    Text,
+    /// Text with escape sequences for colors, attributes and hyperlinks.
+    Ansi,
+    /// Lossless JSON serialization of the terminal grid.
+    Serialized,
+}
+
+impl DumpFormat {
+    /// File extension for the format.
+    pub fn extension(self) -> &'static str {
+        match self {
+            Self::Text => "txt",
+            Self::Ansi => "ansi",
+            Self::Serialized => "json",
+        }
+    }
+}
+
+/// Write the scrollback history and screen of a terminal to a file on a background thread.
+///
+/// Only copying the terminal blocks the caller, `callback` is run on the background thread once
+/// the file is written.
+pub fn save_in_background<T, F>(terminal: &Term<T>, format: DumpFormat, path: PathBuf, callback: F)
+where
+    F: FnOnce(io::Result<()>) + Send + 'static,
+{
+    let snapshot = terminal.snapshot();
+    thread::spawn_named("scrollback writer", move || {
+        callback(save(&snapshot, format, &path));
+    });
+}
+
+/// Write the scrollback history and screen of a terminal to a file.
+pub fn save<T>(terminal: &Term<T>, format: DumpFormat, path: &Path) -> io::Result<()> {
+    let mut file = BufWriter::new(File::create(path)?);
+
+    match format {
+        DumpFormat::Text => file.write_all(terminal.scrollback_to_string().as_bytes())?,
+        DumpFormat::Ansi => file.write_all(terminal.scrollback_to_ansi().as_bytes())?,
+        DumpFormat::Serialized => {
+            serde_json::to_writer(&mut file, terminal.grid()).map_err(io::Error::other)?
+        },
+    }
+
+    file.flush()
+}
+
+/// Read a grid from content saved in any [`DumpFormat`].
+///
+/// Text is written to a separate terminal which only handles text, colors and hyperlinks, so
+/// queries and other escape sequences in the file have no effect.
+pub fn restore<D: Dimensions>(
+    config: Config,
+    dimensions: &D,
+    path: &Path,
+) -> io::Result<Grid<Cell>> {
+    let content = fs::read(path)?;
+
+    // Serialized grids are JSON objects, while text is replayed as terminal output.
+    if content.first() == Some(&b'{') {
+        if let Ok(grid) = serde_json::from_slice::<Grid<Cell>>(&content) {
+            return Ok(grid);
+        }
+    }
+
+    // Return to the first column after every line, since text dumps only use line feeds.
+    let mut terminal = Replay(Term::new(config, dimensions, VoidListener));
+    let mut parser: Processor = Processor::new();
+    for line in content.split_inclusive(|&byte| byte == b'\n') {
+        parser.advance(&mut terminal, line);
+        if line.ends_with(b"\n") {
+            parser.advance(&mut terminal, b"\r");
+        }
+    }
+
+    // Write content buffered by an unterminated synchronized update.
+    parser.stop_sync(&mut terminal);
+
+    Ok(terminal.0.grid().clone())
+}
+
+/// Handler writing only the output of [`save`] to a terminal.
+struct Replay(Term<VoidListener>);
+
+impl Handler for Replay {
+    fn input(&mut self, c: char) {
+        self.0.input(c);
+    }
+
+    fn put_tab(&mut self, count: u16) {
+        self.0.put_tab(count);
+    }
+
+    fn backspace(&mut self) {
+        self.0.backspace();
+    }
+
+    fn carriage_return(&mut self) {
+        self.0.carriage_return();
+    }
+
+    fn linefeed(&mut self) {
+        self.0.linefeed();
+    }
+
+    fn terminal_attribute(&mut self, attr: Attr) {
+        self.0.terminal_attribute(attr);
+    }
+
+    fn set_hyperlink(&mut self, hyperlink: Option<Hyperlink>) {
+        self.0.set_hyperlink(hyperlink);
+    }
+}
+
+impl ExtendedHandler for Replay {}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use std::sync::mpsc;
+
+    use alacritty_terminal::index::Column;
+    use alacritty_terminal::term::test::TermSize;
+
+    fn term() -> Term<VoidListener> {
+        Term::new(Config::default(), &TermSize::new(10, 4), VoidListener)
+    }
+
+    fn restore_term(path: &Path) -> Term<VoidListener> {
+        let mut terminal = term();
+        let grid = restore(Config::default(), &terminal, path).unwrap();
+        terminal.restore_grid(grid);
+        terminal
+    }
+
+    #[test]
+    fn save_and_restore() {
+        let mut terminal = term();
+        let mut parser: Processor = Processor::new();
+        parser.advance(&mut terminal, b"\x1b[32mfirst\x1b[0m\r\nsecond line\r\nthird");
+
+        let dir = tempfile::tempdir().unwrap();
+        for format in [DumpFormat::Text, DumpFormat::Ansi, DumpFormat::Serialized] {
+            let path = dir.path().join(format!("dump.{}", format.extension()));
+            let (sender, receiver) = mpsc::channel();
+            save_in_background(&terminal, format, path.clone(), move |result| {
+                sender.send(result).unwrap();
+            });
+            receiver.recv().unwrap().unwrap();
+
+            let restored = restore_term(&path);
+
+            let text = restored.scrollback_to_string();
+            assert_eq!(text, "first\nsecond line\nthird\n", "{format:?}");
+            assert_eq!(restored.grid().cursor.point.column, Column(0), "{format:?}");
+
+            // Only plain text loses colors.
+            let fg = restored.grid()[restored.topmost_line()][Column(0)].fg;
+            assert_eq!(fg != Cell::default().fg, format != DumpFormat::Text, "{format:?}");
+        }
+    }
+
+    #[test]
+    fn restore_ignores_escapes() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("dump.ansi");
+        fs::write(&path, "first\x1b[c\x1b]2;title\x07\n\x1b[?1049h\x1b[Hsecond\n").unwrap();
+
+        let restored = restore_term(&path);
+        assert_eq!(restored.scrollback_to_string(), "first\nsecond\n");
+    }
+
+    #[test]
+    fn restore_synchronized_update() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("dump.ansi");
+        fs::write(&path, "first\n\x1b[?2026hsecond\n").unwrap();
+
+        let restored = restore_term(&path);
+        assert_eq!(restored.scrollback_to_string(), "first\nsecond\n");
+    }
+}
@@ -0,0 +1,221 @@
+//! Tabs inside a single window.
+
+use std::slice;
+
+use alacritty_terminal::grid::Dimensions;
+
+use crate::display::SizeInfo;
+use crate::pane::{PaneId, PaneLayout};
+
+/// Tab which should be selected.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum TabSelection {
+    /// Tab right of the active one, wrapping around to the first tab.
+    Next,
+
+    /// Tab left of the active one, wrapping around to the last tab.
+    Previous,
+
+    /// Tab at a specific index.
+    Index(usize),
+
+    /// Rightmost tab.
+    Last,
+}
+
+/// Group of panes shown together.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Tab {
+    pub layout: PaneLayout,
+
+    /// Pane receiving keyboard input while the tab is active.
+    pub focused_pane: PaneId,
+}
+
+impl Tab {
+    pub fn new(pane: PaneId) -> Self {
+        Self { layout: PaneLayout::new(pane), focused_pane: pane }
+    }
+}
+
+/// All tabs of a window, with exactly one of them active.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Tabs {
+    tabs: Vec<Tab>,
+    active: usize,
+}
+
+impl Tabs {
+    pub fn new(tab: Tab) -> Self {
+        Self { tabs: vec![tab], active: 0 }
+    }
+
+    /// Currently visible tab.
+    #[inline]
+    pub fn active(&self) -> &Tab {
+        &self.tabs[self.active]
+    }
+
+    /// Currently visible tab.
+    #[inline]
+    pub fn active_mut(&mut self) -> &mut Tab {
+        &mut self.tabs[self.active]
+    }
+
+    /// Index of the currently visible tab.
+    #[inline]
+    pub fn active_index(&self) -> usize {
+        self.active
+    }
+
+    pub fn iter(&self) -> slice::Iter<'_, Tab> {
+        self.tabs.iter()
+    }
+
+    pub fn get_mut(&mut self, index: usize) -> Option<&mut Tab> {
+        self.tabs.get_mut(index)
+    }
+
+    /// Index of the tab containing `pane`.
+    pub fn position(&self, pane: PaneId) -> Option<usize> {
+        self.tabs.iter().position(|tab| tab.layout.panes().contains(&pane))
+    }
+
+    /// Add a new tab after all existing ones and make it active.
+    pub fn push(&mut self, tab: Tab) {
+        self.tabs.push(tab);
+        self.active = self.tabs.len() - 1;
+    }
+
+    /// Change the active tab.
+    ///
+    /// Returns `false` if the active tab did not change.
+    pub fn select(&mut self, selection: TabSelection) -> bool {
+        let index = match selection {
+            TabSelection::Next => (self.active + 1) % self.tabs.len(),
+            TabSelection::Previous => (self.active + self.tabs.len() - 1) % self.tabs.len(),
+            TabSelection::Index(index) if index < self.tabs.len() => index,
+            TabSelection::Index(_) => return false,
+            TabSelection::Last => self.tabs.len() - 1,
+        };
+
+        let changed = index != self.active;
+        self.active = index;
+        changed
+    }
+
+    /// Remove the tab at `index`.
+    ///
+    /// When the active tab is removed, the tab right of it becomes active. The last tab can never
+    /// be removed.
+    pub fn remove(&mut self, index: usize) -> Option<Tab> {
+        if self.tabs.len() <= 1 || index >= self.tabs.len() {
+            return None;
+        }
+
+        let tab = self.tabs.remove(index);
+
+        if index < self.active || self.active == self.tabs.len() {
+            self.active -= 1;
+        }
+
+        Some(tab)
+    }
+}
+
+/// Content of the tab bar at the top of the window.
+#[derive(Debug, Default, Clone, PartialEq, Eq)]
+pub struct TabBar {
+    /// Title of every tab.
+    pub titles: Vec<String>,
+
+    /// Index of the active tab.
+    pub active: usize,
+}
+
+impl TabBar {
+    /// Whether the tab bar should be drawn.
+    #[inline]
+    pub fn is_visible(&self) -> bool {
+        self.titles.len() > 1
+    }
+
+    /// Height of the tab bar in pixels.
+    ///
+    /// The tab bar occupies the first line of the window.
+    pub fn height(&self, size_info: &SizeInfo) -> f32 {
+        if self.is_visible() { size_info.padding_y() + size_info.cell_height() } else { 0. }
+    }
+
+    /// Number of columns available to each tab.
+    ///
+    /// The rightmost tab also fills any remaining columns.
+    pub fn tab_columns(&self, size_info: &SizeInfo) -> usize {
+        (size_info.columns() / self.titles.len().max(1)).max(1)
+    }
+
+    /// Index of the tab at `column`.
+    pub fn tab_at(&self, size_info: &SizeInfo, column: usize) -> usize {
+        let index = column / self.tab_columns(size_info);
+        index.min(self.titles.len().saturating_sub(1))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn tabs(count: usize) -> Tabs {
+        let mut tabs = Tabs::new(Tab::new(PaneId::next()));
+        for _ in 1..count {
+            tabs.push(Tab::new(PaneId::next()));
+        }
+        tabs
+    }
+
+    #[test]
+    fn select() {
+        let mut tabs = tabs(3);
+        assert_eq!(tabs.active_index(), 2);
+
+        assert!(tabs.select(TabSelection::Next));
+        assert_eq!(tabs.active_index(), 0);
+
+        assert!(tabs.select(TabSelection::Previous));
+        assert_eq!(tabs.active_index(), 2);
+
+        assert!(tabs.select(TabSelection::Index(1)));
+        assert_eq!(tabs.active_index(), 1);
+
+        assert!(!tabs.select(TabSelection::Index(3)));
+        assert_eq!(tabs.active_index(), 1);
+
+        assert!(tabs.select(TabSelection::Last));
+        assert!(!tabs.select(TabSelection::Last));
+        assert_eq!(tabs.active_index(), 2);
+    }
+
+    #[test]
+    fn remove() {
+        let mut tabs = tabs(4);
+        let panes: Vec<_> = tabs.iter().map(|tab| tab.focused_pane).collect();
+
+        // Removing tabs before the active one keeps it active.
+        tabs.select(TabSelection::Index(2));
+        assert!(tabs.remove(0).is_some());
+        assert_eq!(tabs.active().focused_pane, panes[2]);
+
+        // Removing the active tab selects the one after it.
+        assert!(tabs.remove(1).is_some());
+        assert_eq!(tabs.active().focused_pane, panes[3]);
+
+        // Removing the rightmost active tab selects the one before it.
+        assert!(tabs.remove(1).is_some());
+        assert_eq!(tabs.active().focused_pane, panes[1]);
+
+        // The last tab can never be removed.
+        assert!(tabs.remove(0).is_none());
+        assert_eq!(tabs.position(panes[1]), Some(0));
+        assert_eq!(tabs.position(panes[0]), None);
+    }
+}
@@ -1,47 +1,50 @@
 //! Terminal window context.
 
+use std::collections::HashMap;
 use std::error::Error;
 use std::fs::File;
-use std::io::Write;
+use std::io::{self, Write};
 use std::mem;
-#[cfg(not(windows))]
-use std::os::unix::io::{AsRawFd, RawFd};
+use std::path::{Path, PathBuf};
 use std::rc::Rc;
-use std::sync::Arc;
 use std::time::Instant;
 
+use ahash::RandomState;
+
 use glutin::config::Config as GlutinConfig;
 use glutin::display::GetGlDisplay;
 #[cfg(all(feature = "x11", not(any(target_os = "macos", windows))))]
 use glutin::platform::x11::X11GlConfigExt;
-use log::info;
 use serde_json as json;
-use winit::event::{Event as WinitEvent, Modifiers, WindowEvent};
+use winit::dpi::PhysicalPosition;
+use winit::event::{ElementState, Event as WinitEvent, Modifiers, WindowEvent};
 use winit::event_loop::{ActiveEventLoop, EventLoopProxy};
 use winit::raw_window_handle::HasDisplayHandle;
 use winit::window::WindowId;
 
 use alacritty_terminal::event::Event as TerminalEvent;
-use alacritty_terminal::event_loop::{EventLoop as PtyEventLoop, Msg, Notifier};
 use alacritty_terminal::grid::{Dimensions, Scroll};
 use alacritty_terminal::index::Direction;
-use alacritty_terminal::sync::FairMutex;
+use alacritty_terminal::term::TermMode;
 use alacritty_terminal::term::test::TermSize;
-use alacritty_terminal::term::{Term, TermMode};
-use alacritty_terminal::tty;
+use alacritty_terminal::tty::Options as PtyOptions;
 
 use crate::cli::{ParsedOptions, WindowOptions};
 use crate::clipboard::Clipboard;
 use crate::config::UiConfig;
-use crate::display::Display;
 use crate::display::window::Window;
+use crate::display::{Display, PaneFrame, SizeInfo, timestamp};
 use crate::event::{
-    ActionContext, Event, EventProxy, InlineSearchState, Mouse, SearchState, TouchPurpose,
+    ActionContext, Event, EventProxy, EventType, InlineSearchState, Mouse, PendingViMark,
+    SearchState, TouchPurpose,
 };
 #[cfg(unix)]
 use crate::logging::LOG_TARGET_IPC_CONFIG;
 use crate::message_bar::MessageBuffer;
-use crate::scheduler::Scheduler;
+use crate::pane::{Pane, PaneId, PaneRect, SplitDirection};
+use crate::scheduler::{Scheduler, TimerId, Topic};
+use crate::scrollback::{self, DumpFormat};
+use crate::tabs::{Tab, TabBar, TabSelection, Tabs};
 use crate::{input, renderer};
 
 /// Event context for one individual Alacritty window.
@@ -50,21 +53,23 @@ pub struct WindowContext {
     pub display: Display,
     pub dirty: bool,
     event_queue: Vec<WinitEvent<Event>>,
-    terminal: Arc<FairMutex<Term<EventProxy>>>,
+    panes: HashMap<PaneId, Pane, RandomState>,
+    tabs: Tabs,
+    mouse_pane: PaneId,
+    mouse_position: PhysicalPosition<f64>,
+    pane_area: PaneRect,
+    proxy: EventLoopProxy<Event>,
     cursor_blink_timed_out: bool,
     prev_bell_cmd: Option<Instant>,
+    prev_notification_cmd: Option<Instant>,
     modifiers: Modifiers,
     inline_search_state: InlineSearchState,
+    pending_vi_mark: Option<PendingViMark>,
     search_state: SearchState,
-    notifier: Notifier,
     mouse: Mouse,
     touch: TouchPurpose,
     occluded: bool,
     preserve_title: bool,
-    #[cfg(not(windows))]
-    master_fd: RawFd,
-    #[cfg(not(windows))]
-    shell_pid: u32,
     window_config: ParsedOptions,
     config: Rc<UiConfig>,
 }
@@ -149,10 +154,8 @@ impl WindowContext {
         // Check if new window will be opened as a tab.
         #[cfg(target_os = "macos")]
         let tabbed = options.window_tabbing_id.is_some();
-        #[cfg(target_os = "linux")]
-        let tabbed = options.window_tabbing_id.is_some();
-        #[cfg(not(any(target_os = "macos", target_os = "linux")))]
-        let tabbed = false; // Tabbing is only supported on macOS and Linux by default
+        #[cfg(not(target_os = "macos"))]
+        let tabbed = false;
 
         let display = Display::new(window, gl_context, &config, tabbed)?;
 
@@ -178,86 +181,285 @@ impl WindowContext {
 
         let preserve_title = options.window_identity.title.is_some();
 
-        info!(
-            "PTY dimensions: {:?} x {:?}",
-            display.size_info.screen_lines(),
-            display.size_info.columns()
-        );
-
-        let event_proxy = EventProxy::new(proxy, display.window.id());
+        // Create the initial pane, covering the entire window.
+        let size_info = display.size_info;
+        let rect = PaneRect::new(0., 0., size_info.width(), size_info.height());
+        let restore = options.terminal_options.restore_scrollback.as_deref();
+        let (pane_id, pane) =
+            Self::spawn_pane(&display, &config, &proxy, &pty_config, size_info, rect, restore)?;
 
-        // Create the terminal.
-        //
-        // This object contains all of the state about what's being displayed. It's
-        // wrapped in a clonable mutex since both the I/O loop and display need to
-        // access it.
-        let terminal = Term::new(config.term_options(), &display.size_info, event_proxy.clone());
-        let terminal = Arc::new(FairMutex::new(terminal));
-
-        // Create the PTY.
-        //
-        // The PTY forks a process to run the shell on the slave side of the
-        // pseudoterminal. A file descriptor for the master side is retained for
-        // reading/writing to the shell.
-        let pty = tty::new(&pty_config, display.size_info.into(), display.window.id().into())?;
-
-        #[cfg(not(windows))]
-        let master_fd = pty.file().as_raw_fd();
-        #[cfg(not(windows))]
-        let shell_pid = pty.child().id();
-
-        // Create the pseudoterminal I/O loop.
-        //
-        // PTY I/O is ran on another thread as to not occupy cycles used by the
-        // renderer and input processing. Note that access to the terminal state is
-        // synchronized since the I/O loop updates the state, and the display
-        // consumes it periodically.
-        let event_loop = PtyEventLoop::new(
-            Arc::clone(&terminal),
-            event_proxy.clone(),
-            pty,
-            pty_config.drain_on_exit,
-            config.debug.ref_test,
-        )?;
+        let mut panes = HashMap::default();
+        panes.insert(pane_id, pane);
 
-        // The event loop channel allows write requests from the event processor
-        // to be sent to the pty loop and ultimately written to the pty.
-        let loop_tx = event_loop.channel();
-
-        // Kick off the I/O thread.
-        let _io_thread = event_loop.spawn();
-
-        // Start cursor blinking, in case `Focused` isn't sent on startup.
-        if config.cursor.style().blinking {
-            event_proxy.send_event(TerminalEvent::CursorBlinkingChange.into());
-        }
+        let mouse = Mouse { bidi: config.terminal.bidi, ..Default::default() };
 
         // Create context for the Alacritty window.
         Ok(WindowContext {
             preserve_title,
-            terminal,
             display,
-            #[cfg(not(windows))]
-            master_fd,
-            #[cfg(not(windows))]
-            shell_pid,
             config,
-            notifier: Notifier(loop_tx),
+            panes,
+            proxy,
+            tabs: Tabs::new(Tab::new(pane_id)),
+            mouse_pane: pane_id,
+            pane_area: rect,
+            mouse_position: Default::default(),
             cursor_blink_timed_out: Default::default(),
             prev_bell_cmd: Default::default(),
+            prev_notification_cmd: Default::default(),
             inline_search_state: Default::default(),
+            pending_vi_mark: Default::default(),
             message_buffer: Default::default(),
             window_config: Default::default(),
             search_state: Default::default(),
             event_queue: Default::default(),
             modifiers: Default::default(),
             occluded: Default::default(),
-            mouse: Default::default(),
             touch: Default::default(),
+            mouse,
             dirty: Default::default(),
         })
     }
 
+    /// Start a new terminal inside this window.
+    fn spawn_pane(
+        display: &Display,
+        config: &UiConfig,
+        proxy: &EventLoopProxy<Event>,
+        pty_config: &PtyOptions,
+        size_info: SizeInfo,
+        rect: PaneRect,
+        restore: Option<&Path>,
+    ) -> Result<(PaneId, Pane), Box<dyn Error>> {
+        let pane_id = PaneId::next();
+        let window_id = display.window.id();
+        let event_proxy = EventProxy::new(proxy.clone(), window_id, pane_id);
+        let pane = Pane::new(config, pty_config, size_info, rect, event_proxy, window_id, restore)?;
+        Ok((pane_id, pane))
+    }
+
+    /// Start a new terminal next to the focused one, inheriting its working directory.
+    ///
+    /// The pane is created with the focused pane's dimensions and resized once it's laid out.
+    fn spawn_child_pane(&self) -> Result<(PaneId, Pane), Box<dyn Error>> {
+        let mut pty_config = self.config.pty_config();
+        pty_config.working_directory = self.working_directory();
+
+        let focused = &self.panes[&self.focused_pane()];
+        let (size_info, rect) = (focused.size_info, focused.rect);
+        Self::spawn_pane(
+            &self.display,
+            &self.config,
+            &self.proxy,
+            &pty_config,
+            size_info,
+            rect,
+            None,
+        )
+    }
+
+    /// Working directory of the focused pane.
+    pub fn working_directory(&self) -> Option<PathBuf> {
+        self.panes[&self.focused_pane()].working_directory()
+    }
+
+    /// Save the scrollback of the focused pane to a file on a background thread.
+    pub fn save_scrollback<F>(&self, format: DumpFormat, path: PathBuf, callback: F)
+    where
+        F: FnOnce(io::Result<()>) + Send + 'static,
+    {
+        let terminal = self.panes[&self.focused_pane()].terminal.lock();
+        scrollback::save_in_background(&terminal, format, path, callback);
+    }
+
+    /// Whether the window has keyboard focus.
+    fn is_focused(&self) -> bool {
+        self.panes[&self.focused_pane()].terminal.lock().is_focused
+    }
+
+    /// Pane receiving keyboard input.
+    #[inline]
+    fn focused_pane(&self) -> PaneId {
+        self.tabs.active().focused_pane
+    }
+
+    /// Split the focused pane, starting a new terminal next to it.
+    pub fn split_pane(&mut self, direction: SplitDirection) -> Result<(), Box<dyn Error>> {
+        let focused_pane = self.focused_pane();
+        let (pane_id, pane) = self.spawn_child_pane()?;
+
+        self.tabs.active_mut().layout.split(focused_pane, pane_id, direction);
+        self.panes.insert(pane_id, pane);
+
+        self.resize_panes();
+        self.focus_pane(pane_id);
+
+        Ok(())
+    }
+
+    /// Open a new tab after all existing ones.
+    pub fn create_tab(&mut self) -> Result<(), Box<dyn Error>> {
+        let focused_pane = self.focused_pane();
+        let (pane_id, pane) = self.spawn_child_pane()?;
+
+        self.panes.insert(pane_id, pane);
+        self.tabs.push(Tab::new(pane_id));
+
+        self.update_tab_bar();
+        self.resize_panes();
+        self.focus_changed(focused_pane);
+
+        Ok(())
+    }
+
+    /// Change the visible tab.
+    pub fn select_tab(&mut self, selection: TabSelection) {
+        let focused_pane = self.focused_pane();
+        if !self.tabs.select(selection) {
+            return;
+        }
+
+        self.update_tab_bar();
+        self.resize_panes();
+        self.focus_changed(focused_pane);
+    }
+
+    /// Close a pane after its terminal has exited.
+    ///
+    /// This will return `false` if the pane is the last one left in this window.
+    pub fn close_pane(&mut self, pane_id: PaneId) -> bool {
+        let index = match self.tabs.position(pane_id) {
+            Some(index) => index,
+            None => return true,
+        };
+
+        let focused_pane = self.focused_pane();
+        let tab = match self.tabs.get_mut(index) {
+            Some(tab) => tab,
+            None => return true,
+        };
+
+        if tab.layout.remove(pane_id) {
+            if tab.focused_pane == pane_id {
+                tab.focused_pane = tab.layout.panes()[0];
+            }
+        } else if self.tabs.remove(index).is_none() {
+            return false;
+        }
+
+        self.update_tab_bar();
+        self.resize_panes();
+
+        // Update focus before removing the pane, so its focus state can be transferred.
+        self.focus_changed(focused_pane);
+        self.panes.remove(&pane_id);
+
+        true
+    }
+
+    /// Direct keyboard input to a different pane of the active tab.
+    fn focus_pane(&mut self, pane_id: PaneId) {
+        let focused_pane = self.focused_pane();
+        self.tabs.active_mut().focused_pane = pane_id;
+        self.focus_changed(focused_pane);
+    }
+
+    /// Update the window after the focused pane might have changed.
+    ///
+    /// The window's focus state is transferred from the previously focused pane.
+    fn focus_changed(&mut self, old_pane_id: PaneId) {
+        let pane_id = self.focused_pane();
+
+        if pane_id != old_pane_id {
+            let is_focused =
+                self.panes.get(&old_pane_id).is_some_and(|pane| pane.set_focused(false));
+            self.panes[&pane_id].set_focused(is_focused);
+
+            // Search matches belong to the previously focused terminal.
+            self.search_state.clear_focused_match();
+        }
+
+        // Stop sending mouse input to panes which aren't visible anymore.
+        if !self.tabs.active().layout.panes().contains(&self.mouse_pane) {
+            self.mouse_pane = pane_id;
+        }
+
+        // Show the title of the focused pane.
+        if !self.preserve_title && self.config.window.dynamic_title {
+            self.display.window.set_title(self.pane_title(pane_id));
+        }
+
+        // Update cursor blinking for the new focused terminal.
+        let event = Event::new(TerminalEvent::CursorBlinkingChange.into(), None);
+        self.event_queue.push(event.into());
+
+        self.dirty = true;
+    }
+
+    /// Title of a pane's terminal.
+    fn pane_title(&self, pane_id: PaneId) -> String {
+        let title = self.panes.get(&pane_id).and_then(|pane| pane.title.clone());
+        title.unwrap_or_else(|| self.config.window.identity.title.clone())
+    }
+
+    /// Update the tab bar to the latest tab titles.
+    fn update_tab_bar(&mut self) {
+        let titles = self.tabs.iter().map(|tab| self.pane_title(tab.focused_pane)).collect();
+        self.display.tab_bar = TabBar { titles, active: self.tabs.active_index() };
+        self.dirty = true;
+    }
+
+    /// Update the position and dimensions of all visible panes.
+    fn resize_panes(&mut self) {
+        let size_info = self.display.size_info;
+        let layout = &self.tabs.active().layout;
+
+        if layout.is_single() && !self.display.tab_bar.is_visible() {
+            self.pane_area = PaneRect::new(0., 0., size_info.width(), size_info.height());
+            if let Some(pane) = self.panes.get_mut(&self.tabs.active().focused_pane) {
+                pane.resize(size_info, self.pane_area);
+            }
+            return;
+        }
+
+        // Don't cover the tab bar above and the message and search bars below the terminal.
+        let reserved =
+            self.message_buffer.message().is_some() || self.search_state.regex().is_some();
+        let bottom = if reserved {
+            size_info.padding_y() + size_info.screen_lines() as f32 * size_info.cell_height()
+        } else {
+            size_info.height()
+        };
+        let top = self.display.tab_bar.height(&size_info);
+        self.pane_area = PaneRect::new(0., top, size_info.width(), bottom - top);
+
+        let scale_factor = self.display.window.scale_factor as f32;
+        let (padding_x, padding_y) = self.config.window.padding(scale_factor);
+        let dynamic_padding = self.config.window.dynamic_padding;
+        let timestamp_gutter = timestamp::gutter_width(self.config.scrolling.timestamps);
+        for (pane_id, rect) in layout.rects(self.pane_area, Self::border_width(scale_factor)) {
+            let mut pane_size = SizeInfo::new(
+                rect.width,
+                rect.height,
+                size_info.cell_width(),
+                size_info.cell_height(),
+                padding_x,
+                padding_y,
+                dynamic_padding,
+            );
+            pane_size.reserve_columns(timestamp_gutter);
+
+            if let Some(pane) = self.panes.get_mut(&pane_id) {
+                pane.resize(pane_size, rect);
+            }
+        }
+    }
+
+    /// Width of the border between two panes.
+    fn border_width(scale_factor: f32) -> f32 {
+        scale_factor.round().max(1.)
+    }
+
     /// Update the terminal window to the latest config.
     pub fn update_config(&mut self, new_config: Rc<UiConfig>) {
         let old_config = mem::replace(&mut self.config, new_config);
@@ -266,7 +468,10 @@ impl WindowContext {
         self.config = self.window_config.override_config_rc(self.config.clone());
 
         self.display.update_config(&self.config);
-        self.terminal.lock().set_options(self.config.term_options());
+        self.mouse.bidi = self.config.terminal.bidi;
+        for pane in self.panes.values() {
+            pane.terminal.lock().set_options(self.config.term_options());
+        }
 
         // Reload cursor if its thickness has changed.
         if (old_config.cursor.thickness() - self.config.cursor.thickness()).abs() > f32::EPSILON {
@@ -287,11 +492,12 @@ impl WindowContext {
         // Always reload the theme to account for auto-theme switching.
         self.display.window.set_theme(self.config.window.theme());
 
-        // Update display if either padding options or resize increments were changed.
+        // Update display if padding options, resize increments or the timestamp gutter changed.
         let window_config = &old_config.window;
         if window_config.padding(1.) != self.config.window.padding(1.)
             || window_config.dynamic_padding != self.config.window.dynamic_padding
             || window_config.resize_increments != self.config.window.resize_increments
+            || old_config.scrolling.timestamps != self.config.scrolling.timestamps
         {
             self.display.pending_update.dirty = true;
         }
@@ -330,6 +536,10 @@ impl WindowContext {
         let event = Event::new(TerminalEvent::CursorBlinkingChange.into(), None);
         self.event_queue.push(event.into());
 
+        // Update text blinking interval.
+        let event = Event::new(EventType::TextBlinkingChange, None);
+        self.event_queue.push(event.into());
+
         self.dirty = true;
     }
 
@@ -388,14 +598,41 @@ impl WindowContext {
         }
 
         // Redraw the window.
-        let terminal = self.terminal.lock();
+        let Tab { layout, focused_pane } = self.tabs.active();
+        let panes = layout
+            .panes()
+            .into_iter()
+            .map(|pane_id| {
+                let pane = &self.panes[&pane_id];
+                PaneFrame {
+                    terminal: pane.terminal.lock(),
+                    size_info: pane.size_info,
+                    rect: pane.rect,
+                    focused: pane_id == *focused_pane,
+                }
+            })
+            .collect();
+        let scale_factor = self.display.window.scale_factor as f32;
+        let borders = layout.borders(self.pane_area, Self::border_width(scale_factor));
         self.display.draw(
-            terminal,
+            panes,
+            &borders,
             scheduler,
             &self.message_buffer,
             &self.config,
             &mut self.search_state,
         );
+
+        // Start blinking once blinking text is drawn.
+        let timer_id = TimerId::new(Topic::BlinkText, self.display.window.id());
+        if self.display.text_blinking
+            && self.config.terminal.text_blink_interval().is_some()
+            && self.is_focused()
+            && !scheduler.scheduled(timer_id)
+        {
+            let event = Event::new(EventType::TextBlinkingChange, None);
+            self.event_queue.push(event.into());
+        }
     }
 
     /// Process events for this terminal window.
@@ -423,28 +660,265 @@ impl WindowContext {
             },
         }
 
-        let mut terminal = self.terminal.lock();
-
         let old_is_searching = self.search_state.history_index.is_some();
 
+        // Route events to their panes, processing consecutive events for the same pane at once.
+        let mut batch = Vec::new();
+        let mut batch_pane = self.focused_pane();
+        for mut event in mem::take(&mut self.event_queue) {
+            let pane_id = match self.route_event(&mut event) {
+                Some(pane_id) => pane_id,
+                None => continue,
+            };
+
+            // Mouse clicks focus the pane below the cursor.
+            let is_press = matches!(event, WinitEvent::WindowEvent {
+                event: WindowEvent::MouseInput { state: ElementState::Pressed, .. },
+                ..
+            });
+            let refocus = is_press && pane_id != self.focused_pane();
+
+            if (pane_id != batch_pane || refocus) && !batch.is_empty() {
+                self.process_events(
+                    batch_pane,
+                    mem::take(&mut batch),
+                    #[cfg(target_os = "macos")]
+                    event_loop,
+                    event_proxy,
+                    clipboard,
+                    scheduler,
+                );
+            }
+
+            if refocus {
+                self.focus_pane(pane_id);
+            }
+
+            batch_pane = pane_id;
+            batch.push(event);
+        }
+        if !batch.is_empty() {
+            self.process_events(
+                batch_pane,
+                batch,
+                #[cfg(target_os = "macos")]
+                event_loop,
+                event_proxy,
+                clipboard,
+                scheduler,
+            );
+        }
+
+        // Events queued while focusing panes are handled with the next batch.
+        if !self.event_queue.is_empty() {
+            self.dirty = true;
+        }
+
+        // Process DisplayUpdate events.
+        if self.display.pending_update.dirty {
+            self.submit_display_update(old_is_searching);
+            self.dirty = true;
+        }
+
+        if self.dirty || self.mouse.hint_highlight_dirty {
+            let focused = &self.panes[&self.focused_pane()];
+            let terminal = focused.terminal.lock();
+            let mouse = (self.mouse_pane == self.focused_pane()).then_some(&self.mouse);
+            self.dirty |= self.display.update_highlighted_hints(
+                &terminal,
+                &self.config,
+                mouse,
+                &focused.size_info,
+                self.modifiers.state(),
+            );
+            self.mouse.hint_highlight_dirty = false;
+        }
+
+        // Don't call `request_redraw` when event is `RedrawRequested` since the `dirty` flag
+        // represents the current frame, but redraw is for the next frame.
+        if self.dirty
+            && self.display.window.has_frame
+            && !self.occluded
+            && !matches!(event, WinitEvent::WindowEvent { event: WindowEvent::RedrawRequested, .. })
+        {
+            self.display.window.request_redraw();
+        }
+    }
+
+    /// Find the pane an event should be handled by.
+    ///
+    /// Mouse positions are translated to be relative to the pane. Events which should be ignored
+    /// will return `None`.
+    fn route_event(&mut self, event: &mut WinitEvent<Event>) -> Option<PaneId> {
+        match event {
+            WinitEvent::WindowEvent {
+                event: WindowEvent::CursorMoved { position, .. }, ..
+            } => {
+                self.mouse_position = *position;
+
+                // Keep sending events to the same pane while a button is held down.
+                let mouse = &self.mouse;
+                let dragging = mouse.left_button_state == ElementState::Pressed
+                    || mouse.middle_button_state == ElementState::Pressed
+                    || mouse.right_button_state == ElementState::Pressed;
+                if !dragging {
+                    let panes = self.tabs.active().layout.panes();
+                    let pane = panes
+                        .into_iter()
+                        .find(|pane_id| self.panes[pane_id].rect.contains(position.x, position.y));
+                    if let Some(pane_id) = pane {
+                        self.mouse_pane = pane_id;
+                    }
+                }
+
+                let rect = self.panes[&self.mouse_pane].rect;
+                *position =
+                    PhysicalPosition::new(position.x - rect.x as f64, position.y - rect.y as f64);
+
+                Some(self.mouse_pane)
+            },
+            WinitEvent::WindowEvent {
+                event: WindowEvent::MouseInput { state: ElementState::Pressed, .. },
+                ..
+            } if self.mouse_position.y
+                < self.display.tab_bar.height(&self.display.size_info) as f64 =>
+            {
+                // Select tabs by clicking on them.
+                let size_info = &self.display.size_info;
+                let column =
+                    (self.mouse_position.x as f32 - size_info.padding_x()) / size_info.cell_width();
+                let index = self.display.tab_bar.tab_at(size_info, column.max(0.) as usize);
+                self.select_tab(TabSelection::Index(index));
+
+                None
+            },
+            WinitEvent::WindowEvent {
+                event: WindowEvent::MouseInput { .. } | WindowEvent::MouseWheel { .. },
+                ..
+            } => Some(self.mouse_pane),
+            WinitEvent::WindowEvent { event: WindowEvent::CloseRequested, .. } => {
+                // Close all other panes with the window.
+                for (pane_id, pane) in &self.panes {
+                    if *pane_id != self.focused_pane() {
+                        pane.terminal.lock().exit();
+                    }
+                }
+
+                Some(self.focused_pane())
+            },
+            // Blinking text is shared by all visible panes.
+            WinitEvent::UserEvent(event) if matches!(event.payload(), EventType::BlinkText) => {
+                self.blink_text();
+                None
+            },
+            WinitEvent::UserEvent(event)
+                if matches!(event.payload(), EventType::TimestampRefresh) =>
+            {
+                self.dirty = true;
+                None
+            },
+            WinitEvent::UserEvent(event) => {
+                let pane_id = match event.pane_id() {
+                    Some(pane_id) if self.panes.contains_key(&pane_id) => pane_id,
+                    Some(_) => return None,
+                    None => return Some(self.focused_pane()),
+                };
+
+                match event.payload() {
+                    EventType::Terminal(TerminalEvent::Title(title)) => {
+                        self.panes.get_mut(&pane_id)?.title = Some(title.clone());
+                        self.update_tab_bar();
+                        (pane_id == self.focused_pane()).then_some(pane_id)
+                    },
+                    EventType::Terminal(TerminalEvent::ResetTitle) => {
+                        self.panes.get_mut(&pane_id)?.title = None;
+                        self.update_tab_bar();
+                        (pane_id == self.focused_pane()).then_some(pane_id)
+                    },
+                    EventType::Terminal(TerminalEvent::WorkingDirectory(path)) => {
+                        self.panes.get_mut(&pane_id)?.reported_directory = Some(path.clone());
+                        None
+                    },
+                    EventType::Terminal(TerminalEvent::PointerShape(shape)) => {
+                        let pointer_shape = shape.as_deref().and_then(input::pointer_shape);
+                        self.panes.get_mut(&pane_id)?.pointer_shape = pointer_shape;
+                        (pane_id == self.mouse_pane).then_some(pane_id)
+                    },
+                    EventType::Terminal(TerminalEvent::Notification { .. })
+                        if self.config.notification.ignore_focused && self.is_focused() =>
+                    {
+                        None
+                    },
+                    // Cursor state is tied to the focused pane and mouse position.
+                    EventType::Terminal(TerminalEvent::CursorBlinkingChange) => {
+                        Some(self.focused_pane())
+                    },
+                    EventType::Terminal(TerminalEvent::MouseCursorDirty) => Some(self.mouse_pane),
+                    _ => Some(pane_id),
+                }
+            },
+            _ => Some(self.focused_pane()),
+        }
+    }
+
+    /// Toggle the visibility of blinking text.
+    fn blink_text(&mut self) {
+        self.display.text_blink_hidden ^= true;
+
+        let mut blinking = false;
+        for pane_id in self.tabs.active().layout.panes() {
+            let terminal = self.panes[&pane_id].terminal.lock();
+            blinking |= self.display.damage_blinking_text(&terminal);
+        }
+        self.dirty |= blinking;
+
+        // Stop the timer once all blinking text is gone.
+        if !blinking {
+            self.display.text_blinking = false;
+            let event = Event::new(EventType::TextBlinkingChange, None);
+            self.event_queue.push(event.into());
+        }
+    }
+
+    /// Process a batch of events for a single pane.
+    fn process_events(
+        &mut self,
+        pane_id: PaneId,
+        events: Vec<WinitEvent<Event>>,
+        #[cfg(target_os = "macos")] event_loop: &ActiveEventLoop,
+        event_proxy: &EventLoopProxy<Event>,
+        clipboard: &mut Clipboard,
+        scheduler: &mut Scheduler,
+    ) {
+        let pane = match self.panes.get_mut(&pane_id) {
+            Some(pane) => pane,
+            None => return,
+        };
+        let mut terminal = pane.terminal.lock();
+
         let context = ActionContext {
             cursor_blink_timed_out: &mut self.cursor_blink_timed_out,
             prev_bell_cmd: &mut self.prev_bell_cmd,
+            prev_notification_cmd: &mut self.prev_notification_cmd,
             message_buffer: &mut self.message_buffer,
             inline_search_state: &mut self.inline_search_state,
+            pending_vi_mark: &mut self.pending_vi_mark,
             search_state: &mut self.search_state,
             modifiers: &mut self.modifiers,
-            notifier: &mut self.notifier,
+            notifier: &mut pane.notifier,
             display: &mut self.display,
+            size_info: pane.size_info,
             mouse: &mut self.mouse,
             touch: &mut self.touch,
             dirty: &mut self.dirty,
             occluded: &mut self.occluded,
             terminal: &mut terminal,
             #[cfg(not(windows))]
-            master_fd: self.master_fd,
+            master_fd: pane.master_fd,
             #[cfg(not(windows))]
-            shell_pid: self.shell_pid,
+            shell_pid: pane.shell_pid,
+            reported_directory: pane.reported_directory.as_deref(),
+            pointer_shape: pane.pointer_shape,
             preserve_title: self.preserve_title,
             config: &self.config,
             event_proxy,
@@ -455,43 +929,9 @@ impl WindowContext {
         };
         let mut processor = input::Processor::new(context);
 
-        for event in self.event_queue.drain(..) {
+        for event in events {
             processor.handle_event(event);
         }
-
-        // Process DisplayUpdate events.
-        if self.display.pending_update.dirty {
-            Self::submit_display_update(
-                &mut terminal,
-                &mut self.display,
-                &mut self.notifier,
-                &self.message_buffer,
-                &mut self.search_state,
-                old_is_searching,
-                &self.config,
-            );
-            self.dirty = true;
-        }
-
-        if self.dirty || self.mouse.hint_highlight_dirty {
-            self.dirty |= self.display.update_highlighted_hints(
-                &terminal,
-                &self.config,
-                &self.mouse,
-                self.modifiers.state(),
-            );
-            self.mouse.hint_highlight_dirty = false;
-        }
-
-        // Don't call `request_redraw` when event is `RedrawRequested` since the `dirty` flag
-        // represents the current frame, but redraw is for the next frame.
-        if self.dirty
-            && self.display.window.has_frame
-            && !self.occluded
-            && !matches!(event, WinitEvent::WindowEvent { event: WindowEvent::RedrawRequested, .. })
-        {
-            self.display.window.request_redraw();
-        }
     }
 
     /// ID of this terminal context.
@@ -502,7 +942,7 @@ impl WindowContext {
     /// Write the ref test results to the disk.
     pub fn write_ref_test_results(&self) {
         // Dump grid state.
-        let mut grid = self.terminal.lock().grid().clone();
+        let mut grid = self.panes[&self.focused_pane()].terminal.lock().grid().clone();
         grid.initialize_all();
         grid.truncate();
 
@@ -528,29 +968,25 @@ impl WindowContext {
     }
 
     /// Submit the pending changes to the `Display`.
-    fn submit_display_update(
-        terminal: &mut Term<EventProxy>,
-        display: &mut Display,
-        notifier: &mut Notifier,
-        message_buffer: &MessageBuffer,
-        search_state: &mut SearchState,
-        old_is_searching: bool,
-        config: &UiConfig,
-    ) {
+    fn submit_display_update(&mut self, old_is_searching: bool) {
         // Compute cursor positions before resize.
+        let terminal = self.panes[&self.focused_pane()].terminal.lock();
         let num_lines = terminal.screen_lines();
         let cursor_at_bottom = terminal.grid().cursor.point.line + 1 == num_lines;
         let origin_at_bottom = if terminal.mode().contains(TermMode::VI) {
             terminal.vi_mode_cursor.point.line == num_lines - 1
         } else {
-            search_state.direction == Direction::Left
+            self.search_state.direction == Direction::Left
         };
+        drop(terminal);
 
-        display.handle_update(terminal, notifier, message_buffer, search_state, config);
+        self.display.handle_update(&self.message_buffer, &mut self.search_state, &self.config);
+        self.resize_panes();
 
-        let new_is_searching = search_state.history_index.is_some();
+        let new_is_searching = self.search_state.history_index.is_some();
         if !old_is_searching && new_is_searching {
             // Scroll on search start to make sure origin is visible with minimal viewport motion.
+            let mut terminal = self.panes[&self.focused_pane()].terminal.lock();
             let display_offset = terminal.grid().display_offset();
             if display_offset == 0 && cursor_at_bottom && !origin_at_bottom {
                 terminal.scroll_display(Scroll::Delta(1));
@@ -560,10 +996,3 @@ impl WindowContext {
         }
     }
 }
-
-impl Drop for WindowContext {
-    fn drop(&mut self) {
-        // Shutdown the terminal's PTY.
-        let _ = self.notifier.0.send(Msg::Shutdown);
-    }
-}
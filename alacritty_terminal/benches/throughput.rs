@@ -0,0 +1,54 @@
+//! Parsing throughput while lines are scrolled into the history.
+//!
+//! Run with `cargo bench --bench throughput`.
+
+use std::hint::black_box;
+use std::time::{Duration, Instant};
+
+use alacritty_terminal::event::VoidListener;
+use alacritty_terminal::parser::Processor;
+use alacritty_terminal::term::test::TermSize;
+use alacritty_terminal::term::{Config, Term};
+
+/// Number of lines written in every run.
+const LINES: usize = 400_000;
+
+/// Number of runs, of which the fastest is reported.
+const RUNS: usize = 5;
+
+fn main() {
+    let mut output = Vec::new();
+    for i in 0..LINES {
+        let line =
+            format!("\x1b[32m{i:>8}\x1b[0m lorem ipsum dolor sit amet, consectetur adipiscing\r\n");
+        output.extend_from_slice(line.as_bytes());
+    }
+
+    let configs = [
+        ("default history", Config::default()),
+        ("1M lines of history", Config { scrolling_history: 1_000_000, ..Default::default() }),
+        ("1M lines with 8MiB in memory", Config {
+            scrolling_history: 1_000_000,
+            scrolling_memory_limit: Some(8 * 1024 * 1024),
+            ..Default::default()
+        }),
+    ];
+
+    for (name, config) in configs {
+        let fastest = (0..RUNS).map(|_| run(config.clone(), &output)).min().unwrap();
+        let mib_per_sec = output.len() as f64 / fastest.as_secs_f64() / 1024. / 1024.;
+        println!("{name:<30}{:>8.0}ms{mib_per_sec:>10.1}MiB/s", fastest.as_secs_f64() * 1000.);
+    }
+}
+
+fn run(config: Config, output: &[u8]) -> Duration {
+    let mut term = Term::new(config, &TermSize::new(80, 24), VoidListener);
+    let mut parser: Processor = Processor::new();
+
+    let start = Instant::now();
+    parser.advance(&mut term, output);
+    let elapsed = start.elapsed();
+
+    black_box(&term);
+    elapsed
+}
@@ -1,5 +1,6 @@
 use std::borrow::Cow;
 use std::fmt::{self, Debug, Formatter};
+use std::path::PathBuf;
 use std::sync::Arc;
 
 use crate::term::ClipboardType;
@@ -20,6 +21,9 @@ pub enum Event {
     /// Reset to the default window title.
     ResetTitle,
 
+    /// Working directory reported by the shell.
+    WorkingDirectory(PathBuf),
+
     /// Request to store a text string in the clipboard.
     ClipboardStore(ClipboardType, String),
 
@@ -44,12 +48,22 @@ pub enum Event {
     /// Cursor blinking state has changed.
     CursorBlinkingChange,
 
+    /// Mouse pointer shape requested by the application.
+    ///
+    /// The shape is a CSS cursor name, `None` resets the pointer to its default shape.
+    PointerShape(Option<String>),
+
     /// New terminal content available.
     Wakeup,
 
     /// Terminal bell ring.
     Bell,
 
+    /// Desktop notification request.
+    ///
+    /// The title is empty if the application only provided a body.
+    Notification { title: String, body: String },
+
     /// Shutdown request.
     Exit,
 
@@ -68,9 +82,12 @@ impl Debug for Event {
             Event::Title(title) => write!(f, "Title({title})"),
             Event::CursorBlinkingChange => write!(f, "CursorBlinkingChange"),
             Event::MouseCursorDirty => write!(f, "MouseCursorDirty"),
+            Event::PointerShape(shape) => write!(f, "PointerShape({shape:?})"),
             Event::ResetTitle => write!(f, "ResetTitle"),
+            Event::WorkingDirectory(path) => write!(f, "WorkingDirectory({path:?})"),
             Event::Wakeup => write!(f, "Wakeup"),
             Event::Bell => write!(f, "Bell"),
+            Event::Notification { title, body } => write!(f, "Notification({title}, {body})"),
             Event::Exit => write!(f, "Exit"),
             Event::ChildExit(code) => write!(f, "ChildExit({code})"),
         }
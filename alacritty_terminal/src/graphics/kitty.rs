@@ -0,0 +1,701 @@
+//! Kitty graphics protocol.
+//!
+//! Commands are received as `ESC _ G <control data> ; <payload> ESC \` APC strings. The control
+//! data is a comma-separated list of `key=value` pairs and the payload is base64 encoded.
+//!
+//! See <https://sw.kovidgoyal.net/kitty/graphics-protocol/> for the protocol specification.
+
+use std::borrow::Cow;
+use std::collections::HashMap;
+use std::ffi::OsStr;
+use std::fmt::{self, Display, Formatter};
+use std::fs::{self, File};
+use std::io::{Read, Seek, SeekFrom};
+use std::path::{Component, Path};
+use std::sync::Arc;
+use std::{env, str};
+
+use base64::engine::DecodePaddingMode;
+use base64::engine::general_purpose::{GeneralPurpose, PAD};
+use base64::{Engine, alphabet};
+use flate2::read::ZlibDecoder;
+use log::debug;
+
+use crate::graphics::{Graphic, Placement};
+
+/// Maximum width and height of an image in pixels.
+const MAX_SIZE: usize = 10_000;
+
+/// Maximum number of bytes transmitted for a single image.
+const MAX_DATA_SIZE: usize = 0x1000_0000;
+
+/// Maximum number of bytes used by all stored images.
+///
+/// When the quota is exceeded, the oldest images are removed first.
+const STORAGE_QUOTA: usize = 0x1400_0000;
+
+/// Base64 engine accepting payloads with and without padding.
+const BASE64: GeneralPurpose = GeneralPurpose::new(
+    &alphabet::STANDARD,
+    PAD.with_decode_padding_mode(DecodePaddingMode::Indifferent),
+);
+
+/// Graphics command.
+///
+/// The fields correspond to the keys of the control data, unknown keys are ignored.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Command {
+    /// Action to perform (`a`).
+    pub action: u8,
+
+    /// Suppress `OK` responses with `1` and all responses with `2` (`q`).
+    pub quiet: u8,
+
+    /// Pixel format, `24` for RGB, `32` for RGBA and `100` for PNG (`f`).
+    pub format: u32,
+
+    /// Transmission medium, `d` for direct and `t` for temporary files (`t`).
+    pub medium: u8,
+
+    /// Compression of the transmitted data, `z` for zlib (`o`).
+    pub compression: Option<u8>,
+
+    /// Image dimensions of RGB and RGBA data (`s` and `v`).
+    pub width: usize,
+    pub height: usize,
+
+    /// Number of bytes and offset to read from a file (`S` and `O`).
+    pub size: usize,
+    pub offset: usize,
+
+    /// Image ID (`i`).
+    pub image_id: u32,
+
+    /// Image number, IDs are assigned by the terminal for numbered images (`I`).
+    pub image_number: u32,
+
+    /// Placement ID (`p`).
+    pub placement_id: u32,
+
+    /// Whether more chunks of this command follow (`m`).
+    pub more: bool,
+
+    /// Displayed region of the image (`x`, `y`, `w` and `h`).
+    ///
+    /// When deleting, `x` and `y` are used as one-based cell coordinates instead.
+    pub source_x: usize,
+    pub source_y: usize,
+    pub source_width: usize,
+    pub source_height: usize,
+
+    /// Pixel offset inside the first cell (`X` and `Y`).
+    pub offset_x: usize,
+    pub offset_y: usize,
+
+    /// Number of cells the image is scaled to (`c` and `r`).
+    pub columns: usize,
+    pub lines: usize,
+
+    /// Stacking order (`z`).
+    pub z_index: i32,
+
+    /// Whether the cursor is moved after displaying the image (`C`).
+    pub move_cursor: bool,
+
+    /// Placements which should be deleted (`d`).
+    pub delete: u8,
+
+    /// Decoded payload.
+    pub payload: Vec<u8>,
+
+    /// Whether the payload of a chunked transmission was discarded for exceeding the size limit.
+    pub truncated: bool,
+}
+
+impl Default for Command {
+    fn default() -> Self {
+        Self {
+            action: b't',
+            format: 32,
+            medium: b'd',
+            move_cursor: true,
+            delete: b'a',
+            quiet: Default::default(),
+            compression: Default::default(),
+            width: Default::default(),
+            height: Default::default(),
+            size: Default::default(),
+            offset: Default::default(),
+            image_id: Default::default(),
+            image_number: Default::default(),
+            placement_id: Default::default(),
+            more: Default::default(),
+            source_x: Default::default(),
+            source_y: Default::default(),
+            source_width: Default::default(),
+            source_height: Default::default(),
+            offset_x: Default::default(),
+            offset_y: Default::default(),
+            columns: Default::default(),
+            lines: Default::default(),
+            z_index: Default::default(),
+            payload: Default::default(),
+            truncated: Default::default(),
+        }
+    }
+}
+
+impl Command {
+    /// Parse the APC string following the `G` prefix.
+    pub fn parse(data: &[u8]) -> Option<Self> {
+        let (control, payload) = match data.iter().position(|&b| b == b';') {
+            Some(index) => (&data[..index], &data[index + 1..]),
+            None => (data, &[][..]),
+        };
+
+        let mut command = Self::default();
+        for kv in control.split(|&b| b == b',').filter(|kv| !kv.is_empty()) {
+            let [key, b'=', value @ ..] = kv else {
+                return None;
+            };
+
+            let number = || str::from_utf8(value).ok()?.parse::<u32>().ok();
+            match key {
+                b'a' => command.action = *value.first()?,
+                b'q' => command.quiet = number()? as u8,
+                b'f' => command.format = number()?,
+                b't' => command.medium = *value.first()?,
+                b'o' => command.compression = value.first().copied(),
+                b's' => command.width = number()? as usize,
+                b'v' => command.height = number()? as usize,
+                b'S' => command.size = number()? as usize,
+                b'O' => command.offset = number()? as usize,
+                b'i' => command.image_id = number()?,
+                b'I' => command.image_number = number()?,
+                b'p' => command.placement_id = number()?,
+                b'm' => command.more = number()? == 1,
+                b'x' => command.source_x = number()? as usize,
+                b'y' => command.source_y = number()? as usize,
+                b'w' => command.source_width = number()? as usize,
+                b'h' => command.source_height = number()? as usize,
+                b'X' => command.offset_x = number()? as usize,
+                b'Y' => command.offset_y = number()? as usize,
+                b'c' => command.columns = number()? as usize,
+                b'r' => command.lines = number()? as usize,
+                b'z' => command.z_index = str::from_utf8(value).ok()?.parse().ok()?,
+                b'C' => command.move_cursor = number()? != 1,
+                b'd' => command.delete = *value.first()?,
+                _ => (),
+            }
+        }
+
+        command.payload = BASE64.decode(payload).ok()?;
+
+        Some(command)
+    }
+}
+
+/// Failure reported to the client.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub struct Error {
+    code: &'static str,
+    message: &'static str,
+}
+
+impl Error {
+    pub const fn new(code: &'static str, message: &'static str) -> Self {
+        Self { code, message }
+    }
+}
+
+impl Display for Error {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        write!(f, "{}:{}", self.code, self.message)
+    }
+}
+
+/// Image stored by the terminal.
+#[derive(Debug)]
+struct Image {
+    graphic: Arc<Graphic>,
+    number: u32,
+
+    /// Insertion order, used to evict the oldest images first.
+    generation: u64,
+}
+
+/// Images transmitted by the client.
+#[derive(Debug, Default)]
+pub struct KittyGraphics {
+    images: HashMap<u32, Image>,
+
+    /// Command whose remaining chunks have not been received yet.
+    chunked: Option<Command>,
+
+    /// Bytes used by all stored images.
+    memory: usize,
+
+    /// Counter for image insertion order.
+    generation: u64,
+
+    /// Last automatically assigned image ID.
+    last_id: u32,
+}
+
+impl KittyGraphics {
+    /// Combine the chunks of a transmission.
+    ///
+    /// Returns the complete command once its last chunk has been received.
+    pub fn assemble(&mut self, mut command: Command) -> Option<Command> {
+        let mut first = match self.chunked.take() {
+            Some(first) => first,
+            None if command.more => {
+                self.chunked = Some(command);
+                return None;
+            },
+            None => return Some(command),
+        };
+
+        if first.payload.len() + command.payload.len() > MAX_DATA_SIZE {
+            first.payload = Vec::new();
+            first.truncated = true;
+        } else if !first.truncated {
+            first.payload.append(&mut command.payload);
+        }
+
+        if command.more {
+            self.chunked = Some(first);
+            None
+        } else {
+            Some(first)
+        }
+    }
+
+    /// Load and store an image, returning its ID.
+    pub fn transmit(&mut self, command: &Command) -> Result<(u32, Arc<Graphic>), Error> {
+        if command.image_id != 0 && command.image_number != 0 {
+            return Err(Error::new("EINVAL", "image ID and number are mutually exclusive"));
+        }
+
+        let graphic = Arc::new(self.load(command)?);
+
+        let image_id = match command.image_id {
+            0 => self.next_id(),
+            image_id => image_id,
+        };
+
+        self.remove_image(image_id);
+        self.memory += graphic.pixels.len();
+        self.generation += 1;
+        let number = command.image_number;
+        let image = Image { graphic: graphic.clone(), number, generation: self.generation };
+        self.images.insert(image_id, image);
+
+        // Evict the oldest images until the quota is met again.
+        while self.memory > STORAGE_QUOTA {
+            let oldest = self.images.iter().min_by_key(|(_, image)| image.generation);
+            match oldest.map(|(id, _)| *id) {
+                Some(id) if id != image_id => self.remove_image(id),
+                _ => break,
+            }
+        }
+
+        Ok((image_id, graphic))
+    }
+
+    /// Load the image described by a command, without storing it.
+    pub fn load(&self, command: &Command) -> Result<Graphic, Error> {
+        if command.truncated {
+            return Err(Error::new("EFBIG", "image data is too large"));
+        }
+
+        let data = match command.medium {
+            b'd' => Cow::Borrowed(&command.payload),
+            b't' => Cow::Owned(read_temporary_file(command)?),
+            _ => return Err(Error::new("EINVAL", "unsupported transmission medium")),
+        };
+
+        let data = match command.compression {
+            None => data,
+            Some(b'z') => Cow::Owned(inflate(&data)?),
+            Some(_) => return Err(Error::new("EINVAL", "unsupported compression")),
+        };
+
+        decode(command, &data)
+    }
+
+    /// Find the image referenced by a command's ID or number.
+    ///
+    /// Numbers always refer to the most recently transmitted image with that number.
+    pub fn image(&self, command: &Command) -> Result<(u32, Arc<Graphic>), Error> {
+        let image = match (command.image_id, command.image_number) {
+            (0, 0) => None,
+            (0, number) => self
+                .images
+                .iter()
+                .filter(|(_, image)| image.number == number)
+                .max_by_key(|(_, image)| image.generation),
+            (image_id, _) => self.images.get_key_value(&image_id),
+        };
+
+        image
+            .map(|(id, image)| (*id, image.graphic.clone()))
+            .ok_or(Error::new("ENOENT", "image not found"))
+    }
+
+    /// Free the data of an image.
+    ///
+    /// Placements of the image stay visible until they are removed from the grid.
+    pub fn remove_image(&mut self, image_id: u32) {
+        if let Some(image) = self.images.remove(&image_id) {
+            self.memory -= image.graphic.pixels.len();
+        }
+    }
+
+    /// Number of stored images.
+    pub fn len(&self) -> usize {
+        self.images.len()
+    }
+
+    /// Whether no images are stored.
+    pub fn is_empty(&self) -> bool {
+        self.images.is_empty()
+    }
+
+    /// Unused ID for images transmitted without one.
+    fn next_id(&mut self) -> u32 {
+        loop {
+            self.last_id = self.last_id.wrapping_add(1);
+            if self.last_id != 0 && !self.images.contains_key(&self.last_id) {
+                return self.last_id;
+            }
+        }
+    }
+}
+
+/// Create the placement for displaying an image.
+pub fn placement(
+    command: &Command,
+    image_id: u32,
+    graphic: Arc<Graphic>,
+    (cell_width, cell_height): (usize, usize),
+) -> Placement {
+    let source_x = command.source_x.min(graphic.width);
+    let source_y = command.source_y.min(graphic.height);
+    let source_width = match command.source_width {
+        0 => graphic.width - source_x,
+        width => width.min(graphic.width - source_x),
+    };
+    let source_height = match command.source_height {
+        0 => graphic.height - source_y,
+        height => height.min(graphic.height - source_y),
+    };
+
+    // Scale to the requested cells, keeping the aspect ratio when only one dimension is given.
+    let (width, height) = match (command.columns, command.lines) {
+        (0, 0) => (source_width, source_height),
+        (columns, 0) => {
+            let width = columns * cell_width;
+            (width, source_height * width / source_width.max(1))
+        },
+        (0, lines) => {
+            let height = lines * cell_height;
+            (source_width * height / source_height.max(1), height)
+        },
+        (columns, lines) => (columns * cell_width, lines * cell_height),
+    };
+
+    let offset_x = command.offset_x.min(cell_width - 1);
+    let offset_y = command.offset_y.min(cell_height - 1);
+    let columns = match command.columns {
+        0 => (offset_x + width).div_ceil(cell_width),
+        columns => columns,
+    };
+    let lines = match command.lines {
+        0 => (offset_y + height).div_ceil(cell_height),
+        lines => lines,
+    };
+
+    Placement {
+        graphic,
+        image_id,
+        placement_id: command.placement_id,
+        source_x,
+        source_y,
+        source_width,
+        source_height,
+        offset_x,
+        offset_y,
+        width,
+        height,
+        columns,
+        lines,
+        z_index: command.z_index,
+    }
+}
+
+/// Response to a command.
+///
+/// Clients only receive responses when they identified the image with an ID or number.
+pub fn response(command: &Command, image_id: u32, result: Result<(), Error>) -> Option<String> {
+    if (command.image_id == 0 && command.image_number == 0)
+        || (result.is_ok() && command.quiet >= 1)
+        || command.quiet >= 2
+    {
+        return None;
+    }
+
+    let mut keys = Vec::new();
+    if image_id != 0 {
+        keys.push(format!("i={image_id}"));
+    }
+    if command.image_number != 0 {
+        keys.push(format!("I={}", command.image_number));
+    }
+    if command.placement_id != 0 {
+        keys.push(format!("p={}", command.placement_id));
+    }
+
+    let message = match result {
+        Ok(()) => String::from("OK"),
+        Err(err) => err.to_string(),
+    };
+
+    Some(format!("\x1b_G{};{message}\x1b\\", keys.join(",")))
+}
+
+/// Read the image data from a temporary file, deleting it afterwards.
+///
+/// To avoid reading arbitrary files, only files inside of temporary directories whose name
+/// contains `tty-graphics-protocol` are accepted.
+fn read_temporary_file(command: &Command) -> Result<Vec<u8>, Error> {
+    const INVALID_FILE: Error = Error::new("EBADF", "invalid temporary file");
+
+    let path = str::from_utf8(&command.payload).map(Path::new).map_err(|_| INVALID_FILE)?;
+
+    let temp_dir = env::temp_dir();
+    let in_temp_dir = [temp_dir.as_path(), Path::new("/tmp"), Path::new("/dev/shm")]
+        .iter()
+        .any(|dir| path.starts_with(dir));
+    let valid_name = path
+        .file_name()
+        .and_then(OsStr::to_str)
+        .is_some_and(|name| name.contains("tty-graphics-protocol"));
+    let is_file = fs::symlink_metadata(path).is_ok_and(|metadata| metadata.is_file());
+    if !path.is_absolute()
+        || path.components().any(|component| component == Component::ParentDir)
+        || !in_temp_dir
+        || !valid_name
+        || !is_file
+    {
+        return Err(INVALID_FILE);
+    }
+
+    let data = read_file(path, command.offset, command.size);
+    let _ = fs::remove_file(path);
+    data
+}
+
+/// Read `size` bytes starting at `offset`, reading until the end of the file when `size` is zero.
+fn read_file(path: &Path, offset: usize, size: usize) -> Result<Vec<u8>, Error> {
+    const READ_FAILED: Error = Error::new("EBADF", "failed to read file");
+
+    let mut file = File::open(path).map_err(|_| READ_FAILED)?;
+    file.seek(SeekFrom::Start(offset as u64)).map_err(|_| READ_FAILED)?;
+
+    let limit = if size == 0 { MAX_DATA_SIZE + 1 } else { size.min(MAX_DATA_SIZE + 1) };
+    let mut data = Vec::new();
+    file.take(limit as u64).read_to_end(&mut data).map_err(|_| READ_FAILED)?;
+
+    if data.len() > MAX_DATA_SIZE {
+        return Err(Error::new("EFBIG", "image data is too large"));
+    }
+
+    Ok(data)
+}
+
+/// Decompress zlib data.
+fn inflate(data: &[u8]) -> Result<Vec<u8>, Error> {
+    let mut decompressed = Vec::new();
+    ZlibDecoder::new(data)
+        .take(MAX_DATA_SIZE as u64 + 1)
+        .read_to_end(&mut decompressed)
+        .map_err(|_| Error::new("EINVAL", "decompression failed"))?;
+
+    if decompressed.len() > MAX_DATA_SIZE {
+        return Err(Error::new("EFBIG", "image data is too large"));
+    }
+
+    Ok(decompressed)
+}
+
+/// Decode image data into RGBA pixels.
+fn decode(command: &Command, data: &[u8]) -> Result<Graphic, Error> {
+    let (width, height, pixels) = match command.format {
+        24 | 32 => {
+            let (width, height) = (command.width, command.height);
+            if width == 0 || height == 0 || width > MAX_SIZE || height > MAX_SIZE {
+                return Err(Error::new("EINVAL", "invalid image dimensions"));
+            }
+
+            let bytes_per_pixel = command.format as usize / 8;
+            let data = data
+                .get(..width * height * bytes_per_pixel)
+                .ok_or(Error::new("ENODATA", "insufficient image data"))?;
+
+            let pixels = if bytes_per_pixel == 4 {
+                data.to_vec()
+            } else {
+                data.chunks_exact(3).flat_map(|rgb| [rgb[0], rgb[1], rgb[2], u8::MAX]).collect()
+            };
+
+            (width, height, pixels)
+        },
+        100 => decode_png(data)?,
+        _ => return Err(Error::new("EINVAL", "unsupported format")),
+    };
+
+    Ok(Graphic::new(width, height, pixels))
+}
+
+/// Decode a PNG image into RGBA pixels.
+fn decode_png(data: &[u8]) -> Result<(usize, usize, Vec<u8>), Error> {
+    const INVALID_PNG: Error = Error::new("EBADPNG", "invalid PNG data");
+
+    let limits = png::Limits { bytes: MAX_DATA_SIZE };
+    let mut decoder = png::Decoder::new_with_limits(data, limits);
+    decoder.set_transformations(png::Transformations::normalize_to_color8());
+
+    let mut reader = decoder.read_info().map_err(|err| {
+        debug!("Failed to decode PNG: {err}");
+        INVALID_PNG
+    })?;
+
+    let (width, height) = (reader.info().width as usize, reader.info().height as usize);
+    if width == 0 || height == 0 || width > MAX_SIZE || height > MAX_SIZE {
+        return Err(Error::new("EINVAL", "invalid image dimensions"));
+    }
+
+    let mut buffer = vec![0; reader.output_buffer_size()];
+    let info = reader.next_frame(&mut buffer).map_err(|err| {
+        debug!("Failed to decode PNG: {err}");
+        INVALID_PNG
+    })?;
+    buffer.truncate(info.buffer_size());
+
+    let pixels = match info.color_type {
+        png::ColorType::Rgba => buffer,
+        png::ColorType::Rgb => {
+            buffer.chunks_exact(3).flat_map(|rgb| [rgb[0], rgb[1], rgb[2], u8::MAX]).collect()
+        },
+        png::ColorType::GrayscaleAlpha => {
+            buffer.chunks_exact(2).flat_map(|ga| [ga[0], ga[0], ga[0], ga[1]]).collect()
+        },
+        png::ColorType::Grayscale => buffer.iter().flat_map(|&g| [g, g, g, u8::MAX]).collect(),
+        png::ColorType::Indexed => return Err(INVALID_PNG),
+    };
+
+    Ok((width, height, pixels))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use std::io::Write;
+    use std::process;
+
+    use flate2::Compression;
+    use flate2::write::ZlibEncoder;
+
+    fn command(data: &str) -> Command {
+        Command::parse(data.as_bytes()).unwrap()
+    }
+
+    #[test]
+    fn parse_command() {
+        let command = command("a=T,i=5,f=24,s=1,v=2,m=1,z=-2,C=1,U=1;AAAA");
+        assert_eq!(command.action, b'T');
+        assert_eq!(command.image_id, 5);
+        assert_eq!(command.format, 24);
+        assert_eq!((command.width, command.height), (1, 2));
+        assert_eq!(command.z_index, -2);
+        assert!(command.more);
+        assert!(!command.move_cursor);
+        assert_eq!(command.payload, [0, 0, 0]);
+
+        assert_eq!(Command::parse(b"a"), None);
+        assert_eq!(Command::parse(b"i=x"), None);
+        assert_eq!(Command::parse(b"a=t;%%%%"), None);
+    }
+
+    #[test]
+    fn quiet_responses() {
+        let ok = || Ok(());
+        let err = || Err(Error::new("ENOENT", "image not found"));
+
+        assert_eq!(response(&command("i=1"), 1, ok()), Some("\x1b_Gi=1;OK\x1b\\".into()));
+        assert_eq!(response(&command("i=1,q=1"), 1, ok()), None);
+        assert_eq!(
+            response(&command("I=2,p=3,q=1"), 4, err()),
+            Some("\x1b_Gi=4,I=2,p=3;ENOENT:image not found\x1b\\".into())
+        );
+        assert_eq!(response(&command("i=1,q=2"), 1, err()), None);
+
+        // Images without ID or number never receive a response.
+        assert_eq!(response(&command(""), 1, err()), None);
+    }
+
+    #[test]
+    fn transmit_formats() {
+        let mut graphics = KittyGraphics::default();
+
+        // RGB pixels are converted to RGBA.
+        let (id, graphic) = graphics.transmit(&command("f=24,s=2,v=1;AQIDBAUG")).unwrap();
+        assert_eq!((id, graphic.width, graphic.height), (1, 2, 1));
+        assert_eq!(graphic.pixels, [1, 2, 3, 255, 4, 5, 6, 255]);
+
+        // Compressed pixels.
+        let mut encoder = ZlibEncoder::new(Vec::new(), Compression::default());
+        encoder.write_all(&[9; 16]).unwrap();
+        let data = BASE64.encode(encoder.finish().unwrap());
+        let command = command(&format!("i=7,s=2,v=2,o=z;{data}"));
+        let (id, graphic) = graphics.transmit(&command).unwrap();
+        assert_eq!((id, graphic.pixels.as_slice()), (7, &[9; 16][..]));
+
+        // PNG images.
+        let mut png = Vec::new();
+        let mut encoder = png::Encoder::new(&mut png, 1, 2);
+        encoder.set_color(png::ColorType::Grayscale);
+        encoder.write_header().unwrap().write_image_data(&[0, 255]).unwrap();
+        let data = BASE64.encode(&png);
+        let graphic = graphics.load(&self::command(&format!("f=100;{data}"))).unwrap();
+        assert_eq!((graphic.width, graphic.height), (1, 2));
+        assert_eq!(graphic.pixels, [0, 0, 0, 255, 255, 255, 255, 255]);
+
+        assert_eq!(graphics.len(), 2);
+        assert_eq!(graphics.load(&self::command("f=100;AAAA")).unwrap_err().code, "EBADPNG");
+    }
+
+    #[test]
+    fn transmit_temporary_file() {
+        let mut graphics = KittyGraphics::default();
+
+        let name = format!("alacritty-tty-graphics-protocol-{}", process::id());
+        let path = env::temp_dir().join(name);
+        fs::write(&path, [0, 1, 2, 3, 4, 5, 6, 7]).unwrap();
+
+        let data = BASE64.encode(path.to_str().unwrap());
+        let command = command(&format!("t=t,f=24,s=1,v=1,O=2,S=3;{data}"));
+        let (_, graphic) = graphics.transmit(&command).unwrap();
+        assert_eq!(graphic.pixels, [2, 3, 4, 255]);
+
+        // Temporary files are deleted after reading them.
+        assert!(!path.exists());
+
+        // Files outside of temporary directories are rejected.
+        let data = BASE64.encode("/etc/tty-graphics-protocol");
+        let command = self::command(&format!("t=t,f=24,s=1,v=1;{data}"));
+        assert_eq!(graphics.transmit(&command).unwrap_err().code, "EBADF");
+    }
+}
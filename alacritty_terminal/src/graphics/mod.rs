@@ -0,0 +1,232 @@
+//! Images displayed inside the terminal grid.
+//!
+//! Images are split into cells when they are placed in the grid, with every cell referencing the
+//! placement it is part of. This way images scroll and are cleared together with the text.
+//!
+//! Only the terminal state is handled here, drawing the placements is left to the frontend. Until
+//! a frontend sets [`Config::graphics`], images are not placed in the grid and kitty graphics
+//! protocol clients are told that they aren't displayed.
+//!
+//! [`Config::graphics`]: crate::term::Config::graphics
+
+use std::fmt::{self, Debug, Formatter};
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::{Arc, Weak};
+
+use crate::graphics::kitty::KittyGraphics;
+use crate::index::Column;
+
+pub mod kitty;
+pub mod sixel;
+
+/// Counter for unique graphic IDs.
+static GRAPHIC_ID: AtomicU64 = AtomicU64::new(0);
+
+/// Unique identifier for the pixel data of a graphic.
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub struct GraphicId(u64);
+
+impl GraphicId {
+    fn next() -> Self {
+        Self(GRAPHIC_ID.fetch_add(1, Ordering::Relaxed))
+    }
+}
+
+/// Decoded image.
+#[derive(PartialEq, Eq)]
+pub struct Graphic {
+    /// Identifier which can be used to cache uploaded textures.
+    pub id: GraphicId,
+
+    /// Width in pixels.
+    pub width: usize,
+
+    /// Height in pixels.
+    pub height: usize,
+
+    /// RGBA pixels, row by row.
+    pub pixels: Vec<u8>,
+}
+
+impl Graphic {
+    pub fn new(width: usize, height: usize, pixels: Vec<u8>) -> Self {
+        debug_assert_eq!(pixels.len(), width * height * 4);
+        Self { id: GraphicId::next(), width, height, pixels }
+    }
+}
+
+impl Debug for Graphic {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        f.debug_struct("Graphic")
+            .field("id", &self.id)
+            .field("width", &self.width)
+            .field("height", &self.height)
+            .finish_non_exhaustive()
+    }
+}
+
+/// Graphic displayed in the grid.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Placement {
+    pub graphic: Arc<Graphic>,
+
+    /// Kitty image ID, zero for sixel graphics.
+    pub image_id: u32,
+
+    /// Kitty placement ID, zero if the client did not specify one.
+    pub placement_id: u32,
+
+    /// Position of the displayed region inside the graphic.
+    pub source_x: usize,
+    pub source_y: usize,
+
+    /// Size of the displayed region inside the graphic.
+    pub source_width: usize,
+    pub source_height: usize,
+
+    /// Pixel offset of the graphic inside its top-left cell.
+    pub offset_x: usize,
+    pub offset_y: usize,
+
+    /// Size of the displayed region on screen in pixels.
+    pub width: usize,
+    pub height: usize,
+
+    /// Number of cells covered by the placement.
+    pub columns: usize,
+    pub lines: usize,
+
+    /// Stacking order, graphics with a negative index are drawn below the text.
+    pub z_index: i32,
+}
+
+impl Placement {
+    /// Display the entire graphic without scaling.
+    pub fn new(graphic: Arc<Graphic>, (cell_width, cell_height): (usize, usize)) -> Self {
+        Self {
+            columns: graphic.width.div_ceil(cell_width),
+            lines: graphic.height.div_ceil(cell_height),
+            source_width: graphic.width,
+            source_height: graphic.height,
+            width: graphic.width,
+            height: graphic.height,
+            graphic,
+            placement_id: Default::default(),
+            image_id: Default::default(),
+            source_x: Default::default(),
+            source_y: Default::default(),
+            offset_x: Default::default(),
+            offset_y: Default::default(),
+            z_index: Default::default(),
+        }
+    }
+}
+
+/// Part of a placement displayed in a single cell.
+#[derive(Debug, Clone)]
+pub struct GraphicCell {
+    pub placement: Arc<Placement>,
+
+    /// Column of the cell inside the placement.
+    pub column: usize,
+
+    /// Line of the cell inside the placement.
+    pub line: usize,
+}
+
+impl PartialEq for GraphicCell {
+    fn eq(&self, other: &Self) -> bool {
+        Arc::ptr_eq(&self.placement, &other.placement)
+            && self.column == other.column
+            && self.line == other.line
+    }
+}
+
+impl Eq for GraphicCell {}
+
+/// Top-left cells of the placements in a grid.
+///
+/// This allows finding placements without searching every cell of the grid. Lines are stored as
+/// anchors, see [`Grid::anchor`].
+///
+/// [`Grid::anchor`]: crate::grid::Grid::anchor
+#[derive(Debug, Default, Clone)]
+pub(crate) struct PlacementIndex {
+    anchors: Vec<PlacementAnchor>,
+}
+
+#[derive(Debug, Clone)]
+struct PlacementAnchor {
+    placement: Weak<Placement>,
+    anchor: i64,
+    column: Column,
+}
+
+impl PlacementIndex {
+    /// Add a placement whose top-left cell is at the anchored line and column.
+    pub fn insert(&mut self, placement: &Arc<Placement>, anchor: i64, column: Column) {
+        // Forget placements which were removed from the grid with their cells.
+        self.anchors.retain(|anchor| anchor.placement.strong_count() > 0);
+
+        let placement = Arc::downgrade(placement);
+        self.anchors.push(PlacementAnchor { placement, anchor, column });
+    }
+
+    /// Stop tracking a placement.
+    pub fn remove(&mut self, placement: &Arc<Placement>) {
+        let placement = Arc::downgrade(placement);
+        self.anchors.retain(|anchor| !anchor.placement.ptr_eq(&placement));
+    }
+
+    /// Update the anchored lines, dropping placements when no new line is returned.
+    pub fn move_anchors<F: FnMut(i64) -> Option<i64>>(&mut self, mut f: F) {
+        self.anchors.retain_mut(|anchor| match f(anchor.anchor) {
+            Some(new_anchor) => {
+                anchor.anchor = new_anchor;
+                true
+            },
+            None => false,
+        });
+    }
+
+    /// All placements which are still part of the grid, with their anchored line and column.
+    pub fn iter(&self) -> impl Iterator<Item = (Arc<Placement>, i64, Column)> + '_ {
+        let anchors = self.anchors.iter();
+        anchors
+            .filter_map(|anchor| Some((anchor.placement.upgrade()?, anchor.anchor, anchor.column)))
+    }
+
+    #[inline]
+    pub fn is_empty(&self) -> bool {
+        self.anchors.is_empty()
+    }
+
+    #[inline]
+    pub fn clear(&mut self) {
+        self.anchors.clear();
+    }
+}
+
+/// Graphics state of a terminal.
+#[derive(Debug, Default)]
+pub struct Graphics {
+    /// Images transmitted using the kitty graphics protocol.
+    pub kitty: KittyGraphics,
+
+    /// Cell dimensions in pixels, used to map graphics to the grid.
+    cell_size: Option<(usize, usize)>,
+}
+
+impl Graphics {
+    /// Cell width and height in pixels.
+    #[inline]
+    pub fn cell_size(&self) -> Option<(usize, usize)> {
+        self.cell_size
+    }
+
+    /// Update the pixel dimensions of a cell.
+    #[inline]
+    pub fn set_cell_size(&mut self, width: usize, height: usize) {
+        self.cell_size = (width > 0 && height > 0).then_some((width, height));
+    }
+}
@@ -0,0 +1,378 @@
+//! Sixel image decoding.
+//!
+//! Sixel data is received as the payload of a `DCS P1 ; P2 ; P3 q ... ST` sequence. Each data
+//! byte describes a column of six vertical pixels, which are painted with the active color
+//! register.
+
+use std::mem;
+
+use log::debug;
+
+use crate::graphics::Graphic;
+use crate::vte::Params;
+
+/// Maximum width and height of an image in pixels.
+const MAX_SIZE: usize = 4096;
+
+/// Number of color registers.
+const PALETTE_SIZE: usize = 256;
+
+/// Default color registers of the VT340, as RGB percentages.
+const VT340_PALETTE: [[u16; 3]; 16] = [
+    [0, 0, 0],
+    [20, 20, 80],
+    [80, 13, 13],
+    [20, 80, 20],
+    [80, 20, 80],
+    [20, 80, 80],
+    [80, 80, 20],
+    [53, 53, 53],
+    [26, 26, 26],
+    [33, 33, 60],
+    [60, 26, 26],
+    [33, 60, 33],
+    [60, 33, 60],
+    [33, 60, 60],
+    [60, 60, 33],
+    [80, 80, 80],
+];
+
+/// RGBA color.
+type Rgba = [u8; 4];
+
+/// Command whose numeric parameters are currently being read.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+enum Command {
+    /// Sixel data bytes.
+    Data,
+
+    /// Raster attributes (`"`).
+    RasterAttributes,
+
+    /// Graphics repeat introducer (`!`).
+    Repeat,
+
+    /// Graphics color introducer (`#`).
+    Color,
+}
+
+/// Incremental sixel decoder.
+#[derive(Debug)]
+pub struct Parser {
+    command: Command,
+    params: Vec<u16>,
+
+    /// Color registers.
+    palette: Vec<Rgba>,
+
+    /// Active color register.
+    color: usize,
+
+    /// Position of the next sixel, `y` is the top of the current band.
+    x: usize,
+    y: usize,
+
+    /// Dimensions of the image.
+    width: usize,
+    height: usize,
+
+    /// Dimensions of the allocated pixel buffer.
+    buffer_width: usize,
+    buffer_height: usize,
+    pixels: Vec<u8>,
+
+    /// Whether pixels which are never painted stay transparent.
+    transparent: bool,
+}
+
+impl Parser {
+    /// Create a parser using the parameters of the DCS sequence.
+    pub fn new(params: &Params) -> Self {
+        // P2 selects whether unpainted pixels use color register 0 or are left transparent.
+        let transparent = params.iter().nth(1).is_some_and(|param| param[0] == 1);
+
+        let mut palette = vec![[0, 0, 0, u8::MAX]; PALETTE_SIZE];
+        for (color, [r, g, b]) in palette.iter_mut().zip(VT340_PALETTE) {
+            *color = [percent(r), percent(g), percent(b), u8::MAX];
+        }
+
+        Self {
+            transparent,
+            palette,
+            command: Command::Data,
+            params: Default::default(),
+            color: Default::default(),
+            x: Default::default(),
+            y: Default::default(),
+            width: Default::default(),
+            height: Default::default(),
+            buffer_width: Default::default(),
+            buffer_height: Default::default(),
+            pixels: Default::default(),
+        }
+    }
+
+    /// Process a byte of the sixel data stream.
+    pub fn put(&mut self, byte: u8) {
+        match byte {
+            b'0'..=b'9' if self.command != Command::Data => {
+                let param = self.params.last_mut().unwrap();
+                *param = param.saturating_mul(10).saturating_add((byte - b'0') as u16);
+                return;
+            },
+            b';' if self.command != Command::Data => {
+                self.params.push(0);
+                return;
+            },
+            _ => (),
+        }
+
+        // Any other byte terminates the active command.
+        let command = mem::replace(&mut self.command, Command::Data);
+        match command {
+            Command::RasterAttributes => self.set_raster_attributes(),
+            Command::Color => self.set_color(),
+            Command::Repeat if (0x3f..=0x7e).contains(&byte) => {
+                let count = self.params[0].max(1) as usize;
+                self.draw(byte - 0x3f, count);
+                return;
+            },
+            Command::Repeat | Command::Data => (),
+        }
+
+        match byte {
+            b'"' => self.start_command(Command::RasterAttributes),
+            b'!' => self.start_command(Command::Repeat),
+            b'#' => self.start_command(Command::Color),
+            // Graphics carriage return.
+            b'$' => self.x = 0,
+            // Graphics new line.
+            b'-' => {
+                self.x = 0;
+                self.y += 6;
+            },
+            0x3f..=0x7e => self.draw(byte - 0x3f, 1),
+            _ => (),
+        }
+    }
+
+    /// Finish decoding, returning the image if any pixels were painted.
+    pub fn finish(mut self) -> Option<Graphic> {
+        // Apply pending commands.
+        self.put(0);
+
+        if self.width == 0 || self.height == 0 {
+            return None;
+        }
+
+        self.resize_buffer(self.width, self.height);
+
+        // Copy the image out of the buffer, filling unpainted pixels with the background.
+        let background = if self.transparent { [0; 4] } else { self.palette[0] };
+        let mut pixels = Vec::with_capacity(self.width * self.height * 4);
+        for row in self.pixels.chunks_exact(self.buffer_width * 4).take(self.height) {
+            for pixel in row[..self.width * 4].chunks_exact(4) {
+                let pixel = if pixel[3] == 0 { &background } else { pixel };
+                pixels.extend_from_slice(pixel);
+            }
+        }
+
+        Some(Graphic::new(self.width, self.height, pixels))
+    }
+
+    fn start_command(&mut self, command: Command) {
+        self.command = command;
+        self.params.clear();
+        self.params.push(0);
+    }
+
+    /// Apply the `" Pan ; Pad ; Ph ; Pv` raster attributes.
+    ///
+    /// The pixel aspect ratio is ignored, only the image dimensions are used.
+    fn set_raster_attributes(&mut self) {
+        if let [_, _, width, height, ..] = self.params[..] {
+            self.width = self.width.max((width as usize).min(MAX_SIZE));
+            self.height = self.height.max((height as usize).min(MAX_SIZE));
+        }
+    }
+
+    /// Select or define a color register using `# Pc ; Pu ; Px ; Py ; Pz`.
+    fn set_color(&mut self) {
+        let register = self.params[0] as usize % PALETTE_SIZE;
+
+        match self.params[..] {
+            [_] => (),
+            // HLS color space.
+            [_, 1, hue, lightness, saturation, ..] => {
+                self.palette[register] = hls_to_rgba(hue, lightness, saturation);
+            },
+            // RGB color space.
+            [_, 2, r, g, b, ..] => {
+                self.palette[register] = [percent(r), percent(g), percent(b), u8::MAX];
+            },
+            _ => {
+                debug!("Invalid sixel color introducer: {:?}", self.params);
+                return;
+            },
+        }
+
+        self.color = register;
+    }
+
+    /// Paint a sixel `count` times with the active color.
+    fn draw(&mut self, sixel: u8, count: usize) {
+        let start = self.x;
+        self.x = self.x.saturating_add(count);
+
+        let end = self.x.min(MAX_SIZE);
+        if start >= end || self.y >= MAX_SIZE || sixel == 0 {
+            return;
+        }
+
+        let bottom = (self.y + 6).min(MAX_SIZE);
+        self.resize_buffer(end, bottom);
+
+        let color = self.palette[self.color];
+        for y in (self.y..bottom).filter(|y| sixel & (1 << (y - self.y)) != 0) {
+            let row = y * self.buffer_width * 4;
+            for pixel in self.pixels[row + start * 4..row + end * 4].chunks_exact_mut(4) {
+                pixel.copy_from_slice(&color);
+            }
+            self.height = self.height.max(y + 1);
+        }
+        self.width = self.width.max(end);
+    }
+
+    /// Grow the pixel buffer to fit at least `width` x `height` pixels.
+    fn resize_buffer(&mut self, width: usize, height: usize) {
+        if width <= self.buffer_width && height <= self.buffer_height {
+            return;
+        }
+
+        // Grow exponentially to avoid copying the buffer for every sixel.
+        let buffer_width = if width > self.buffer_width {
+            width.max(self.buffer_width * 2).min(MAX_SIZE)
+        } else {
+            self.buffer_width
+        };
+        let buffer_height = if height > self.buffer_height {
+            height.max(self.buffer_height * 2).min(MAX_SIZE)
+        } else {
+            self.buffer_height
+        };
+
+        let mut pixels = vec![0; buffer_width * buffer_height * 4];
+        if self.buffer_width > 0 {
+            let rows = self.pixels.chunks_exact(self.buffer_width * 4);
+            for (new_row, row) in pixels.chunks_exact_mut(buffer_width * 4).zip(rows) {
+                new_row[..row.len()].copy_from_slice(row);
+            }
+        }
+
+        self.pixels = pixels;
+        self.buffer_width = buffer_width;
+        self.buffer_height = buffer_height;
+    }
+}
+
+/// Convert a color percentage to an 8 bit channel.
+fn percent(value: u16) -> u8 {
+    ((value.min(100) as u32 * 255 + 50) / 100) as u8
+}
+
+/// Convert a sixel HLS color to RGBA.
+///
+/// Sixel hues are rotated compared to the usual HSL color wheel, with blue at 0 degrees.
+fn hls_to_rgba(hue: u16, lightness: u16, saturation: u16) -> Rgba {
+    let hue = ((hue as f32 + 240.) % 360.) / 60.;
+    let lightness = lightness.min(100) as f32 / 100.;
+    let saturation = saturation.min(100) as f32 / 100.;
+
+    let chroma = (1. - (2. * lightness - 1.).abs()) * saturation;
+    let x = chroma * (1. - (hue % 2. - 1.).abs());
+    let (r, g, b) = match hue as u8 {
+        0 => (chroma, x, 0.),
+        1 => (x, chroma, 0.),
+        2 => (0., chroma, x),
+        3 => (0., x, chroma),
+        4 => (x, 0., chroma),
+        _ => (chroma, 0., x),
+    };
+
+    let m = lightness - chroma / 2.;
+    let channel = |value: f32| ((value + m) * 255.).round() as u8;
+    [channel(r), channel(g), channel(b), u8::MAX]
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use crate::vte::{Parser as VteParser, Perform};
+
+    /// Collect the image of a DCS sixel sequence.
+    #[derive(Default)]
+    struct Decoder {
+        parser: Option<Parser>,
+        graphic: Option<Graphic>,
+    }
+
+    impl Perform for Decoder {
+        fn hook(&mut self, params: &Params, _intermediates: &[u8], _ignore: bool, _action: char) {
+            self.parser = Some(Parser::new(params));
+        }
+
+        fn put(&mut self, byte: u8) {
+            self.parser.as_mut().unwrap().put(byte);
+        }
+
+        fn unhook(&mut self) {
+            self.graphic = self.parser.take().unwrap().finish();
+        }
+    }
+
+    fn decode(bytes: &[u8]) -> Option<Graphic> {
+        let mut decoder = Decoder::default();
+        VteParser::new().advance(&mut decoder, bytes);
+        decoder.graphic
+    }
+
+    fn pixel(graphic: &Graphic, x: usize, y: usize) -> Rgba {
+        let offset = (y * graphic.width + x) * 4;
+        graphic.pixels[offset..offset + 4].try_into().unwrap()
+    }
+
+    #[test]
+    fn decode_colors() {
+        let graphic = decode(b"\x1bPq#1;2;100;0;0#2;2;0;0;100#1~~#2!3~-#1@\x1b\\").unwrap();
+
+        assert_eq!((graphic.width, graphic.height), (5, 7));
+        assert_eq!(pixel(&graphic, 0, 0), [255, 0, 0, 255]);
+        assert_eq!(pixel(&graphic, 1, 5), [255, 0, 0, 255]);
+        assert_eq!(pixel(&graphic, 2, 0), [0, 0, 255, 255]);
+        assert_eq!(pixel(&graphic, 4, 5), [0, 0, 255, 255]);
+        assert_eq!(pixel(&graphic, 0, 6), [255, 0, 0, 255]);
+
+        // Unpainted pixels use color register 0.
+        assert_eq!(pixel(&graphic, 1, 6), [0, 0, 0, 255]);
+    }
+
+    #[test]
+    fn decode_transparent() {
+        let graphic = decode(b"\x1bP0;1q\"1;1;4;12#0;1;120;50;100$@\x1b\\").unwrap();
+
+        // Raster attributes define the minimum image size.
+        assert_eq!((graphic.width, graphic.height), (4, 12));
+
+        // HLS hue 120 is red.
+        assert_eq!(pixel(&graphic, 0, 0), [255, 0, 0, 255]);
+        assert_eq!(pixel(&graphic, 0, 1), [0, 0, 0, 0]);
+        assert_eq!(pixel(&graphic, 3, 11), [0, 0, 0, 0]);
+    }
+
+    #[test]
+    fn decode_empty() {
+        assert_eq!(decode(b"\x1bPq#1;2;100;0;0\x1b\\"), None);
+        assert_eq!(decode(b"\x1bPq??-\x1b\\"), None);
+    }
+}
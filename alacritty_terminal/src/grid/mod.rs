@@ -1,7 +1,10 @@
 //! A specialized 2D grid implementation optimized for use in a terminal.
 
+use std::borrow::Cow;
 use std::cmp::{max, min};
+use std::collections::{BTreeMap, BTreeSet};
 use std::ops::{Bound, Deref, Index, IndexMut, Range, RangeBounds};
+use std::time::SystemTime;
 
 #[cfg(feature = "serde")]
 use serde::{Deserialize, Serialize};
@@ -10,13 +13,17 @@ use crate::index::{Column, Line, Point};
 use crate::term::cell::{Flags, ResetDiscriminant};
 use crate::vte::ansi::{CharsetIndex, StandardCharset};
 
+mod packed;
 pub mod resize;
 mod row;
+mod spill;
 mod storage;
 #[cfg(test)]
 mod tests;
 
-pub use self::row::Row;
+pub use self::packed::{PackCell, Packer};
+pub(crate) use self::packed::{read_str, read_u8, read_u32, write_str};
+pub use self::row::{LineSize, PromptMarks, Row, ViMarks};
 use self::storage::Storage;
 
 pub trait GridCell: Sized {
@@ -28,6 +35,13 @@ pub trait GridCell: Sized {
 
     fn flags(&self) -> &Flags;
     fn flags_mut(&mut self) -> &mut Flags;
+
+    /// Compression for rows in the scrollback history.
+    ///
+    /// The history is kept uncompressed for cells without a packer.
+    fn packer() -> Option<Packer<Self>> {
+        None
+    }
 }
 
 #[derive(Debug, Default, Clone, PartialEq, Eq)]
@@ -118,6 +132,13 @@ pub struct Grid<T> {
 
     /// Lines in the grid. Each row holds a list of cells corresponding to the
     /// columns in that row.
+    #[cfg_attr(
+        feature = "serde",
+        serde(bound(
+            serialize = "T: Clone + Serialize",
+            deserialize = "T: GridCell + Deserialize<'de>"
+        ))
+    )]
     raw: Storage<T>,
 
     /// Number of columns.
@@ -135,6 +156,22 @@ pub struct Grid<T> {
 
     /// Maximum number of lines in history.
     max_scroll_limit: usize,
+
+    /// Number of lines rotated into the history, see [`Grid::anchor`].
+    #[cfg_attr(feature = "serde", serde(skip))]
+    rotation: i64,
+
+    /// Anchored positions of the lines vi mode marks are set on.
+    ///
+    /// The marks stored in each row remain authoritative, this only avoids searching for them.
+    #[cfg_attr(feature = "serde", serde(skip))]
+    vi_marks: BTreeMap<char, i64>,
+
+    /// Anchored positions of the lines with shell integration marks.
+    ///
+    /// Like vi mode marks, the marks stored in each row remain authoritative.
+    #[cfg_attr(feature = "serde", serde(skip))]
+    prompt_lines: BTreeSet<i64>,
 }
 
 impl<T: GridCell + Default + PartialEq> Grid<T> {
@@ -147,6 +184,9 @@ impl<T: GridCell + Default + PartialEq> Grid<T> {
             cursor: Cursor::default(),
             lines,
             columns,
+            rotation: 0,
+            vi_marks: Default::default(),
+            prompt_lines: Default::default(),
         }
     }
 
@@ -160,6 +200,11 @@ impl<T: GridCell + Default + PartialEq> Grid<T> {
         self.max_scroll_limit = history_size;
     }
 
+    /// Update the memory compressed history may use, before it is moved to disk.
+    pub fn set_memory_limit(&mut self, limit: Option<usize>) {
+        self.raw.set_memory_limit(limit);
+    }
+
     pub fn scroll_display(&mut self, scroll: Scroll) {
         self.display_offset = match scroll {
             Scroll::Delta(count) => {
@@ -170,6 +215,8 @@ impl<T: GridCell + Default + PartialEq> Grid<T> {
             Scroll::Top => self.history_size(),
             Scroll::Bottom => 0,
         };
+
+        self.raw.release_unpacked();
     }
 
     fn increase_scroll_limit(&mut self, count: usize) {
@@ -193,6 +240,12 @@ impl<T: GridCell + Default + PartialEq> Grid<T> {
         T: ResetDiscriminant<D>,
         D: PartialEq,
     {
+        // Move marks down within the region.
+        self.move_marks(0, |line| match region.contains(&line) {
+            true => Some(line + positions).filter(|line| *line < region.end),
+            false => Some(line),
+        });
+
         // When rotating the entire region, just reset everything.
         if region.end - region.start <= positions {
             for i in (region.start.0..region.end.0).map(Line::from) {
@@ -254,6 +307,21 @@ impl<T: GridCell + Default + PartialEq> Grid<T> {
         T: ResetDiscriminant<D>,
         D: PartialEq,
     {
+        // Move marks up within the region, or into the history if it starts at the top.
+        if region.start != 0 {
+            self.move_marks(0, |line| match region.contains(&line) {
+                true => Some(line - positions).filter(|line| *line >= region.start),
+                false => Some(line),
+            });
+        } else if region.end < self.screen_lines() as i32 {
+            self.move_marks(positions as i64, |line| match line >= region.end {
+                true => Some(line),
+                false => Some(line - positions),
+            });
+        } else {
+            self.rotation += positions as i64;
+        }
+
         // When rotating the entire region with fixed lines at the top, just reset everything.
         if region.end - region.start <= positions && region.start != 0 {
             for i in (region.start.0..region.end.0).map(Line::from) {
@@ -339,6 +407,8 @@ impl<T: GridCell + Default + PartialEq> Grid<T> {
         D: PartialEq,
     {
         self.clear_history();
+        self.vi_marks.clear();
+        self.prompt_lines.clear();
 
         self.saved_cursor = Cursor::default();
         self.cursor = Cursor::default();
@@ -379,6 +449,36 @@ impl<T> Grid<T> {
         }
     }
 
+    /// Copy a rectangular area within the grid, placing its top-left cell at `destination`.
+    ///
+    /// Source and destination are allowed to overlap, but must both be within the grid.
+    pub fn copy_rect(&mut self, lines: Range<Line>, columns: Range<Column>, destination: Point)
+    where
+        T: Clone,
+    {
+        let rows: Vec<Vec<T>> = (lines.start.0..lines.end.0)
+            .map(|line| self[Line(line)][columns.clone()].to_vec())
+            .collect();
+
+        for (offset, row) in rows.into_iter().enumerate() {
+            let end = destination.column + row.len();
+            let cells = &mut self[destination.line + offset][destination.column..end];
+            for (cell, source) in cells.iter_mut().zip(row) {
+                *cell = source;
+            }
+        }
+    }
+
+    /// Modify all cells within a rectangular area.
+    pub fn update_rect<F>(&mut self, lines: Range<Line>, columns: Range<Column>, mut f: F)
+    where
+        F: FnMut(&mut T),
+    {
+        for line in (lines.start.0..lines.end.0).map(Line::from) {
+            self[line][columns.clone()].iter_mut().for_each(&mut f);
+        }
+    }
+
     #[inline]
     pub fn clear_history(&mut self) {
         // Explicitly purge all lines from history.
@@ -414,6 +514,29 @@ impl<T> Grid<T> {
         GridIterator { grid: self, point, end }
     }
 
+    /// Iterate over copies of all cells in the grid starting at a specific point.
+    ///
+    /// Unlike `Grid::iter_from`, this doesn't keep compressed rows in the history unpacked, so it
+    /// should be preferred for searching through large parts of the history.
+    #[inline]
+    pub fn read_from(&self, point: Point) -> ReadIterator<'_, T>
+    where
+        T: Clone,
+    {
+        ReadIterator { row: self.read_row(point.line), iter: self.iter_from(point) }
+    }
+
+    /// Get a row without keeping it unpacked.
+    ///
+    /// This should be preferred over indexing when reading large parts of the history.
+    #[inline]
+    pub fn read_row(&self, line: Line) -> Cow<'_, Row<T>>
+    where
+        T: Clone,
+    {
+        self.raw.read(line)
+    }
+
     /// Iterate over all visible cells.
     ///
     /// This is slightly more optimized than calling `Grid::iter_from` in combination with
@@ -433,10 +556,145 @@ impl<T> Grid<T> {
         self.display_offset
     }
 
+    /// Time at which content was first written to a line.
+    #[inline]
+    pub fn timestamp(&self, line: Line) -> Option<SystemTime> {
+        self[line].timestamp()
+    }
+
+    /// Position of a line which doesn't change while lines are rotated into the history.
+    ///
+    /// Anchors are not updated when scrolling only part of the screen, or reflowing the grid.
+    #[inline]
+    pub fn anchor(&self, line: Line) -> i64 {
+        self.rotation + line.0 as i64
+    }
+
+    /// Line at an anchored position, if it is still part of the grid.
+    #[inline]
+    pub fn anchor_line(&self, anchor: i64) -> Option<Line> {
+        let line = Line(i32::try_from(anchor - self.rotation).ok()?);
+        (line >= self.topmost_line() && line <= self.bottommost_line()).then_some(line)
+    }
+
+    /// Check if a row contains a cell matching the predicate, without unpacking it.
+    ///
+    /// The predicate can't rely on the characters of compressed cells. Rows moved to disk are
+    /// skipped, so this only finds cells which [`PackCell::write`] keeps in memory.
+    #[inline]
+    pub fn row_contains<F: FnMut(&T) -> bool>(&self, line: Line, f: F) -> bool {
+        self.raw.row_contains(line, f)
+    }
+
+    /// Set the vi mode mark `name` on a line, removing it from the line it was previously set on.
+    pub fn set_vi_mark(&mut self, name: char, line: Line) {
+        if let Some(previous) = self.vi_mark_line(name) {
+            let mut marks = self.raw.vi_marks(previous);
+            marks.remove(name);
+            self.raw.set_vi_marks(previous, marks);
+        }
+
+        let mut marks = self.raw.vi_marks(line);
+        marks.insert(name);
+        self.raw.set_vi_marks(line, marks);
+
+        self.vi_marks.insert(name, self.anchor(line));
+    }
+
+    /// Find the line the vi mode mark `name` is set on.
+    pub fn vi_mark_line(&self, name: char) -> Option<Line> {
+        let line = self.anchor_line(*self.vi_marks.get(&name)?)?;
+
+        // Marks are removed when their line is reset.
+        self.raw.vi_marks(line).contains(name).then_some(line)
+    }
+
+    /// All vi mode marks and their lines, sorted by name.
+    pub fn vi_marks(&self) -> Vec<(char, Line)> {
+        let names = self.vi_marks.keys();
+        names.filter_map(|&name| Some((name, self.vi_mark_line(name)?))).collect()
+    }
+
+    /// Add a shell integration mark to a line.
+    pub fn insert_prompt_mark(&mut self, line: Line, mark: PromptMarks) {
+        self[line].prompt_marks.insert(mark);
+
+        // Forget lines which have been removed from the history.
+        let topmost = self.anchor(self.topmost_line());
+        if self.prompt_lines.first().is_some_and(|&first| first < topmost) {
+            self.prompt_lines = self.prompt_lines.split_off(&topmost);
+        }
+
+        self.prompt_lines.insert(self.anchor(line));
+    }
+
+    /// Lines within `lines` which have any of the shell integration `marks`, from top to bottom.
+    ///
+    /// Only lines which had marks added are checked, without unpacking any rows.
+    pub fn prompt_lines(
+        &self,
+        lines: Range<Line>,
+        marks: PromptMarks,
+    ) -> impl DoubleEndedIterator<Item = Line> + '_ {
+        let start = self.anchor(lines.start);
+        let end = max(start, self.anchor(lines.end));
+        self.prompt_lines.range(start..end).filter_map(move |&anchor| {
+            let line = self.anchor_line(anchor)?;
+            self.raw.prompt_marks(line).intersects(marks).then_some(line)
+        })
+    }
+
+    /// Rebuild the positions of vi mode and shell integration marks from the rows.
+    pub(crate) fn index_marks(&mut self) {
+        self.vi_marks.clear();
+        self.prompt_lines.clear();
+        for line in (self.topmost_line().0..=self.bottommost_line().0).map(Line) {
+            for name in self.raw.vi_marks(line).iter() {
+                self.vi_marks.insert(name, self.anchor(line));
+            }
+
+            if !self.raw.prompt_marks(line).is_empty() {
+                self.prompt_lines.insert(self.anchor(line));
+            }
+        }
+    }
+
+    /// Move marks to a new line, while rotating `rotation` lines into the history.
+    ///
+    /// Marks are dropped when no new line is returned for them.
+    fn move_marks<F: Fn(Line) -> Option<Line>>(&mut self, rotation: i64, f: F) {
+        let previous_rotation = self.rotation;
+        self.rotation += rotation;
+
+        let rotation = self.rotation;
+        self.vi_marks.retain(|_, position| {
+            let line = i32::try_from(*position - previous_rotation).ok().map(Line);
+            match line.and_then(&f) {
+                Some(line) => {
+                    *position = rotation + line.0 as i64;
+                    true
+                },
+                None => false,
+            }
+        });
+
+        // Lines in the history keep their position, so only lines on the screen are moved.
+        let screen_lines = self.prompt_lines.split_off(&previous_rotation);
+        for position in screen_lines {
+            let line = Line((position - previous_rotation) as i32);
+            if let Some(line) = f(line) {
+                self.prompt_lines.insert(rotation + line.0 as i64);
+            }
+        }
+    }
+
+    /// Cell at the cursor position, recording the time its row was first written to.
     #[inline]
     pub fn cursor_cell(&mut self) -> &mut T {
         let point = self.cursor.point;
-        &mut self[point.line][point.column]
+        let row = &mut self[point.line];
+        row.stamp();
+        &mut row[point.column]
     }
 }
 
@@ -587,12 +845,9 @@ impl<'a, T> GridIterator<'a, T> {
     pub fn cell(&self) -> &'a T {
         &self.grid[self.point]
     }
-}
 
-impl<'a, T> Iterator for GridIterator<'a, T> {
-    type Item = Indexed<&'a T>;
-
-    fn next(&mut self) -> Option<Self::Item> {
+    /// Move to the next cell, without reading it.
+    fn step(&mut self) -> Option<Point> {
         // Stop once we've reached the end of the grid.
         if self.point >= self.end {
             return None;
@@ -606,7 +861,37 @@ impl<'a, T> Iterator for GridIterator<'a, T> {
             _ => self.point.column += Column(1),
         }
 
-        Some(Indexed { cell: &self.grid[self.point], point: self.point })
+        Some(self.point)
+    }
+
+    /// Move to the previous cell, without reading it.
+    fn step_back(&mut self) -> Option<Point> {
+        let topmost_line = self.grid.topmost_line();
+        let last_column = self.grid.last_column();
+
+        // Stop once we've reached the end of the grid.
+        if self.point <= Point::new(topmost_line, Column(0)) {
+            return None;
+        }
+
+        match self.point {
+            Point { column: Column(0), .. } => {
+                self.point.column = last_column;
+                self.point.line -= 1;
+            },
+            _ => self.point.column -= Column(1),
+        }
+
+        Some(self.point)
+    }
+}
+
+impl<'a, T> Iterator for GridIterator<'a, T> {
+    type Item = Indexed<&'a T>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let point = self.step()?;
+        Some(Indexed { cell: &self.grid[point], point })
     }
 
     fn size_hint(&self) -> (usize, Option<usize>) {
@@ -635,22 +920,60 @@ pub trait BidirectionalIterator: Iterator {
 
 impl<T> BidirectionalIterator for GridIterator<'_, T> {
     fn prev(&mut self) -> Option<Self::Item> {
-        let topmost_line = self.grid.topmost_line();
-        let last_column = self.grid.last_column();
+        let point = self.step_back()?;
+        Some(Indexed { cell: &self.grid[point], point })
+    }
+}
 
-        // Stop once we've reached the end of the grid.
-        if self.point <= Point::new(topmost_line, Column(0)) {
-            return None;
-        }
+/// Grid cell iterator which doesn't keep compressed rows unpacked.
+///
+/// Only the row at the current position is kept, so cells are yielded as copies.
+pub struct ReadIterator<'a, T: Clone> {
+    iter: GridIterator<'a, T>,
 
-        match self.point {
-            Point { column: Column(0), .. } => {
-                self.point.column = last_column;
-                self.point.line -= 1;
-            },
-            _ => self.point.column -= Column(1),
+    /// Row at the current position of the iterator.
+    row: Cow<'a, Row<T>>,
+}
+
+impl<'a, T: Clone> ReadIterator<'a, T> {
+    /// Current iterator position.
+    pub fn point(&self) -> Point {
+        self.iter.point
+    }
+
+    /// Cell at the current iterator position.
+    pub fn cell(&self) -> &T {
+        &self.row[self.iter.point.column]
+    }
+
+    /// Read the cell at a new position.
+    fn read(&mut self, previous: Point, point: Point) -> Indexed<T> {
+        if previous.line != point.line {
+            self.row = self.iter.grid.read_row(point.line);
         }
 
-        Some(Indexed { cell: &self.grid[self.point], point: self.point })
+        Indexed { cell: self.row[point.column].clone(), point }
+    }
+}
+
+impl<T: Clone> Iterator for ReadIterator<'_, T> {
+    type Item = Indexed<T>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let previous = self.iter.point;
+        let point = self.iter.step()?;
+        Some(self.read(previous, point))
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        self.iter.size_hint()
+    }
+}
+
+impl<T: Clone> BidirectionalIterator for ReadIterator<'_, T> {
+    fn prev(&mut self) -> Option<Self::Item> {
+        let previous = self.iter.point;
+        let point = self.iter.step_back()?;
+        Some(self.read(previous, point))
     }
 }
@@ -0,0 +1,389 @@
+//! Compressed rows for the scrollback history.
+
+use std::fmt::{self, Debug, Formatter};
+use std::mem;
+
+use crate::grid::GridCell;
+use crate::grid::row::{LineSize, PromptMarks, Row, Timestamp, ViMarks};
+
+/// Cell which can be compressed by separating its character from its other attributes.
+pub trait PackCell: GridCell + Clone + Default + PartialEq {
+    /// Character stored in the cell.
+    fn character(&self) -> char;
+
+    /// Replace the character stored in the cell.
+    fn set_character(&mut self, c: char);
+
+    /// Serialize the cell's attributes, returning `false` if they can't be written to disk.
+    fn write(&self, _buf: &mut Vec<u8>) -> bool {
+        false
+    }
+
+    /// Deserialize cell attributes written by [`PackCell::write`].
+    fn read(_bytes: &mut &[u8]) -> Option<Self> {
+        None
+    }
+}
+
+/// Compression of rows for a specific cell type.
+pub struct Packer<T> {
+    pack: fn(&Row<T>) -> PackedRow<T>,
+    unpack: fn(&PackedRow<T>) -> Row<T>,
+    write: fn(&PackedRow<T>, &mut Vec<u8>) -> bool,
+    read: fn(&[u8], usize) -> Row<T>,
+}
+
+impl<T: PackCell> Default for Packer<T> {
+    fn default() -> Self {
+        Self {
+            pack: PackedRow::new,
+            unpack: PackedRow::unpack,
+            write: PackedRow::write,
+            read: PackedRow::read,
+        }
+    }
+}
+
+impl<T> Packer<T> {
+    /// Compress a row.
+    #[inline]
+    pub(crate) fn pack(&self, row: &Row<T>) -> PackedRow<T> {
+        (self.pack)(row)
+    }
+
+    /// Restore a compressed row.
+    #[inline]
+    pub(crate) fn unpack(&self, packed: &PackedRow<T>) -> Row<T> {
+        (self.unpack)(packed)
+    }
+
+    /// Serialize a compressed row, returning `false` if it can't be written to disk.
+    #[inline]
+    pub(crate) fn write(&self, packed: &PackedRow<T>, buf: &mut Vec<u8>) -> bool {
+        buf.clear();
+        (self.write)(packed, buf)
+    }
+
+    /// Restore a row serialized by [`Packer::write`].
+    ///
+    /// Invalid data is replaced by an empty row with the specified number of columns.
+    #[inline]
+    pub(crate) fn read(&self, bytes: &[u8], columns: usize) -> Row<T> {
+        (self.read)(bytes, columns)
+    }
+}
+
+impl<T> Clone for Packer<T> {
+    fn clone(&self) -> Self {
+        *self
+    }
+}
+
+impl<T> Copy for Packer<T> {}
+
+impl<T> Debug for Packer<T> {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        f.write_str("Packer")
+    }
+}
+
+/// Row in compressed form.
+///
+/// Cell attributes are run-length encoded, with every distinct set of attributes stored only
+/// once. Since attributes are cloned, shared data like hyperlinks is not duplicated.
+#[derive(Clone, Debug, PartialEq)]
+pub(crate) struct PackedRow<T> {
+    /// Characters of all cells, without trailing spaces.
+    text: Box<str>,
+
+    /// Distinct cell attributes within the row.
+    attributes: Box<[T]>,
+
+    /// Runs of cells with identical attributes, as length and index into `attributes`.
+    runs: Box<[(u32, u32)]>,
+
+    occ: usize,
+    prompt_marks: PromptMarks,
+    vi_marks: ViMarks,
+    line_size: LineSize,
+    timestamp: Timestamp,
+}
+
+impl<T> PackedRow<T> {
+    /// Number of cells in the row.
+    #[inline]
+    pub fn columns(&self) -> usize {
+        self.runs.iter().map(|(len, _)| *len as usize).sum()
+    }
+
+    /// Check if any cell matches the predicate, with the characters of all cells left blank.
+    #[inline]
+    pub fn any_attributes<F: FnMut(&T) -> bool>(&self, f: F) -> bool {
+        self.attributes.iter().any(f)
+    }
+
+    /// Shell integration marks of the row.
+    #[inline]
+    pub fn prompt_marks(&self) -> PromptMarks {
+        self.prompt_marks
+    }
+
+    /// Vi mode marks of the row.
+    #[inline]
+    pub fn vi_marks(&self) -> ViMarks {
+        self.vi_marks
+    }
+
+    /// Replace the vi mode marks of the row, without unpacking it.
+    #[inline]
+    pub fn set_vi_marks(&mut self, marks: ViMarks) {
+        self.vi_marks = marks;
+    }
+
+    /// Approximate number of bytes used by the row.
+    #[inline]
+    pub fn size(&self) -> usize {
+        mem::size_of::<Self>()
+            + self.text.len()
+            + self.attributes.len() * mem::size_of::<T>()
+            + self.runs.len() * mem::size_of::<(u32, u32)>()
+    }
+}
+
+impl<T: PackCell> PackedRow<T> {
+    fn new(row: &Row<T>) -> Self {
+        let mut text = String::with_capacity(row.len());
+        let mut attributes: Vec<T> = Vec::new();
+        let mut runs: Vec<(u32, u32)> = Vec::new();
+
+        for cell in row {
+            text.push(cell.character());
+
+            let mut cell = cell.clone();
+            cell.set_character(' ');
+
+            // Most cells share the attributes of the previous cell.
+            match runs.last_mut() {
+                Some((len, index)) if attributes[*index as usize] == cell => *len += 1,
+                _ => {
+                    let index = match attributes.iter().position(|attributes| *attributes == cell) {
+                        Some(index) => index,
+                        None => {
+                            attributes.push(cell);
+                            attributes.len() - 1
+                        },
+                    };
+                    runs.push((1, index as u32));
+                },
+            }
+        }
+
+        text.truncate(text.trim_end_matches(' ').len());
+
+        Self {
+            text: text.into_boxed_str(),
+            attributes: attributes.into_boxed_slice(),
+            runs: runs.into_boxed_slice(),
+            occ: row.occ,
+            prompt_marks: row.prompt_marks,
+            vi_marks: row.vi_marks,
+            line_size: row.line_size,
+            timestamp: row.timestamp,
+        }
+    }
+
+    fn unpack(&self) -> Row<T> {
+        let mut cells = Vec::with_capacity(self.columns());
+
+        let mut chars = self.text.chars();
+        for &(len, index) in self.runs.iter() {
+            let attributes = &self.attributes[index as usize];
+            for _ in 0..len {
+                let mut cell = attributes.clone();
+                cell.set_character(chars.next().unwrap_or(' '));
+                cells.push(cell);
+            }
+        }
+
+        let mut row = Row::from_vec(cells, self.occ);
+        row.prompt_marks = self.prompt_marks;
+        row.vi_marks = self.vi_marks;
+        row.line_size = self.line_size;
+        row.timestamp = self.timestamp;
+        row
+    }
+
+    fn write(&self, buf: &mut Vec<u8>) -> bool {
+        buf.extend_from_slice(&(self.occ as u32).to_le_bytes());
+        buf.push(self.prompt_marks.bits());
+        buf.extend_from_slice(&self.vi_marks.0.to_le_bytes());
+        buf.push(self.line_size as u8);
+        buf.extend_from_slice(&self.timestamp.0.to_le_bytes());
+        write_str(buf, &self.text);
+
+        buf.extend_from_slice(&(self.attributes.len() as u32).to_le_bytes());
+        if !self.attributes.iter().all(|attributes| attributes.write(buf)) {
+            return false;
+        }
+
+        buf.extend_from_slice(&(self.runs.len() as u32).to_le_bytes());
+        for (len, index) in self.runs.iter() {
+            buf.extend_from_slice(&len.to_le_bytes());
+            buf.extend_from_slice(&index.to_le_bytes());
+        }
+
+        true
+    }
+
+    fn read(mut bytes: &[u8], columns: usize) -> Row<T> {
+        match Self::parse(&mut bytes) {
+            Some(packed) if packed.columns() == columns => packed.unpack(),
+            _ => Row::new(columns),
+        }
+    }
+
+    fn parse(bytes: &mut &[u8]) -> Option<Self> {
+        let occ = read_u32(bytes)? as usize;
+        let prompt_marks = PromptMarks::from_bits_truncate(read_u8(bytes)?);
+        let vi_marks = ViMarks(read_u32(bytes)?);
+        let line_size = match read_u8(bytes)? {
+            0 => LineSize::Single,
+            1 => LineSize::DoubleWidth,
+            2 => LineSize::DoubleHeightTop,
+            3 => LineSize::DoubleHeightBottom,
+            _ => return None,
+        };
+        let timestamp = Timestamp(read_u32(bytes)?);
+        let text = read_str(bytes)?.into();
+
+        let attributes_len = read_u32(bytes)?;
+        let attributes = (0..attributes_len).map(|_| T::read(bytes)).collect::<Option<_>>()?;
+
+        let runs_len = read_u32(bytes)?;
+        let runs: Box<[_]> = (0..runs_len)
+            .map(|_| Some((read_u32(bytes)?, read_u32(bytes)?)))
+            .collect::<Option<_>>()?;
+        if runs.iter().any(|(_, index)| *index >= attributes_len) {
+            return None;
+        }
+
+        Some(Self { text, attributes, runs, occ, prompt_marks, vi_marks, line_size, timestamp })
+    }
+}
+
+/// Append a length-prefixed string to a buffer.
+pub(crate) fn write_str(buf: &mut Vec<u8>, text: &str) {
+    buf.extend_from_slice(&(text.len() as u32).to_le_bytes());
+    buf.extend_from_slice(text.as_bytes());
+}
+
+/// Read a length-prefixed string.
+pub(crate) fn read_str<'a>(bytes: &mut &'a [u8]) -> Option<&'a str> {
+    let len = read_u32(bytes)? as usize;
+    let text = bytes.get(..len)?;
+    *bytes = &bytes[len..];
+    std::str::from_utf8(text).ok()
+}
+
+/// Read a little-endian [`u32`].
+pub(crate) fn read_u32(bytes: &mut &[u8]) -> Option<u32> {
+    let (value, rest) = bytes.split_first_chunk()?;
+    *bytes = rest;
+    Some(u32::from_le_bytes(*value))
+}
+
+/// Read a single byte.
+pub(crate) fn read_u8(bytes: &mut &[u8]) -> Option<u8> {
+    let (value, rest) = bytes.split_first()?;
+    *bytes = rest;
+    Some(*value)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use crate::index::Column;
+    use crate::term::cell::{Cell, Flags, Hyperlink};
+    use crate::vte::ansi::{Color, NamedColor, Rgb};
+
+    #[test]
+    fn roundtrip() {
+        let mut row = Row::<Cell>::new(10);
+        for (i, c) in "ab 界".chars().enumerate() {
+            row[Column(i)].c = c;
+        }
+        row[Column(3)].flags.insert(Flags::WIDE_CHAR);
+        row[Column(4)].flags.insert(Flags::WIDE_CHAR_SPACER);
+        row[Column(5)].push_zerowidth('\u{301}');
+        row[Column(9)].flags.insert(Flags::WRAPLINE);
+        row.prompt_marks = PromptMarks::PROMPT_START;
+        row.line_size = LineSize::DoubleWidth;
+        row.timestamp = Timestamp::now();
+
+        let packed = PackedRow::new(&row);
+        assert_eq!(&*packed.text, "ab 界");
+        assert_eq!(packed.attributes.len(), 5);
+
+        let unpacked = packed.unpack();
+        assert_eq!(unpacked, row);
+        assert_eq!(unpacked.occ, row.occ);
+        assert_eq!(unpacked.prompt_marks, row.prompt_marks);
+        assert_eq!(unpacked.timestamp, row.timestamp);
+    }
+
+    #[test]
+    fn shared_hyperlinks() {
+        let hyperlink = Hyperlink::new(Some("id"), String::from("https://example.org"));
+
+        let mut row = Row::<Cell>::new(6);
+        for cell in &mut row[Column(1)..Column(5)] {
+            cell.c = 'x';
+            cell.set_hyperlink(Some(hyperlink.clone()));
+        }
+
+        let packed = PackedRow::new(&row);
+        assert_eq!(packed.runs[..], [(1, 0), (4, 1), (1, 0)]);
+
+        let unpacked = packed.unpack();
+        assert_eq!(unpacked, row);
+        assert_eq!(unpacked[Column(4)].hyperlink(), Some(hyperlink));
+    }
+
+    #[test]
+    fn serialize() {
+        let mut row = Row::<Cell>::new(8);
+        for (i, c) in "a界".chars().enumerate() {
+            row[Column(i * 2)].c = c;
+        }
+        row[Column(2)].flags.insert(Flags::WIDE_CHAR | Flags::BOLD);
+        row[Column(3)].flags.insert(Flags::WIDE_CHAR_SPACER);
+        row[Column(4)].push_zerowidth('\u{301}');
+        row[Column(5)].bg = Color::Spec(Rgb { r: 1, g: 2, b: 3 });
+        row[Column(6)].fg = Color::Indexed(42);
+        row[Column(6)].set_underline_color(Some(Color::Named(NamedColor::DimRed)));
+        row[Column(7)]
+            .set_hyperlink(Some(Hyperlink::new(Some("id"), "https://example.org".into())));
+        row.prompt_marks = PromptMarks::OUTPUT_START;
+        row.vi_marks.insert('m');
+        row.line_size = LineSize::DoubleHeightBottom;
+        row.timestamp = Timestamp(1_700_000_000);
+        row.occ = 8;
+
+        let packer = Packer::<Cell>::default();
+        let mut buf = Vec::new();
+        assert!(packer.write(&packer.pack(&row), &mut buf));
+
+        let read = packer.read(&buf, 8);
+        assert_eq!(read, row);
+        assert_eq!(read.occ, row.occ);
+        assert_eq!(read.prompt_marks, row.prompt_marks);
+        assert_eq!(read.vi_marks, row.vi_marks);
+        assert_eq!(read.line_size, row.line_size);
+        assert_eq!(read.timestamp, row.timestamp);
+
+        // Invalid data is replaced by an empty row.
+        assert_eq!(packer.read(&buf[..buf.len() - 1], 8), Row::new(8));
+        assert_eq!(packer.read(&buf, 4), Row::new(4));
+    }
+}
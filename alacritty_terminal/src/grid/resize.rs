@@ -6,7 +6,7 @@ use std::mem;
 use crate::index::{Boundary, Column, Line};
 use crate::term::cell::{Flags, ResetDiscriminant};
 
-use crate::grid::row::Row;
+use crate::grid::row::{Row, Timestamp};
 use crate::grid::{Dimensions, Grid, GridCell};
 
 impl<T: GridCell + Default + PartialEq> Grid<T> {
@@ -25,12 +25,18 @@ impl<T: GridCell + Default + PartialEq> Grid<T> {
             Ordering::Equal => (),
         }
 
+        let reflowed = self.columns != columns;
         match self.columns.cmp(&columns) {
             Ordering::Less => self.grow_columns(reflow, columns),
             Ordering::Greater => self.shrink_columns(reflow, columns),
             Ordering::Equal => (),
         }
 
+        // Find marks again, since reflow moves them to different lines.
+        if reflowed && (!self.vi_marks.is_empty() || !self.prompt_lines.is_empty()) {
+            self.index_marks();
+        }
+
         // Restore template cell.
         self.cursor.template = template;
     }
@@ -47,6 +53,9 @@ impl<T: GridCell + Default + PartialEq> Grid<T> {
     {
         let lines_added = target - self.lines;
 
+        // All lines are moved down by the lines added at the top.
+        self.rotation -= lines_added as i64;
+
         // Need to resize before updating buffer.
         self.raw.grow_visible_lines(target);
         self.lines = target;
@@ -107,7 +116,7 @@ impl<T: GridCell + Default + PartialEq> Grid<T> {
 
         self.columns = columns;
 
-        let mut reversed: Vec<Row<T>> = Vec::with_capacity(self.raw.len());
+        let mut reversed = self.raw.row_buffer(columns);
         let mut cursor_line_delta = 0;
 
         // Remove the linewrap special case, by moving the cursor outside of the grid.
@@ -116,9 +125,9 @@ impl<T: GridCell + Default + PartialEq> Grid<T> {
             self.cursor.point.column += 1;
         }
 
-        let mut rows = self.raw.take_all();
+        let rows = self.raw.take_all();
 
-        for (i, mut row) in rows.drain(..).enumerate().rev() {
+        for (i, mut row) in rows.enumerate().rev() {
             // Check if reflowing should be performed.
             let last_row = match reversed.last_mut() {
                 Some(last_row) if should_reflow(last_row) => last_row,
@@ -164,6 +173,14 @@ impl<T: GridCell + Default + PartialEq> Grid<T> {
             // Add removed cells to previous row and reflow content.
             last_row.append(&mut cells);
 
+            // Keep shell integration marks, vi marks and timestamps of rows which are fully merged
+            // into the previous one.
+            if row.is_clear() {
+                last_row.prompt_marks |= row.prompt_marks;
+                last_row.vi_marks |= row.vi_marks;
+                last_row.timestamp = last_row.timestamp.earliest(row.timestamp);
+            }
+
             let cursor_buffer_line = self.lines - self.cursor.point.line.0 as usize - 1;
 
             if i == cursor_buffer_line && reflow {
@@ -226,16 +243,8 @@ impl<T: GridCell + Default + PartialEq> Grid<T> {
             self.cursor.point.line = max(self.cursor.point.line - overflow, Line(0));
         }
 
-        // Reverse iterator and fill all rows that are still too short.
-        let mut new_raw = Vec::with_capacity(reversed.len());
-        for mut row in reversed.drain(..).rev() {
-            if row.len() < columns {
-                row.grow(columns);
-            }
-            new_raw.push(row);
-        }
-
-        self.raw.replace_inner(new_raw);
+        // Use the buffer as the new grid storage, filling all rows that are still too short.
+        self.raw.replace_inner(reversed);
 
         // Clamp display offset in case lines above it got merged.
         self.display_offset = min(self.display_offset, self.history_size());
@@ -251,13 +260,13 @@ impl<T: GridCell + Default + PartialEq> Grid<T> {
             self.cursor.point.column += 1;
         }
 
-        let mut new_raw = Vec::with_capacity(self.raw.len());
-        let mut buffered: Option<Vec<T>> = None;
+        let mut new_raw = self.raw.row_buffer(columns);
+        let mut buffered: Option<(Vec<T>, Timestamp)> = None;
 
-        let mut rows = self.raw.take_all();
-        for (i, mut row) in rows.drain(..).enumerate().rev() {
+        let rows = self.raw.take_all();
+        for (i, mut row) in rows.enumerate().rev() {
             // Append lines left over from the previous row.
-            if let Some(buffered) = buffered.take() {
+            if let Some((buffered, timestamp)) = buffered.take() {
                 // Add a column for every cell added before the cursor, if it goes beyond the new
                 // width it is then later reflown.
                 let cursor_buffer_line = self.lines - self.cursor.point.line.0 as usize - 1;
@@ -266,6 +275,7 @@ impl<T: GridCell + Default + PartialEq> Grid<T> {
                 }
 
                 row.append_front(buffered);
+                row.timestamp = row.timestamp.earliest(timestamp);
             }
 
             loop {
@@ -311,6 +321,7 @@ impl<T: GridCell + Default + PartialEq> Grid<T> {
                     }
                 }
 
+                let timestamp = row.timestamp;
                 new_raw.push(row);
 
                 // Set line as wrapped if cells got removed.
@@ -330,7 +341,7 @@ impl<T: GridCell + Default + PartialEq> Grid<T> {
                     }
 
                     // Add removed cells to start of next row.
-                    buffered = Some(wrapped);
+                    buffered = Some((wrapped, timestamp));
                     break;
                 } else {
                     // Reflow cursor if a line below it is deleted.
@@ -354,6 +365,7 @@ impl<T: GridCell + Default + PartialEq> Grid<T> {
                         wrapped.resize_with(columns, T::default);
                     }
                     row = Row::from_vec(wrapped, occ);
+                    row.timestamp = timestamp;
 
                     if i < self.display_offset {
                         // Since we added a new line, rotate up the viewport.
@@ -363,10 +375,9 @@ impl<T: GridCell + Default + PartialEq> Grid<T> {
             }
         }
 
-        // Reverse iterator and use it as the new grid storage.
-        let mut reversed: Vec<Row<T>> = new_raw.drain(..).rev().collect();
-        reversed.truncate(self.max_scroll_limit + self.lines);
-        self.raw.replace_inner(reversed);
+        // Use the buffer as the new grid storage.
+        new_raw.truncate_front(self.max_scroll_limit + self.lines);
+        self.raw.replace_inner(new_raw);
 
         // Clamp display offset in case some lines went off.
         self.display_offset = min(self.display_offset, self.history_size());
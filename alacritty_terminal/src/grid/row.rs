@@ -1,9 +1,13 @@
 //! Defines the Row type which makes up lines in the grid.
 
 use std::cmp::{max, min};
-use std::ops::{Index, IndexMut, Range, RangeFrom, RangeFull, RangeTo, RangeToInclusive};
+use std::ops::{
+    BitOrAssign, Index, IndexMut, Range, RangeFrom, RangeFull, RangeTo, RangeToInclusive,
+};
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
 use std::{ptr, slice};
 
+use bitflags::bitflags;
 #[cfg(feature = "serde")]
 use serde::{Deserialize, Serialize};
 
@@ -11,6 +15,125 @@ use crate::grid::GridCell;
 use crate::index::Column;
 use crate::term::cell::ResetDiscriminant;
 
+bitflags! {
+    /// Shell integration marks within a row.
+    #[derive(Debug, Default, Clone, Copy, PartialEq, Eq, Hash)]
+    #[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+    pub struct PromptMarks: u8 {
+        const PROMPT_START  = 0b0001;
+        const COMMAND_START = 0b0010;
+        const OUTPUT_START  = 0b0100;
+        const COMMAND_END   = 0b1000;
+    }
+}
+
+/// Named vi mode marks within a row.
+///
+/// Marks are named by the lowercase ASCII letters `a` through `z`.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub struct ViMarks(pub(crate) u32);
+
+impl ViMarks {
+    /// Check if a mark is contained in the set.
+    #[inline]
+    pub fn contains(self, name: char) -> bool {
+        Self::bit(name).is_some_and(|bit| self.0 & bit != 0)
+    }
+
+    #[inline]
+    pub fn is_empty(self) -> bool {
+        self.0 == 0
+    }
+
+    /// Names of all marks in alphabetical order.
+    pub fn iter(self) -> impl Iterator<Item = char> {
+        (b'a'..=b'z').map(char::from).filter(move |name| self.contains(*name))
+    }
+
+    #[inline]
+    pub(crate) fn insert(&mut self, name: char) {
+        self.0 |= Self::bit(name).unwrap_or_default();
+    }
+
+    #[inline]
+    pub(crate) fn remove(&mut self, name: char) {
+        self.0 &= !Self::bit(name).unwrap_or_default();
+    }
+
+    /// Bit representing a mark, if the name is valid.
+    fn bit(name: char) -> Option<u32> {
+        name.is_ascii_lowercase().then(|| 1 << (name as u8 - b'a'))
+    }
+}
+
+impl BitOrAssign for ViMarks {
+    #[inline]
+    fn bitor_assign(&mut self, rhs: Self) {
+        self.0 |= rhs.0;
+    }
+}
+
+/// Display size of a row.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub enum LineSize {
+    /// Single-width and single-height line (DECSWL).
+    #[default]
+    Single,
+
+    /// Double-width line (DECDWL).
+    DoubleWidth,
+
+    /// Top half of a double-height line (DECDHL).
+    DoubleHeightTop,
+
+    /// Bottom half of a double-height line (DECDHL).
+    DoubleHeightBottom,
+}
+
+impl LineSize {
+    /// Whether every cell of the line is displayed at twice its width.
+    #[inline]
+    pub fn is_double_width(self) -> bool {
+        self != Self::Single
+    }
+}
+
+/// Time a row was first written to, in seconds since the UNIX epoch.
+///
+/// Rows which haven't been written to since they were last reset have no timestamp.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub(crate) struct Timestamp(pub(crate) u32);
+
+impl Timestamp {
+    /// Current time.
+    pub fn now() -> Self {
+        Self::from_time(Some(SystemTime::now()))
+    }
+
+    pub fn from_time(time: Option<SystemTime>) -> Self {
+        let seconds = time
+            .and_then(|time| time.duration_since(UNIX_EPOCH).ok())
+            .map_or(0, |duration| duration.as_secs().clamp(1, u32::MAX.into()) as u32);
+        Self(seconds)
+    }
+
+    pub fn time(self) -> Option<SystemTime> {
+        (self.0 != 0).then(|| UNIX_EPOCH + Duration::from_secs(self.0.into()))
+    }
+
+    /// Earlier of two timestamps, ignoring missing ones.
+    pub fn earliest(self, other: Self) -> Self {
+        match (self.0, other.0) {
+            (0, _) => other,
+            (_, 0) => self,
+            _ => Self(min(self.0, other.0)),
+        }
+    }
+}
+
 /// A row in the grid.
 #[derive(Default, Clone, Debug)]
 #[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
@@ -22,11 +145,27 @@ pub struct Row<T> {
     /// This is the upper bound on the number of elements in the row, which have been modified
     /// since the last reset. All cells after this point are guaranteed to be equal.
     pub(crate) occ: usize,
+
+    /// Shell integration marks set while the cursor was in this row.
+    #[cfg_attr(feature = "serde", serde(default))]
+    pub(crate) prompt_marks: PromptMarks,
+
+    /// Vi mode marks set on this row.
+    #[cfg_attr(feature = "serde", serde(default))]
+    pub(crate) vi_marks: ViMarks,
+
+    /// Display size of the row's cells.
+    #[cfg_attr(feature = "serde", serde(default))]
+    pub(crate) line_size: LineSize,
+
+    /// Time the row was first written to.
+    #[cfg_attr(feature = "serde", serde(default))]
+    pub(crate) timestamp: Timestamp,
 }
 
 impl<T: PartialEq> PartialEq for Row<T> {
     fn eq(&self, other: &Self) -> bool {
-        self.inner == other.inner
+        self.inner == other.inner && self.line_size == other.line_size
     }
 }
 
@@ -52,7 +191,14 @@ impl<T: Default> Row<T> {
             inner.set_len(columns);
         }
 
-        Row { inner, occ: 0 }
+        Row {
+            inner,
+            occ: 0,
+            prompt_marks: PromptMarks::empty(),
+            vi_marks: ViMarks::default(),
+            line_size: LineSize::Single,
+            timestamp: Timestamp::default(),
+        }
     }
 
     /// Increase the number of columns in the row.
@@ -107,6 +253,10 @@ impl<T: Default> Row<T> {
         }
 
         self.occ = 0;
+        self.prompt_marks = PromptMarks::empty();
+        self.vi_marks = ViMarks::default();
+        self.line_size = LineSize::Single;
+        self.timestamp = Timestamp::default();
     }
 }
 
@@ -114,7 +264,54 @@ impl<T: Default> Row<T> {
 impl<T> Row<T> {
     #[inline]
     pub fn from_vec(vec: Vec<T>, occ: usize) -> Row<T> {
-        Row { inner: vec, occ }
+        Row {
+            inner: vec,
+            occ,
+            prompt_marks: PromptMarks::empty(),
+            vi_marks: ViMarks::default(),
+            line_size: LineSize::Single,
+            timestamp: Timestamp::default(),
+        }
+    }
+
+    /// Shell integration marks within the row.
+    #[inline]
+    pub fn prompt_marks(&self) -> PromptMarks {
+        self.prompt_marks
+    }
+
+    /// Vi mode marks set on the row.
+    #[inline]
+    pub fn vi_marks(&self) -> ViMarks {
+        self.vi_marks
+    }
+
+    /// Display size of the row's cells.
+    #[inline]
+    pub fn line_size(&self) -> LineSize {
+        self.line_size
+    }
+
+    /// Time the row was first written to since it was last reset.
+    #[inline]
+    pub fn timestamp(&self) -> Option<SystemTime> {
+        self.timestamp.time()
+    }
+
+    /// Replace the time the row was first written to.
+    ///
+    /// The time is stored with a precision of one second.
+    #[inline]
+    pub fn set_timestamp(&mut self, time: Option<SystemTime>) {
+        self.timestamp = Timestamp::from_time(time);
+    }
+
+    /// Record the current time, unless the row already has a timestamp.
+    #[inline]
+    pub(crate) fn stamp(&mut self) {
+        if self.timestamp.0 == 0 {
+            self.timestamp = Timestamp::now();
+        }
     }
 
     #[inline]
@@ -0,0 +1,118 @@
+//! Disk storage for compressed rows exceeding the scrollback memory limit.
+
+use std::fs::File;
+use std::io::{self, Seek, SeekFrom, Write};
+use std::sync::Arc;
+
+use super::{PromptMarks, ViMarks};
+
+/// Minimum number of unused bytes before the file is rewritten.
+const MIN_COMPACT_SIZE: u64 = 64 * 1024 * 1024;
+
+/// Append-only temporary file for rows.
+///
+/// The file has no name and is only accessible by the current user. It is removed by the
+/// operating system once it is closed, even if the process is terminated.
+#[derive(Clone, Debug)]
+pub(crate) struct SpillFile {
+    file: Arc<File>,
+
+    /// Number of bytes written to the file.
+    len: u64,
+
+    /// Number of bytes belonging to rows which are no longer stored in the file.
+    unused: u64,
+}
+
+impl SpillFile {
+    pub fn new() -> io::Result<Self> {
+        Ok(Self { file: Arc::new(tempfile::tempfile()?), len: 0, unused: 0 })
+    }
+
+    /// Append bytes to the file, returning their offset.
+    pub fn write(&mut self, bytes: &[u8]) -> io::Result<u64> {
+        // The file might be shared with a clone, so the offset is read from the file itself.
+        let mut file = &*self.file;
+        let offset = file.seek(SeekFrom::End(0))?;
+        file.write_all(bytes)?;
+
+        self.len = offset + bytes.len() as u64;
+
+        Ok(offset)
+    }
+
+    /// Read bytes previously written to the file.
+    pub fn read(&self, offset: u64, len: u32) -> io::Result<Vec<u8>> {
+        let mut bytes = vec![0; len as usize];
+        read_exact_at(&self.file, &mut bytes, offset)?;
+        Ok(bytes)
+    }
+
+    /// Mark a row's bytes as unused.
+    #[inline]
+    pub fn release(&mut self, len: u32) {
+        self.unused += u64::from(len);
+    }
+
+    /// Check if most of the file is unused and it should be rewritten.
+    #[inline]
+    pub fn should_compact(&self) -> bool {
+        self.unused >= MIN_COMPACT_SIZE && self.unused * 2 > self.len
+    }
+}
+
+#[cfg(unix)]
+fn read_exact_at(file: &File, buf: &mut [u8], offset: u64) -> io::Result<()> {
+    use std::os::unix::fs::FileExt;
+
+    file.read_exact_at(buf, offset)
+}
+
+#[cfg(windows)]
+fn read_exact_at(file: &File, mut buf: &mut [u8], mut offset: u64) -> io::Result<()> {
+    use std::os::windows::fs::FileExt;
+
+    while !buf.is_empty() {
+        match file.seek_read(buf, offset) {
+            Ok(0) => return Err(io::ErrorKind::UnexpectedEof.into()),
+            Ok(n) => {
+                buf = &mut buf[n..];
+                offset += n as u64;
+            },
+            Err(err) if err.kind() == io::ErrorKind::Interrupted => (),
+            Err(err) => return Err(err),
+        }
+    }
+
+    Ok(())
+}
+
+/// Location of a row in the [`SpillFile`].
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub(crate) struct SpilledRow {
+    pub offset: u64,
+    pub len: u32,
+    pub columns: u32,
+
+    /// Shell integration marks of the row, to find them without reading it.
+    pub prompt_marks: PromptMarks,
+
+    /// Vi mode marks of the row, which can change after it was written.
+    pub vi_marks: ViMarks,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn write_read() {
+        let mut file = SpillFile::new().unwrap();
+        assert_eq!(file.write(b"first").unwrap(), 0);
+        assert_eq!(file.write(b"second").unwrap(), 5);
+
+        assert_eq!(file.read(5, 6).unwrap(), b"second");
+        assert_eq!(file.read(0, 5).unwrap(), b"first");
+        assert!(file.read(8, 6).is_err());
+    }
+}
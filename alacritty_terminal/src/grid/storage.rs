@@ -1,17 +1,37 @@
+use std::borrow::Cow;
 use std::cmp::max;
-use std::mem;
 use std::mem::MaybeUninit;
 use std::ops::{Index, IndexMut};
+use std::sync::{Mutex, MutexGuard, OnceLock, PoisonError};
+use std::{io, mem, vec};
 
+use log::error;
 #[cfg(feature = "serde")]
-use serde::{Deserialize, Serialize};
+use serde::{Deserialize, Deserializer, Serialize, Serializer};
 
-use super::Row;
+use super::packed::{PackedRow, Packer};
+use super::spill::{SpillFile, SpilledRow};
+use super::{GridCell, PromptMarks, Row, ViMarks};
 use crate::index::Line;
 
 /// Maximum number of buffered lines outside of the grid for performance optimization.
 const MAX_CACHE_SIZE: usize = 1_000;
 
+/// Number of lines at the bottom of the history which are never compressed.
+const UNPACKED_HISTORY: usize = 1_000;
+
+/// Number of lines at the bottom of the history which are not compressed without a memory limit.
+///
+/// This was the maximum history size before rows were compressed, so terminals with a history
+/// of this size don't pay for compression.
+const UNLIMITED_UNPACKED_HISTORY: usize = 100_000;
+
+/// Minimum number of rows which are compressed at once.
+const PACK_BATCH: usize = 1_000;
+
+/// Maximum number of compressed rows kept unpacked after they have been read.
+const MAX_UNPACKED: usize = 1_000;
+
 /// A ring buffer for optimizing indexing and rotation.
 ///
 /// The [`Storage::rotate`] and [`Storage::rotate_down`] functions are fast modular additions on
@@ -25,13 +45,23 @@ const MAX_CACHE_SIZE: usize = 1_000;
 /// implementation is provided. Anything from [`Vec`] that should be exposed must be done so
 /// manually.
 ///
+/// Rows which move more than [`UNPACKED_HISTORY`] lines into the history are compressed in
+/// batches, if the cell type provides a [`Packer`]. Without a memory limit, the most recent
+/// [`UNLIMITED_UNPACKED_HISTORY`] lines are kept uncompressed instead. Indexing a compressed row
+/// unpacks a copy of it, which is dropped again once too many rows are unpacked or they exceed the
+/// memory limit. [`Storage::read`] unpacks a temporary copy instead, for reading large parts of the
+/// history. Writing to a compressed row decompresses it permanently.
+///
+/// Once compressed rows exceed the configured memory limit, the oldest ones are moved to a
+/// temporary file. They are read back into memory like compressed rows, but their copies are
+/// dropped on the next release, since the limit is meant to keep them out of memory.
+///
 /// [`slice::rotate_left`]: https://doc.rust-lang.org/std/primitive.slice.html#method.rotate_left
 /// [`Deref`]: std::ops::Deref
 /// [`zero`]: #structfield.zero
 #[derive(Clone, Debug)]
-#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
 pub struct Storage<T> {
-    inner: Vec<Row<T>>,
+    inner: Vec<Slot<T>>,
 
     /// Starting point for the storage of rows.
     ///
@@ -50,6 +80,22 @@ pub struct Storage<T> {
     /// As long as `len` is bigger than `inner`, it is also possible to grow the scrollback buffer
     /// without any additional insertions.
     len: usize,
+
+    /// Compression for rows in the history.
+    packer: Option<Packer<T>>,
+
+    /// Compressed rows which have been unpacked for reading.
+    unpacked: Unpacked,
+
+    /// Memory used by compressed rows.
+    budget: Budget,
+
+    /// Rows starting at this position have been moved to disk, or can't be moved there.
+    spill_start: usize,
+
+    /// Number of rows which have moved past the uncompressed part of the history since it was
+    /// last compressed.
+    pending_pack: usize,
 }
 
 impl<T: PartialEq> PartialEq for Storage<T> {
@@ -58,7 +104,9 @@ impl<T: PartialEq> PartialEq for Storage<T> {
         assert_eq!(self.zero, 0);
         assert_eq!(other.zero, 0);
 
-        self.inner == other.inner && self.len == other.len
+        self.len == other.len
+            && self.inner.len() == other.inner.len()
+            && (0..self.inner.len()).all(|i| self.row(i) == other.row(i))
     }
 }
 
@@ -66,13 +114,38 @@ impl<T> Storage<T> {
     #[inline]
     pub fn with_capacity(visible_lines: usize, columns: usize) -> Storage<T>
     where
-        T: Default,
+        T: GridCell + Default,
     {
         // Initialize visible lines; the scrollback buffer is initialized dynamically.
         let mut inner = Vec::with_capacity(visible_lines);
-        inner.resize_with(visible_lines, || Row::new(columns));
+        inner.resize_with(visible_lines, || Slot::Row(Row::new(columns)));
+
+        Storage {
+            inner,
+            zero: 0,
+            visible_lines,
+            len: visible_lines,
+            packer: T::packer(),
+            unpacked: Default::default(),
+            budget: Default::default(),
+            spill_start: visible_lines,
+            pending_pack: 0,
+        }
+    }
+
+    /// Set the number of bytes compressed rows may use before they're moved to disk.
+    #[inline]
+    pub fn set_memory_limit(&mut self, limit: Option<usize>) {
+        let was_limited = self.budget.memory_limit.is_some();
+        self.budget.memory_limit = limit;
+
+        // Compress the rows which are no longer part of the smaller uncompressed history.
+        if limit.is_some() && !was_limited {
+            self.pending_pack = self.len;
+            self.pack_pending();
+        }
 
-        Storage { inner, zero: 0, visible_lines, len: visible_lines }
+        self.spill_history();
     }
 
     /// Increase the number of lines in the buffer.
@@ -100,12 +173,16 @@ impl<T> Storage<T> {
 
         // Update visible lines.
         self.visible_lines = next;
+
+        // Compress rows pushed deeper into the history.
+        self.pack_history(shrinkage);
     }
 
     /// Shrink the number of lines in the buffer.
     #[inline]
     pub fn shrink_lines(&mut self, shrinkage: usize) {
         self.len -= shrinkage;
+        self.spill_start = self.spill_start.min(self.len);
 
         // Free memory.
         if self.inner.len() > self.len + MAX_CACHE_SIZE {
@@ -118,6 +195,9 @@ impl<T> Storage<T> {
     pub fn truncate(&mut self) {
         self.rezero();
 
+        for slot in &self.inner[self.len..] {
+            self.budget.release(slot);
+        }
         self.inner.truncate(self.len);
     }
 
@@ -131,7 +211,12 @@ impl<T> Storage<T> {
             self.rezero();
 
             let realloc_size = self.inner.len() + max(additional_rows, MAX_CACHE_SIZE);
-            self.inner.resize_with(realloc_size, || Row::new(columns));
+            self.inner.resize_with(realloc_size, || Slot::Row(Row::new(columns)));
+        }
+
+        // Keep new lines at the top of the history in front of the rows moved to disk.
+        if self.spill_start == self.len {
+            self.spill_start += additional_rows;
         }
 
         self.len += additional_rows;
@@ -147,11 +232,12 @@ impl<T> Storage<T> {
     /// Exploits the known size of Row<T> to produce a slightly more efficient
     /// swap than going through slice::swap.
     ///
-    /// The default implementation from swap generates 8 movups and 4 movaps
-    /// instructions. This implementation achieves the swap in only 8 movups
+    /// The default implementation from swap generates additional movaps
+    /// instructions. This implementation achieves the swap using only movups
     /// instructions.
     pub fn swap(&mut self, a: Line, b: Line) {
-        debug_assert_eq!(mem::size_of::<Row<T>>(), mem::size_of::<usize>() * 4);
+        let qwords = mem::size_of::<Slot<T>>() / mem::size_of::<usize>();
+        debug_assert_eq!(mem::size_of::<Slot<T>>(), mem::size_of::<usize>() * qwords);
 
         let a = self.compute_index(a);
         let b = self.compute_index(b);
@@ -167,21 +253,48 @@ impl<T> Storage<T> {
             //
             // The optimizer unrolls this loop and vectorizes it.
             let mut tmp: MaybeUninit<usize>;
-            for i in 0..4 {
-                tmp = *a_ptr.offset(i);
-                *a_ptr.offset(i) = *b_ptr.offset(i);
-                *b_ptr.offset(i) = tmp;
+            for i in 0..qwords {
+                tmp = *a_ptr.add(i);
+                *a_ptr.add(i) = *b_ptr.add(i);
+                *b_ptr.add(i) = tmp;
             }
         }
     }
 
     /// Rotate the grid, moving all lines up/down in history.
     #[inline]
-    pub fn rotate(&mut self, count: isize) {
+    pub fn rotate(&mut self, count: isize)
+    where
+        T: Default,
+    {
         debug_assert!(count.unsigned_abs() <= self.inner.len());
 
         let len = self.inner.len();
         self.zero = (self.zero as isize + count + len as isize) as usize % len;
+
+        if count >= 0 {
+            self.spill_start = self.spill_start.saturating_sub(count as usize);
+            return;
+        }
+
+        let count = count.unsigned_abs();
+        self.spill_start = (self.spill_start + count).min(self.len);
+
+        // Replace compressed rows which are recycled at the bottom, instead of unpacking them.
+        for positive in 0..count {
+            let index = (self.zero + positive) % len;
+            let columns = match &self.inner[index] {
+                Slot::Row(_) => continue,
+                Slot::Packed(packed, _) => packed.columns(),
+                Slot::Spilled(row, _) => row.columns as usize,
+            };
+
+            let slot = mem::replace(&mut self.inner[index], Slot::Row(Row::new(columns)));
+            self.budget.release(&slot);
+        }
+
+        // Compress rows pushed deeper into the history.
+        self.pack_history(count);
     }
 
     /// Rotate all existing lines down in history.
@@ -194,25 +307,306 @@ impl<T> Storage<T> {
         self.zero = (self.zero + count) % self.inner.len();
     }
 
+    /// Create a buffer for rebuilding all rows, starting at the top of the history.
+    #[inline]
+    pub fn row_buffer(&self, columns: usize) -> RowBuffer<T> {
+        RowBuffer {
+            slots: Vec::with_capacity(self.len),
+            unpacked: self.visible_lines + self.unpacked_history(),
+            packer: self.packer,
+            columns,
+            budget: Budget { memory_limit: self.budget.memory_limit, ..Default::default() },
+            spill_end: 0,
+        }
+    }
+
     /// Update the raw storage buffer.
     #[inline]
-    pub fn replace_inner(&mut self, vec: Vec<Row<T>>) {
-        self.len = vec.len();
-        self.inner = vec;
+    pub fn replace_inner(&mut self, rows: RowBuffer<T>)
+    where
+        T: Default,
+    {
+        let columns = rows.columns;
+        let mut inner = rows.slots;
+
+        // Grow rows which were not compressed while filling the buffer.
+        for slot in &mut inner {
+            if let Slot::Row(row) = slot {
+                row.grow(columns);
+            }
+        }
+        inner.reverse();
+
+        self.len = inner.len();
+        self.spill_start = inner.len() - rows.spill_end;
+        self.pending_pack = 0;
+        self.inner = inner;
         self.zero = 0;
+        *self.unpacked.get_mut() = Default::default();
+        self.budget = rows.budget;
     }
 
     /// Remove all rows from storage.
     #[inline]
-    pub fn take_all(&mut self) -> Vec<Row<T>> {
+    pub fn take_all(&mut self) -> Rows<T> {
         self.truncate();
+        *self.unpacked.get_mut() = Default::default();
 
-        let mut buffer = Vec::new();
-
-        mem::swap(&mut buffer, &mut self.inner);
+        let slots = mem::take(&mut self.inner);
         self.len = 0;
+        self.spill_start = 0;
+        self.pending_pack = 0;
+
+        let budget = mem::take(&mut self.budget);
+        self.budget.memory_limit = budget.memory_limit;
+
+        Rows { slots: slots.into_iter(), packer: self.packer, file: budget.file }
+    }
+
+    /// Drop the unpacked copies of rows read from disk, and of all compressed rows once there
+    /// are too many of them.
+    #[inline]
+    pub fn release_unpacked(&mut self) {
+        let unpacked = self.unpacked.get_mut();
+        if unpacked.indices.len() > MAX_UNPACKED || self.budget.exceeded_by(unpacked.memory) {
+            self.clear_unpacked();
+            return;
+        }
+
+        let unpacked = self.unpacked.get_mut();
+        unpacked.indices.retain(|&index| match &mut self.inner[index] {
+            Slot::Spilled(spilled, row) => {
+                if row.take().is_some() {
+                    unpacked.memory -= row_size::<T>(spilled.columns as usize);
+                }
+                false
+            },
+            _ => true,
+        });
+    }
+
+    /// Number of lines at the bottom of the history which are not compressed.
+    #[inline]
+    fn unpacked_history(&self) -> usize {
+        match self.budget.memory_limit {
+            Some(_) => UNPACKED_HISTORY,
+            None => UNLIMITED_UNPACKED_HISTORY,
+        }
+    }
+
+    /// Compress rows once enough of them have been moved past the uncompressed part of the
+    /// history.
+    #[inline]
+    fn pack_history(&mut self, count: usize) {
+        if self.packer.is_none() {
+            return;
+        }
 
-        buffer
+        let packable = self.len.saturating_sub(self.visible_lines + self.unpacked_history());
+        self.pending_pack = (self.pending_pack + count).min(packable);
+        if self.pending_pack >= PACK_BATCH {
+            self.pack_pending();
+        }
+    }
+
+    /// Compress all rows which have been moved past the uncompressed part of the history.
+    fn pack_pending(&mut self) {
+        let packer = match self.packer {
+            Some(packer) => packer,
+            None => return,
+        };
+
+        let start = self.visible_lines + self.unpacked_history();
+        let end = (start + mem::take(&mut self.pending_pack)).min(self.len);
+        for positive in start..end {
+            let index = (self.zero + positive) % self.inner.len();
+            if let Slot::Row(row) = &self.inner[index] {
+                let packed = packer.pack(row);
+                self.budget.memory += packed.size();
+                self.inner[index] = Slot::Packed(Box::new(packed), OnceLock::new());
+            }
+        }
+
+        self.spill_history();
+        self.release_unpacked();
+    }
+
+    /// Move the oldest compressed rows to disk, until they no longer exceed the memory limit.
+    fn spill_history(&mut self) {
+        let packer = match self.packer {
+            Some(packer) => packer,
+            None => return,
+        };
+
+        let end = self.visible_lines + self.unpacked_history();
+        while self.budget.exceeded() && self.spill_start > end {
+            self.spill_start -= 1;
+
+            let index = (self.zero + self.spill_start) % self.inner.len();
+            self.budget.spill(&mut self.inner[index], packer);
+        }
+
+        if self.budget.file.as_ref().is_some_and(SpillFile::should_compact) {
+            if let Err(err) = self.compact_spill_file() {
+                error!("Unable to compact scrollback file: {err}");
+                self.budget.memory_limit = None;
+            }
+        }
+    }
+
+    /// Rewrite all rows on disk to a new file, dropping unused data.
+    fn compact_spill_file(&mut self) -> io::Result<()> {
+        let old_file = match &self.budget.file {
+            Some(file) => file,
+            None => return Ok(()),
+        };
+
+        let mut file = SpillFile::new()?;
+        let mut offsets = Vec::new();
+        for slot in &self.inner {
+            if let Slot::Spilled(row, _) = slot {
+                let bytes = old_file.read(row.offset, row.len)?;
+                offsets.push(file.write(&bytes)?);
+            }
+        }
+
+        let rows = self.inner.iter_mut().filter_map(|slot| match slot {
+            Slot::Spilled(row, _) => Some(row),
+            _ => None,
+        });
+        for (row, offset) in rows.zip(offsets) {
+            row.offset = offset;
+        }
+
+        self.budget.file = Some(file);
+
+        Ok(())
+    }
+
+    /// Drop the unpacked copies of all compressed rows.
+    fn clear_unpacked(&mut self) {
+        let unpacked = mem::take(self.unpacked.get_mut());
+        for index in unpacked.indices {
+            if let Some(Slot::Packed(_, row) | Slot::Spilled(_, row)) = self.inner.get_mut(index) {
+                row.take();
+            }
+        }
+    }
+
+    /// Get a row without keeping it unpacked.
+    ///
+    /// Compressed rows are only borrowed if a copy has already been unpacked for indexing.
+    #[inline]
+    pub fn read(&self, line: Line) -> Cow<'_, Row<T>>
+    where
+        T: Clone,
+    {
+        self.read_raw(self.compute_index(line))
+    }
+
+    /// Get the row at a raw index without keeping it unpacked.
+    #[inline]
+    fn read_raw(&self, index: usize) -> Cow<'_, Row<T>>
+    where
+        T: Clone,
+    {
+        match &self.inner[index] {
+            Slot::Row(row) => Cow::Borrowed(row),
+            Slot::Packed(packed, row) => match row.get() {
+                Some(row) => Cow::Borrowed(row),
+                None => Cow::Owned(unpack(self.packer, packed)),
+            },
+            Slot::Spilled(spilled, row) => match row.get() {
+                Some(row) => Cow::Borrowed(row),
+                None => Cow::Owned(read_spilled(self.packer, self.budget.file.as_ref(), spilled)),
+            },
+        }
+    }
+
+    /// Check if a row contains a cell matching the predicate, without unpacking it.
+    #[inline]
+    pub fn row_contains<F: FnMut(&T) -> bool>(&self, line: Line, f: F) -> bool {
+        match &self.inner[self.compute_index(line)] {
+            Slot::Row(row) => row[..].iter().any(f),
+            Slot::Packed(packed, _) => packed.any_attributes(f),
+            Slot::Spilled(..) => false,
+        }
+    }
+
+    /// Shell integration marks of a row, without unpacking it.
+    #[inline]
+    pub fn prompt_marks(&self, line: Line) -> PromptMarks {
+        match &self.inner[self.compute_index(line)] {
+            Slot::Row(row) => row.prompt_marks,
+            Slot::Packed(packed, _) => packed.prompt_marks(),
+            Slot::Spilled(spilled, _) => spilled.prompt_marks,
+        }
+    }
+
+    /// Vi mode marks of a row, without unpacking it.
+    #[inline]
+    pub fn vi_marks(&self, line: Line) -> ViMarks {
+        match &self.inner[self.compute_index(line)] {
+            Slot::Row(row) => row.vi_marks,
+            Slot::Packed(packed, _) => packed.vi_marks(),
+            Slot::Spilled(spilled, _) => spilled.vi_marks,
+        }
+    }
+
+    /// Replace the vi mode marks of a row, without unpacking it.
+    #[inline]
+    pub fn set_vi_marks(&mut self, line: Line, marks: ViMarks) {
+        let index = self.compute_index(line);
+        let row = match &mut self.inner[index] {
+            Slot::Row(row) => row,
+            Slot::Packed(packed, row) => {
+                packed.set_vi_marks(marks);
+                match row.get_mut() {
+                    Some(row) => row,
+                    None => return,
+                }
+            },
+            Slot::Spilled(spilled, row) => {
+                spilled.vi_marks = marks;
+                match row.get_mut() {
+                    Some(row) => row,
+                    None => return,
+                }
+            },
+        };
+        row.vi_marks = marks;
+    }
+
+    /// Get the row at a raw index, unpacking it if necessary.
+    #[inline]
+    fn row(&self, index: usize) -> &Row<T> {
+        match &self.inner[index] {
+            Slot::Row(row) => row,
+            Slot::Packed(packed, row) => row.get_or_init(|| {
+                self.unpacked.push(index, row_size::<T>(packed.columns()));
+                Box::new(unpack(self.packer, packed))
+            }),
+            Slot::Spilled(spilled, row) => row.get_or_init(|| {
+                self.unpacked.push(index, row_size::<T>(spilled.columns as usize));
+                Box::new(read_spilled(self.packer, self.budget.file.as_ref(), spilled))
+            }),
+        }
+    }
+
+    /// Get the row at a raw index for writing, decompressing it if necessary.
+    #[inline]
+    fn row_mut(&mut self, index: usize) -> &mut Row<T> {
+        let slot = &mut self.inner[index];
+        if !matches!(slot, Slot::Row(_)) {
+            let row = mem::replace(slot, Slot::Row(Row::from_vec(Vec::new(), 0)));
+            self.budget.release(&row);
+            *slot = Slot::Row(row.into_row(self.packer, self.budget.file.as_ref()));
+        }
+
+        match slot {
+            Slot::Row(row) => row,
+            _ => unreachable!(),
+        }
     }
 
     /// Compute actual index in underlying storage given the requested index.
@@ -240,6 +634,9 @@ impl<T> Storage<T> {
             return;
         }
 
+        // Unpacked rows are tracked by their raw index.
+        self.clear_unpacked();
+
         self.inner.rotate_left(self.zero);
         self.zero = 0;
     }
@@ -251,7 +648,7 @@ impl<T> Index<Line> for Storage<T> {
     #[inline]
     fn index(&self, index: Line) -> &Self::Output {
         let index = self.compute_index(index);
-        &self.inner[index]
+        self.row(index)
     }
 }
 
@@ -259,17 +656,374 @@ impl<T> IndexMut<Line> for Storage<T> {
     #[inline]
     fn index_mut(&mut self, index: Line) -> &mut Self::Output {
         let index = self.compute_index(index);
-        &mut self.inner[index]
+        self.row_mut(index)
+    }
+}
+
+#[cfg(feature = "serde")]
+impl<T: Clone + Serialize> Serialize for Storage<T> {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        let inner: Vec<_> = (0..self.inner.len()).map(|i| self.read_raw(i)).collect();
+        let raw =
+            RawStorage { inner, zero: self.zero, visible_lines: self.visible_lines, len: self.len };
+        raw.serialize(serializer)
+    }
+}
+
+#[cfg(feature = "serde")]
+impl<'de, T: GridCell + Deserialize<'de>> Deserialize<'de> for Storage<T> {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        let raw = RawStorage::<Row<T>>::deserialize(deserializer)?;
+        Ok(Storage {
+            inner: raw.inner.into_iter().map(Slot::Row).collect(),
+            zero: raw.zero,
+            visible_lines: raw.visible_lines,
+            len: raw.len,
+            packer: T::packer(),
+            unpacked: Default::default(),
+            budget: Default::default(),
+            spill_start: raw.len,
+            pending_pack: raw.len,
+        })
+    }
+}
+
+/// Serialized storage, with all rows uncompressed.
+#[cfg(feature = "serde")]
+#[derive(Serialize, Deserialize)]
+struct RawStorage<R> {
+    inner: Vec<R>,
+    zero: usize,
+    visible_lines: usize,
+    len: usize,
+}
+
+/// Row within the storage's ring buffer.
+#[derive(Clone, Debug)]
+enum Slot<T> {
+    Row(Row<T>),
+
+    /// Compressed row, with a copy unpacked for reading.
+    Packed(Box<PackedRow<T>>, OnceLock<Box<Row<T>>>),
+
+    /// Compressed row stored on disk, with a copy unpacked for reading.
+    Spilled(SpilledRow, OnceLock<Box<Row<T>>>),
+}
+
+impl<T> Slot<T> {
+    /// Convert the slot into an uncompressed row.
+    fn into_row(self, packer: Option<Packer<T>>, file: Option<&SpillFile>) -> Row<T> {
+        match self {
+            Slot::Row(row) => row,
+            Slot::Packed(packed, row) => {
+                row.into_inner().map_or_else(|| unpack(packer, &packed), |row| *row)
+            },
+            Slot::Spilled(spilled, row) => {
+                row.into_inner().map_or_else(|| read_spilled(packer, file, &spilled), |row| *row)
+            },
+        }
+    }
+}
+
+impl<T: PartialEq> PartialEq for Slot<T> {
+    fn eq(&self, other: &Self) -> bool {
+        match (self, other) {
+            (Slot::Row(row), Slot::Row(other)) => row == other,
+            (Slot::Packed(packed, _), Slot::Packed(other, _)) => packed == other,
+            (Slot::Spilled(spilled, _), Slot::Spilled(other, _)) => spilled == other,
+            _ => false,
+        }
+    }
+}
+
+/// Approximate memory used by an unpacked row.
+#[inline]
+fn row_size<T>(columns: usize) -> usize {
+    mem::size_of::<Row<T>>() + columns * mem::size_of::<T>()
+}
+
+/// Decompress a row.
+#[inline]
+fn unpack<T>(packer: Option<Packer<T>>, packed: &PackedRow<T>) -> Row<T> {
+    packer.expect("compressed row without packer").unpack(packed)
+}
+
+/// Read a compressed row from disk.
+fn read_spilled<T>(
+    packer: Option<Packer<T>>,
+    file: Option<&SpillFile>,
+    spilled: &SpilledRow,
+) -> Row<T> {
+    let packer = packer.expect("compressed row without packer");
+    let file = file.expect("spilled row without file");
+
+    let bytes = file.read(spilled.offset, spilled.len).unwrap_or_else(|err| {
+        error!("Unable to read scrollback from disk: {err}");
+        Vec::new()
+    });
+
+    let mut row = packer.read(&bytes, spilled.columns as usize);
+    row.vi_marks = spilled.vi_marks;
+    row
+}
+
+/// Memory used by compressed rows, moving them to disk once a limit is exceeded.
+#[derive(Clone, Debug, Default)]
+struct Budget {
+    /// Maximum number of bytes used by compressed rows.
+    memory_limit: Option<usize>,
+
+    /// Number of bytes used by compressed rows.
+    memory: usize,
+
+    /// File storing rows moved to disk.
+    file: Option<SpillFile>,
+
+    /// Buffer for serializing rows.
+    buf: Vec<u8>,
+}
+
+impl Budget {
+    /// Check if compressed rows use more memory than allowed.
+    #[inline]
+    fn exceeded(&self) -> bool {
+        self.exceeded_by(0)
+    }
+
+    /// Check if compressed rows and `additional` bytes use more memory than allowed.
+    #[inline]
+    fn exceeded_by(&self, additional: usize) -> bool {
+        self.memory_limit.is_some_and(|limit| self.memory + additional > limit)
+    }
+
+    /// Stop tracking a slot which is about to be dropped.
+    #[inline]
+    fn release<T>(&mut self, slot: &Slot<T>) {
+        match slot {
+            Slot::Row(_) => (),
+            Slot::Packed(packed, _) => self.memory -= packed.size(),
+            Slot::Spilled(spilled, _) => {
+                if let Some(file) = &mut self.file {
+                    file.release(spilled.len);
+                }
+            },
+        }
+    }
+
+    /// Move a compressed row to disk.
+    fn spill<T>(&mut self, slot: &mut Slot<T>, packer: Packer<T>) {
+        let packed = match slot {
+            Slot::Packed(packed, _) => packed,
+            _ => return,
+        };
+
+        // Rows with data which can't be serialized are kept in memory.
+        if !packer.write(packed, &mut self.buf) {
+            return;
+        }
+
+        if self.file.is_none() {
+            match SpillFile::new() {
+                Ok(file) => self.file = Some(file),
+                Err(err) => {
+                    error!("Unable to create scrollback file: {err}");
+                    self.memory_limit = None;
+                    return;
+                },
+            }
+        }
+
+        let file = self.file.as_mut().unwrap();
+        match file.write(&self.buf) {
+            Ok(offset) => {
+                let len = self.buf.len() as u32;
+                let spilled = SpilledRow {
+                    offset,
+                    len,
+                    columns: packed.columns() as u32,
+                    prompt_marks: packed.prompt_marks(),
+                    vi_marks: packed.vi_marks(),
+                };
+                self.memory -= packed.size();
+                *slot = Slot::Spilled(spilled, OnceLock::new());
+            },
+            Err(err) => {
+                error!("Unable to write scrollback to disk: {err}");
+                self.memory_limit = None;
+            },
+        }
+    }
+}
+
+/// Compressed rows which have been unpacked for reading.
+#[derive(Debug, Default)]
+struct Unpacked(Mutex<UnpackedRows>);
+
+#[derive(Clone, Debug, Default)]
+struct UnpackedRows {
+    /// Raw indices of the rows.
+    indices: Vec<usize>,
+
+    /// Approximate number of bytes used by the unpacked copies.
+    memory: usize,
+}
+
+impl Unpacked {
+    /// Track a row which has just been unpacked.
+    #[inline]
+    fn push(&self, index: usize, size: usize) {
+        let mut unpacked = self.lock();
+        unpacked.indices.push(index);
+        unpacked.memory += size;
+    }
+
+    #[inline]
+    fn lock(&self) -> MutexGuard<'_, UnpackedRows> {
+        self.0.lock().unwrap_or_else(PoisonError::into_inner)
+    }
+
+    #[inline]
+    fn get_mut(&mut self) -> &mut UnpackedRows {
+        self.0.get_mut().unwrap_or_else(PoisonError::into_inner)
+    }
+}
+
+impl Clone for Unpacked {
+    fn clone(&self) -> Self {
+        Self(Mutex::new(self.lock().clone()))
+    }
+}
+
+/// Rows removed from the storage, which are decompressed while iterating.
+pub struct Rows<T> {
+    slots: vec::IntoIter<Slot<T>>,
+    packer: Option<Packer<T>>,
+    file: Option<SpillFile>,
+}
+
+impl<T> Iterator for Rows<T> {
+    type Item = Row<T>;
+
+    #[inline]
+    fn next(&mut self) -> Option<Row<T>> {
+        Some(self.slots.next()?.into_row(self.packer, self.file.as_ref()))
+    }
+
+    #[inline]
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        self.slots.size_hint()
+    }
+}
+
+impl<T> DoubleEndedIterator for Rows<T> {
+    #[inline]
+    fn next_back(&mut self) -> Option<Row<T>> {
+        Some(self.slots.next_back()?.into_row(self.packer, self.file.as_ref()))
+    }
+}
+
+impl<T> ExactSizeIterator for Rows<T> {}
+
+/// Rows for rebuilding the storage, ordered from the top of the history to the bottom.
+///
+/// Rows are compressed as soon as enough rows have been pushed below them, and moved to disk
+/// starting at the top once they exceed the memory limit.
+pub struct RowBuffer<T> {
+    slots: Vec<Slot<T>>,
+
+    /// Number of rows at the bottom which are kept uncompressed.
+    unpacked: usize,
+
+    packer: Option<Packer<T>>,
+    columns: usize,
+    budget: Budget,
+
+    /// Rows before this index have been moved to disk, or can't be moved there.
+    spill_end: usize,
+}
+
+impl<T: Default> RowBuffer<T> {
+    /// Add a row to the bottom.
+    #[inline]
+    pub fn push(&mut self, row: Row<T>) {
+        self.slots.push(Slot::Row(row));
+
+        let index = match self.slots.len().checked_sub(self.unpacked + 1) {
+            Some(index) => index,
+            None => return,
+        };
+
+        let packer = match (self.packer, &mut self.slots[index]) {
+            (Some(packer), Slot::Row(row)) => {
+                row.grow(self.columns);
+                let packed = packer.pack(row);
+                self.budget.memory += packed.size();
+                self.slots[index] = Slot::Packed(Box::new(packed), OnceLock::new());
+                packer
+            },
+            _ => return,
+        };
+
+        while self.budget.exceeded() && self.spill_end <= index {
+            self.budget.spill(&mut self.slots[self.spill_end], packer);
+            self.spill_end += 1;
+        }
+    }
+
+    /// Append rows to the bottom until the buffer contains `len` rows.
+    #[inline]
+    pub fn resize_with<F: FnMut() -> Row<T>>(&mut self, len: usize, mut f: F) {
+        while self.slots.len() < len {
+            self.push(f());
+        }
+    }
+}
+
+impl<T> RowBuffer<T> {
+    /// Bottommost row.
+    #[inline]
+    pub fn last_mut(&mut self) -> Option<&mut Row<T>> {
+        match self.slots.last_mut()? {
+            Slot::Row(row) => Some(row),
+            Slot::Packed(..) | Slot::Spilled(..) => None,
+        }
+    }
+
+    #[inline]
+    pub fn len(&self) -> usize {
+        self.slots.len()
+    }
+
+    /// Remove rows from the bottom, keeping the first `len` rows.
+    #[inline]
+    pub fn truncate(&mut self, len: usize) {
+        for slot in self.slots.get(len..).unwrap_or_default() {
+            self.budget.release(slot);
+        }
+        self.slots.truncate(len);
+        self.spill_end = self.spill_end.min(self.slots.len());
+    }
+
+    /// Remove rows from the top, keeping the last `len` rows.
+    #[inline]
+    pub fn truncate_front(&mut self, len: usize) {
+        let removed = self.slots.len().saturating_sub(len);
+        for slot in self.slots.drain(..removed) {
+            self.budget.release(&slot);
+        }
+        self.spill_end = self.spill_end.saturating_sub(removed);
     }
 }
 
 #[cfg(test)]
 mod tests {
     use crate::grid::GridCell;
-    use crate::grid::row::Row;
-    use crate::grid::storage::{MAX_CACHE_SIZE, Storage};
+    use crate::grid::row::{Row, ViMarks};
+    use crate::grid::storage::{
+        MAX_CACHE_SIZE, PACK_BATCH, Slot, Storage, UNLIMITED_UNPACKED_HISTORY, UNPACKED_HISTORY,
+    };
     use crate::index::{Column, Line};
-    use crate::term::cell::Flags;
+    use crate::term::cell::{Cell, Flags};
 
     impl GridCell for char {
         fn is_empty(&self) -> bool {
@@ -349,24 +1103,16 @@ mod tests {
     #[test]
     fn grow_after_zero() {
         // Setup storage area.
-        let mut storage: Storage<char> = Storage {
-            inner: vec![filled_row('0'), filled_row('1'), filled_row('-')],
-            zero: 0,
-            visible_lines: 3,
-            len: 3,
-        };
+        let mut storage: Storage<char> =
+            new_storage(vec![filled_row('0'), filled_row('1'), filled_row('-')], 0, 3, 3);
 
         // Grow buffer.
         storage.grow_visible_lines(4);
 
         // Make sure the result is correct.
-        let mut expected = Storage {
-            inner: vec![filled_row('0'), filled_row('1'), filled_row('-')],
-            zero: 0,
-            visible_lines: 4,
-            len: 4,
-        };
-        expected.inner.append(&mut vec![filled_row('\0'); MAX_CACHE_SIZE]);
+        let mut expected =
+            new_storage(vec![filled_row('0'), filled_row('1'), filled_row('-')], 0, 4, 4);
+        expected.inner.append(&mut vec![Slot::Row(filled_row('\0')); MAX_CACHE_SIZE]);
 
         assert_eq!(storage.visible_lines, expected.visible_lines);
         assert_eq!(storage.inner, expected.inner);
@@ -390,24 +1136,16 @@ mod tests {
     #[test]
     fn grow_before_zero() {
         // Setup storage area.
-        let mut storage: Storage<char> = Storage {
-            inner: vec![filled_row('-'), filled_row('0'), filled_row('1')],
-            zero: 1,
-            visible_lines: 3,
-            len: 3,
-        };
+        let mut storage: Storage<char> =
+            new_storage(vec![filled_row('-'), filled_row('0'), filled_row('1')], 1, 3, 3);
 
         // Grow buffer.
         storage.grow_visible_lines(4);
 
         // Make sure the result is correct.
-        let mut expected = Storage {
-            inner: vec![filled_row('0'), filled_row('1'), filled_row('-')],
-            zero: 0,
-            visible_lines: 4,
-            len: 4,
-        };
-        expected.inner.append(&mut vec![filled_row('\0'); MAX_CACHE_SIZE]);
+        let mut expected =
+            new_storage(vec![filled_row('0'), filled_row('1'), filled_row('-')], 0, 4, 4);
+        expected.inner.append(&mut vec![Slot::Row(filled_row('\0')); MAX_CACHE_SIZE]);
 
         assert_eq!(storage.visible_lines, expected.visible_lines);
         assert_eq!(storage.inner, expected.inner);
@@ -428,23 +1166,15 @@ mod tests {
     #[test]
     fn shrink_before_zero() {
         // Setup storage area.
-        let mut storage: Storage<char> = Storage {
-            inner: vec![filled_row('2'), filled_row('0'), filled_row('1')],
-            zero: 1,
-            visible_lines: 3,
-            len: 3,
-        };
+        let mut storage: Storage<char> =
+            new_storage(vec![filled_row('2'), filled_row('0'), filled_row('1')], 1, 3, 3);
 
         // Shrink buffer.
         storage.shrink_visible_lines(2);
 
         // Make sure the result is correct.
-        let expected = Storage {
-            inner: vec![filled_row('2'), filled_row('0'), filled_row('1')],
-            zero: 1,
-            visible_lines: 2,
-            len: 2,
-        };
+        let expected =
+            new_storage(vec![filled_row('2'), filled_row('0'), filled_row('1')], 1, 2, 2);
         assert_eq!(storage.visible_lines, expected.visible_lines);
         assert_eq!(storage.inner, expected.inner);
         assert_eq!(storage.zero, expected.zero);
@@ -464,23 +1194,15 @@ mod tests {
     #[test]
     fn shrink_after_zero() {
         // Setup storage area.
-        let mut storage: Storage<char> = Storage {
-            inner: vec![filled_row('0'), filled_row('1'), filled_row('2')],
-            zero: 0,
-            visible_lines: 3,
-            len: 3,
-        };
+        let mut storage: Storage<char> =
+            new_storage(vec![filled_row('0'), filled_row('1'), filled_row('2')], 0, 3, 3);
 
         // Shrink buffer.
         storage.shrink_visible_lines(2);
 
         // Make sure the result is correct.
-        let expected = Storage {
-            inner: vec![filled_row('0'), filled_row('1'), filled_row('2')],
-            zero: 0,
-            visible_lines: 2,
-            len: 2,
-        };
+        let expected =
+            new_storage(vec![filled_row('0'), filled_row('1'), filled_row('2')], 0, 2, 2);
         assert_eq!(storage.visible_lines, expected.visible_lines);
         assert_eq!(storage.inner, expected.inner);
         assert_eq!(storage.zero, expected.zero);
@@ -506,8 +1228,8 @@ mod tests {
     #[test]
     fn shrink_before_and_after_zero() {
         // Setup storage area.
-        let mut storage: Storage<char> = Storage {
-            inner: vec![
+        let mut storage: Storage<char> = new_storage(
+            vec![
                 filled_row('4'),
                 filled_row('5'),
                 filled_row('0'),
@@ -515,17 +1237,17 @@ mod tests {
                 filled_row('2'),
                 filled_row('3'),
             ],
-            zero: 2,
-            visible_lines: 6,
-            len: 6,
-        };
+            2,
+            6,
+            6,
+        );
 
         // Shrink buffer.
         storage.shrink_visible_lines(2);
 
         // Make sure the result is correct.
-        let expected = Storage {
-            inner: vec![
+        let expected = new_storage(
+            vec![
                 filled_row('4'),
                 filled_row('5'),
                 filled_row('0'),
@@ -533,10 +1255,10 @@ mod tests {
                 filled_row('2'),
                 filled_row('3'),
             ],
-            zero: 2,
-            visible_lines: 2,
-            len: 2,
-        };
+            2,
+            2,
+            2,
+        );
         assert_eq!(storage.visible_lines, expected.visible_lines);
         assert_eq!(storage.inner, expected.inner);
         assert_eq!(storage.zero, expected.zero);
@@ -558,8 +1280,8 @@ mod tests {
     #[test]
     fn truncate_invisible_lines() {
         // Setup storage area.
-        let mut storage: Storage<char> = Storage {
-            inner: vec![
+        let mut storage: Storage<char> = new_storage(
+            vec![
                 filled_row('4'),
                 filled_row('5'),
                 filled_row('0'),
@@ -567,21 +1289,16 @@ mod tests {
                 filled_row('2'),
                 filled_row('3'),
             ],
-            zero: 2,
-            visible_lines: 1,
-            len: 2,
-        };
+            2,
+            1,
+            2,
+        );
 
         // Truncate buffer.
         storage.truncate();
 
         // Make sure the result is correct.
-        let expected = Storage {
-            inner: vec![filled_row('0'), filled_row('1')],
-            zero: 0,
-            visible_lines: 1,
-            len: 2,
-        };
+        let expected = new_storage(vec![filled_row('0'), filled_row('1')], 0, 1, 2);
         assert_eq!(storage.visible_lines, expected.visible_lines);
         assert_eq!(storage.inner, expected.inner);
         assert_eq!(storage.zero, expected.zero);
@@ -600,23 +1317,14 @@ mod tests {
     #[test]
     fn truncate_invisible_lines_beginning() {
         // Setup storage area.
-        let mut storage: Storage<char> = Storage {
-            inner: vec![filled_row('1'), filled_row('2'), filled_row('0')],
-            zero: 2,
-            visible_lines: 1,
-            len: 2,
-        };
+        let mut storage: Storage<char> =
+            new_storage(vec![filled_row('1'), filled_row('2'), filled_row('0')], 2, 1, 2);
 
         // Truncate buffer.
         storage.truncate();
 
         // Make sure the result is correct.
-        let expected = Storage {
-            inner: vec![filled_row('0'), filled_row('1')],
-            zero: 0,
-            visible_lines: 1,
-            len: 2,
-        };
+        let expected = new_storage(vec![filled_row('0'), filled_row('1')], 0, 1, 2);
         assert_eq!(storage.visible_lines, expected.visible_lines);
         assert_eq!(storage.inner, expected.inner);
         assert_eq!(storage.zero, expected.zero);
@@ -650,8 +1358,8 @@ mod tests {
     #[test]
     fn shrink_then_grow() {
         // Setup storage area.
-        let mut storage: Storage<char> = Storage {
-            inner: vec![
+        let mut storage: Storage<char> = new_storage(
+            vec![
                 filled_row('4'),
                 filled_row('5'),
                 filled_row('0'),
@@ -659,17 +1367,17 @@ mod tests {
                 filled_row('2'),
                 filled_row('3'),
             ],
-            zero: 2,
-            visible_lines: 0,
-            len: 6,
-        };
+            2,
+            0,
+            6,
+        );
 
         // Shrink buffer.
         storage.shrink_lines(3);
 
         // Make sure the result after shrinking is correct.
-        let shrinking_expected = Storage {
-            inner: vec![
+        let shrinking_expected = new_storage(
+            vec![
                 filled_row('4'),
                 filled_row('5'),
                 filled_row('0'),
@@ -677,10 +1385,10 @@ mod tests {
                 filled_row('2'),
                 filled_row('3'),
             ],
-            zero: 2,
-            visible_lines: 0,
-            len: 3,
-        };
+            2,
+            0,
+            3,
+        );
         assert_eq!(storage.inner, shrinking_expected.inner);
         assert_eq!(storage.zero, shrinking_expected.zero);
         assert_eq!(storage.len, shrinking_expected.len);
@@ -689,8 +1397,8 @@ mod tests {
         storage.initialize(1, 1);
 
         // Make sure the previously freed elements are reused.
-        let growing_expected = Storage {
-            inner: vec![
+        let growing_expected = new_storage(
+            vec![
                 filled_row('4'),
                 filled_row('5'),
                 filled_row('0'),
@@ -698,10 +1406,10 @@ mod tests {
                 filled_row('2'),
                 filled_row('3'),
             ],
-            zero: 2,
-            visible_lines: 0,
-            len: 4,
-        };
+            2,
+            0,
+            4,
+        );
 
         assert_eq!(storage.inner, growing_expected.inner);
         assert_eq!(storage.zero, growing_expected.zero);
@@ -711,8 +1419,8 @@ mod tests {
     #[test]
     fn initialize() {
         // Setup storage area.
-        let mut storage: Storage<char> = Storage {
-            inner: vec![
+        let mut storage: Storage<char> = new_storage(
+            vec![
                 filled_row('4'),
                 filled_row('5'),
                 filled_row('0'),
@@ -720,10 +1428,10 @@ mod tests {
                 filled_row('2'),
                 filled_row('3'),
             ],
-            zero: 2,
-            visible_lines: 0,
-            len: 6,
-        };
+            2,
+            0,
+            6,
+        );
 
         // Initialize additional lines.
         let init_size = 3;
@@ -740,7 +1448,7 @@ mod tests {
         ];
         let expected_init_size = std::cmp::max(init_size, MAX_CACHE_SIZE);
         expected_inner.append(&mut vec![filled_row('\0'); expected_init_size]);
-        let expected_storage = Storage { inner: expected_inner, zero: 0, visible_lines: 0, len: 9 };
+        let expected_storage = new_storage(expected_inner, 0, 0, 9);
 
         assert_eq!(storage.len, expected_storage.len);
         assert_eq!(storage.zero, expected_storage.zero);
@@ -749,18 +1457,129 @@ mod tests {
 
     #[test]
     fn rotate_wrap_zero() {
-        let mut storage: Storage<char> = Storage {
-            inner: vec![filled_row('-'), filled_row('-'), filled_row('-')],
-            zero: 2,
-            visible_lines: 0,
-            len: 3,
-        };
+        let mut storage: Storage<char> =
+            new_storage(vec![filled_row('-'), filled_row('-'), filled_row('-')], 2, 0, 3);
 
         storage.rotate(2);
 
         assert!(storage.zero < storage.inner.len());
     }
 
+    #[test]
+    fn pack_history() {
+        let history = UNPACKED_HISTORY + PACK_BATCH;
+        let mut storage = Storage::<Cell>::with_capacity(1, 1);
+        storage.initialize(history, 1);
+        storage[Line(0)][Column(0)].c = 'x';
+        storage.rotate(-(history as isize));
+
+        // Without a memory limit, rows are only packed beyond a much larger history.
+        let deepest = Line(-(history as i32));
+        let index = storage.compute_index(deepest);
+        assert!(matches!(storage.inner[index], Slot::Row(_)));
+
+        // Only rows beyond the uncompressed part of the history are packed.
+        storage.set_memory_limit(Some(1 << 30));
+        assert!(matches!(storage.inner[index], Slot::Packed(..)));
+        let shallowest = Line(-(UNPACKED_HISTORY as i32));
+        assert!(matches!(storage.inner[storage.compute_index(shallowest)], Slot::Row(_)));
+
+        // Reading a row without indexing doesn't keep it unpacked.
+        assert_eq!(storage.read(deepest)[Column(0)].c, 'x');
+        assert!(storage.unpacked.lock().indices.is_empty());
+
+        // Indexing a row unpacks a copy.
+        assert_eq!(storage[deepest][Column(0)].c, 'x');
+        assert_eq!(storage.unpacked.lock().indices[..], [index]);
+        assert!(matches!(&storage.inner[index], Slot::Packed(_, row) if row.get().is_some()));
+
+        // Marks are changed without decompressing the row.
+        storage.set_vi_marks(deepest, ViMarks(1));
+        assert_eq!(storage[deepest].vi_marks, ViMarks(1));
+        assert_eq!(storage.vi_marks(deepest), ViMarks(1));
+        assert!(matches!(storage.inner[index], Slot::Packed(..)));
+
+        // Writing to a row decompresses it.
+        storage[deepest][Column(0)].c = 'y';
+        assert!(matches!(storage.inner[index], Slot::Row(_)));
+    }
+
+    #[test]
+    fn spill_history() {
+        let history = UNPACKED_HISTORY + PACK_BATCH;
+        let mut storage = Storage::<Cell>::with_capacity(1, 1);
+        storage.set_memory_limit(Some(0));
+        storage.initialize(history, 1);
+        storage[Line(0)][Column(0)].c = 'x';
+        storage.rotate(-1);
+        storage[Line(0)][Column(0)].c = 'y';
+        storage.rotate(-(history as isize) + 1);
+
+        // Compressed rows are moved to disk once they exceed the memory limit.
+        let deepest = Line(-(history as i32));
+        let index = storage.compute_index(deepest);
+        assert!(matches!(storage.inner[index], Slot::Spilled(..)));
+        assert!(matches!(storage.inner[storage.compute_index(deepest + 1)], Slot::Spilled(..)));
+        assert_eq!(storage.budget.memory, 0);
+
+        // Reading a row loads a copy from disk.
+        assert_eq!(storage[deepest][Column(0)].c, 'x');
+        assert_eq!(storage[deepest + 1][Column(0)].c, 'y');
+        assert_eq!(storage.unpacked.lock().indices.len(), 2);
+
+        // Rows read from disk are not kept in memory.
+        storage.release_unpacked();
+        assert!(matches!(&storage.inner[index], Slot::Spilled(_, row) if row.get().is_none()));
+        assert_eq!(storage.unpacked.lock().memory, 0);
+
+        // Writing to a row moves it back into memory.
+        storage[deepest][Column(0)].c = 'z';
+        assert!(matches!(storage.inner[index], Slot::Row(_)));
+        assert_eq!(storage[deepest][Column(0)].c, 'z');
+    }
+
+    #[test]
+    fn pack_history_in_batches() {
+        let mut storage = Storage::<Cell>::with_capacity(1, 1);
+        storage.initialize(UNLIMITED_UNPACKED_HISTORY + PACK_BATCH, 1);
+
+        // Rows are left uncompressed until a full batch has moved past the uncompressed history.
+        let packed = Line(-(UNLIMITED_UNPACKED_HISTORY as i32) - 1);
+        storage.rotate(-(PACK_BATCH as isize) + 1);
+        assert!(matches!(storage.inner[storage.compute_index(packed)], Slot::Row(_)));
+
+        storage.rotate(-1);
+        assert!(matches!(storage.inner[storage.compute_index(packed)], Slot::Packed(..)));
+        let unpacked = packed + 1;
+        assert!(matches!(storage.inner[storage.compute_index(unpacked)], Slot::Row(_)));
+    }
+
+    #[test]
+    fn storage_is_sync() {
+        fn assert_sync<T: Sync>() {}
+        assert_sync::<Storage<Cell>>();
+    }
+
+    fn new_storage(
+        inner: Vec<Row<char>>,
+        zero: usize,
+        visible_lines: usize,
+        len: usize,
+    ) -> Storage<char> {
+        let inner = inner.into_iter().map(Slot::Row).collect();
+        Storage {
+            inner,
+            zero,
+            visible_lines,
+            len,
+            packer: None,
+            unpacked: Default::default(),
+            budget: Default::default(),
+            spill_start: len,
+            pending_pack: 0,
+        }
+    }
+
     fn filled_row(content: char) -> Row<char> {
         let mut row = Row::new(1);
         row[Column(0)] = content;
@@ -1,5 +1,7 @@
 //! Tests for the Grid.
 
+use std::time::{Duration, UNIX_EPOCH};
+
 use super::*;
 
 use crate::term::cell::Cell;
@@ -187,6 +189,124 @@ fn shrink_reflow() {
     assert_eq!(grid[Line(0)][Column(1)], Cell::default());
 }
 
+#[test]
+fn compressed_history() {
+    let mut grid = Grid::<Cell>::new(1, 3, 3000);
+    grid.set_memory_limit(Some(1 << 30));
+    for i in 0..3000 {
+        let c = (b'a' + (i % 26) as u8) as char;
+        grid[Line(0)][Column(0)] = cell(c);
+        grid[Line(0)][Column(2)] = cell(c);
+        grid.scroll_up(&(Line(0)..Line(1)), 1);
+    }
+
+    assert_eq!(grid[Line(-2600)][..], [cell('k'), Cell::default(), cell('k')]);
+    assert_eq!(grid[Line(-3)][..], [cell('h'), Cell::default(), cell('h')]);
+
+    // Writing to a compressed row decompresses it.
+    grid[Line(-2600)][Column(1)] = cell('!');
+    assert_eq!(grid[Line(-2600)][..], [cell('k'), cell('!'), cell('k')]);
+
+    grid.resize(false, 1, 2);
+    assert_eq!(grid[Line(-2600)][..], [cell('k'), cell('!')]);
+
+    grid.resize(false, 1, 4);
+    assert_eq!(grid[Line(-2599)][..], [
+        cell('l'),
+        Cell::default(),
+        Cell::default(),
+        Cell::default()
+    ]);
+}
+
+#[test]
+fn spilled_history() {
+    let mut grid = Grid::<Cell>::new(1, 3, 3000);
+    grid.set_memory_limit(Some(0));
+    for i in 0..4000 {
+        let c = (b'a' + (i % 26) as u8) as char;
+        grid[Line(0)][Column(0)] = cell(c);
+        grid[Line(0)][Column(2)] = cell(c);
+        grid.scroll_up(&(Line(0)..Line(1)), 1);
+    }
+
+    assert_eq!(grid.history_size(), 3000);
+    assert_eq!(grid[Line(-3000)][..], [cell('m'), Cell::default(), cell('m')]);
+    assert_eq!(grid[Line(-2600)][..], [cell('w'), Cell::default(), cell('w')]);
+    assert_eq!(grid[Line(-3)][..], [cell('t'), Cell::default(), cell('t')]);
+
+    // Writing to a row on disk moves it back into memory.
+    grid[Line(-2600)][Column(1)] = cell('!');
+    assert_eq!(grid[Line(-2600)][..], [cell('w'), cell('!'), cell('w')]);
+
+    grid.resize(false, 1, 2);
+    assert_eq!(grid[Line(-2600)][..], [cell('w'), cell('!')]);
+    assert_eq!(grid[Line(-2599)][..], [cell('x'), Cell::default()]);
+
+    grid.resize(false, 1, 4);
+    assert_eq!(grid[Line(-2999)][..], [
+        cell('n'),
+        Cell::default(),
+        Cell::default(),
+        Cell::default()
+    ]);
+}
+
+#[test]
+fn vi_marks_follow_history() {
+    let mut grid = Grid::<Cell>::new(1, 3, 3000);
+    grid.set_memory_limit(Some(1 << 30));
+    grid[Line(0)][Column(0)] = cell('x');
+    grid[Line(0)].vi_marks.insert('a');
+
+    // Marks move with their row, even once it is compressed.
+    for _ in 0..2500 {
+        grid.scroll_up(&(Line(0)..Line(1)), 1);
+    }
+    assert!(grid[Line(-2500)].vi_marks().contains('a'));
+    assert_eq!(grid[Line(-2500)][Column(0)], cell('x'));
+    assert!(grid[Line(0)].vi_marks().is_empty());
+
+    // Marks are dropped once their row leaves the history.
+    for _ in 0..501 {
+        grid.scroll_up(&(Line(0)..Line(1)), 1);
+    }
+    let topmost_line = grid.topmost_line();
+    assert!((topmost_line.0..=0).all(|line| grid[Line(line)].vi_marks().is_empty()));
+}
+
+#[test]
+fn prompt_marks_follow_history() {
+    let mut grid = Grid::<Cell>::new(2, 3, 3000);
+    grid.set_memory_limit(Some(1 << 30));
+    grid.insert_prompt_mark(Line(0), PromptMarks::PROMPT_START);
+    grid.insert_prompt_mark(Line(1), PromptMarks::OUTPUT_START);
+
+    // Marks move with their row into the history and while scrolling part of the screen.
+    for _ in 0..2500 {
+        grid.scroll_up(&(Line(0)..Line(2)), 1);
+    }
+    grid.insert_prompt_mark(Line(0), PromptMarks::PROMPT_START);
+    grid.scroll_up(&(Line(0)..Line(1)), 1);
+
+    let lines = grid.topmost_line()..Line(2);
+    let prompts: Vec<_> = grid.prompt_lines(lines.clone(), PromptMarks::PROMPT_START).collect();
+    assert_eq!(prompts, [Line(-2501), Line(-1)]);
+    let output: Vec<_> = grid.prompt_lines(lines, PromptMarks::OUTPUT_START).collect();
+    assert_eq!(output, [Line(-2500)]);
+
+    // Marks are dropped when their row is reset or leaves the history.
+    grid.insert_prompt_mark(Line(1), PromptMarks::PROMPT_START);
+    grid.scroll_down(&(Line(0)..Line(2)), 1);
+    for _ in 0..500 {
+        grid.scroll_up(&(Line(0)..Line(2)), 1);
+    }
+    let lines = grid.topmost_line()..Line(2);
+    let marks = PromptMarks::PROMPT_START | PromptMarks::OUTPUT_START;
+    let lines: Vec<_> = grid.prompt_lines(lines, marks).collect();
+    assert_eq!(lines, [Line(-3000), Line(-501)]);
+}
+
 #[test]
 fn shrink_reflow_twice() {
     let mut grid = Grid::<Cell>::new(1, 5, 2);
@@ -276,6 +396,34 @@ fn grow_reflow() {
     assert_eq!(grid[Line(1)][Column(2)], Cell::default());
 }
 
+#[test]
+fn reflow_timestamps() {
+    let time = |seconds| Some(UNIX_EPOCH + Duration::from_secs(seconds));
+
+    let mut grid = Grid::<Cell>::new(2, 4, 10);
+    grid[Line(0)][Column(0)] = cell('1');
+    grid[Line(0)][Column(3)] = cell('2');
+    grid[Line(0)].set_timestamp(time(100));
+    grid[Line(1)][Column(0)] = cell('3');
+    grid[Line(1)].set_timestamp(time(200));
+    grid.cursor.point = Point::new(Line(1), Column(0));
+
+    // Rows split by reflow keep their timestamp.
+    grid.resize(true, 2, 2);
+    assert_eq!(grid[Line(-1)][Column(0)], cell('1'));
+    assert_eq!(grid[Line(-1)].timestamp(), time(100));
+    assert_eq!(grid[Line(0)][Column(1)], cell('2'));
+    assert_eq!(grid[Line(0)].timestamp(), time(100));
+    assert_eq!(grid[Line(1)].timestamp(), time(200));
+
+    // Merged rows keep the earliest timestamp.
+    grid[Line(0)].set_timestamp(time(150));
+    grid.resize(true, 2, 4);
+    assert_eq!(grid[Line(0)][Column(3)], cell('2'));
+    assert_eq!(grid[Line(0)].timestamp(), time(100));
+    assert_eq!(grid[Line(1)].timestamp(), time(200));
+}
+
 #[test]
 fn grow_reflow_multiline() {
     let mut grid = Grid::<Cell>::new(3, 2, 0);
@@ -388,3 +536,28 @@ fn wrap_cell(c: char) -> Cell {
     cell.flags.insert(Flags::WRAPLINE);
     cell
 }
+
+#[test]
+fn copy_rect_overlapping() {
+    let mut grid = Grid::<usize>::new(4, 4, 0);
+    for line in 0..4 {
+        for column in 0..4 {
+            grid[Line(line)][Column(column)] = line as usize * 4 + column;
+        }
+    }
+
+    grid.copy_rect(Line(0)..Line(2), Column(0)..Column(3), Point::new(Line(1), Column(1)));
+
+    let cells: Vec<usize> = (0..4).flat_map(|line| grid[Line(line)][..].to_vec()).collect();
+    assert_eq!(cells, [0, 1, 2, 3, 4, 0, 1, 2, 8, 4, 5, 6, 12, 13, 14, 15]);
+}
+
+#[test]
+fn update_rect() {
+    let mut grid = Grid::<usize>::new(3, 3, 0);
+
+    grid.update_rect(Line(1)..Line(3), Column(1)..Column(2), |cell| *cell += 1);
+
+    let cells: Vec<usize> = (0..3).flat_map(|line| grid[Line(line)][..].to_vec()).collect();
+    assert_eq!(cells, [0, 0, 0, 0, 1, 0, 0, 1, 0]);
+}
@@ -6,8 +6,10 @@
 
 pub mod event;
 pub mod event_loop;
+pub mod graphics;
 pub mod grid;
 pub mod index;
+pub mod parser;
 pub mod selection;
 pub mod sync;
 pub mod term;
@@ -0,0 +1,1313 @@
+//! Escape sequence processing.
+//!
+//! The [`Processor`] wraps [`vte::ansi::Processor`], which handles everything understood by
+//! [`vte::ansi::Handler`]. A second [`Parser`] receives the same bytes to pick out the remaining
+//! sequences, which are dispatched to the [`ExtendedHandler`] trait in between.
+
+use std::path::PathBuf;
+use std::time::Duration;
+use std::{mem, str};
+
+use base64::Engine;
+use base64::engine::general_purpose::STANDARD as Base64;
+use log::debug;
+
+use crate::graphics::{Graphic, kitty, sixel};
+use crate::grid::LineSize;
+use crate::vte::ansi::{
+    self, Attr, C0, ClearMode, Color, Handler, LineClearMode, NamedPrivateMode, PrivateMode, Rgb,
+    StdSyncHandler, Timeout,
+};
+use crate::vte::{Params, ParamsIter, Parser, Perform};
+
+/// Maximum time before a synchronized update is aborted.
+const SYNC_UPDATE_TIMEOUT: Duration = Duration::from_millis(150);
+
+/// Maximum number of bytes read in one synchronized update (2MiB).
+const SYNC_BUFFER_SIZE: usize = 0x20_0000;
+
+/// Number of bytes in the BSU/ESU CSI sequences.
+const SYNC_ESCAPE_LEN: usize = 8;
+
+/// BSU CSI sequence for beginning or extending synchronized updates.
+const BSU_CSI: [u8; SYNC_ESCAPE_LEN] = *b"\x1b[?2026h";
+
+/// ESU CSI sequence for terminating synchronized updates.
+const ESU_CSI: [u8; SYNC_ESCAPE_LEN] = *b"\x1b[?2026l";
+
+/// Maximum number of bytes buffered for the title or body of a chunked notification.
+const MAX_NOTIFICATION_LEN: usize = 0x1000;
+
+/// Maximum number of bytes in an APC string (16MiB).
+const MAX_APC_LEN: usize = 0x100_0000;
+
+/// Maximum number of bytes in the payload of an XTGETTCAP or DECRQSS request.
+const MAX_QUERY_LEN: usize = 0x1000;
+
+/// Shell integration mark (OSC 133).
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum SemanticPrompt {
+    /// Start of the prompt.
+    PromptStart,
+
+    /// End of the prompt and start of the user's command input.
+    CommandStart,
+
+    /// Command was submitted and its output follows.
+    OutputStart,
+
+    /// Command finished, with its exit code when the shell reported it.
+    CommandEnd(Option<i32>),
+}
+
+/// Rectangular area of a VT400 rectangle operation.
+///
+/// Coordinates are one-based and subject to origin mode. A missing bottom or right edge extends
+/// the area to the end of the page.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub struct Rectangle {
+    pub top: usize,
+    pub left: usize,
+    pub bottom: Option<usize>,
+    pub right: Option<usize>,
+}
+
+/// Report requested by DECRQPSR.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum PresentationReport {
+    /// Cursor information report (DECCIR).
+    CursorInformation,
+
+    /// Tab stop report (DECTABSR).
+    TabStops,
+}
+
+/// Color coordinate system of a color table report (DECCTR).
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum ColorSpace {
+    /// Hue, lightness and saturation, with blue at a hue of zero degrees.
+    Hls,
+
+    /// Red, green and blue percentages.
+    Rgb,
+}
+
+/// Setting requested by DECRQSS.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum StatusSetting {
+    /// Graphic rendition (SGR).
+    GraphicRendition,
+
+    /// Top and bottom margins (DECSTBM).
+    TopBottomMargins,
+
+    /// Left and right margins (DECSLRM).
+    LeftRightMargins,
+
+    /// Cursor style (DECSCUSR).
+    CursorStyle,
+
+    /// Attribute change extent (DECSACE).
+    AttributeChangeExtent,
+
+    /// Conformance level (DECSCL).
+    ConformanceLevel,
+
+    /// Character protection attribute (DECSCA).
+    ProtectionAttribute,
+}
+
+impl StatusSetting {
+    /// Control function selecting the setting, as used in DECRQSS requests and responses.
+    pub fn function(self) -> &'static str {
+        match self {
+            Self::GraphicRendition => "m",
+            Self::TopBottomMargins => "r",
+            Self::LeftRightMargins => "s",
+            Self::CursorStyle => " q",
+            Self::AttributeChangeExtent => "*x",
+            Self::ConformanceLevel => "\"p",
+            Self::ProtectionAttribute => "\"q",
+        }
+    }
+}
+
+/// Handler for sequences not covered by [`vte::ansi::Handler`].
+///
+/// All methods have empty default implementations, so only the sequences which are actually
+/// supported need to be implemented.
+pub trait ExtendedHandler: Handler {
+    /// OSC 133 shell integration mark at the cursor position.
+    fn semantic_prompt(&mut self, _mark: SemanticPrompt) {}
+
+    /// OSC 7 working directory report.
+    ///
+    /// The hostname of the reported `file://` URI is not validated.
+    fn set_working_directory(&mut self, _path: PathBuf) {}
+
+    /// Desktop notification request (OSC 9, OSC 777 and OSC 99).
+    ///
+    /// The title is empty if the sequence only provided a body.
+    fn desktop_notification(&mut self, _title: String, _body: String) {}
+
+    /// OSC 22 mouse pointer shape request.
+    ///
+    /// The shape is a comma-separated list of cursor names in order of preference, `None`
+    /// requests the default shape.
+    fn set_pointer_shape(&mut self, _shape: Option<String>) {}
+
+    /// Display an image at the cursor position.
+    fn insert_graphic(&mut self, _graphic: Graphic) {}
+
+    /// Kitty graphics protocol command (APC G).
+    ///
+    /// Chunked transmissions are passed on unassembled, one command per chunk.
+    fn kitty_graphics(&mut self, _command: kitty::Command) {}
+
+    /// CSI s, setting the left and right margins (DECSLRM) while they are enabled.
+    ///
+    /// Without left and right margin mode (DECLRMM), this saves the cursor position instead.
+    fn set_left_right_margins(&mut self, _left: usize, _right: Option<usize>) {
+        self.save_cursor_position();
+    }
+
+    /// Copy a rectangular area, placing its top-left corner at `(line, column)` (DECCRA).
+    fn copy_rectangle(&mut self, _area: Rectangle, _line: usize, _column: usize) {}
+
+    /// Fill a rectangular area with a character using the current attributes (DECFRA).
+    fn fill_rectangle(&mut self, _c: char, _area: Rectangle) {}
+
+    /// Erase a rectangular area (DECERA).
+    ///
+    /// Selective erasure (DECSERA) only clears characters, without touching their attributes.
+    fn erase_rectangle(&mut self, _area: Rectangle, _selective: bool) {}
+
+    /// Change the attributes of an area (DECCARA), or toggle them when `reverse` is set (DECRARA).
+    ///
+    /// Depending on the attribute change extent, the area is either a rectangle or a stream of
+    /// characters from its top-left to its bottom-right corner.
+    fn change_rectangle_attributes(&mut self, _area: Rectangle, _attrs: Vec<Attr>, _reverse: bool) {
+    }
+
+    /// Select whether attribute changes apply to rectangles or character streams (DECSACE).
+    fn set_attribute_change_extent(&mut self, _rectangle: bool) {}
+
+    /// Change the display size of the cursor line (DECSWL, DECDWL and DECDHL).
+    fn set_line_size(&mut self, _size: LineSize) {}
+
+    /// SGR 53 and SGR 55, enabling or disabling overlined text.
+    fn set_overline(&mut self, _enabled: bool) {}
+
+    /// Request the value of a terminfo capability (XTGETTCAP).
+    ///
+    /// The name is `None` if it was not properly hex encoded.
+    fn request_termcap(&mut self, _name: Option<String>) {}
+
+    /// Request the current value of a setting (DECRQSS).
+    ///
+    /// The setting is `None` if it is not supported.
+    fn request_status(&mut self, _setting: Option<StatusSetting>) {}
+
+    /// Reset modes, margins and attributes without clearing the screen (DECSTR).
+    fn soft_reset(&mut self) {}
+
+    /// Protect characters written after this from selective erasure (DECSCA, SPA and EPA).
+    fn set_protected(&mut self, _protected: bool) {}
+
+    /// Erase all unprotected characters in parts of the cursor line (DECSEL).
+    ///
+    /// Like DECSERA, this only clears characters, without touching their attributes.
+    fn selective_clear_line(&mut self, _mode: LineClearMode) {}
+
+    /// Erase all unprotected characters in parts of the screen (DECSED).
+    ///
+    /// Like DECSERA, this only clears characters, without touching their attributes.
+    fn selective_clear_screen(&mut self, _mode: ClearMode) {}
+
+    /// Request the checksum of the characters in a rectangular area (DECRQCRA).
+    ///
+    /// The `id` is echoed in the response, to match it to its request.
+    fn request_checksum(&mut self, _id: u16, _area: Rectangle) {}
+
+    /// Request a presentation state report (DECRQPSR).
+    fn request_presentation_state(&mut self, _report: PresentationReport) {}
+
+    /// Request the color table report (DECRQTSR).
+    fn request_color_table(&mut self, _space: ColorSpace) {}
+}
+
+/// Internal state for the processor.
+#[derive(Debug, Default)]
+struct ProcessorState<T: Timeout> {
+    /// State for synchronized terminal updates.
+    sync_state: SyncState<T>,
+
+    /// Chunked OSC 99 notification which has not been completed yet.
+    notification: Option<PendingNotification>,
+
+    /// DCS sequence whose payload is currently being received.
+    dcs: Option<Dcs>,
+
+    /// APC strings, which are ignored by the [`Parser`].
+    apc: ApcScanner,
+}
+
+/// Handler for the payload of a DCS sequence.
+#[derive(Debug)]
+enum Dcs {
+    /// Sixel image data.
+    Sixel(Box<sixel::Parser>),
+
+    /// Hex encoded terminfo capability names of an XTGETTCAP request.
+    TermcapRequest(Vec<u8>),
+
+    /// Control function of a DECRQSS request.
+    StatusRequest(Vec<u8>),
+}
+
+/// Collector for APC strings.
+///
+/// The [`Parser`] silently discards APC strings, so this follows its state transitions just far
+/// enough to find where APC strings start and end.
+#[derive(Debug, Default)]
+struct ApcScanner {
+    state: ApcState,
+    payload: Vec<u8>,
+}
+
+impl ApcScanner {
+    /// Process bytes which have been passed to the [`Parser`].
+    ///
+    /// Returns the payload of the APC string terminated by these bytes.
+    fn advance(&mut self, bytes: &[u8]) -> Option<Vec<u8>> {
+        let mut apc = None;
+        let mut i = 0;
+        while i != bytes.len() {
+            // Outside of escapes, only string terminators can change the state.
+            if self.state != ApcState::Escape {
+                let remaining = &bytes[i..];
+                let len =
+                    remaining.iter().position(|&b| is_terminator(b)).unwrap_or(remaining.len());
+                if self.state == ApcState::Apc {
+                    self.push(&remaining[..len]);
+                }
+
+                i += len;
+                if i == bytes.len() {
+                    break;
+                }
+            }
+
+            let byte = bytes[i];
+            let state = self.state.next(byte);
+            if self.state == ApcState::Apc {
+                // Strings are only dispatched when terminated by ESC, CAN and SUB abort them.
+                let payload = mem::take(&mut self.payload);
+                apc = (byte == 0x1B).then_some(payload);
+            }
+            self.state = state;
+            i += 1;
+        }
+        apc
+    }
+
+    /// Add bytes to the current APC string, ignoring the string if it gets too long.
+    fn push(&mut self, bytes: &[u8]) {
+        if self.payload.len() + bytes.len() > MAX_APC_LEN {
+            debug!("Ignoring APC string exceeding {MAX_APC_LEN} bytes");
+            self.payload = Vec::new();
+            self.state = ApcState::Ignored;
+        } else {
+            self.payload.extend_from_slice(bytes);
+        }
+    }
+}
+
+/// Parser states relevant for APC strings.
+#[derive(Debug, Default, Copy, Clone, PartialEq, Eq)]
+enum ApcState {
+    /// Not inside of an escape or string.
+    #[default]
+    Ground,
+
+    /// After ESC.
+    Escape,
+
+    /// Inside of an APC string.
+    Apc,
+
+    /// Inside of an SOS or PM string, or an APC string which is being ignored.
+    Ignored,
+}
+
+impl ApcState {
+    /// State after processing a byte.
+    fn next(self, byte: u8) -> Self {
+        match (self, byte) {
+            (_, 0x18 | 0x1A) => Self::Ground,
+            (_, 0x1B) => Self::Escape,
+            (Self::Escape, b'_') => Self::Apc,
+            (Self::Escape, b'X' | b'^') => Self::Ignored,
+            // C0 controls are executed without leaving the escape.
+            (Self::Escape, 0x00..=0x1F | 0x7F) => Self::Escape,
+            (Self::Escape, _) => Self::Ground,
+            (state, _) => state,
+        }
+    }
+}
+
+/// Check if a byte aborts or terminates escapes and strings.
+#[inline]
+fn is_terminator(byte: u8) -> bool {
+    matches!(byte, 0x18 | 0x1A | 0x1B)
+}
+
+/// Partially received OSC 99 notification.
+#[derive(Debug, Default)]
+struct PendingNotification {
+    id: Vec<u8>,
+    title: String,
+    body: String,
+}
+
+#[derive(Debug)]
+struct SyncState<T: Timeout> {
+    /// Handler for synchronized updates.
+    timeout: T,
+
+    /// Bytes read during the synchronized update.
+    buffer: Vec<u8>,
+}
+
+impl<T: Timeout> Default for SyncState<T> {
+    fn default() -> Self {
+        Self { buffer: Vec::with_capacity(SYNC_BUFFER_SIZE), timeout: Default::default() }
+    }
+}
+
+/// The processor wraps [`vte::ansi::Processor`] to ultimately call methods on an
+/// [`ExtendedHandler`].
+#[derive(Default)]
+pub struct Processor<T: Timeout = StdSyncHandler> {
+    /// Processor for all sequences supported by vte.
+    inner: ansi::Processor<T>,
+
+    /// Parser receiving the same bytes as `inner`, to find the sequences vte does not support.
+    parser: Parser,
+
+    state: ProcessorState<T>,
+}
+
+impl<T: Timeout> Processor<T> {
+    #[inline]
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Synchronized update timeout.
+    pub fn sync_timeout(&self) -> &T {
+        &self.state.sync_state.timeout
+    }
+
+    /// Process a new byte from the PTY.
+    #[inline]
+    pub fn advance<H>(&mut self, handler: &mut H, bytes: &[u8])
+    where
+        H: ExtendedHandler,
+    {
+        let mut processed = 0;
+        while processed != bytes.len() {
+            if self.state.sync_state.timeout.pending_timeout() {
+                processed += self.advance_sync(handler, &bytes[processed..]);
+            } else {
+                processed += self.advance_parser(handler, &bytes[processed..], true);
+            }
+        }
+    }
+
+    /// Pass bytes to vte, dispatching extension sequences and APC strings in between.
+    ///
+    /// When `until_sync` is set, processing stops once a synchronized update is started.
+    /// Returns the number of bytes processed.
+    fn advance_parser<H>(&mut self, handler: &mut H, bytes: &[u8], until_sync: bool) -> usize
+    where
+        H: ExtendedHandler,
+    {
+        let mut processed = 0;
+        while processed != bytes.len() {
+            // Split after every string terminator, so APC strings are dispatched before the
+            // sequences following them.
+            let remaining = &bytes[processed..];
+            let len =
+                remaining.iter().position(|&b| is_terminator(b)).map_or(remaining.len(), |i| i + 1);
+            let segment = &remaining[..len];
+
+            // Stop in front of the next extension sequence.
+            let mut performer = Performer::new(&mut self.state);
+            let count = self.parser.advance_until_terminated(&mut performer, segment);
+            let Performer { dispatch, replace, .. } = performer;
+
+            // Sequences replacing vte's implementation are aborted with CAN before their final
+            // byte, so vte only sees the bytes in front of them.
+            if replace {
+                self.inner.advance(handler, &segment[..count - 1]);
+                self.inner.advance(handler, &[C0::CAN]);
+            } else {
+                self.inner.advance(handler, &segment[..count]);
+            }
+
+            if let Some(dispatch) = dispatch {
+                dispatch(handler);
+            }
+
+            if let Some(apc) = self.state.apc.advance(&segment[..count]) {
+                apc_dispatch(handler, &apc);
+            }
+
+            processed += count;
+            if until_sync && self.state.sync_state.timeout.pending_timeout() {
+                break;
+            }
+        }
+        processed
+    }
+
+    /// End a synchronized update.
+    pub fn stop_sync<H>(&mut self, handler: &mut H)
+    where
+        H: ExtendedHandler,
+    {
+        self.stop_sync_internal(handler, None);
+    }
+
+    /// End a synchronized update.
+    ///
+    /// The `bsu_offset` parameter should be passed if the sync buffer contains a new BSU escape
+    /// that is not part of the current synchronized update.
+    fn stop_sync_internal<H>(&mut self, handler: &mut H, bsu_offset: Option<usize>)
+    where
+        H: ExtendedHandler,
+    {
+        // Process all synchronized bytes.
+        //
+        // NOTE: We do not stop at new synchronized updates here since BSU sequences are
+        // processed automatically during the synchronized update.
+        let buffer = mem::take(&mut self.state.sync_state.buffer);
+        let offset = bsu_offset.unwrap_or(buffer.len());
+        self.advance_parser(handler, &buffer[..offset], false);
+        self.state.sync_state.buffer = buffer;
+        match bsu_offset {
+            // Just clear processed bytes if there is a new BSU.
+            //
+            // NOTE: We do not need to re-process for a new ESU since the `advance_sync`
+            // function checks for BSUs in reverse.
+            Some(bsu_offset) => {
+                let new_len = self.state.sync_state.buffer.len() - bsu_offset;
+                self.state.sync_state.buffer.copy_within(bsu_offset.., 0);
+                self.state.sync_state.buffer.truncate(new_len);
+            },
+            // Report mode and clear state if no new BSU is present.
+            None => {
+                handler.unset_private_mode(NamedPrivateMode::SyncUpdate.into());
+                self.state.sync_state.timeout.clear_timeout();
+                self.state.sync_state.buffer.clear();
+            },
+        }
+    }
+
+    /// Number of bytes in the synchronization buffer.
+    #[inline]
+    pub fn sync_bytes_count(&self) -> usize {
+        self.state.sync_state.buffer.len()
+    }
+
+    /// Process a new byte during a synchronized update.
+    ///
+    /// Returns the number of bytes processed.
+    #[cold]
+    fn advance_sync<H>(&mut self, handler: &mut H, bytes: &[u8]) -> usize
+    where
+        H: ExtendedHandler,
+    {
+        // Advance sync parser or stop sync if we'd exceed the maximum buffer size.
+        if self.state.sync_state.buffer.len() + bytes.len() >= SYNC_BUFFER_SIZE - 1 {
+            // Terminate the synchronized update.
+            self.stop_sync_internal(handler, None);
+
+            // Just parse the bytes normally.
+            self.advance_parser(handler, bytes, true)
+        } else {
+            self.state.sync_state.buffer.extend(bytes);
+            self.advance_sync_csi(handler, bytes.len());
+            bytes.len()
+        }
+    }
+
+    /// Handle BSU/ESU CSI sequences during synchronized update.
+    fn advance_sync_csi<H>(&mut self, handler: &mut H, new_bytes: usize)
+    where
+        H: ExtendedHandler,
+    {
+        // Get constraints within which a new escape character might be relevant.
+        let buffer_len = self.state.sync_state.buffer.len();
+        let start_offset = (buffer_len - new_bytes).saturating_sub(SYNC_ESCAPE_LEN - 1);
+        let end_offset = buffer_len.saturating_sub(SYNC_ESCAPE_LEN - 1);
+        let search_buffer = &self.state.sync_state.buffer[start_offset..end_offset];
+
+        // Search for termination/extension escapes in the added bytes.
+        //
+        // NOTE: It is technically legal to specify multiple private modes in the same
+        // escape, but we only allow EXACTLY `\e[?2026h`/`\e[?2026l` to keep the parser
+        // more simple.
+        let mut bsu_offset = None;
+        let escapes = search_buffer.iter().enumerate().filter(|(_, byte)| **byte == 0x1B);
+        for (index, _) in escapes.rev() {
+            let offset = start_offset + index;
+            let escape = &self.state.sync_state.buffer[offset..offset + SYNC_ESCAPE_LEN];
+
+            if escape == BSU_CSI {
+                self.state.sync_state.timeout.set_timeout(SYNC_UPDATE_TIMEOUT);
+                bsu_offset = Some(offset);
+            } else if escape == ESU_CSI {
+                self.stop_sync_internal(handler, bsu_offset);
+                break;
+            }
+        }
+    }
+}
+
+/// Dispatch a complete APC string.
+fn apc_dispatch<H: ExtendedHandler>(handler: &mut H, apc: &[u8]) {
+    match apc {
+        [b'G', data @ ..] => match kitty::Command::parse(data) {
+            Some(command) => handler.kitty_graphics(command),
+            None => debug!("Invalid kitty graphics command: {:?}", String::from_utf8_lossy(data)),
+        },
+        _ => debug!("[unhandled apc] {:?}", String::from_utf8_lossy(apc)),
+    }
+}
+
+/// Deferred call to an [`ExtendedHandler`].
+type Dispatch<'a, H> = Box<dyn FnOnce(&mut H) + 'a>;
+
+/// Helper type that implements [`Perform`].
+///
+/// Processor creates a Performer when running advance and passes the Performer to [`Parser`].
+/// It ignores everything supported by vte and terminates the parser at the first extension
+/// sequence, so the handler is only called once vte has processed all bytes in front of it.
+struct Performer<'a, 'd, H: ExtendedHandler, T: Timeout> {
+    state: &'a mut ProcessorState<T>,
+
+    /// Handler call for the extension sequence which terminated the parser.
+    dispatch: Option<Dispatch<'d, H>>,
+
+    /// Whether the extension sequence must be hidden from vte.
+    replace: bool,
+}
+
+impl<'a, 'd, H: ExtendedHandler + 'd, T: Timeout> Performer<'a, 'd, H, T> {
+    /// Create a performer.
+    #[inline]
+    fn new(state: &'a mut ProcessorState<T>) -> Self {
+        Performer { state, dispatch: None, replace: false }
+    }
+
+    /// Dispatch a sequence which is ignored by vte.
+    fn dispatch(&mut self, dispatch: impl FnOnce(&mut H) + 'd) {
+        self.dispatch = Some(Box::new(dispatch));
+    }
+
+    /// Dispatch a sequence instead of vte's implementation.
+    fn replace(&mut self, dispatch: impl FnOnce(&mut H) + 'd) {
+        self.dispatch(dispatch);
+        self.replace = true;
+    }
+
+    /// Process a chunk of an OSC 99 notification.
+    ///
+    /// Metadata is in the format `key1=value1:key2=value2`. Only the title and body payloads are
+    /// supported, everything else is ignored.
+    fn kitty_notification(&mut self, metadata: &[u8], payload: &[u8]) -> Option<()> {
+        let mut id: &[u8] = b"";
+        let mut done = true;
+        let mut encoded = false;
+        let mut is_title = true;
+        for kv in metadata.split(|&b| b == b':').filter(|kv| !kv.is_empty()) {
+            let separator = kv.iter().position(|&b| b == b'=')?;
+            let (key, value) = (&kv[..separator], &kv[separator + 1..]);
+            match key {
+                b"i" => id = value,
+                b"d" => done = value != b"0",
+                b"e" => encoded = value == b"1",
+                b"p" if value == b"title" => is_title = true,
+                b"p" if value == b"body" => is_title = false,
+                b"p" => return None,
+                _ => (),
+            }
+        }
+
+        let payload = if encoded { Base64.decode(payload).ok()? } else { payload.to_vec() };
+
+        // Chunks with a new ID replace the previous notification.
+        if self.state.notification.as_ref().is_some_and(|pending| pending.id != id) {
+            self.state.notification = None;
+        }
+        let pending = self
+            .state
+            .notification
+            .get_or_insert_with(|| PendingNotification { id: id.to_vec(), ..Default::default() });
+
+        let text = String::from_utf8_lossy(&payload);
+        let field = if is_title { &mut pending.title } else { &mut pending.body };
+        if field.len() + text.len() <= MAX_NOTIFICATION_LEN {
+            field.push_str(&text);
+        }
+
+        if done {
+            let PendingNotification { title, body, .. } = self.state.notification.take()?;
+            if !title.is_empty() || !body.is_empty() {
+                self.dispatch(move |handler| handler.desktop_notification(title, body));
+            }
+        }
+
+        Some(())
+    }
+}
+
+impl<'d, H, T> Perform for Performer<'_, 'd, H, T>
+where
+    H: ExtendedHandler + 'd,
+    T: Timeout,
+{
+    #[inline]
+    fn hook(&mut self, params: &Params, intermediates: &[u8], ignore: bool, action: char) {
+        if ignore {
+            return;
+        }
+
+        self.state.dcs = match (action, intermediates) {
+            // Sixel graphics.
+            ('q', []) => Some(Dcs::Sixel(Box::new(sixel::Parser::new(params)))),
+            ('q', [b'+']) => Some(Dcs::TermcapRequest(Vec::new())),
+            ('q', [b'$']) => Some(Dcs::StatusRequest(Vec::new())),
+            _ => None,
+        };
+    }
+
+    #[inline]
+    fn put(&mut self, byte: u8) {
+        match &mut self.state.dcs {
+            Some(Dcs::Sixel(parser)) => parser.put(byte),
+            Some(Dcs::TermcapRequest(payload) | Dcs::StatusRequest(payload))
+                if payload.len() < MAX_QUERY_LEN =>
+            {
+                payload.push(byte)
+            },
+            _ => (),
+        }
+    }
+
+    #[inline]
+    fn unhook(&mut self) {
+        match self.state.dcs.take() {
+            Some(Dcs::Sixel(parser)) => {
+                if let Some(graphic) = parser.finish() {
+                    self.dispatch(move |handler| handler.insert_graphic(graphic));
+                }
+            },
+            Some(Dcs::TermcapRequest(payload)) => {
+                let names: Vec<_> = payload.split(|&byte| byte == b';').map(hex_decode).collect();
+                self.dispatch(move |handler| {
+                    for name in names {
+                        handler.request_termcap(name);
+                    }
+                });
+            },
+            Some(Dcs::StatusRequest(payload)) => {
+                let setting = match &payload[..] {
+                    b"m" => Some(StatusSetting::GraphicRendition),
+                    b"r" => Some(StatusSetting::TopBottomMargins),
+                    b"s" => Some(StatusSetting::LeftRightMargins),
+                    b" q" => Some(StatusSetting::CursorStyle),
+                    b"*x" => Some(StatusSetting::AttributeChangeExtent),
+                    b"\"p" => Some(StatusSetting::ConformanceLevel),
+                    b"\"q" => Some(StatusSetting::ProtectionAttribute),
+                    _ => None,
+                };
+                self.dispatch(move |handler| handler.request_status(setting));
+            },
+            None => (),
+        }
+    }
+
+    #[inline]
+    fn osc_dispatch(&mut self, params: &[&[u8]], _bell_terminated: bool) {
+        if params.len() < 2 {
+            return;
+        }
+
+        match params[0] {
+            // Report working directory.
+            b"7" => {
+                // NOTE: The path is URL-encoded, but `;` might still be passed as is.
+                let uri = params[1..].join(&b';');
+                match parse_file_uri(&uri) {
+                    Some(path) => self.dispatch(move |handler| handler.set_working_directory(path)),
+                    None => debug!("Invalid OSC 7 URI: {:?}", String::from_utf8_lossy(&uri)),
+                }
+            },
+
+            // Desktop notification.
+            //
+            // NOTE: ConEmu uses OSC 9 with numeric subcommands like `9;4;1;50` for other
+            // purposes, these are not treated as notifications.
+            b"9" => {
+                if params.len() > 2 && params[1].iter().all(u8::is_ascii_digit) {
+                    return;
+                }
+
+                let body = String::from_utf8_lossy(&params[1..].join(&b';')).into_owned();
+                self.dispatch(move |handler| handler.desktop_notification(String::new(), body));
+            },
+
+            // Desktop notification with chunking support.
+            b"99" => {
+                let payload = params[2..].join(&b';');
+                if self.kitty_notification(params[1], &payload).is_none() {
+                    debug!("Invalid OSC 99 notification: {:?}", String::from_utf8_lossy(params[1]));
+                }
+            },
+
+            // Set mouse pointer shape.
+            b"22" => {
+                let shape = String::from_utf8_lossy(&params[1..].join(&b';')).trim().to_owned();
+                self.dispatch(move |handler| {
+                    handler.set_pointer_shape((!shape.is_empty()).then_some(shape))
+                });
+            },
+
+            // Shell integration marks.
+            b"133" => {
+                let mark = match params[1] {
+                    b"A" => SemanticPrompt::PromptStart,
+                    b"B" => SemanticPrompt::CommandStart,
+                    b"C" => SemanticPrompt::OutputStart,
+                    b"D" => {
+                        let exit_code = params
+                            .get(2)
+                            .and_then(|code| str::from_utf8(code).ok())
+                            .and_then(|code| code.parse().ok());
+                        SemanticPrompt::CommandEnd(exit_code)
+                    },
+                    _ => return,
+                };
+                self.dispatch(move |handler| handler.semantic_prompt(mark));
+            },
+
+            // Desktop notification with title.
+            b"777" if params.len() >= 3 && params[1] == b"notify" => {
+                let title = String::from_utf8_lossy(params[2]).into_owned();
+                let body = String::from_utf8_lossy(&params[3..].join(&b';')).into_owned();
+                self.dispatch(move |handler| handler.desktop_notification(title, body));
+            },
+
+            _ => (),
+        }
+    }
+
+    #[inline]
+    fn csi_dispatch(
+        &mut self,
+        params: &Params,
+        intermediates: &[u8],
+        has_ignored_intermediates: bool,
+        action: char,
+    ) {
+        macro_rules! unhandled {
+            () => {{
+                debug!(
+                    "[Unhandled CSI] action={:?}, params={:?}, intermediates={:?}",
+                    action, params, intermediates
+                );
+            }};
+        }
+
+        if has_ignored_intermediates || intermediates.len() > 2 {
+            return;
+        }
+
+        let mut params_iter = params.iter();
+
+        let mut next_param_or = |default: u16| match params_iter.next() {
+            Some(&[param, ..]) if param != 0 => param,
+            _ => default,
+        };
+
+        match (action, intermediates) {
+            // Handle sync updates opaquely, instead of letting vte buffer them.
+            ('h', [b'?'])
+                if params.iter().any(|param| param[0] == NamedPrivateMode::SyncUpdate as u16) =>
+            {
+                self.state.sync_state.timeout.set_timeout(SYNC_UPDATE_TIMEOUT);
+
+                let modes: Vec<_> = params_iter.map(|param| private_mode(param[0])).collect();
+                self.replace(move |handler| {
+                    for mode in modes {
+                        handler.set_private_mode(mode);
+                    }
+                });
+            },
+            ('J', [b'?']) => {
+                let mode = match next_param_or(0) {
+                    0 => ClearMode::Below,
+                    1 => ClearMode::Above,
+                    2 => ClearMode::All,
+                    _ => return unhandled!(),
+                };
+
+                self.dispatch(move |handler| handler.selective_clear_screen(mode));
+            },
+            ('K', [b'?']) => {
+                let mode = match next_param_or(0) {
+                    0 => LineClearMode::Right,
+                    1 => LineClearMode::Left,
+                    2 => LineClearMode::All,
+                    _ => return unhandled!(),
+                };
+
+                self.dispatch(move |handler| handler.selective_clear_line(mode));
+            },
+            ('m', []) => {
+                // Overlines are applied after vte processed the other attributes, so they are
+                // only dispatched when not followed by a reset.
+                let mut overline = None;
+                while let Some(param) = params_iter.next() {
+                    match param {
+                        [0] => overline = None,
+                        [53] => overline = Some(true),
+                        [55] => overline = Some(false),
+                        // Skip color arguments, so they are not mistaken for attributes.
+                        [38 | 48 | 58] => {
+                            parse_sgr_color(&mut params_iter.by_ref().map(|param| param[0]));
+                        },
+                        _ => (),
+                    }
+                }
+
+                if let Some(enabled) = overline {
+                    self.dispatch(move |handler| handler.set_overline(enabled));
+                }
+            },
+            ('p', [b'!']) => self.dispatch(|handler| handler.soft_reset()),
+            ('q', [b'"']) => {
+                let protected = match next_param_or(0) {
+                    0 | 2 => false,
+                    1 => true,
+                    _ => return unhandled!(),
+                };
+                self.dispatch(move |handler| handler.set_protected(protected));
+            },
+            ('r', [b'$']) | ('t', [b'$']) => {
+                let area = rectangle(&mut params_iter);
+                let params: Vec<_> = params_iter.map(|param| param[0]).collect();
+
+                // Without attributes all are reset, while unsupported attributes are ignored.
+                let attrs: Vec<_> = if params.is_empty() {
+                    vec![Attr::Reset]
+                } else {
+                    params.into_iter().filter_map(rectangle_attribute).collect()
+                };
+                if attrs.is_empty() {
+                    return unhandled!();
+                }
+
+                let reverse = action == 't';
+                self.dispatch(move |handler| {
+                    handler.change_rectangle_attributes(area, attrs, reverse)
+                });
+            },
+            ('s', []) => {
+                let left = next_param_or(1) as usize;
+                let right =
+                    params_iter.next().map(|param| param[0] as usize).filter(|&param| param != 0);
+
+                self.replace(move |handler| handler.set_left_right_margins(left, right));
+            },
+            ('u', [b'$']) => {
+                // Only the color table report of DECRQTSR is supported.
+                if next_param_or(0) != 2 {
+                    return unhandled!();
+                }
+
+                let space = match next_param_or(1) {
+                    1 => ColorSpace::Hls,
+                    2 => ColorSpace::Rgb,
+                    _ => return unhandled!(),
+                };
+
+                self.dispatch(move |handler| handler.request_color_table(space));
+            },
+            ('v', [b'$']) => {
+                let area = rectangle(&mut params_iter);
+
+                // Skip the source page, the destination page is ignored as well.
+                params_iter.next();
+                let Rectangle { top, left, .. } = rectangle(&mut params_iter);
+
+                self.dispatch(move |handler| handler.copy_rectangle(area, top, left));
+            },
+            ('w', [b'$']) => {
+                let report = match next_param_or(0) {
+                    1 => PresentationReport::CursorInformation,
+                    2 => PresentationReport::TabStops,
+                    _ => return unhandled!(),
+                };
+                self.dispatch(move |handler| handler.request_presentation_state(report));
+            },
+            ('x', [b'$']) => {
+                let c = match char::from_u32(next_param_or(0) as u32) {
+                    Some(c @ (' '..='~' | '\u{a0}'..='\u{ff}')) => c,
+                    _ => return unhandled!(),
+                };
+                let area = rectangle(&mut params_iter);
+                self.dispatch(move |handler| handler.fill_rectangle(c, area));
+            },
+            ('x', [b'*']) => {
+                let rectangle = next_param_or(0) == 2;
+                self.dispatch(move |handler| handler.set_attribute_change_extent(rectangle));
+            },
+            ('y', [b'*']) => {
+                let id = next_param_or(0);
+                // Only a single page is supported, so the page is ignored.
+                params_iter.next();
+
+                let area = rectangle(&mut params_iter);
+                self.dispatch(move |handler| handler.request_checksum(id, area));
+            },
+            ('z', [b'$']) | ('{', [b'$']) => {
+                let area = rectangle(&mut params_iter);
+                let selective = action == '{';
+                self.dispatch(move |handler| handler.erase_rectangle(area, selective));
+            },
+            _ => (),
+        }
+    }
+
+    #[inline]
+    fn esc_dispatch(&mut self, intermediates: &[u8], _ignore: bool, byte: u8) {
+        let size = match (byte, intermediates) {
+            (b'V', []) => return self.dispatch(|handler| handler.set_protected(true)),
+            (b'W', []) => return self.dispatch(|handler| handler.set_protected(false)),
+            (b'3', [b'#']) => LineSize::DoubleHeightTop,
+            (b'4', [b'#']) => LineSize::DoubleHeightBottom,
+            (b'5', [b'#']) => LineSize::Single,
+            (b'6', [b'#']) => LineSize::DoubleWidth,
+            _ => return,
+        };
+        self.dispatch(move |handler| handler.set_line_size(size));
+    }
+
+    #[inline]
+    fn terminated(&self) -> bool {
+        self.dispatch.is_some()
+    }
+}
+
+/// Parse the `Pt ; Pl ; Pb ; Pr` parameters of a rectangular area.
+fn rectangle(params: &mut ParamsIter<'_>) -> Rectangle {
+    let mut next_param =
+        || params.next().map(|param| param[0] as usize).filter(|&param| param != 0);
+    let top = next_param().unwrap_or(1);
+    let left = next_param().unwrap_or(1);
+    let bottom = next_param();
+    let right = next_param();
+    Rectangle { top, left, bottom, right }
+}
+
+/// Convert a DECCARA or DECRARA parameter to its [`Attr`].
+fn rectangle_attribute(param: u16) -> Option<Attr> {
+    let attr = match param {
+        0 => Attr::Reset,
+        1 => Attr::Bold,
+        4 => Attr::Underline,
+        5 => Attr::BlinkSlow,
+        7 => Attr::Reverse,
+        8 => Attr::Hidden,
+        9 => Attr::Strike,
+        22 => Attr::CancelBoldDim,
+        24 => Attr::CancelUnderline,
+        25 => Attr::CancelBlink,
+        27 => Attr::CancelReverse,
+        28 => Attr::CancelHidden,
+        29 => Attr::CancelStrike,
+        _ => return None,
+    };
+    Some(attr)
+}
+
+/// Decode a hex encoded UTF-8 string.
+fn hex_decode(hex: &[u8]) -> Option<String> {
+    if hex.len() % 2 != 0 {
+        return None;
+    }
+
+    let bytes = hex
+        .chunks(2)
+        .map(|digits| {
+            let high = char::from(digits[0]).to_digit(16)?;
+            let low = char::from(digits[1]).to_digit(16)?;
+            Some((high << 4 | low) as u8)
+        })
+        .collect::<Option<Vec<u8>>>()?;
+    String::from_utf8(bytes).ok()
+}
+
+/// Convert a raw private DEC mode to its [`PrivateMode`].
+fn private_mode(mode: u16) -> PrivateMode {
+    let named = match mode {
+        1 => NamedPrivateMode::CursorKeys,
+        3 => NamedPrivateMode::ColumnMode,
+        6 => NamedPrivateMode::Origin,
+        7 => NamedPrivateMode::LineWrap,
+        12 => NamedPrivateMode::BlinkingCursor,
+        25 => NamedPrivateMode::ShowCursor,
+        1000 => NamedPrivateMode::ReportMouseClicks,
+        1002 => NamedPrivateMode::ReportCellMouseMotion,
+        1003 => NamedPrivateMode::ReportAllMouseMotion,
+        1004 => NamedPrivateMode::ReportFocusInOut,
+        1005 => NamedPrivateMode::Utf8Mouse,
+        1006 => NamedPrivateMode::SgrMouse,
+        1007 => NamedPrivateMode::AlternateScroll,
+        1042 => NamedPrivateMode::UrgencyHints,
+        1049 => NamedPrivateMode::SwapScreenAndSetRestoreCursor,
+        2004 => NamedPrivateMode::BracketedPaste,
+        2026 => NamedPrivateMode::SyncUpdate,
+        _ => return PrivateMode::Unknown(mode),
+    };
+    PrivateMode::Named(named)
+}
+
+/// Parse a color specifier from list of attributes.
+fn parse_sgr_color(params: &mut dyn Iterator<Item = u16>) -> Option<Color> {
+    match params.next() {
+        Some(2) => Some(Color::Spec(Rgb {
+            r: u8::try_from(params.next()?).ok()?,
+            g: u8::try_from(params.next()?).ok()?,
+            b: u8::try_from(params.next()?).ok()?,
+        })),
+        Some(5) => Some(Color::Indexed(u8::try_from(params.next()?).ok()?)),
+        _ => None,
+    }
+}
+
+/// Parse the path of a `file://host/path` URI.
+fn parse_file_uri(uri: &[u8]) -> Option<PathBuf> {
+    let uri = uri.strip_prefix(b"file://")?;
+    let path = &uri[uri.iter().position(|&byte| byte == b'/')?..];
+
+    // Decode percent-encoded bytes.
+    let mut decoded = Vec::with_capacity(path.len());
+    let mut bytes = path.iter();
+    while let Some(&byte) = bytes.next() {
+        if byte == b'%' {
+            let hex = [*bytes.next()?, *bytes.next()?];
+            decoded.push(u8::from_str_radix(str::from_utf8(&hex).ok()?, 16).ok()?);
+        } else {
+            decoded.push(byte);
+        }
+    }
+    let path = String::from_utf8(decoded).ok()?;
+
+    // Strip the leading slash in front of drive letters like `/C:/Users`.
+    #[cfg(windows)]
+    let path = match path.strip_prefix('/') {
+        Some(stripped) if stripped.as_bytes().get(1) == Some(&b':') => stripped.to_owned(),
+        _ => path,
+    };
+
+    Some(PathBuf::from(path))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[derive(Default)]
+    struct MockHandler {
+        marks: Vec<SemanticPrompt>,
+        title: Option<String>,
+        working_directory: Option<PathBuf>,
+        notifications: Vec<(String, String)>,
+        pointer_shapes: Vec<Option<String>>,
+        text: String,
+        graphics: Vec<(usize, u32)>,
+        overlines: Vec<bool>,
+        attrs: Vec<Attr>,
+    }
+
+    impl Handler for MockHandler {
+        fn input(&mut self, c: char) {
+            self.text.push(c);
+        }
+
+        fn set_title(&mut self, title: Option<String>) {
+            self.title = title;
+        }
+
+        fn terminal_attribute(&mut self, attr: Attr) {
+            self.attrs.push(attr);
+        }
+    }
+
+    impl ExtendedHandler for MockHandler {
+        fn semantic_prompt(&mut self, mark: SemanticPrompt) {
+            self.marks.push(mark);
+        }
+
+        fn set_working_directory(&mut self, path: PathBuf) {
+            self.working_directory = Some(path);
+        }
+
+        fn desktop_notification(&mut self, title: String, body: String) {
+            self.notifications.push((title, body));
+        }
+
+        fn set_pointer_shape(&mut self, shape: Option<String>) {
+            self.pointer_shapes.push(shape);
+        }
+
+        fn kitty_graphics(&mut self, command: kitty::Command) {
+            self.graphics.push((self.text.len(), command.image_id));
+        }
+
+        fn set_overline(&mut self, enabled: bool) {
+            self.overlines.push(enabled);
+        }
+    }
+
+    #[test]
+    fn parse_semantic_prompt() {
+        let mut parser: Processor = Processor::new();
+        let mut handler = MockHandler::default();
+
+        parser.advance(&mut handler, b"\x1b]133;A\x07\x1b]133;B\x1b\\\x1b]133;C\x07");
+        parser.advance(&mut handler, b"\x1b]133;D;1\x07\x1b]133;D\x07\x1b]133;X\x07");
+
+        assert_eq!(handler.marks, [
+            SemanticPrompt::PromptStart,
+            SemanticPrompt::CommandStart,
+            SemanticPrompt::OutputStart,
+            SemanticPrompt::CommandEnd(Some(1)),
+            SemanticPrompt::CommandEnd(None),
+        ]);
+    }
+
+    #[test]
+    fn parse_working_directory() {
+        let mut parser: Processor = Processor::new();
+        let mut handler = MockHandler::default();
+
+        parser.advance(&mut handler, b"\x1b]7;file://host/tmp/a%20b;c\x07");
+        assert_eq!(handler.working_directory, Some(PathBuf::from("/tmp/a b;c")));
+
+        parser.advance(&mut handler, b"\x1b]7;file:///home\x1b\\");
+        assert_eq!(handler.working_directory, Some(PathBuf::from("/home")));
+
+        // Invalid URIs are ignored.
+        parser.advance(&mut handler, b"\x1b]7;/tmp\x07\x1b]7;file://host/%2\x07");
+        assert_eq!(handler.working_directory, Some(PathBuf::from("/home")));
+    }
+
+    #[test]
+    fn parse_desktop_notification() {
+        let mut parser: Processor = Processor::new();
+        let mut handler = MockHandler::default();
+
+        parser.advance(&mut handler, b"\x1b]9;done;ok\x07\x1b]9;4;1;50\x07");
+        parser.advance(&mut handler, b"\x1b]777;notify;make;exit 0\x1b\\");
+        parser.advance(&mut handler, b"\x1b]99;;hello\x07");
+
+        // Chunked notifications with a base64 encoded body.
+        parser.advance(&mut handler, b"\x1b]99;i=1:d=0;title\x07\x1b]99;i=2:d=0;other\x07");
+        parser.advance(&mut handler, b"\x1b]99;i=2:d=0:p=body;a\x07");
+        parser.advance(&mut handler, b"\x1b]99;i=2:p=body:e=1;Ym9keQ==\x07");
+
+        // Unsupported payload types are ignored.
+        parser.advance(&mut handler, b"\x1b]99;p=close;\x07");
+
+        let notifications: Vec<_> = handler
+            .notifications
+            .iter()
+            .map(|(title, body)| (title.as_str(), body.as_str()))
+            .collect();
+        assert_eq!(notifications, [
+            ("", "done;ok"),
+            ("make", "exit 0"),
+            ("hello", ""),
+            ("other", "abody"),
+        ]);
+    }
+
+    #[test]
+    fn parse_pointer_shape() {
+        let mut parser: Processor = Processor::new();
+        let mut handler = MockHandler::default();
+
+        parser.advance(&mut handler, b"\x1b]22;pointer\x07\x1b]22;text,xterm\x1b\\");
+        parser.advance(&mut handler, b"\x1b]22;\x07\x1b]22\x07");
+
+        assert_eq!(handler.pointer_shapes, [
+            Some(String::from("pointer")),
+            Some(String::from("text,xterm")),
+            None,
+        ]);
+    }
+
+    #[test]
+    fn parse_kitty_graphics() {
+        let mut parser: Processor = Processor::new();
+        let mut handler = MockHandler::default();
+
+        parser.advance(&mut handler, b"a\x1b_Ga=t,i=1;AAAA\x1b\\b");
+
+        // Commands split across reads.
+        parser.advance(&mut handler, b"\x1b_Ga=t,");
+        parser.advance(&mut handler, b"i=2\x1b");
+        parser.advance(&mut handler, b"\\c");
+
+        // CAN aborts the command, other APC strings are ignored.
+        parser.advance(&mut handler, b"\x1b_Gi=3\x18d\x1b_Xi=4\x1b\\");
+
+        // Commands inside of synchronized updates.
+        parser.advance(&mut handler, b"\x1b[?2026h\x1b_Gi=5\x1b\\e\x1b[?2026l");
+
+        assert_eq!(handler.text, "abcde");
+        assert_eq!(handler.graphics, [(1, 1), (2, 2), (4, 5)]);
+    }
+
+    #[test]
+    fn parse_vte_sequences() {
+        let mut parser: Processor = Processor::new();
+        let mut handler = MockHandler::default();
+
+        parser.advance(&mut handler, b"\x1b]2;alacritty\x07");
+
+        assert_eq!(handler.title.as_deref(), Some("alacritty"));
+    }
+
+    #[test]
+    fn parse_overline() {
+        let mut parser: Processor = Processor::new();
+        let mut handler = MockHandler::default();
+
+        parser.advance(&mut handler, b"\x1b[1;53m\x1b[55;0m\x1b[38;5;53;55m\x1b[53;2m");
+
+        assert_eq!(handler.overlines, [true, false, true]);
+        assert_eq!(handler.attrs, [
+            Attr::Bold,
+            Attr::Reset,
+            Attr::Foreground(Color::Indexed(53)),
+            Attr::Dim,
+        ]);
+    }
+}
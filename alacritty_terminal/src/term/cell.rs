@@ -5,31 +5,35 @@ use bitflags::bitflags;
 #[cfg(feature = "serde")]
 use serde::{Deserialize, Serialize};
 
-use crate::grid::{self, GridCell};
+use crate::graphics::GraphicCell;
+use crate::grid::{self, GridCell, PackCell, Packer};
 use crate::index::Column;
-use crate::vte::ansi::{Color, Hyperlink as VteHyperlink, NamedColor};
+use crate::vte::ansi::{Color, Hyperlink as VteHyperlink, NamedColor, Rgb};
 
 bitflags! {
     #[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
     #[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
-    pub struct Flags: u16 {
-        const INVERSE                   = 0b0000_0000_0000_0001;
-        const BOLD                      = 0b0000_0000_0000_0010;
-        const ITALIC                    = 0b0000_0000_0000_0100;
-        const BOLD_ITALIC               = 0b0000_0000_0000_0110;
-        const UNDERLINE                 = 0b0000_0000_0000_1000;
-        const WRAPLINE                  = 0b0000_0000_0001_0000;
-        const WIDE_CHAR                 = 0b0000_0000_0010_0000;
-        const WIDE_CHAR_SPACER          = 0b0000_0000_0100_0000;
-        const DIM                       = 0b0000_0000_1000_0000;
-        const DIM_BOLD                  = 0b0000_0000_1000_0010;
-        const HIDDEN                    = 0b0000_0001_0000_0000;
-        const STRIKEOUT                 = 0b0000_0010_0000_0000;
-        const LEADING_WIDE_CHAR_SPACER  = 0b0000_0100_0000_0000;
-        const DOUBLE_UNDERLINE          = 0b0000_1000_0000_0000;
-        const UNDERCURL                 = 0b0001_0000_0000_0000;
-        const DOTTED_UNDERLINE          = 0b0010_0000_0000_0000;
-        const DASHED_UNDERLINE          = 0b0100_0000_0000_0000;
+    pub struct Flags: u32 {
+        const INVERSE                   = 0b0000_0000_0000_0000_0000_0000_0000_0001;
+        const BOLD                      = 0b0000_0000_0000_0000_0000_0000_0000_0010;
+        const ITALIC                    = 0b0000_0000_0000_0000_0000_0000_0000_0100;
+        const BOLD_ITALIC               = 0b0000_0000_0000_0000_0000_0000_0000_0110;
+        const UNDERLINE                 = 0b0000_0000_0000_0000_0000_0000_0000_1000;
+        const WRAPLINE                  = 0b0000_0000_0000_0000_0000_0000_0001_0000;
+        const WIDE_CHAR                 = 0b0000_0000_0000_0000_0000_0000_0010_0000;
+        const WIDE_CHAR_SPACER          = 0b0000_0000_0000_0000_0000_0000_0100_0000;
+        const DIM                       = 0b0000_0000_0000_0000_0000_0000_1000_0000;
+        const DIM_BOLD                  = 0b0000_0000_0000_0000_0000_0000_1000_0010;
+        const HIDDEN                    = 0b0000_0000_0000_0000_0000_0001_0000_0000;
+        const STRIKEOUT                 = 0b0000_0000_0000_0000_0000_0010_0000_0000;
+        const LEADING_WIDE_CHAR_SPACER  = 0b0000_0000_0000_0000_0000_0100_0000_0000;
+        const DOUBLE_UNDERLINE          = 0b0000_0000_0000_0000_0000_1000_0000_0000;
+        const UNDERCURL                 = 0b0000_0000_0000_0000_0001_0000_0000_0000;
+        const DOTTED_UNDERLINE          = 0b0000_0000_0000_0000_0010_0000_0000_0000;
+        const DASHED_UNDERLINE          = 0b0000_0000_0000_0000_0100_0000_0000_0000;
+        const BLINK                     = 0b0000_0000_0000_0000_1000_0000_0000_0000;
+        const OVERLINE                  = 0b0000_0000_0000_0001_0000_0000_0000_0000;
+        const PROTECTED                 = 0b0000_0000_0000_0010_0000_0000_0000_0000;
         const ALL_UNDERLINES            = Self::UNDERLINE.bits() | Self::DOUBLE_UNDERLINE.bits()
                                         | Self::UNDERCURL.bits() | Self::DOTTED_UNDERLINE.bits()
                                         | Self::DASHED_UNDERLINE.bits();
@@ -126,6 +130,8 @@ pub struct CellExtra {
     zerowidth: Vec<char>,
     underline_color: Option<Color>,
     hyperlink: Option<Hyperlink>,
+    #[cfg_attr(feature = "serde", serde(skip))]
+    graphic: Option<GraphicCell>,
 }
 
 /// Content and attributes of a single cell in the terminal grid.
@@ -180,10 +186,9 @@ impl Cell {
     pub fn set_underline_color(&mut self, color: Option<Color>) {
         // If we reset color and we don't have zerowidth we should drop extra storage.
         if color.is_none()
-            && self
-                .extra
-                .as_ref()
-                .is_none_or(|extra| extra.zerowidth.is_empty() && extra.hyperlink.is_none())
+            && self.extra.as_ref().is_none_or(|extra| {
+                extra.zerowidth.is_empty() && extra.hyperlink.is_none() && extra.graphic.is_none()
+            })
         {
             self.extra = None;
         } else {
@@ -201,10 +206,11 @@ impl Cell {
     /// Set hyperlink.
     pub fn set_hyperlink(&mut self, hyperlink: Option<Hyperlink>) {
         let should_drop = hyperlink.is_none()
-            && self
-                .extra
-                .as_ref()
-                .is_none_or(|extra| extra.zerowidth.is_empty() && extra.underline_color.is_none());
+            && self.extra.as_ref().is_none_or(|extra| {
+                extra.zerowidth.is_empty()
+                    && extra.underline_color.is_none()
+                    && extra.graphic.is_none()
+            });
 
         if should_drop {
             self.extra = None;
@@ -219,6 +225,29 @@ impl Cell {
     pub fn hyperlink(&self) -> Option<Hyperlink> {
         self.extra.as_ref()?.hyperlink.clone()
     }
+
+    /// Set the part of a graphic displayed in this cell.
+    pub fn set_graphic(&mut self, graphic: Option<GraphicCell>) {
+        let should_drop = graphic.is_none()
+            && self.extra.as_ref().is_none_or(|extra| {
+                extra.zerowidth.is_empty()
+                    && extra.underline_color.is_none()
+                    && extra.hyperlink.is_none()
+            });
+
+        if should_drop {
+            self.extra = None;
+        } else {
+            let extra = self.extra.get_or_insert(Default::default());
+            Arc::make_mut(extra).graphic = graphic;
+        }
+    }
+
+    /// Part of a graphic displayed in this cell.
+    #[inline]
+    pub fn graphic(&self) -> Option<&GraphicCell> {
+        self.extra.as_ref()?.graphic.as_ref()
+    }
 }
 
 impl GridCell for Cell {
@@ -231,11 +260,15 @@ impl GridCell for Cell {
                 Flags::INVERSE
                     | Flags::ALL_UNDERLINES
                     | Flags::STRIKEOUT
+                    | Flags::OVERLINE
                     | Flags::WRAPLINE
                     | Flags::WIDE_CHAR_SPACER
                     | Flags::LEADING_WIDE_CHAR_SPACER,
             )
-            && self.extra.as_ref().map(|extra| extra.zerowidth.is_empty()) != Some(false)
+            && self
+                .extra
+                .as_ref()
+                .is_none_or(|extra| extra.zerowidth.is_empty() && extra.graphic.is_none())
     }
 
     #[inline]
@@ -252,8 +285,164 @@ impl GridCell for Cell {
     fn reset(&mut self, template: &Self) {
         *self = Cell { bg: template.bg, ..Cell::default() };
     }
+
+    #[inline]
+    fn packer() -> Option<Packer<Self>> {
+        Some(Packer::default())
+    }
 }
 
+impl PackCell for Cell {
+    #[inline]
+    fn character(&self) -> char {
+        self.c
+    }
+
+    #[inline]
+    fn set_character(&mut self, c: char) {
+        self.c = c;
+    }
+
+    fn write(&self, buf: &mut Vec<u8>) -> bool {
+        write_color(buf, self.fg);
+        write_color(buf, self.bg);
+        buf.extend_from_slice(&self.flags.bits().to_le_bytes());
+
+        let extra = match &self.extra {
+            Some(extra) => extra,
+            None => {
+                buf.push(0);
+                return true;
+            },
+        };
+
+        // Graphics are only kept in memory.
+        if extra.graphic.is_some() {
+            return false;
+        }
+
+        buf.push(1);
+        buf.extend_from_slice(&(extra.zerowidth.len() as u32).to_le_bytes());
+        for c in &extra.zerowidth {
+            buf.extend_from_slice(&u32::from(*c).to_le_bytes());
+        }
+
+        match extra.underline_color {
+            Some(color) => {
+                buf.push(1);
+                write_color(buf, color);
+            },
+            None => buf.push(0),
+        }
+
+        match &extra.hyperlink {
+            Some(hyperlink) => {
+                buf.push(1);
+                grid::write_str(buf, hyperlink.id());
+                grid::write_str(buf, hyperlink.uri());
+            },
+            None => buf.push(0),
+        }
+
+        true
+    }
+
+    fn read(bytes: &mut &[u8]) -> Option<Self> {
+        let fg = read_color(bytes)?;
+        let bg = read_color(bytes)?;
+        let flags = Flags::from_bits_truncate(grid::read_u32(bytes)?);
+
+        let extra = match grid::read_u8(bytes)? {
+            0 => None,
+            _ => {
+                let zerowidth_len = grid::read_u32(bytes)?;
+                let zerowidth = (0..zerowidth_len)
+                    .map(|_| char::from_u32(grid::read_u32(bytes)?))
+                    .collect::<Option<_>>()?;
+
+                let underline_color = match grid::read_u8(bytes)? {
+                    0 => None,
+                    _ => Some(read_color(bytes)?),
+                };
+
+                let hyperlink = match grid::read_u8(bytes)? {
+                    0 => None,
+                    _ => {
+                        let id = grid::read_str(bytes)?;
+                        let uri = grid::read_str(bytes)?;
+                        Some(Hyperlink::new(Some(id), uri.to_owned()))
+                    },
+                };
+
+                Some(Arc::new(CellExtra { zerowidth, underline_color, hyperlink, graphic: None }))
+            },
+        };
+
+        Some(Cell { c: ' ', fg, bg, flags, extra })
+    }
+}
+
+/// Serialize a color for [`PackCell::write`].
+fn write_color(buf: &mut Vec<u8>, color: Color) {
+    match color {
+        Color::Named(color) => {
+            buf.push(0);
+            buf.extend_from_slice(&(color as u16).to_le_bytes());
+        },
+        Color::Spec(rgb) => buf.extend_from_slice(&[1, rgb.r, rgb.g, rgb.b]),
+        Color::Indexed(index) => buf.extend_from_slice(&[2, index]),
+    }
+}
+
+/// Deserialize a color written by [`write_color`].
+fn read_color(bytes: &mut &[u8]) -> Option<Color> {
+    match grid::read_u8(bytes)? {
+        0 => {
+            let index = u16::from(grid::read_u8(bytes)?) | u16::from(grid::read_u8(bytes)?) << 8;
+            NAMED_COLORS.iter().find(|color| **color as u16 == index).copied().map(Color::Named)
+        },
+        1 => {
+            let (r, g, b) = (grid::read_u8(bytes)?, grid::read_u8(bytes)?, grid::read_u8(bytes)?);
+            Some(Color::Spec(Rgb { r, g, b }))
+        },
+        2 => Some(Color::Indexed(grid::read_u8(bytes)?)),
+        _ => None,
+    }
+}
+
+/// All named colors, to look them up by their index.
+const NAMED_COLORS: [NamedColor; 29] = [
+    NamedColor::Black,
+    NamedColor::Red,
+    NamedColor::Green,
+    NamedColor::Yellow,
+    NamedColor::Blue,
+    NamedColor::Magenta,
+    NamedColor::Cyan,
+    NamedColor::White,
+    NamedColor::BrightBlack,
+    NamedColor::BrightRed,
+    NamedColor::BrightGreen,
+    NamedColor::BrightYellow,
+    NamedColor::BrightBlue,
+    NamedColor::BrightMagenta,
+    NamedColor::BrightCyan,
+    NamedColor::BrightWhite,
+    NamedColor::Foreground,
+    NamedColor::Background,
+    NamedColor::Cursor,
+    NamedColor::DimBlack,
+    NamedColor::DimRed,
+    NamedColor::DimGreen,
+    NamedColor::DimYellow,
+    NamedColor::DimBlue,
+    NamedColor::DimMagenta,
+    NamedColor::DimCyan,
+    NamedColor::DimWhite,
+    NamedColor::BrightForeground,
+    NamedColor::DimForeground,
+];
+
 impl From<Color> for Cell {
     #[inline]
     fn from(color: Color) -> Self {
@@ -1,8 +1,10 @@
 //! Exports the `Term` type which is a high-level API for the Grid.
 
 use std::ops::{Index, IndexMut, Range};
+use std::path::PathBuf;
 use std::sync::Arc;
-use std::{cmp, mem, ptr, slice, str};
+use std::time::SystemTime;
+use std::{cmp, iter, mem, ptr, slice, str};
 
 #[cfg(feature = "serde")]
 use serde::{Deserialize, Serialize};
@@ -11,11 +13,16 @@ use base64::Engine;
 use base64::engine::general_purpose::STANDARD as Base64;
 use bitflags::bitflags;
 use log::{debug, trace};
-use unicode_width::UnicodeWidthChar;
+use unicode_segmentation::UnicodeSegmentation;
+use unicode_width::{UnicodeWidthChar, UnicodeWidthStr};
 
-use crate::event::{Event, EventListener};
-use crate::grid::{Dimensions, Grid, GridIterator, Scroll};
+use crate::event::{Event, EventListener, VoidListener};
+use crate::graphics::{Graphic, GraphicCell, Graphics, Placement, PlacementIndex, kitty};
+use crate::grid::{Dimensions, Grid, GridIterator, LineSize, PromptMarks, Scroll};
 use crate::index::{self, Boundary, Column, Direction, Line, Point, Side};
+use crate::parser::{
+    ColorSpace, ExtendedHandler, PresentationReport, Rectangle, SemanticPrompt, StatusSetting,
+};
 use crate::selection::{Selection, SelectionRange, SelectionType};
 use crate::term::cell::{Cell, Flags, LineLength};
 use crate::term::color::Colors;
@@ -29,6 +36,7 @@ use crate::vte::ansi::{
 pub mod cell;
 pub mod color;
 pub mod search;
+pub mod termcap;
 
 /// Minimum number of columns.
 ///
@@ -50,6 +58,49 @@ const KEYBOARD_MODE_STACK_MAX_DEPTH: usize = TITLE_STACK_MAX_DEPTH;
 /// Default tab interval, corresponding to terminfo `it` value.
 const INITIAL_TABSTOPS: usize = 8;
 
+/// Left and right margin mode (DECLRMM), which is not part of [`NamedPrivateMode`].
+const LEFT_RIGHT_MARGIN_MODE: u16 = 69;
+
+/// Private mode for X10 compatible mouse reporting.
+const X10_MOUSE_MODE: u16 = 9;
+
+/// Private mode for the urxvt mouse encoding.
+const URXVT_MOUSE_MODE: u16 = 1015;
+
+/// Private mode for the SGR mouse encoding with pixel coordinates.
+const SGR_PIXEL_MOUSE_MODE: u16 = 1016;
+
+/// Private mode for grapheme cluster segmentation of input.
+const GRAPHEME_CLUSTERING_MODE: u16 = 2027;
+
+/// Private modes which are recognized, but always set.
+const PERMANENTLY_SET_PRIVATE_MODES: &[u16] = &[
+    2, // ANSI mode (DECANM).
+    8, // Keyboard autorepeat (DECARM).
+];
+
+/// Private modes which are recognized, but can never be set.
+const PERMANENTLY_RESET_PRIVATE_MODES: &[u16] = &[
+    4,    // Smooth scrolling (DECSCLM).
+    5,    // Reverse video (DECSCNM).
+    45,   // Reverse wraparound.
+    67,   // Backarrow key sends backspace (DECBKM).
+    1001, // Highlight mouse tracking.
+    1034, // Interpret meta key.
+    1047, // Alternate screen buffer without saving the cursor.
+    1048, // Save cursor as in DECSC.
+];
+
+/// ANSI modes which are recognized, but always set.
+const PERMANENTLY_SET_MODES: &[u16] = &[
+    12, // Send/receive without local echo (SRM).
+];
+
+/// ANSI modes which are recognized, but can never be set.
+const PERMANENTLY_RESET_MODES: &[u16] = &[
+    2, // Keyboard action (KAM).
+];
+
 bitflags! {
     #[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
     pub struct TermMode: u32 {
@@ -77,7 +128,16 @@ bitflags! {
         const REPORT_ALTERNATE_KEYS   = 1 << 20;
         const REPORT_ALL_KEYS_AS_ESC  = 1 << 21;
         const REPORT_ASSOCIATED_TEXT  = 1 << 22;
-        const MOUSE_MODE              = Self::MOUSE_REPORT_CLICK.bits() | Self::MOUSE_MOTION.bits() | Self::MOUSE_DRAG.bits();
+        const LEFT_RIGHT_MARGIN       = 1 << 23;
+        const RECTANGULAR_EXTENT      = 1 << 24;
+        const SGR_PIXEL_MOUSE         = 1 << 25;
+        const URXVT_MOUSE             = 1 << 26;
+        const X10_MOUSE               = 1 << 27;
+        const GRAPHEME_CLUSTERING     = 1 << 28;
+        const MOUSE_MODE              = Self::MOUSE_REPORT_CLICK.bits() | Self::MOUSE_MOTION.bits() | Self::MOUSE_DRAG.bits()
+                                      | Self::X10_MOUSE.bits();
+        const MOUSE_ENCODING          = Self::UTF8_MOUSE.bits() | Self::SGR_MOUSE.bits() | Self::SGR_PIXEL_MOUSE.bits()
+                                      | Self::URXVT_MOUSE.bits();
         const KITTY_KEYBOARD_PROTOCOL = Self::DISAMBIGUATE_ESC_CODES.bits()
                                       | Self::REPORT_EVENT_TYPES.bits()
                                       | Self::REPORT_ALTERNATE_KEYS.bits()
@@ -300,6 +360,12 @@ pub struct Term<T> {
     /// Range going from top to bottom of the terminal, indexed from the top of the viewport.
     scroll_region: Range<Line>,
 
+    /// Left and right margins.
+    ///
+    /// Range going from the left to the right margin, covering all columns unless set while
+    /// [`TermMode::LEFT_RIGHT_MARGIN`] is active.
+    horizontal_margins: Range<Column>,
+
     /// Modified terminal colors.
     colors: Colors,
 
@@ -325,6 +391,18 @@ pub struct Term<T> {
     /// Information about damaged cells.
     damage: TermDamageState,
 
+    /// State required for placing images in the grid.
+    graphics: Graphics,
+
+    /// Placements in the active grid.
+    placements: PlacementIndex,
+
+    /// Placements in the inactive grid.
+    inactive_placements: PlacementIndex,
+
+    /// Buffer for segmenting the grapheme cluster before the cursor.
+    grapheme_buffer: String,
+
     /// Config directly for the terminal.
     config: Config,
 }
@@ -335,6 +413,12 @@ pub struct Config {
     /// The maximum amount of scrolling history.
     pub scrolling_history: usize,
 
+    /// Number of bytes compressed scrolling history may use before the oldest lines are moved
+    /// to a temporary file.
+    ///
+    /// Setting a limit also compresses more of the history, to keep most of it within the limit.
+    pub scrolling_memory_limit: Option<usize>,
+
     /// Default cursor style to reset the cursor to.
     pub default_cursor_style: CursorStyle,
 
@@ -351,17 +435,26 @@ pub struct Config {
 
     /// OSC52 support mode.
     pub osc52: Osc52,
+
+    /// Whether graphics in the grid are displayed.
+    ///
+    /// Sixel graphics are ignored and kitty graphics protocol queries and display requests fail
+    /// without it, so clients don't assume their images are visible. Sixel support is only
+    /// reported in the primary device attributes with it.
+    pub graphics: bool,
 }
 
 impl Default for Config {
     fn default() -> Self {
         Self {
             scrolling_history: 10000,
+            scrolling_memory_limit: Default::default(),
             semantic_escape_chars: SEMANTIC_ESCAPE_CHARS.to_owned(),
             default_cursor_style: Default::default(),
             vi_mode_cursor_style: Default::default(),
             kitty_keyboard: Default::default(),
             osc52: Default::default(),
+            graphics: Default::default(),
         }
     }
 }
@@ -412,12 +505,14 @@ impl<T> Term<T> {
         let num_lines = dimensions.screen_lines();
 
         let history_size = config.scrolling_history;
-        let grid = Grid::new(num_lines, num_cols, history_size);
+        let mut grid = Grid::new(num_lines, num_cols, history_size);
+        grid.set_memory_limit(config.scrolling_memory_limit);
         let inactive_grid = Grid::new(num_lines, num_cols, 0);
 
         let tabs = TabStops::new(grid.columns());
 
         let scroll_region = Line(0)..Line(grid.screen_lines() as i32);
+        let horizontal_margins = Column(0)..Column(num_cols);
 
         // Initialize terminal damage, covering the entire terminal upon launch.
         let damage = TermDamageState::new(num_cols, num_lines);
@@ -425,6 +520,7 @@ impl<T> Term<T> {
         Term {
             inactive_grid,
             scroll_region,
+            horizontal_margins,
             event_proxy,
             damage,
             config,
@@ -437,6 +533,10 @@ impl<T> Term<T> {
             cursor_style: Default::default(),
             colors: color::Colors::default(),
             title_stack: Default::default(),
+            graphics: Default::default(),
+            placements: Default::default(),
+            inactive_placements: Default::default(),
+            grapheme_buffer: Default::default(),
             is_focused: Default::default(),
             selection: Default::default(),
             title: Default::default(),
@@ -479,6 +579,15 @@ impl<T> Term<T> {
         // Always damage current cursor.
         self.damage_cursor();
 
+        // Cells of double-width lines are displayed at twice their column, so the entire line
+        // is redrawn instead.
+        let last_column = self.columns() - 1;
+        for damage in self.damage.lines.iter_mut().filter(|damage| damage.is_damaged()) {
+            if self.grid[Line(damage.line as i32)].line_size().is_double_width() {
+                damage.expand(0, last_column);
+            }
+        }
+
         // NOTE: damage which changes all the content when the display offset is non-zero (e.g.
         // scrolling) is handled via full damage.
         let display_offset = self.grid().display_offset();
@@ -514,6 +623,8 @@ impl<T> Term<T> {
         } else {
             self.grid.update_history(self.config.scrolling_history);
         }
+        self.grid.set_memory_limit(self.config.scrolling_memory_limit);
+        self.inactive_grid.set_memory_limit(self.config.scrolling_memory_limit);
 
         if self.config.kitty_keyboard != old_config.kitty_keyboard {
             self.keyboard_mode_stack = Vec::new();
@@ -527,6 +638,20 @@ impl<T> Term<T> {
 
     /// Convert the active selection to a String.
     pub fn selection_to_string(&self) -> Option<String> {
+        self.selection_text(None)
+    }
+
+    /// Convert the active selection to a String, prefixing every line with its timestamp.
+    ///
+    /// Lines without a timestamp are not prefixed.
+    pub fn selection_to_string_with_timestamps<F>(&self, format: F) -> Option<String>
+    where
+        F: Fn(SystemTime) -> String,
+    {
+        self.selection_text(Some(&format))
+    }
+
+    fn selection_text(&self, timestamp: Option<&dyn Fn(SystemTime) -> String>) -> Option<String> {
         let selection_range = self.selection.as_ref().and_then(|s| s.to_range(self))?;
         let SelectionRange { start, end, .. } = selection_range;
 
@@ -535,19 +660,21 @@ impl<T> Term<T> {
         match self.selection.as_ref() {
             Some(Selection { ty: SelectionType::Block, .. }) => {
                 for line in (start.line.0..end.line.0).map(Line::from) {
+                    self.push_timestamp(&mut res, line, timestamp);
                     res += self
                         .line_to_string(line, start.column..end.column, start.column.0 != 0)
                         .trim_end();
                     res += "\n";
                 }
 
+                self.push_timestamp(&mut res, end.line, timestamp);
                 res += self.line_to_string(end.line, start.column..end.column, true).trim_end();
             },
             Some(Selection { ty: SelectionType::Lines, .. }) => {
-                res = self.bounds_to_string(start, end) + "\n";
+                res = self.bounds_text(start, end, timestamp) + "\n";
             },
             _ => {
-                res = self.bounds_to_string(start, end);
+                res = self.bounds_text(start, end, timestamp);
             },
         }
 
@@ -556,18 +683,193 @@ impl<T> Term<T> {
 
     /// Convert range between two points to a String.
     pub fn bounds_to_string(&self, start: Point, end: Point) -> String {
+        self.bounds_text(start, end, None)
+    }
+
+    fn bounds_text(
+        &self,
+        start: Point,
+        end: Point,
+        timestamp: Option<&dyn Fn(SystemTime) -> String>,
+    ) -> String {
         let mut res = String::new();
 
         for line in (start.line.0..=end.line.0).map(Line::from) {
             let start_col = if line == start.line { start.column } else { Column(0) };
             let end_col = if line == end.line { end.column } else { self.last_column() };
 
+            // Only prefix the first row of wrapped lines.
+            let last_column = self.last_column();
+            let wrapped = line != start.line
+                && self.grid.read_row(line - 1i32)[last_column].flags.contains(Flags::WRAPLINE);
+            if !wrapped {
+                self.push_timestamp(&mut res, line, timestamp);
+            }
+
             res += &self.line_to_string(line, start_col..end_col, line == end.line);
         }
 
         res.strip_suffix('\n').map(str::to_owned).unwrap_or(res)
     }
 
+    /// Append the formatted timestamp of a line.
+    fn push_timestamp(
+        &self,
+        text: &mut String,
+        line: Line,
+        format: Option<&dyn Fn(SystemTime) -> String>,
+    ) {
+        if let Some((format, time)) = format.zip(self.grid.timestamp(line)) {
+            text.push_str(&format(time));
+        }
+    }
+
+    /// Convert the scrollback history and screen to a String.
+    ///
+    /// Empty lines below the last line with content are omitted.
+    pub fn scrollback_to_string(&self) -> String {
+        let end = Point::new(self.last_content_line(), self.last_column());
+        let mut text = self.bounds_to_string(Point::new(self.topmost_line(), Column(0)), end);
+        text.push('\n');
+        text
+    }
+
+    /// Convert the scrollback history and screen to a String with escape sequences.
+    ///
+    /// Colors, text attributes and hyperlinks are reproduced, so writing the text to a terminal
+    /// with the same number of columns recreates the content.
+    pub fn scrollback_to_ansi(&self) -> String {
+        let mut text = String::new();
+        let mut rendition = String::from("0");
+        let mut hyperlink = None;
+
+        for line in (self.topmost_line().0..=self.last_content_line().0).map(Line::from) {
+            let row = self.grid.read_row(line);
+            let line_length = row.line_length();
+
+            let mut tab_mode = false;
+            for (column, cell) in row[..line_length].iter().enumerate() {
+                // Skip over cells until next tab-stop once a tab was found.
+                if tab_mode {
+                    if self.tabs[Column(column)] || cell.c != ' ' {
+                        tab_mode = false;
+                    } else {
+                        continue;
+                    }
+                }
+
+                if cell.c == '\t' {
+                    tab_mode = true;
+                }
+
+                if cell.flags.intersects(Flags::WIDE_CHAR_SPACER | Flags::LEADING_WIDE_CHAR_SPACER)
+                {
+                    continue;
+                }
+
+                let cell_rendition = sgr_parameters(cell);
+                if cell_rendition != rendition {
+                    text.push_str(&format!("\x1b[{cell_rendition}m"));
+                    rendition = cell_rendition;
+                }
+
+                let cell_hyperlink = cell.hyperlink();
+                if cell_hyperlink != hyperlink {
+                    match &cell_hyperlink {
+                        Some(link) => {
+                            text.push_str(&format!("\x1b]8;id={};{}\x1b\\", link.id(), link.uri()))
+                        },
+                        None => text.push_str("\x1b]8;;\x1b\\"),
+                    }
+                    hyperlink = cell_hyperlink;
+                }
+
+                text.push(cell.c);
+                text.extend(cell.zerowidth().into_iter().flatten());
+            }
+
+            // Wrapped lines are continued by the terminal's automatic wrapping.
+            let wrapped = line_length == self.columns()
+                && row[line_length - 1].flags.contains(Flags::WRAPLINE);
+            if !wrapped {
+                text.push_str("\r\n");
+            }
+        }
+
+        if hyperlink.is_some() {
+            text.push_str("\x1b]8;;\x1b\\");
+        }
+        if rendition != "0" {
+            text.push_str("\x1b[0m");
+        }
+
+        text
+    }
+
+    /// Copy the scrollback history and screen into a terminal without an event listener.
+    ///
+    /// The copy can be read on another thread, without blocking this terminal while it is
+    /// converted to text.
+    pub fn snapshot(&self) -> Term<VoidListener> {
+        let mut term = Term::new(self.config.clone(), self, VoidListener);
+        term.grid = self.grid.clone();
+        term.tabs = self.tabs.clone();
+        term
+    }
+
+    /// Replace the scrollback history and primary screen with a grid from another terminal.
+    ///
+    /// The grid is reflowed to the terminal's dimensions and the cursor is placed at the start of
+    /// the line below its last content.
+    pub fn restore_grid(&mut self, mut grid: Grid<Cell>) {
+        // Serialized grids have no cursor, so it is moved to the bottom of the content to keep
+        // everything above it during reflow.
+        let last_line = (0..grid.screen_lines() as i32)
+            .map(Line::from)
+            .rev()
+            .find(|&line| !grid[line].is_clear());
+        grid.cursor = Default::default();
+        grid.cursor.point.line = last_line.unwrap_or_default();
+
+        grid.update_history(self.config.scrolling_history);
+        grid.set_memory_limit(self.config.scrolling_memory_limit);
+        grid.resize(true, self.screen_lines(), self.columns());
+        grid.scroll_display(Scroll::Bottom);
+
+        if last_line.is_some() {
+            let line = grid.cursor.point.line;
+            if line + 1 < grid.screen_lines() {
+                grid.cursor.point.line += 1;
+            } else {
+                grid.scroll_up(&(Line(0)..Line(grid.screen_lines() as i32)), 1);
+            }
+        }
+        grid.cursor.point.column = Column(0);
+        grid.saved_cursor = grid.cursor.clone();
+        grid.index_marks();
+
+        // Graphics are not serialized.
+        if self.mode.contains(TermMode::ALT_SCREEN) {
+            self.inactive_grid = grid;
+            self.inactive_placements.clear();
+        } else {
+            self.grid = grid;
+            self.placements.clear();
+        }
+
+        self.selection = None;
+        self.mark_fully_damaged();
+    }
+
+    /// Last line with content on the screen, or the topmost line if there is none.
+    fn last_content_line(&self) -> Line {
+        (self.topmost_line().0..self.screen_lines() as i32)
+            .map(Line::from)
+            .rev()
+            .find(|&line| !self.grid.read_row(line).is_clear())
+            .unwrap_or(self.topmost_line())
+    }
+
     /// Convert a single line in the grid to a String.
     fn line_to_string(
         &self,
@@ -577,8 +879,9 @@ impl<T> Term<T> {
     ) -> String {
         let mut text = String::new();
 
-        let grid_line = &self.grid[line];
+        let grid_line = self.grid.read_row(line);
         let line_length = cmp::min(grid_line.line_length(), cols.end + 1);
+        let line_length = cmp::min(line_length, self.visible_columns(line));
 
         // Include wide char when trailing spacer is selected.
         if grid_line[cols.start].flags.contains(Flags::WIDE_CHAR_SPACER) {
@@ -614,8 +917,7 @@ impl<T> Term<T> {
         }
 
         if cols.end >= self.columns() - 1
-            && (line_length.0 == 0
-                || !self.grid[line][line_length - 1].flags.contains(Flags::WRAPLINE))
+            && (line_length.0 == 0 || !grid_line[line_length - 1].flags.contains(Flags::WRAPLINE))
         {
             text.push('\n');
         }
@@ -626,7 +928,7 @@ impl<T> Term<T> {
             && grid_line[line_length - 1].flags.contains(Flags::LEADING_WIDE_CHAR_SPACER)
             && include_wrapped_wide
         {
-            text.push(self.grid[line - 1i32][Column(0)].c);
+            text.push(self.grid.read_row(line - 1i32)[Column(0)].c);
         }
 
         text
@@ -651,6 +953,29 @@ impl<T> Term<T> {
         &mut self.grid
     }
 
+    /// Update the pixel dimensions of a cell.
+    ///
+    /// Images are only placed in the grid once the cell size is known.
+    #[inline]
+    pub fn set_cell_size(&mut self, width: usize, height: usize) {
+        self.graphics.set_cell_size(width, height);
+    }
+
+    /// Graphics state, including all images transmitted with the kitty graphics protocol.
+    #[inline]
+    pub fn graphics(&self) -> &Graphics {
+        &self.graphics
+    }
+
+    /// All placements in the grid, with the position of their top-left cell.
+    ///
+    /// Placements whose top-left cell is no longer part of the grid are not included.
+    pub fn placements(&self) -> impl Iterator<Item = (Point, Arc<Placement>)> + '_ {
+        self.placements.iter().filter_map(|(placement, anchor, column)| {
+            Some((Point::new(self.grid.anchor_line(anchor)?, column), placement))
+        })
+    }
+
     /// Resize terminal to new dimensions.
     pub fn resize<S: Dimensions>(&mut self, size: S) {
         let old_cols = self.columns();
@@ -681,6 +1006,10 @@ impl<T> Term<T> {
         if old_cols != num_cols {
             self.selection = None;
 
+            // Find placements again, since reflow moves them to different lines.
+            index_placements(&self.grid, &mut self.placements);
+            index_placements(&self.inactive_grid, &mut self.inactive_placements);
+
             // Recreate tabs list.
             self.tabs.resize(num_cols);
         } else if let Some(selection) = self.selection.take() {
@@ -697,8 +1026,9 @@ impl<T> Term<T> {
             cmp::max(cmp::min(vi_point.line, viewport_bottom), viewport_top);
         self.vi_mode_cursor.point.column = cmp::min(vi_point.column, self.last_column());
 
-        // Reset scrolling region.
+        // Reset scrolling region and margins.
         self.scroll_region = Line(0)..Line(self.screen_lines() as i32);
+        self.horizontal_margins = Column(0)..Column(num_cols);
 
         // Resize damage information.
         self.damage.resize(num_cols, num_lines);
@@ -721,6 +1051,7 @@ impl<T> Term<T> {
 
             // Reset alternate screen contents.
             self.inactive_grid.reset_region(..);
+            self.inactive_placements.clear();
         }
 
         mem::swap(&mut self.keyboard_mode_stack, &mut self.inactive_keyboard_mode_stack);
@@ -729,6 +1060,7 @@ impl<T> Term<T> {
         self.set_keyboard_mode(keyboard_mode, KeyboardModesApplyBehavior::Replace);
 
         mem::swap(&mut self.grid, &mut self.inactive_grid);
+        mem::swap(&mut self.placements, &mut self.inactive_placements);
         self.mode ^= TermMode::ALT_SCREEN;
         self.selection = None;
         self.mark_fully_damaged();
@@ -747,9 +1079,15 @@ impl<T> Term<T> {
 
         let region = origin..self.scroll_region.end;
 
+        if self.has_horizontal_margins() {
+            self.scroll_margins(region, -(lines as i32));
+            return;
+        }
+
         // Scroll selection.
         self.selection =
             self.selection.take().and_then(|s| s.rotate(self, &region, -(lines as i32)));
+        self.scroll_placements(&region, -(lines as i32));
 
         // Scroll vi mode cursor.
         let line = &mut self.vi_mode_cursor.point.line;
@@ -774,8 +1112,14 @@ impl<T> Term<T> {
 
         let region = origin..self.scroll_region.end;
 
+        if self.has_horizontal_margins() {
+            self.scroll_margins(region, lines as i32);
+            return;
+        }
+
         // Scroll selection.
         self.selection = self.selection.take().and_then(|s| s.rotate(self, &region, lines as i32));
+        self.scroll_placements(&region, lines as i32);
 
         self.grid.scroll_up(&region, lines);
 
@@ -789,6 +1133,247 @@ impl<T> Term<T> {
         self.mark_fully_damaged();
     }
 
+    /// Move placements with the lines of a scrolled region.
+    ///
+    /// Text moves up for positive `lines` and down for negative ones.
+    fn scroll_placements(&mut self, region: &Range<Line>, lines: i32) {
+        // Anchors already follow lines rotated into the history.
+        let rotates_screen = region.start == 0 && lines > 0;
+        if self.placements.is_empty() || (rotates_screen && region.end == self.screen_lines()) {
+            return;
+        }
+
+        let grid = &self.grid;
+        self.placements.move_anchors(|anchor| {
+            let line = grid.anchor_line(anchor)?;
+            if rotates_screen {
+                // Lines fixed below the region are not rotated.
+                return Some(if line >= region.end { anchor + lines as i64 } else { anchor });
+            }
+
+            if !region.contains(&line) {
+                return Some(anchor);
+            }
+            region.contains(&(line - lines)).then_some(anchor - lines as i64)
+        });
+    }
+
+    /// Scroll the region between the left and right margins.
+    ///
+    /// Text moves up for positive `lines` and down for negative ones. Since only part of each
+    /// line moves, nothing is pushed into the scrollback history.
+    fn scroll_margins(&mut self, region: Range<Line>, lines: i32) {
+        trace!("Scrolling margins: region={region:?}, lines={lines}");
+
+        let columns = self.horizontal_margins.clone();
+        let height = (region.end - region.start).0;
+        let count = cmp::min(lines.unsigned_abs() as i32, height);
+
+        // Move lines toward the scrolling direction, starting with the one closest to it.
+        for index in 0..height - count {
+            let (destination, source) = if lines > 0 {
+                (region.start + index, region.start + index + count)
+            } else {
+                (region.end - index - 1, region.end - index - count - 1)
+            };
+
+            for column in columns.start.0..columns.end.0 {
+                let cell = mem::take(&mut self.grid[source][Column(column)]);
+                self.grid[destination][Column(column)] = cell;
+            }
+        }
+
+        // Clear the lines which were scrolled in.
+        let bg = self.grid.cursor.template.bg;
+        let cleared = if lines > 0 {
+            region.end - count..region.end
+        } else {
+            region.start..region.start + count
+        };
+        for line in (cleared.start.0..cleared.end.0).map(Line) {
+            for cell in &mut self.grid[line][columns.clone()] {
+                *cell = bg.into();
+            }
+        }
+
+        self.mark_fully_damaged();
+    }
+
+    /// Whether left and right margins are restricting the scrolling region.
+    #[inline]
+    fn has_horizontal_margins(&self) -> bool {
+        self.horizontal_margins != (Column(0)..Column(self.columns()))
+    }
+
+    /// Whether the cursor is between the left and right margins.
+    #[inline]
+    fn cursor_within_margins(&self) -> bool {
+        self.horizontal_margins.contains(&self.grid.cursor.point.column)
+    }
+
+    /// Column after the last one the cursor can advance to without wrapping.
+    ///
+    /// This is the right margin unless the cursor is already past it.
+    #[inline]
+    fn line_end(&self) -> Column {
+        let end = if self.grid.cursor.point.column < self.horizontal_margins.end {
+            self.horizontal_margins.end
+        } else {
+            Column(self.columns())
+        };
+        cmp::min(end, self.visible_columns(self.grid.cursor.point.line))
+    }
+
+    /// Number of columns displayed in a line.
+    ///
+    /// Double-width lines only display the first half of their cells.
+    #[inline]
+    fn visible_columns(&self, line: Line) -> Column {
+        if self.grid[line].line_size().is_double_width() {
+            Column(cmp::max(self.columns() / 2, 1))
+        } else {
+            Column(self.columns())
+        }
+    }
+
+    /// Column a carriage return moves the cursor to.
+    ///
+    /// This is the left margin unless the cursor is already left of it.
+    #[inline]
+    fn line_start(&self) -> Column {
+        if self.grid.cursor.point.column >= self.horizontal_margins.start {
+            self.horizontal_margins.start
+        } else {
+            Column(0)
+        }
+    }
+
+    /// Convert a position relative to the origin into a grid position.
+    ///
+    /// With origin mode enabled, positions are relative to the scrolling region and margins.
+    #[inline]
+    fn origin_relative(&self, line: Line, column: Column) -> (Line, Column) {
+        if self.mode.contains(TermMode::ORIGIN) {
+            (line + self.scroll_region.start, column + self.horizontal_margins.start)
+        } else {
+            (line, column)
+        }
+    }
+
+    /// Area addressable by origin-relative positions.
+    ///
+    /// With origin mode enabled, this is the scrolling region and margins, otherwise the screen.
+    #[inline]
+    fn page(&self) -> (Range<Line>, Range<Column>) {
+        if self.mode.contains(TermMode::ORIGIN) {
+            (self.scroll_region.clone(), self.horizontal_margins.clone())
+        } else {
+            (Line(0)..Line(self.screen_lines() as i32), Column(0)..Column(self.columns()))
+        }
+    }
+
+    /// SGR parameters reproducing the current graphic rendition.
+    fn graphic_rendition(&self) -> String {
+        sgr_parameters(&self.grid.cursor.template)
+    }
+
+    /// Cursor information report (DECCIR) parameters.
+    fn cursor_information(&self) -> String {
+        let cursor = &self.grid.cursor;
+
+        let flag = |set: bool, value: u8| if set { value } else { 0 };
+        let flags = cursor.template.flags;
+        let rendition = 0x40
+            | flag(flags.contains(Flags::BOLD), 1)
+            | flag(flags.intersects(Flags::ALL_UNDERLINES), 2)
+            | flag(flags.contains(Flags::BLINK), 4)
+            | flag(flags.contains(Flags::INVERSE), 8);
+        let attributes = 0x40 | flag(flags.contains(Flags::PROTECTED), 1);
+        let state =
+            0x40 | flag(self.mode.contains(TermMode::ORIGIN), 1) | flag(cursor.input_needs_wrap, 8);
+
+        let designations: String =
+            [CharsetIndex::G0, CharsetIndex::G1, CharsetIndex::G2, CharsetIndex::G3]
+                .into_iter()
+                .map(|index| match cursor.charsets[index] {
+                    StandardCharset::Ascii => 'B',
+                    StandardCharset::SpecialCharacterAndLineDrawing => '0',
+                })
+                .collect();
+
+        format!(
+            "1$u{};{};1;{};{};{};{};2;@;{designations}",
+            cursor.point.line + 1,
+            cursor.point.column + 1,
+            rendition as char,
+            attributes as char,
+            state as char,
+            self.active_charset as u8,
+        )
+    }
+
+    /// Convert a rectangular area into grid lines and columns, clamped to the page.
+    ///
+    /// Returns `None` if the area is empty.
+    fn rectangle_bounds(&self, area: Rectangle) -> Option<(Range<Line>, Range<Column>)> {
+        let (page_lines, page_columns) = self.page();
+
+        let top = page_lines.start + (area.top - 1);
+        let left = page_columns.start + (area.left - 1);
+        let bottom = area
+            .bottom
+            .map_or(page_lines.end, |bottom| cmp::min(page_lines.start + bottom, page_lines.end));
+        let right = area.right.map_or(page_columns.end, |right| {
+            cmp::min(page_columns.start + right, page_columns.end)
+        });
+
+        (top < bottom && left < right).then_some((top..bottom, left..right))
+    }
+
+    /// Mark a rectangular area as damaged.
+    #[inline]
+    fn damage_rect(&mut self, lines: Range<Line>, columns: Range<Column>) {
+        for line in lines.start.0..lines.end.0 {
+            self.damage.damage_line(line as usize, columns.start.0, columns.end.0 - 1);
+        }
+    }
+
+    /// Clear the characters of all unprotected cells in an area, keeping their attributes.
+    fn selective_erase(&mut self, lines: Range<Line>, columns: Range<Column>) {
+        if lines.is_empty() || columns.is_empty() {
+            return;
+        }
+
+        self.grid.update_rect(lines.clone(), columns.clone(), |cell| {
+            if !cell.flags.contains(Flags::PROTECTED) {
+                cell.clear_wide();
+                cell.flags.remove(Flags::WIDE_CHAR_SPACER | Flags::LEADING_WIDE_CHAR_SPACER);
+            }
+        });
+        self.damage_rect(lines, columns);
+    }
+
+    /// Move the cursor, without leaving the area it is confined to.
+    ///
+    /// With origin mode enabled, the cursor cannot leave the scrolling region and margins.
+    #[inline]
+    fn move_cursor_to(&mut self, line: Line, column: Column) {
+        let (max_line, max_column) = if self.mode.contains(TermMode::ORIGIN) {
+            (self.scroll_region.end - 1, self.horizontal_margins.end - 1)
+        } else {
+            (self.bottommost_line(), self.last_column())
+        };
+
+        let line = cmp::max(cmp::min(line, max_line), Line(0));
+        let max_column = cmp::min(max_column, self.visible_columns(line) - 1);
+
+        self.damage_cursor();
+        self.grid.cursor.point.line = line;
+        self.grid.cursor.point.column = cmp::min(column, max_column);
+        self.damage_cursor();
+        self.grid.cursor.input_needs_wrap = false;
+    }
+
     fn deccolm(&mut self)
     where
         T: EventListener,
@@ -925,6 +1510,65 @@ impl<T> Term<T> {
         point
     }
 
+    /// Find the closest line with a prompt start mark before or after `line`.
+    pub fn prompt_line(&self, line: Line, direction: Direction) -> Option<Line> {
+        let prompt = PromptMarks::PROMPT_START;
+        match direction {
+            Direction::Left => {
+                self.grid.prompt_lines(self.topmost_line()..line, prompt).next_back()
+            },
+            Direction::Right => {
+                self.grid.prompt_lines(line + 1..self.bottommost_line() + 1, prompt).next()
+            },
+        }
+    }
+
+    /// Bounds of the output produced by the most recent command.
+    ///
+    /// The output starts at the last line marked through OSC 133 as command output and ends
+    /// before the next prompt, or at the cursor while the command is still running.
+    pub fn last_command_output(&self) -> Option<(Point, Point)> {
+        let cursor_line = self.grid.cursor.point.line;
+        let lines = self.topmost_line()..cursor_line + 1;
+        let start = self.grid.prompt_lines(lines, PromptMarks::OUTPUT_START).next_back()?;
+
+        let mut prompts = self.grid.prompt_lines(start..cursor_line + 1, PromptMarks::PROMPT_START);
+        let end = match prompts.next() {
+            Some(prompt_line) if prompt_line == start => return None,
+            Some(prompt_line) => prompt_line - 1,
+            None => cursor_line,
+        };
+
+        Some((Point::new(start, Column(0)), Point::new(end, self.last_column())))
+    }
+
+    /// Set the vi mode mark `name` on a line, removing it from the line it was previously set on.
+    ///
+    /// Only the lowercase ASCII letters `a` through `z` are valid mark names, others are ignored.
+    pub fn set_vi_mark(&mut self, name: char, line: Line) {
+        if !name.is_ascii_lowercase() {
+            return;
+        }
+
+        self.grid.set_vi_mark(name, line);
+    }
+
+    /// Find the line the vi mode mark `name` is set on.
+    ///
+    /// Marks move with their line and are dropped once it is removed from the scrollback history.
+    pub fn vi_mark_line(&self, name: char) -> Option<Line> {
+        if !name.is_ascii_lowercase() {
+            return None;
+        }
+
+        self.grid.vi_mark_line(name)
+    }
+
+    /// All vi mode marks and their lines, sorted by name.
+    pub fn vi_marks(&self) -> Vec<(char, Line)> {
+        self.grid.vi_marks()
+    }
+
     #[inline]
     pub fn semantic_escape_chars(&self) -> &str {
         &self.config.semantic_escape_chars
@@ -967,6 +1611,12 @@ impl<T> Term<T> {
 
         self.grid.cursor_cell().flags.insert(Flags::WRAPLINE);
 
+        let column = if self.line_end() == self.horizontal_margins.end {
+            self.horizontal_margins.start
+        } else {
+            Column(0)
+        };
+
         if self.grid.cursor.point.line + 1 >= self.scroll_region.end {
             self.linefeed();
         } else {
@@ -974,7 +1624,7 @@ impl<T> Term<T> {
             self.grid.cursor.point.line += 1;
         }
 
-        self.grid.cursor.point.column = Column(0);
+        self.grid.cursor.point.column = column;
         self.grid.cursor.input_needs_wrap = false;
         self.damage_cursor();
     }
@@ -1017,6 +1667,76 @@ impl<T> Term<T> {
         cursor_cell.extra = extra;
     }
 
+    /// Add a character to the grapheme cluster before the cursor.
+    ///
+    /// Returns `false` if the character starts a new grapheme cluster instead.
+    fn extend_grapheme(&mut self, c: char) -> bool {
+        let line = self.grid.cursor.point.line;
+        let mut column = self.grid.cursor.point.column;
+        if !self.grid.cursor.input_needs_wrap {
+            if column == 0 {
+                return false;
+            }
+            column -= 1;
+        }
+
+        // Clusters are stored in the first cell of fullwidth characters.
+        if self.grid[line][column].flags.contains(Flags::WIDE_CHAR_SPACER) {
+            column.0 = column.saturating_sub(1);
+        }
+
+        let cell = &self.grid[line][column];
+        let zerowidth = cell.zerowidth().unwrap_or_default();
+        let last = zerowidth.last().copied().unwrap_or(cell.c);
+        if !may_join_grapheme(last, c) {
+            return false;
+        }
+
+        let cluster = &mut self.grapheme_buffer;
+        cluster.clear();
+        cluster.push(cell.c);
+        cluster.extend(zerowidth);
+        cluster.push(c);
+
+        if cluster.graphemes(true).nth(1).is_some() {
+            return false;
+        }
+
+        let wide = cell.flags.contains(Flags::WIDE_CHAR);
+        let narrow = cluster.width() < 2;
+        self.grid[line][column].push_zerowidth(c);
+
+        // Widen the cluster if it fits, otherwise it is kept at its current width.
+        if wide || narrow || self.grid.cursor.input_needs_wrap {
+            return true;
+        }
+
+        // If in insert mode, first shift cells to the right.
+        let columns = self.line_end().0;
+        let cursor_column = self.grid.cursor.point.column;
+        if self.mode.contains(TermMode::INSERT) && cursor_column + 1 < columns {
+            let row = &mut self.grid[line][..];
+            for col in (cursor_column.0..(columns - 1)).rev() {
+                row.swap(col + 1, col);
+            }
+        }
+
+        self.grid[line][column].flags.insert(Flags::WIDE_CHAR);
+
+        // Write spacer to cell following the wide cluster.
+        self.grid.cursor.template.flags.insert(Flags::WIDE_CHAR_SPACER);
+        self.write_at_cursor(' ');
+        self.grid.cursor.template.flags.remove(Flags::WIDE_CHAR_SPACER);
+
+        if cursor_column + 1 < columns {
+            self.grid.cursor.point.column += 1;
+        } else {
+            self.grid.cursor.input_needs_wrap = true;
+        }
+
+        true
+    }
+
     #[inline]
     fn damage_cursor(&mut self) {
         // The normal cursor coordinates are always in viewport.
@@ -1066,6 +1786,11 @@ impl<T: EventListener> Handler for Term<T> {
             None => return,
         };
 
+        // Join characters with the grapheme cluster before the cursor.
+        if self.mode.contains(TermMode::GRAPHEME_CLUSTERING) && self.extend_grapheme(c) {
+            return;
+        }
+
         // Handle zero-width characters.
         if width == 0 {
             // Get previous column.
@@ -1089,8 +1814,14 @@ impl<T: EventListener> Handler for Term<T> {
             self.wrapline();
         }
 
+        // Keep the cursor within the displayed part of double-width lines.
+        let visible_columns = self.visible_columns(self.grid.cursor.point.line);
+        if self.grid.cursor.point.column >= visible_columns {
+            self.grid.cursor.point.column = visible_columns - 1;
+        }
+
         // If in insert mode, first shift cells to the right.
-        let columns = self.columns();
+        let columns = self.line_end().0;
         if self.mode.contains(TermMode::INSERT) && self.grid.cursor.point.column + width < columns {
             let line = self.grid.cursor.point.line;
             let col = self.grid.cursor.point.column;
@@ -1147,6 +1878,7 @@ impl<T: EventListener> Handler for Term<T> {
                 *cell = Cell::default();
                 cell.c = 'E';
             }
+            self.grid[line].line_size = LineSize::Single;
         }
 
         self.mark_fully_damaged();
@@ -1158,42 +1890,41 @@ impl<T: EventListener> Handler for Term<T> {
         let col = Column(col);
 
         trace!("Going to: line={line}, col={col}");
-        let (y_offset, max_y) = if self.mode.contains(TermMode::ORIGIN) {
-            (self.scroll_region.start, self.scroll_region.end - 1)
-        } else {
-            (Line(0), self.bottommost_line())
-        };
-
-        self.damage_cursor();
-        self.grid.cursor.point.line = cmp::max(cmp::min(line + y_offset, max_y), Line(0));
-        self.grid.cursor.point.column = cmp::min(col, self.last_column());
-        self.damage_cursor();
-        self.grid.cursor.input_needs_wrap = false;
-    }
+        let (line, col) = self.origin_relative(line, col);
+        self.move_cursor_to(line, col);
+    }
 
     #[inline]
     fn goto_line(&mut self, line: i32) {
         trace!("Going to line: {line}");
-        self.goto(line, self.grid.cursor.point.column.0)
+        let (line, _) = self.origin_relative(Line(line), Column(0));
+        self.move_cursor_to(line, self.grid.cursor.point.column);
     }
 
     #[inline]
     fn goto_col(&mut self, col: usize) {
         trace!("Going to column: {col}");
-        self.goto(self.grid.cursor.point.line.0, col)
+        let (_, col) = self.origin_relative(Line(0), Column(col));
+        self.move_cursor_to(self.grid.cursor.point.line, col);
     }
 
     #[inline]
     fn insert_blank(&mut self, count: usize) {
+        // Characters outside of the margins cannot be shifted.
+        if !self.cursor_within_margins() {
+            return;
+        }
+
+        let columns = self.horizontal_margins.end.0;
         let cursor = &self.grid.cursor;
         let bg = cursor.template.bg;
 
         // Ensure inserting within terminal bounds
-        let count = cmp::min(count, self.columns() - cursor.point.column.0);
+        let count = cmp::min(count, columns - cursor.point.column.0);
 
         let source = cursor.point.column;
         let destination = cursor.point.column.0 + count;
-        let num_cells = self.columns() - destination;
+        let num_cells = columns - destination;
 
         let line = cursor.point.line;
         self.damage.damage_line(line.0 as usize, 0, self.columns() - 1);
@@ -1216,8 +1947,7 @@ impl<T: EventListener> Handler for Term<T> {
         trace!("Moving up: {lines}");
 
         let line = self.grid.cursor.point.line - lines;
-        let column = self.grid.cursor.point.column;
-        self.goto(line.0, column.0)
+        self.move_cursor_to(line, self.grid.cursor.point.column);
     }
 
     #[inline]
@@ -1225,14 +1955,13 @@ impl<T: EventListener> Handler for Term<T> {
         trace!("Moving down: {lines}");
 
         let line = self.grid.cursor.point.line + lines;
-        let column = self.grid.cursor.point.column;
-        self.goto(line.0, column.0)
+        self.move_cursor_to(line, self.grid.cursor.point.column);
     }
 
     #[inline]
     fn move_forward(&mut self, cols: usize) {
         trace!("Moving forward: {cols}");
-        let last_column = cmp::min(self.grid.cursor.point.column + cols, self.last_column());
+        let last_column = cmp::min(self.grid.cursor.point.column + cols, self.line_end() - 1);
 
         let cursor_line = self.grid.cursor.point.line.0 as usize;
         self.damage.damage_line(cursor_line, self.grid.cursor.point.column.0, last_column.0);
@@ -1245,6 +1974,7 @@ impl<T: EventListener> Handler for Term<T> {
     fn move_backward(&mut self, cols: usize) {
         trace!("Moving backward: {cols}");
         let column = self.grid.cursor.point.column.saturating_sub(cols);
+        let column = cmp::max(column, self.line_start().0);
 
         let cursor_line = self.grid.cursor.point.line.0 as usize;
         self.damage.damage_line(cursor_line, column, self.grid.cursor.point.column.0);
@@ -1258,8 +1988,9 @@ impl<T: EventListener> Handler for Term<T> {
         match intermediate {
             None => {
                 trace!("Reporting primary device attributes");
-                let text = String::from("\x1b[?6c");
-                self.event_proxy.send_event(Event::PtyWrite(text));
+                // Report VT220 with sixel graphics if they are displayed.
+                let text = if self.config.graphics { "\x1b[?62;4c" } else { "\x1b[?6c" };
+                self.event_proxy.send_event(Event::PtyWrite(text.into()));
             },
             Some('>') => {
                 trace!("Reporting secondary device attributes");
@@ -1350,7 +2081,7 @@ impl<T: EventListener> Handler for Term<T> {
         trace!("Moving down and cr: {lines}");
 
         let line = self.grid.cursor.point.line + lines;
-        self.goto(line.0, 0)
+        self.move_cursor_to(line, self.line_start());
     }
 
     #[inline]
@@ -1358,7 +2089,7 @@ impl<T: EventListener> Handler for Term<T> {
         trace!("Moving up and cr: {lines}");
 
         let line = self.grid.cursor.point.line - lines;
-        self.goto(line.0, 0)
+        self.move_cursor_to(line, self.line_start());
     }
 
     /// Insert tab at cursor position.
@@ -1370,7 +2101,8 @@ impl<T: EventListener> Handler for Term<T> {
             return;
         }
 
-        while self.grid.cursor.point.column < self.columns() && count != 0 {
+        let line_end = self.line_end();
+        while self.grid.cursor.point.column < line_end && count != 0 {
             count -= 1;
 
             let c = self.grid.cursor.charsets[self.active_charset].map('\t');
@@ -1380,7 +2112,7 @@ impl<T: EventListener> Handler for Term<T> {
             }
 
             loop {
-                if (self.grid.cursor.point.column + 1) == self.columns() {
+                if (self.grid.cursor.point.column + 1) == line_end {
                     break;
                 }
 
@@ -1398,7 +2130,8 @@ impl<T: EventListener> Handler for Term<T> {
     fn backspace(&mut self) {
         trace!("Backspace");
 
-        if self.grid.cursor.point.column > Column(0) {
+        let column = self.grid.cursor.point.column;
+        if column > Column(0) && column != self.horizontal_margins.start {
             let line = self.grid.cursor.point.line.0 as usize;
             let column = self.grid.cursor.point.column.0;
             self.grid.cursor.point.column -= 1;
@@ -1411,7 +2144,7 @@ impl<T: EventListener> Handler for Term<T> {
     #[inline]
     fn carriage_return(&mut self) {
         trace!("Carriage return");
-        let new_col = 0;
+        let new_col = self.line_start().0;
         let line = self.grid.cursor.point.line.0 as usize;
         self.damage.damage_line(line, new_col, self.grid.cursor.point.column.0);
         self.grid.cursor.point.column = Column(new_col);
@@ -1424,7 +2157,10 @@ impl<T: EventListener> Handler for Term<T> {
         trace!("Linefeed");
         let next = self.grid.cursor.point.line + 1;
         if next == self.scroll_region.end {
-            self.scroll_up(1);
+            // Only the area inside the margins can scroll.
+            if self.cursor_within_margins() {
+                self.scroll_up(1);
+            }
         } else if next < self.screen_lines() {
             self.damage_cursor();
             self.grid.cursor.point.line += 1;
@@ -1498,7 +2234,7 @@ impl<T: EventListener> Handler for Term<T> {
         trace!("Inserting blank {lines} lines");
 
         let origin = self.grid.cursor.point.line;
-        if self.scroll_region.contains(&origin) {
+        if self.scroll_region.contains(&origin) && self.cursor_within_margins() {
             self.scroll_down_relative(origin, lines);
         }
     }
@@ -1510,7 +2246,7 @@ impl<T: EventListener> Handler for Term<T> {
 
         trace!("Deleting {lines} lines");
 
-        if lines > 0 && self.scroll_region.contains(&origin) {
+        if lines > 0 && self.scroll_region.contains(&origin) && self.cursor_within_margins() {
             self.scroll_up_relative(origin, lines);
         }
     }
@@ -1536,12 +2272,17 @@ impl<T: EventListener> Handler for Term<T> {
 
     #[inline]
     fn delete_chars(&mut self, count: usize) {
-        let columns = self.columns();
+        // Characters outside of the margins cannot be shifted.
+        if !self.cursor_within_margins() {
+            return;
+        }
+
+        let columns = self.horizontal_margins.end.0;
         let cursor = &self.grid.cursor;
         let bg = cursor.template.bg;
 
         // Ensure deleting within terminal bounds.
-        let count = cmp::min(count, columns);
+        let count = cmp::min(count, columns - cursor.point.column.0);
 
         let start = cursor.point.column.0;
         let end = cmp::min(start + count, columns - 1);
@@ -1558,7 +2299,7 @@ impl<T: EventListener> Handler for Term<T> {
         // Clear last `count` cells in the row. If deleting 1 char, need to delete
         // 1 cell.
         let end = columns - count;
-        for cell in &mut row[end..] {
+        for cell in &mut row[end..columns] {
             *cell = bg.into();
         }
     }
@@ -1841,6 +2582,7 @@ impl<T: EventListener> Handler for Term<T> {
         self.grid.reset();
         self.inactive_grid.reset();
         self.scroll_region = Line(0)..Line(self.screen_lines() as i32);
+        self.horizontal_margins = Column(0)..Column(self.columns());
         self.tabs = TabStops::new(self.columns());
         self.title_stack = Vec::new();
         self.title = None;
@@ -1848,12 +2590,16 @@ impl<T: EventListener> Handler for Term<T> {
         self.vi_mode_cursor = Default::default();
         self.keyboard_mode_stack = Default::default();
         self.inactive_keyboard_mode_stack = Default::default();
+        self.graphics.kitty = Default::default();
+        self.placements.clear();
+        self.inactive_placements.clear();
 
         // Preserve vi mode across resets.
         self.mode &= TermMode::VI;
         self.mode.insert(TermMode::default());
 
         self.event_proxy.send_event(Event::CursorBlinkingChange);
+        self.event_proxy.send_event(Event::PointerShape(None));
         self.mark_fully_damaged();
     }
 
@@ -1862,7 +2608,10 @@ impl<T: EventListener> Handler for Term<T> {
         trace!("Reversing index");
         // If cursor is at the top.
         if self.grid.cursor.point.line == self.scroll_region.start {
-            self.scroll_down(1);
+            // Only the area inside the margins can scroll.
+            if self.cursor_within_margins() {
+                self.scroll_down(1);
+            }
         } else {
             self.damage_cursor();
             self.grid.cursor.point.line = cmp::max(self.grid.cursor.point.line - 1, Line(0));
@@ -1888,7 +2637,8 @@ impl<T: EventListener> Handler for Term<T> {
             Attr::Reset => {
                 cursor.template.fg = Color::Named(NamedColor::Foreground);
                 cursor.template.bg = Color::Named(NamedColor::Background);
-                cursor.template.flags = Flags::empty();
+                // Character protection is not part of the rendition.
+                cursor.template.flags &= Flags::PROTECTED;
                 cursor.template.set_underline_color(None);
             },
             Attr::Reverse => cursor.template.flags.insert(Flags::INVERSE),
@@ -1924,9 +2674,8 @@ impl<T: EventListener> Handler for Term<T> {
             Attr::CancelHidden => cursor.template.flags.remove(Flags::HIDDEN),
             Attr::Strike => cursor.template.flags.insert(Flags::STRIKEOUT),
             Attr::CancelStrike => cursor.template.flags.remove(Flags::STRIKEOUT),
-            _ => {
-                debug!("Term got unhandled attr: {attr:?}");
-            },
+            Attr::BlinkSlow | Attr::BlinkFast => cursor.template.flags.insert(Flags::BLINK),
+            Attr::CancelBlink => cursor.template.flags.remove(Flags::BLINK),
         }
     }
 
@@ -1934,6 +2683,35 @@ impl<T: EventListener> Handler for Term<T> {
     fn set_private_mode(&mut self, mode: PrivateMode) {
         let mode = match mode {
             PrivateMode::Named(mode) => mode,
+            PrivateMode::Unknown(LEFT_RIGHT_MARGIN_MODE) => {
+                trace!("Setting private mode: LeftRightMargin");
+                self.mode.insert(TermMode::LEFT_RIGHT_MARGIN);
+                return;
+            },
+            PrivateMode::Unknown(X10_MOUSE_MODE) => {
+                trace!("Setting private mode: X10Mouse");
+                self.mode.remove(TermMode::MOUSE_MODE);
+                self.mode.insert(TermMode::X10_MOUSE);
+                self.event_proxy.send_event(Event::MouseCursorDirty);
+                return;
+            },
+            PrivateMode::Unknown(GRAPHEME_CLUSTERING_MODE) => {
+                trace!("Setting private mode: GraphemeClustering");
+                self.mode.insert(TermMode::GRAPHEME_CLUSTERING);
+                return;
+            },
+            PrivateMode::Unknown(URXVT_MOUSE_MODE) => {
+                trace!("Setting private mode: UrxvtMouse");
+                self.mode.remove(TermMode::MOUSE_ENCODING);
+                self.mode.insert(TermMode::URXVT_MOUSE);
+                return;
+            },
+            PrivateMode::Unknown(SGR_PIXEL_MOUSE_MODE) => {
+                trace!("Setting private mode: SgrPixelMouse");
+                self.mode.remove(TermMode::MOUSE_ENCODING);
+                self.mode.insert(TermMode::SGR_PIXEL_MOUSE);
+                return;
+            },
             PrivateMode::Unknown(mode) => {
                 debug!("Ignoring unknown mode {mode} in set_private_mode");
                 return;
@@ -1970,11 +2748,11 @@ impl<T: EventListener> Handler for Term<T> {
             NamedPrivateMode::BracketedPaste => self.mode.insert(TermMode::BRACKETED_PASTE),
             // Mouse encodings are mutually exclusive.
             NamedPrivateMode::SgrMouse => {
-                self.mode.remove(TermMode::UTF8_MOUSE);
+                self.mode.remove(TermMode::MOUSE_ENCODING);
                 self.mode.insert(TermMode::SGR_MOUSE);
             },
             NamedPrivateMode::Utf8Mouse => {
-                self.mode.remove(TermMode::SGR_MOUSE);
+                self.mode.remove(TermMode::MOUSE_ENCODING);
                 self.mode.insert(TermMode::UTF8_MOUSE);
             },
             NamedPrivateMode::AlternateScroll => self.mode.insert(TermMode::ALTERNATE_SCROLL),
@@ -1997,6 +2775,33 @@ impl<T: EventListener> Handler for Term<T> {
     fn unset_private_mode(&mut self, mode: PrivateMode) {
         let mode = match mode {
             PrivateMode::Named(mode) => mode,
+            PrivateMode::Unknown(LEFT_RIGHT_MARGIN_MODE) => {
+                trace!("Unsetting private mode: LeftRightMargin");
+                self.mode.remove(TermMode::LEFT_RIGHT_MARGIN);
+                self.horizontal_margins = Column(0)..Column(self.columns());
+                return;
+            },
+            PrivateMode::Unknown(X10_MOUSE_MODE) => {
+                trace!("Unsetting private mode: X10Mouse");
+                self.mode.remove(TermMode::X10_MOUSE);
+                self.event_proxy.send_event(Event::MouseCursorDirty);
+                return;
+            },
+            PrivateMode::Unknown(GRAPHEME_CLUSTERING_MODE) => {
+                trace!("Unsetting private mode: GraphemeClustering");
+                self.mode.remove(TermMode::GRAPHEME_CLUSTERING);
+                return;
+            },
+            PrivateMode::Unknown(URXVT_MOUSE_MODE) => {
+                trace!("Unsetting private mode: UrxvtMouse");
+                self.mode.remove(TermMode::URXVT_MOUSE);
+                return;
+            },
+            PrivateMode::Unknown(SGR_PIXEL_MOUSE_MODE) => {
+                trace!("Unsetting private mode: SgrPixelMouse");
+                self.mode.remove(TermMode::SGR_PIXEL_MOUSE);
+                return;
+            },
             PrivateMode::Unknown(mode) => {
                 debug!("Ignoring unknown mode {mode} in unset_private_mode");
                 return;
@@ -2082,7 +2887,27 @@ impl<T: EventListener> Handler for Term<T> {
                     self.mode.contains(TermMode::BRACKETED_PASTE).into()
                 },
                 NamedPrivateMode::SyncUpdate => ModeState::Reset,
-                NamedPrivateMode::ColumnMode => ModeState::NotSupported,
+                // Switching to 132 columns only clears the screen.
+                NamedPrivateMode::ColumnMode => ModeState::PermanentlyReset,
+            },
+            PrivateMode::Unknown(LEFT_RIGHT_MARGIN_MODE) => {
+                self.mode.contains(TermMode::LEFT_RIGHT_MARGIN).into()
+            },
+            PrivateMode::Unknown(X10_MOUSE_MODE) => self.mode.contains(TermMode::X10_MOUSE).into(),
+            PrivateMode::Unknown(URXVT_MOUSE_MODE) => {
+                self.mode.contains(TermMode::URXVT_MOUSE).into()
+            },
+            PrivateMode::Unknown(SGR_PIXEL_MOUSE_MODE) => {
+                self.mode.contains(TermMode::SGR_PIXEL_MOUSE).into()
+            },
+            PrivateMode::Unknown(GRAPHEME_CLUSTERING_MODE) => {
+                self.mode.contains(TermMode::GRAPHEME_CLUSTERING).into()
+            },
+            PrivateMode::Unknown(mode) if PERMANENTLY_SET_PRIVATE_MODES.contains(&mode) => {
+                ModeState::PermanentlySet
+            },
+            PrivateMode::Unknown(mode) if PERMANENTLY_RESET_PRIVATE_MODES.contains(&mode) => {
+                ModeState::PermanentlyReset
             },
             PrivateMode::Unknown(_) => ModeState::NotSupported,
         };
@@ -2141,6 +2966,12 @@ impl<T: EventListener> Handler for Term<T> {
                     self.mode.contains(TermMode::LINE_FEED_NEW_LINE).into()
                 },
             },
+            ansi::Mode::Unknown(mode) if PERMANENTLY_SET_MODES.contains(&mode) => {
+                ModeState::PermanentlySet
+            },
+            ansi::Mode::Unknown(mode) if PERMANENTLY_RESET_MODES.contains(&mode) => {
+                ModeState::PermanentlyReset
+            },
             ansi::Mode::Unknown(_) => ModeState::NotSupported,
         };
 
@@ -2271,6 +3102,780 @@ impl<T: EventListener> Handler for Term<T> {
     }
 }
 
+impl<T: EventListener> ExtendedHandler for Term<T> {
+    #[inline]
+    fn semantic_prompt(&mut self, mark: SemanticPrompt) {
+        trace!("Setting semantic prompt mark {mark:?}");
+
+        let mark = match mark {
+            SemanticPrompt::PromptStart => PromptMarks::PROMPT_START,
+            SemanticPrompt::CommandStart => PromptMarks::COMMAND_START,
+            SemanticPrompt::OutputStart => PromptMarks::OUTPUT_START,
+            SemanticPrompt::CommandEnd(_) => PromptMarks::COMMAND_END,
+        };
+
+        let line = self.grid.cursor.point.line;
+        self.grid.insert_prompt_mark(line, mark);
+    }
+
+    #[inline]
+    fn set_working_directory(&mut self, path: PathBuf) {
+        trace!("Setting working directory to {path:?}");
+        self.event_proxy.send_event(Event::WorkingDirectory(path));
+    }
+
+    #[inline]
+    fn desktop_notification(&mut self, title: String, body: String) {
+        trace!("Desktop notification: {title:?} {body:?}");
+        self.event_proxy.send_event(Event::Notification { title, body });
+    }
+
+    #[inline]
+    fn set_pointer_shape(&mut self, shape: Option<String>) {
+        trace!("Setting pointer shape to {shape:?}");
+        self.event_proxy.send_event(Event::PointerShape(shape));
+    }
+
+    #[inline]
+    fn insert_graphic(&mut self, graphic: Graphic) {
+        // Graphics would replace the text below them without being visible.
+        if !self.config.graphics {
+            debug!("Ignoring graphic, graphics are not displayed");
+            return;
+        }
+
+        let cell_size = match self.graphics.cell_size() {
+            Some(cell_size) => cell_size,
+            None => {
+                debug!("Ignoring graphic, cell size is unknown");
+                return;
+            },
+        };
+        trace!("Inserting graphic: {graphic:?}");
+
+        let column = self.grid.cursor.point.column;
+        let placement = Placement::new(Arc::new(graphic), cell_size);
+        self.place_graphic(Arc::new(placement), true, true);
+
+        // Continue below the graphic, at the column where it started.
+        self.linefeed();
+        self.grid.cursor.point.column = column;
+        self.grid.cursor.input_needs_wrap = false;
+    }
+
+    #[inline]
+    fn kitty_graphics(&mut self, command: kitty::Command) {
+        trace!("Kitty graphics command: {command:?}");
+
+        let command = match self.graphics.kitty.assemble(command) {
+            Some(command) => command,
+            None => return,
+        };
+
+        let mut image_id = command.image_id;
+        let result = match command.action {
+            b'T' | b'p' | b'q' if !self.config.graphics => {
+                Err(kitty::Error::new("ENOTSUP", "graphics are not displayed"))
+            },
+            b't' => self.graphics.kitty.transmit(&command).map(|(id, _)| image_id = id),
+            b'T' => self.graphics.kitty.transmit(&command).and_then(|(id, graphic)| {
+                image_id = id;
+                self.kitty_place(&command, image_id, graphic)
+            }),
+            b'p' => self.graphics.kitty.image(&command).and_then(|(id, graphic)| {
+                image_id = id;
+                self.kitty_place(&command, image_id, graphic)
+            }),
+            b'q' => self.graphics.kitty.load(&command).map(|_| ()),
+            b'd' => {
+                self.kitty_delete(&command);
+                return;
+            },
+            _ => Err(kitty::Error::new("EINVAL", "unsupported action")),
+        };
+
+        if let Some(response) = kitty::response(&command, image_id, result) {
+            self.event_proxy.send_event(Event::PtyWrite(response));
+        }
+    }
+
+    #[inline]
+    fn set_left_right_margins(&mut self, left: usize, right: Option<usize>) {
+        if !self.mode.contains(TermMode::LEFT_RIGHT_MARGIN) {
+            self.save_cursor_position();
+            return;
+        }
+
+        // Fallback to the last column as default.
+        let right = cmp::min(right.unwrap_or_else(|| self.columns()), self.columns());
+
+        if left >= right {
+            debug!("Invalid left and right margins: ({left};{right})");
+            return;
+        }
+
+        let start = Column(left - 1);
+        let end = Column(right);
+
+        trace!("Setting left and right margins: ({start};{end})");
+
+        self.horizontal_margins = start..end;
+        self.goto(0, 0);
+    }
+
+    #[inline]
+    fn copy_rectangle(&mut self, area: Rectangle, line: usize, column: usize) {
+        let Some((lines, columns)) = self.rectangle_bounds(area) else { return };
+        let (page_lines, page_columns) = self.page();
+
+        let destination =
+            Point::new(page_lines.start + (line - 1), page_columns.start + (column - 1));
+        if destination.line >= page_lines.end || destination.column >= page_columns.end {
+            return;
+        }
+
+        trace!("Copying rectangle {lines:?}x{columns:?} to {destination:?}");
+
+        // Clip the copied area at the edges of the page.
+        let height = cmp::min(lines.end - lines.start, page_lines.end - destination.line);
+        let width = cmp::min(columns.end - columns.start, page_columns.end - destination.column);
+        let lines = lines.start..lines.start + height;
+        let columns = columns.start..columns.start + width.0;
+
+        self.grid.copy_rect(lines, columns, destination);
+        self.damage_rect(
+            destination.line..destination.line + height,
+            destination.column..destination.column + width.0,
+        );
+    }
+
+    #[inline]
+    fn fill_rectangle(&mut self, c: char, area: Rectangle) {
+        let Some((lines, columns)) = self.rectangle_bounds(area) else { return };
+
+        trace!("Filling rectangle {lines:?}x{columns:?} with {c:?}");
+
+        let mut template = self.grid.cursor.template.clone();
+        template.flags.remove(Flags::WRAPLINE | Flags::WIDE_CHAR | Flags::WIDE_CHAR_SPACER);
+        template.c = c;
+
+        self.grid.update_rect(lines.clone(), columns.clone(), |cell| *cell = template.clone());
+        self.damage_rect(lines, columns);
+    }
+
+    #[inline]
+    fn erase_rectangle(&mut self, area: Rectangle, selective: bool) {
+        let Some((lines, columns)) = self.rectangle_bounds(area) else { return };
+
+        trace!("Erasing rectangle {lines:?}x{columns:?}, selective: {selective}");
+
+        if selective {
+            self.selective_erase(lines, columns);
+            return;
+        }
+
+        let bg = self.grid.cursor.template.bg;
+        self.grid.update_rect(lines.clone(), columns.clone(), |cell| *cell = bg.into());
+        self.damage_rect(lines, columns);
+    }
+
+    #[inline]
+    fn change_rectangle_attributes(&mut self, area: Rectangle, attrs: Vec<Attr>, reverse: bool) {
+        let Some((lines, columns)) = self.rectangle_bounds(area) else { return };
+
+        trace!("Changing attributes of {lines:?}x{columns:?}: {attrs:?}, reverse: {reverse}");
+
+        let apply = |cell: &mut Cell| {
+            for attr in &attrs {
+                if reverse {
+                    cell.flags.toggle(match attr {
+                        Attr::Reset => {
+                            Flags::BOLD | Flags::UNDERLINE | Flags::BLINK | Flags::INVERSE
+                        },
+                        Attr::Bold => Flags::BOLD,
+                        Attr::Underline => Flags::UNDERLINE,
+                        Attr::BlinkSlow => Flags::BLINK,
+                        Attr::Reverse => Flags::INVERSE,
+                        _ => Flags::empty(),
+                    });
+                    continue;
+                }
+
+                match attr {
+                    Attr::Reset => cell.flags.remove(
+                        Flags::BOLD
+                            | Flags::DIM
+                            | Flags::ALL_UNDERLINES
+                            | Flags::BLINK
+                            | Flags::INVERSE
+                            | Flags::HIDDEN
+                            | Flags::STRIKEOUT,
+                    ),
+                    Attr::Bold => cell.flags.insert(Flags::BOLD),
+                    Attr::CancelBoldDim => cell.flags.remove(Flags::BOLD | Flags::DIM),
+                    Attr::Underline => {
+                        cell.flags.remove(Flags::ALL_UNDERLINES);
+                        cell.flags.insert(Flags::UNDERLINE);
+                    },
+                    Attr::CancelUnderline => cell.flags.remove(Flags::ALL_UNDERLINES),
+                    Attr::BlinkSlow | Attr::BlinkFast => cell.flags.insert(Flags::BLINK),
+                    Attr::CancelBlink => cell.flags.remove(Flags::BLINK),
+                    Attr::Reverse => cell.flags.insert(Flags::INVERSE),
+                    Attr::CancelReverse => cell.flags.remove(Flags::INVERSE),
+                    Attr::Hidden => cell.flags.insert(Flags::HIDDEN),
+                    Attr::CancelHidden => cell.flags.remove(Flags::HIDDEN),
+                    Attr::Strike => cell.flags.insert(Flags::STRIKEOUT),
+                    Attr::CancelStrike => cell.flags.remove(Flags::STRIKEOUT),
+                    _ => (),
+                }
+            }
+        };
+
+        if self.mode.contains(TermMode::RECTANGULAR_EXTENT) {
+            self.grid.update_rect(lines.clone(), columns.clone(), apply);
+            self.damage_rect(lines, columns);
+            return;
+        }
+
+        // Stream extent wraps from the right edge of the page to the left edge of the next line.
+        let (_, page_columns) = self.page();
+        for line in (lines.start.0..lines.end.0).map(Line) {
+            let start = if line == lines.start { columns.start } else { page_columns.start };
+            let end = if line == lines.end - 1 { columns.end } else { page_columns.end };
+            self.grid.update_rect(line..line + 1, start..end, apply);
+            self.damage_rect(line..line + 1, start..end);
+        }
+    }
+
+    #[inline]
+    fn set_attribute_change_extent(&mut self, rectangle: bool) {
+        trace!("Setting rectangular attribute change extent: {rectangle}");
+        self.mode.set(TermMode::RECTANGULAR_EXTENT, rectangle);
+    }
+
+    #[inline]
+    fn set_line_size(&mut self, size: LineSize) {
+        trace!("Setting line size: {size:?}");
+
+        let line = self.grid.cursor.point.line;
+        self.grid[line].line_size = size;
+        self.damage.damage_line(line.0 as usize, 0, self.columns() - 1);
+
+        // Move the cursor into the displayed part of the line.
+        let visible_columns = self.visible_columns(line);
+        if self.grid.cursor.point.column >= visible_columns {
+            self.grid.cursor.point.column = visible_columns - 1;
+            self.grid.cursor.input_needs_wrap = false;
+        }
+    }
+
+    #[inline]
+    fn set_overline(&mut self, enabled: bool) {
+        trace!("Setting overline: {enabled}");
+        self.grid.cursor.template.flags.set(Flags::OVERLINE, enabled);
+    }
+
+    #[inline]
+    fn request_termcap(&mut self, name: Option<String>) {
+        trace!("Requesting terminfo capability {name:?}");
+        let text = termcap::response(name.as_deref());
+        self.event_proxy.send_event(Event::PtyWrite(text));
+    }
+
+    #[inline]
+    fn request_status(&mut self, setting: Option<StatusSetting>) {
+        trace!("Requesting status of {setting:?}");
+
+        let Some(setting) = setting else {
+            self.event_proxy.send_event(Event::PtyWrite(String::from("\x1bP0$r\x1b\\")));
+            return;
+        };
+
+        let value = match setting {
+            StatusSetting::GraphicRendition => self.graphic_rendition(),
+            StatusSetting::TopBottomMargins => {
+                format!("{};{}", self.scroll_region.start + 1, self.scroll_region.end)
+            },
+            StatusSetting::LeftRightMargins => {
+                format!("{};{}", self.horizontal_margins.start + 1, self.horizontal_margins.end)
+            },
+            StatusSetting::CursorStyle => {
+                let style = self.cursor_style.unwrap_or(self.config.default_cursor_style);
+                let shape = match style.shape {
+                    CursorShape::Underline => 3,
+                    CursorShape::Beam => 5,
+                    _ => 1,
+                };
+                (shape + u8::from(!style.blinking)).to_string()
+            },
+            StatusSetting::AttributeChangeExtent => {
+                let rectangle = self.mode.contains(TermMode::RECTANGULAR_EXTENT);
+                String::from(if rectangle { "2" } else { "1" })
+            },
+            // Report VT220 with 7-bit controls, matching the primary device attributes.
+            StatusSetting::ConformanceLevel => String::from("62;1"),
+            StatusSetting::ProtectionAttribute => {
+                let protected = self.grid.cursor.template.flags.contains(Flags::PROTECTED);
+                String::from(if protected { "1" } else { "0" })
+            },
+        };
+
+        let text = format!("\x1bP1$r{value}{}\x1b\\", setting.function());
+        self.event_proxy.send_event(Event::PtyWrite(text));
+    }
+
+    #[inline]
+    fn soft_reset(&mut self) {
+        trace!("Soft reset");
+
+        self.mode.insert(TermMode::SHOW_CURSOR | TermMode::LINE_WRAP);
+        self.mode.remove(
+            TermMode::INSERT | TermMode::ORIGIN | TermMode::APP_CURSOR | TermMode::APP_KEYPAD,
+        );
+
+        self.scroll_region = Line(0)..Line(self.screen_lines() as i32);
+        self.horizontal_margins = Column(0)..Column(self.columns());
+
+        // Reset attributes and character sets, keeping the cursor position.
+        self.active_charset = Default::default();
+        self.grid.cursor.template = Default::default();
+        self.grid.cursor.charsets = Default::default();
+        self.grid.saved_cursor = Default::default();
+
+        self.damage_cursor();
+    }
+
+    #[inline]
+    fn set_protected(&mut self, protected: bool) {
+        trace!("Setting character protection: {protected}");
+        self.grid.cursor.template.flags.set(Flags::PROTECTED, protected);
+    }
+
+    #[inline]
+    fn selective_clear_line(&mut self, mode: ansi::LineClearMode) {
+        trace!("Selectively clearing line: {mode:?}");
+
+        let point = self.grid.cursor.point;
+        let columns = match mode {
+            ansi::LineClearMode::Right => point.column..Column(self.columns()),
+            ansi::LineClearMode::Left => Column(0)..point.column + 1,
+            ansi::LineClearMode::All => Column(0)..Column(self.columns()),
+        };
+
+        self.selective_erase(point.line..point.line + 1, columns);
+    }
+
+    #[inline]
+    fn selective_clear_screen(&mut self, mode: ansi::ClearMode) {
+        trace!("Selectively clearing screen: {mode:?}");
+
+        let point = self.grid.cursor.point;
+        let screen_lines = Line(self.screen_lines() as i32);
+        let columns = Column(self.columns());
+
+        match mode {
+            ansi::ClearMode::Above => {
+                self.selective_erase(Line(0)..point.line, Column(0)..columns);
+                self.selective_erase(point.line..point.line + 1, Column(0)..point.column + 1);
+            },
+            ansi::ClearMode::Below => {
+                self.selective_erase(point.line..point.line + 1, point.column..columns);
+                self.selective_erase(point.line + 1..screen_lines, Column(0)..columns);
+            },
+            ansi::ClearMode::All => self.selective_erase(Line(0)..screen_lines, Column(0)..columns),
+            ansi::ClearMode::Saved => (),
+        }
+    }
+
+    #[inline]
+    fn request_checksum(&mut self, id: u16, area: Rectangle) {
+        trace!("Requesting checksum {id} of {area:?}");
+
+        // Sum of all characters and their attributes, negated as by the VT420.
+        let mut checksum = 0u16;
+        if let Some((lines, columns)) = self.rectangle_bounds(area) {
+            for line in (lines.start.0..lines.end.0).map(Line::from) {
+                for cell in &self.grid[line][columns.clone()] {
+                    checksum = checksum.wrapping_add(cell_checksum(cell));
+                }
+            }
+        }
+
+        let text = format!("\x1bP{id}!~{:04X}\x1b\\", checksum.wrapping_neg());
+        self.event_proxy.send_event(Event::PtyWrite(text));
+    }
+
+    #[inline]
+    fn request_presentation_state(&mut self, report: PresentationReport) {
+        trace!("Requesting presentation state: {report:?}");
+
+        let text = match report {
+            PresentationReport::CursorInformation => self.cursor_information(),
+            PresentationReport::TabStops => {
+                // The first column is never reported, since tabs can't move the cursor there.
+                let tabs: Vec<_> = (1..self.columns())
+                    .filter(|&column| self.tabs[Column(column)])
+                    .map(|column| (column + 1).to_string())
+                    .collect();
+                format!("2$u{}", tabs.join("/"))
+            },
+        };
+
+        self.event_proxy.send_event(Event::PtyWrite(format!("\x1bP{text}\x1b\\")));
+    }
+
+    #[inline]
+    fn request_color_table(&mut self, space: ColorSpace) {
+        trace!("Requesting color table: {space:?}");
+
+        // Colors are resolved by the event listener, which writes the entries in order.
+        self.event_proxy.send_event(Event::PtyWrite(String::from("\x1bP2$s")));
+        for index in 0..256 {
+            self.event_proxy.send_event(Event::ColorRequest(
+                index,
+                Arc::new(move |color| {
+                    let separator = if index == 0 { "" } else { "/" };
+                    format!("{separator}{index};{}", color_table_entry(color, space))
+                }),
+            ));
+        }
+        self.event_proxy.send_event(Event::PtyWrite(String::from("\x1b\\")));
+    }
+}
+
+/// Contribution of a cell to a DECRQCRA checksum.
+fn cell_checksum(cell: &Cell) -> u16 {
+    // Fullwidth characters are only counted once.
+    if cell.flags.contains(Flags::WIDE_CHAR_SPACER) {
+        return 0;
+    }
+
+    let zerowidth = cell.zerowidth().unwrap_or_default();
+    let mut checksum = iter::once(&cell.c)
+        .chain(zerowidth)
+        .fold(0u16, |checksum, &c| checksum.wrapping_add(c as u16));
+
+    let attributes = [
+        (Flags::ALL_UNDERLINES, 0x10),
+        (Flags::INVERSE, 0x20),
+        (Flags::BLINK, 0x40),
+        (Flags::BOLD, 0x80),
+    ];
+    for (flags, value) in attributes {
+        if cell.flags.intersects(flags) {
+            checksum = checksum.wrapping_add(value);
+        }
+    }
+
+    checksum
+}
+
+/// Color table entry of a DECCTR report, without its color number.
+fn color_table_entry(color: Rgb, space: ColorSpace) -> String {
+    let percent = |value: f64| (value * 100.).round() as u8;
+    let (r, g, b) = (color.r as f64 / 255., color.g as f64 / 255., color.b as f64 / 255.);
+
+    if space == ColorSpace::Rgb {
+        return format!("2;{};{};{}", percent(r), percent(g), percent(b));
+    }
+
+    let max = r.max(g).max(b);
+    let min = r.min(g).min(b);
+    let lightness = (max + min) / 2.;
+    let delta = max - min;
+
+    if delta == 0. {
+        return format!("1;0;{};0", percent(lightness));
+    }
+
+    let saturation = delta / (1. - (2. * lightness - 1.).abs());
+    let hue = if max == r {
+        (g - b) / delta + if g < b { 6. } else { 0. }
+    } else if max == g {
+        (b - r) / delta + 2.
+    } else {
+        (r - g) / delta + 4.
+    };
+
+    // DEC hues are rotated, starting at blue instead of red.
+    let hue = (hue * 60. + 120.).round() as u16 % 360;
+
+    format!("1;{hue};{};{}", percent(lightness), percent(saturation))
+}
+
+/// SGR parameters reproducing the rendition of a cell, starting with a reset.
+fn sgr_parameters(cell: &Cell) -> String {
+    let mut params = vec![String::from("0")];
+
+    let flags = [
+        (Flags::BOLD, "1"),
+        (Flags::DIM, "2"),
+        (Flags::ITALIC, "3"),
+        (Flags::UNDERLINE, "4"),
+        (Flags::DOUBLE_UNDERLINE, "4:2"),
+        (Flags::UNDERCURL, "4:3"),
+        (Flags::DOTTED_UNDERLINE, "4:4"),
+        (Flags::DASHED_UNDERLINE, "4:5"),
+        (Flags::BLINK, "5"),
+        (Flags::INVERSE, "7"),
+        (Flags::HIDDEN, "8"),
+        (Flags::STRIKEOUT, "9"),
+        (Flags::OVERLINE, "53"),
+    ];
+    for (flag, param) in flags {
+        if cell.flags.contains(flag) {
+            params.push(param.into());
+        }
+    }
+
+    params.extend(sgr_color(cell.fg, 30));
+    params.extend(sgr_color(cell.bg, 40));
+    match cell.underline_color() {
+        Some(Color::Indexed(index)) => params.push(format!("58;5;{index}")),
+        Some(Color::Spec(Rgb { r, g, b })) => params.push(format!("58;2;{r};{g};{b}")),
+        _ => (),
+    }
+
+    params.join(";")
+}
+
+/// SGR parameters selecting a foreground or background color.
+///
+/// The `base` is the parameter of the first named color, like `30` for the foreground. Default
+/// colors have no parameters, since they are restored by resetting the rendition.
+fn sgr_color(color: Color, base: u16) -> Option<String> {
+    match color {
+        Color::Named(named) if (named as u16) < 8 => Some((base + named as u16).to_string()),
+        Color::Named(named) if (named as u16) < 16 => {
+            Some((base + 60 + named as u16 - 8).to_string())
+        },
+        Color::Named(_) => None,
+        Color::Indexed(index) => Some(format!("{};5;{index}", base + 8)),
+        Color::Spec(Rgb { r, g, b }) => Some(format!("{};2;{r};{g};{b}", base + 8)),
+    }
+}
+
+impl<T: EventListener> Term<T> {
+    /// Split a placement into cells, starting at the cursor position.
+    ///
+    /// With `scroll` enabled, the terminal is scrolled whenever the placement reaches the bottom
+    /// of the scrolling region and the cursor is left on its last line. Otherwise the placement
+    /// is clipped at the bottom of the screen and the cursor does not move.
+    fn place_graphic(&mut self, placement: Arc<Placement>, scroll: bool, erase_text: bool) {
+        let start = self.grid.cursor.point;
+        self.placements.insert(&placement, self.grid.anchor(start.line), start.column);
+
+        let end = cmp::min(start.column.0 + placement.columns, self.columns());
+        let lines = cmp::min(placement.lines, self.grid.total_lines());
+
+        for index in 0..lines {
+            let line = if scroll {
+                if index > 0 {
+                    self.linefeed();
+                }
+                self.grid.cursor.point.line
+            } else if start.line.0 as usize + index < self.screen_lines() {
+                start.line + index
+            } else {
+                break;
+            };
+
+            let bg = self.grid.cursor.template.bg;
+            let cells = &mut self.grid[line][start.column..Column(end)];
+            for (column, cell) in cells.iter_mut().enumerate() {
+                if erase_text {
+                    *cell = Cell { bg, ..Cell::default() };
+                }
+
+                let placement = placement.clone();
+                cell.set_graphic(Some(GraphicCell { placement, column, line: index }));
+            }
+        }
+
+        self.mark_fully_damaged();
+    }
+
+    /// Display a kitty graphics protocol image at the cursor position.
+    fn kitty_place(
+        &mut self,
+        command: &kitty::Command,
+        image_id: u32,
+        graphic: Arc<Graphic>,
+    ) -> Result<(), kitty::Error> {
+        let cell_size =
+            self.graphics.cell_size().ok_or(kitty::Error::new("EINVAL", "cell size is unknown"))?;
+        let placement = kitty::placement(command, image_id, graphic, cell_size);
+
+        // Placements are replaced when their ID is reused.
+        if command.placement_id != 0 {
+            self.delete_placements(true, |_, _, placement| {
+                placement.image_id == image_id && placement.placement_id == command.placement_id
+            });
+        }
+
+        let start = self.grid.cursor.point.column;
+        let columns = placement.columns;
+        self.place_graphic(Arc::new(placement), command.move_cursor, false);
+
+        // Move the cursor to the right of the image, on its last line.
+        if command.move_cursor {
+            let column = start.0 + columns;
+            if column < self.columns() {
+                self.grid.cursor.point.column = Column(column);
+                self.grid.cursor.input_needs_wrap = false;
+            } else {
+                self.grid.cursor.point.column = self.last_column();
+                self.grid.cursor.input_needs_wrap = true;
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Delete kitty graphics protocol placements.
+    ///
+    /// Uppercase selectors also free the image data of the deleted placements.
+    fn kitty_delete(&mut self, command: &kitty::Command) {
+        let cursor = self.grid.cursor.point;
+        let column = Column(command.source_x.saturating_sub(1));
+        let line = Line(command.source_y.saturating_sub(1) as i32);
+
+        let image_id = match command.delete.to_ascii_lowercase() {
+            b'n' => self.graphics.kitty.image(command).map_or(0, |(id, _)| id),
+            _ => command.image_id,
+        };
+        let placement_id = command.placement_id;
+        let (first_id, last_id) = (command.source_x as u32, command.source_y as u32);
+
+        let selector = command.delete;
+        let matches =
+            |lines: Range<Line>, columns: Range<Column>, placement: &Placement| match selector
+                .to_ascii_lowercase()
+            {
+                b'a' => true,
+                b'i' | b'n' => {
+                    placement.image_id == image_id
+                        && (placement_id == 0 || placement.placement_id == placement_id)
+                },
+                b'r' => (first_id..=last_id).contains(&placement.image_id),
+                b'c' => lines.contains(&cursor.line) && columns.contains(&cursor.column),
+                b'p' => lines.contains(&line) && columns.contains(&column),
+                b'x' => columns.contains(&column),
+                b'y' => lines.contains(&line),
+                b'z' => placement.z_index == command.z_index,
+                _ => false,
+            };
+
+        // Deleting by ID includes the scrollback history, everything else only the screen.
+        let history = matches!(selector.to_ascii_lowercase(), b'i' | b'n' | b'r');
+        let deleted = self.delete_placements(history, matches);
+
+        if selector.is_ascii_uppercase() {
+            for image_id in deleted {
+                self.graphics.kitty.remove_image(image_id);
+            }
+
+            if matches!(selector, b'I' | b'N') {
+                self.graphics.kitty.remove_image(image_id);
+            }
+        }
+    }
+
+    /// Remove all placements which have at least one cell matching the predicate.
+    ///
+    /// The predicate receives the lines and columns covered by a placement. Placements without
+    /// any cells on the screen are only checked when `history` is enabled.
+    ///
+    /// Returns the image IDs of the removed placements.
+    fn delete_placements<F>(&mut self, history: bool, mut predicate: F) -> Vec<u32>
+    where
+        F: FnMut(Range<Line>, Range<Column>, &Placement) -> bool,
+    {
+        let screen_end = Line(self.screen_lines() as i32);
+        let last_column = Column(self.columns());
+
+        // Find matching placements.
+        let deleted: Vec<_> = self
+            .placements
+            .iter()
+            .filter_map(|(placement, anchor, column)| {
+                let start = self.grid.anchor_line(anchor)?;
+                let lines = start..cmp::min(start + placement.lines, screen_end);
+                let columns = column..cmp::min(column + placement.columns, last_column);
+
+                let matches = (history || lines.end > 0)
+                    && predicate(lines.clone(), columns.clone(), &placement);
+                matches.then_some((placement, lines, columns))
+            })
+            .collect();
+
+        if deleted.is_empty() {
+            return Vec::new();
+        }
+
+        // Remove all cells of the placements, including the ones outside of the screen.
+        for (placement, lines, columns) in &deleted {
+            self.placements.remove(placement);
+
+            for line in (lines.start.0..lines.end.0).map(Line) {
+                for cell in &mut self.grid[line][columns.clone()] {
+                    let graphic = cell.graphic();
+                    if graphic.is_some_and(|graphic| Arc::ptr_eq(&graphic.placement, placement)) {
+                        cell.set_graphic(None);
+                    }
+                }
+            }
+        }
+
+        self.mark_fully_damaged();
+
+        deleted.iter().map(|(placement, ..)| placement.image_id).collect()
+    }
+}
+
+/// Find all placements in a grid, replacing the previously indexed ones.
+fn index_placements(grid: &Grid<Cell>, placements: &mut PlacementIndex) {
+    if placements.is_empty() {
+        return;
+    }
+    placements.clear();
+
+    let is_top_left = |cell: &Cell| {
+        cell.graphic().is_some_and(|graphic| graphic.column == 0 && graphic.line == 0)
+    };
+
+    // Rows with graphics are never moved to disk, so they are found without unpacking any rows.
+    for line in (grid.topmost_line().0..=grid.bottommost_line().0).map(Line) {
+        if !grid.row_contains(line, is_top_left) {
+            continue;
+        }
+
+        let row = grid.read_row(line);
+        for (column, cell) in row[..].iter().enumerate() {
+            if let Some(graphic) = cell.graphic().filter(|_| is_top_left(cell)) {
+                placements.insert(&graphic.placement, grid.anchor(line), Column(column));
+            }
+        }
+    }
+}
+
+/// Check if a character could join the grapheme cluster ending in `last`.
+///
+/// This avoids segmenting the cluster for characters which can never join it.
+fn may_join_grapheme(last: char, c: char) -> bool {
+    match c.width() {
+        // Combining marks, joiners and variation selectors.
+        Some(0) => true,
+        // Spacing marks, prepended characters and regional indicators are never ASCII.
+        Some(1) => !last.is_ascii() || !c.is_ascii(),
+        // Emoji modifiers, emoji following a joiner and Hangul syllables following leading jamo.
+        _ => {
+            matches!(c, '\u{1f3fb}'..='\u{1f3ff}')
+                || matches!(last, '\u{200d}' | '\u{1100}'..='\u{115f}' | '\u{a960}'..='\u{a97f}')
+        },
+    }
+}
+
 /// The state of the [`Mode`] and [`PrivateMode`].
 #[repr(u8)]
 #[derive(Debug, Clone, Copy)]
@@ -2281,6 +3886,10 @@ enum ModeState {
     Set = 1,
     /// The mode is currently not set.
     Reset = 2,
+    /// The mode is always set.
+    PermanentlySet = 3,
+    /// The mode can never be set.
+    PermanentlyReset = 4,
 }
 
 impl From<bool> for ModeState {
@@ -2316,6 +3925,7 @@ pub enum ClipboardType {
     Selection,
 }
 
+#[derive(Clone)]
 struct TabStops {
     tabs: Vec<bool>,
 }
@@ -2508,14 +4118,18 @@ pub mod test {
 mod tests {
     use super::*;
 
+    use std::cell::RefCell;
     use std::mem;
+    use std::rc::Rc;
+    use std::time::{Duration, UNIX_EPOCH};
 
     use crate::event::VoidListener;
     use crate::grid::{Grid, Scroll};
     use crate::index::{Column, Point, Side};
+    use crate::parser::Processor;
     use crate::selection::{Selection, SelectionType};
     use crate::term::cell::{Cell, Flags};
-    use crate::term::test::TermSize;
+    use crate::term::test::{TermSize, mock_term};
     use crate::vte::ansi::{self, CharsetIndex, Handler, StandardCharset};
 
     #[test]
@@ -2732,6 +4346,41 @@ mod tests {
         assert_eq!(term.selection_to_string(), Some(String::from("\na\"\na\"\na")));
     }
 
+    #[test]
+    fn selection_timestamps() {
+        let size = TermSize::new(5, 3);
+        let mut term = Term::new(Config::default(), &size, VoidListener);
+
+        for c in "abcdefg".chars() {
+            term.input(c);
+        }
+        term.carriage_return();
+        term.linefeed();
+        term.input('h');
+
+        // Input stamps every line it writes to.
+        assert!(term.grid.timestamp(Line(0)).is_some());
+        assert!(term.grid.timestamp(Line(1)).is_some());
+        assert!(term.grid.timestamp(Line(2)).is_some());
+
+        let time = UNIX_EPOCH + Duration::from_secs(100);
+        term.grid[Line(0)].set_timestamp(Some(time));
+        term.grid[Line(2)].set_timestamp(None);
+
+        term.selection =
+            Some(Selection::new(SelectionType::Simple, Point::new(Line(0), Column(0)), Side::Left));
+        if let Some(s) = term.selection.as_mut() {
+            s.update(Point::new(Line(2), Column(4)), Side::Right);
+        }
+
+        // Wrapped rows and rows without timestamp are not prefixed.
+        let format =
+            |time: SystemTime| format!("{} ", time.duration_since(UNIX_EPOCH).unwrap().as_secs());
+        let text = term.selection_to_string_with_timestamps(format);
+        assert_eq!(text, Some(String::from("100 abcdefg\nh")));
+        assert_eq!(term.selection_to_string(), Some(String::from("abcdefg\nh")));
+    }
+
     /// Check that the grid can be serialized back and forth losslessly.
     ///
     /// This test is in the term module as opposed to the grid since we want to
@@ -2746,6 +4395,49 @@ mod tests {
         assert_eq!(deserialized, grid);
     }
 
+    #[test]
+    fn scrollback_ansi_roundtrip() {
+        let size = TermSize::new(8, 4);
+        let mut term = Term::new(Config::default(), &size, VoidListener);
+        let mut parser: Processor = Processor::new();
+        parser.advance(&mut term, b"\x1b[1;31mred\x1b[0m \x1b[4:3;48;5;42mcurl\x1b[0m\r\n");
+        parser.advance(&mut term, b"\x1b]8;id=1;https://example.org\x1b\\link\x1b]8;;\x1b\\\r\n");
+        parser.advance(&mut term, "wrapped \u{4f60}\u{597d} text\r\n\ta".as_bytes());
+
+        assert_eq!(
+            term.scrollback_to_string(),
+            "red curl\nlink\nwrapped \u{4f60}\u{597d} text\n\ta\n"
+        );
+
+        let ansi = term.scrollback_to_ansi();
+        let mut restored = Term::new(Config::default(), &size, VoidListener);
+        parser.advance(&mut restored, ansi.as_bytes());
+
+        // The restored terminal continues on a new line below the content.
+        assert_eq!(restored.history_size(), term.history_size() + 1);
+        for line in (term.topmost_line().0..term.screen_lines() as i32).map(Line::from) {
+            let column = term.grid[line].line_length();
+            assert_eq!(restored.grid[line - 1i32][..column], term.grid[line][..column]);
+        }
+    }
+
+    #[test]
+    #[cfg(feature = "serde")]
+    fn restore_serialized_grid() {
+        let mut term = Term::new(Config::default(), &TermSize::new(10, 3), VoidListener);
+        let mut parser: Processor = Processor::new();
+        parser.advance(&mut term, b"abcdefgh\r\nx");
+        let serialized = serde_json::to_string(&term.grid).unwrap();
+
+        let mut restored = Term::new(Config::default(), &TermSize::new(5, 3), VoidListener);
+        restored.restore_grid(serde_json::from_str(&serialized).unwrap());
+
+        // Lines are reflowed and the cursor is placed below the content.
+        assert_eq!(restored.scrollback_to_string(), "abcdefgh\nx\n");
+        assert_eq!(restored.grid[Line(-1)][Column(4)].flags, Flags::WRAPLINE);
+        assert_eq!(restored.grid.cursor.point, Point::new(Line(2), Column(0)));
+    }
+
     #[test]
     fn input_line_drawing_character() {
         let size = TermSize::new(7, 17);
@@ -3291,6 +4983,629 @@ mod tests {
         assert_eq!(term.title, None);
     }
 
+    #[test]
+    fn semantic_prompt_marks() {
+        let mut size = TermSize::new(10, 5);
+        let mut term = Term::new(Config::default(), &size, VoidListener);
+        let mut parser: Processor = Processor::new();
+
+        // Run two commands, moving the first prompt into the scrollback history.
+        parser.advance(&mut term, b"\x1b]133;A\x07$ \x1b]133;B\x07ls\r\n\x1b]133;C\x07a\r\nb\r\n");
+        parser.advance(&mut term, b"\x1b]133;D;0\x07\x1b]133;A\x07$ \x1b]133;B\x07pwd\r\n");
+        parser.advance(&mut term, b"\x1b]133;C\x07/tmp\r\n\x1b]133;D;0\x07\x1b]133;A\x07$ ");
+
+        assert_eq!(term.history_size(), 1);
+        assert_eq!(term.prompt_line(Line(4), Direction::Left), Some(Line(2)));
+        assert_eq!(term.prompt_line(Line(2), Direction::Left), Some(Line(-1)));
+        assert_eq!(term.prompt_line(Line(-1), Direction::Left), None);
+        assert_eq!(term.prompt_line(Line(-1), Direction::Right), Some(Line(2)));
+
+        let (start, end) = term.last_command_output().unwrap();
+        assert_eq!(term.bounds_to_string(start, end), "/tmp");
+
+        // Marks are kept when the lines are reflowed.
+        size.columns = 3;
+        term.resize(size);
+
+        assert_eq!(term.prompt_line(Line(4), Direction::Left), Some(Line(0)));
+        assert_eq!(term.prompt_line(Line(0), Direction::Left), Some(Line(-4)));
+        let (start, end) = term.last_command_output().unwrap();
+        assert_eq!(term.bounds_to_string(start, end), "/tmp");
+
+        // Commands without output have no output bounds.
+        parser.advance(&mut term, b"true\r\n\x1b]133;C\x07\x1b]133;D;0\x07\x1b]133;A\x07$ ");
+        assert_eq!(term.last_command_output(), None);
+    }
+
+    #[test]
+    fn vi_marks() {
+        let mut size = TermSize::new(10, 3);
+        let mut term = Term::new(Config::default(), &size, VoidListener);
+        let mut parser: Processor = Processor::new();
+        parser.advance(&mut term, b"one\r\ntwo\r\n");
+
+        term.set_vi_mark('a', Line(0));
+        term.set_vi_mark('b', Line(1));
+        term.set_vi_mark('B', Line(1));
+        assert_eq!(term.vi_marks(), [('a', Line(0)), ('b', Line(1))]);
+
+        // Setting a mark again moves it.
+        term.set_vi_mark('b', Line(0));
+        assert_eq!(term.vi_marks(), [('a', Line(0)), ('b', Line(0))]);
+        assert_eq!(term.grid[Line(0)].vi_marks().iter().collect::<String>(), "ab");
+
+        // Marks follow their line into the scrollback history.
+        term.set_vi_mark('c', Line(1));
+        parser.advance(&mut term, b"three\r\nfour\r\n");
+        assert_eq!(term.vi_mark_line('a'), Some(Line(-2)));
+        assert_eq!(term.vi_mark_line('c'), Some(Line(-1)));
+        assert_eq!(term.vi_mark_line('d'), None);
+
+        // Marks stay on the first row of reflowed lines.
+        size.columns = 2;
+        term.resize(size);
+        assert_eq!(term.vi_mark_line('a'), Some(Line(-7)));
+        assert_eq!(term.vi_mark_line('c'), Some(Line(-5)));
+
+        // Marks move with lines scrolled within the screen, until they leave it.
+        term.set_vi_mark('e', Line(1));
+        term.set_vi_mark('f', Line(2));
+        parser.advance(&mut term, b"\x1b[H\x1b[L");
+        assert_eq!(term.vi_mark_line('e'), Some(Line(2)));
+        assert_eq!(term.vi_mark_line('f'), None);
+
+        // Marks move with lines pulled from the history.
+        term.resize(TermSize::new(2, 5));
+        assert_eq!(term.vi_mark_line('a'), Some(Line(-5)));
+        assert_eq!(term.vi_mark_line('e'), Some(Line(4)));
+
+        // Marks are removed with the history.
+        term.grid.clear_history();
+        assert_eq!(term.vi_marks(), [('e', Line(4))]);
+    }
+
+    #[test]
+    fn sixel_graphic_placement() {
+        let size = TermSize::new(5, 4);
+        let mut term = Term::new(graphics_config(), &size, VoidListener);
+        let mut parser: Processor = Processor::new();
+
+        // Graphics are ignored until the cell size is known.
+        let sixel = b"\x1bPq\"1;1;3;12#1~~~-~~~\x1b\\";
+        parser.advance(&mut term, b"x\r\n ");
+        parser.advance(&mut term, sixel);
+        assert_eq!(term.grid.cursor.point, Point::new(Line(1), Column(1)));
+
+        // The graphic covers 2x4 cells and scrolls the terminal.
+        term.set_cell_size(2, 3);
+        parser.advance(&mut term, sixel);
+        assert_eq!(term.grid.cursor.point, Point::new(Line(3), Column(1)));
+        assert_eq!(term.grid[Line(-2)][Column(0)].c, 'x');
+
+        let graphic = |term: &Term<VoidListener>, line, column| {
+            let cell = term.grid[Line(line)][Column(column)].graphic()?;
+            Some((cell.column, cell.line))
+        };
+        assert_eq!(graphic(&term, -1, 0), None);
+        assert_eq!(graphic(&term, -1, 1), Some((0, 0)));
+        assert_eq!(graphic(&term, 2, 2), Some((1, 3)));
+        assert_eq!(graphic(&term, 2, 3), None);
+
+        // Graphics are cleared with the text.
+        parser.advance(&mut term, b"\x1b[1;1H\x1b[2K");
+        assert_eq!(graphic(&term, 0, 1), None);
+        assert_eq!(graphic(&term, 1, 1), Some((0, 2)));
+    }
+
+    #[test]
+    fn sixel_graphics_not_displayed() {
+        let size = TermSize::new(5, 4);
+        let writes = PtyWrites::default();
+        let mut term = Term::new(Config::default(), &size, writes.clone());
+        let mut parser: Processor = Processor::new();
+        term.set_cell_size(2, 3);
+
+        // Text below the graphic is kept and sixel support isn't reported.
+        parser.advance(&mut term, b"xyz\r\x1bPq\"1;1;3;12#1~~~-~~~\x1b\\\x1b[c");
+        assert_eq!(term.grid.cursor.point, Point::new(Line(0), Column(0)));
+        assert_eq!(term.grid[Line(0)][Column(1)].c, 'y');
+        assert!(term.grid[Line(0)][Column(1)].graphic().is_none());
+        assert_eq!(writes.take(), ["\x1b[?6c"]);
+
+        term.set_options(graphics_config());
+        parser.advance(&mut term, b"\x1b[c");
+        assert_eq!(writes.take(), ["\x1b[?62;4c"]);
+    }
+
+    /// Listener collecting all text written to the PTY.
+    #[derive(Default, Clone)]
+    struct PtyWrites(Rc<RefCell<Vec<String>>>);
+
+    impl PtyWrites {
+        fn take(&self) -> Vec<String> {
+            mem::take(&mut self.0.borrow_mut())
+        }
+    }
+
+    impl EventListener for PtyWrites {
+        fn send_event(&self, event: Event) {
+            match event {
+                Event::PtyWrite(text) => self.0.borrow_mut().push(text),
+                // Answer color requests with a color derived from their index.
+                Event::ColorRequest(index, format) => {
+                    let color = Rgb { r: index as u8, g: 0, b: 255 };
+                    self.0.borrow_mut().push(format(color));
+                },
+                _ => (),
+            }
+        }
+    }
+
+    /// Red 4x2 RGBA image.
+    const KITTY_IMAGE: &str = "f=32,s=4,v=2;/wAA//8AAP//AAD//wAA//8AAP//AAD//wAA//8AAP8=";
+
+    fn graphics_config() -> Config {
+        Config { graphics: true, ..Default::default() }
+    }
+
+    #[test]
+    fn kitty_graphics_display() {
+        let size = TermSize::new(5, 4);
+        let writes = PtyWrites::default();
+        let mut term = Term::new(graphics_config(), &size, writes.clone());
+        let mut parser: Processor = Processor::new();
+        term.set_cell_size(2, 2);
+
+        // Transmit and display, moving the cursor behind the image.
+        parser.advance(&mut term, format!("\x1b_Ga=T,i=1,{KITTY_IMAGE}\x1b\\").as_bytes());
+        assert_eq!(writes.take(), ["\x1b_Gi=1;OK\x1b\\"]);
+        assert_eq!(term.grid.cursor.point, Point::new(Line(0), Column(2)));
+        assert_eq!(term.graphics().kitty.len(), 1);
+
+        let placements: Vec<_> = term.placements().collect();
+        assert_eq!(placements.len(), 1);
+        let (point, placement) = &placements[0];
+        assert_eq!(*point, Point::new(Line(0), Column(0)));
+        assert_eq!((placement.image_id, placement.columns, placement.lines), (1, 2, 1));
+        assert_eq!(term.grid[Line(0)][Column(1)].graphic().map(|cell| cell.column), Some(1));
+        assert_eq!(term.grid[Line(0)][Column(2)].graphic(), None);
+
+        // Display a scaled placement again without moving the cursor.
+        parser.advance(&mut term, b"\x1b[2;2H\x1b_Ga=p,i=1,p=7,c=3,r=2,C=1,q=1\x1b\\x");
+        assert_eq!(writes.take(), Vec::<String>::new());
+        assert_eq!(term.grid.cursor.point, Point::new(Line(1), Column(2)));
+        assert_eq!(term.grid[Line(1)][Column(1)].c, 'x');
+
+        let placement = &term.grid[Line(2)][Column(3)].graphic().unwrap().placement;
+        assert_eq!((placement.placement_id, placement.width, placement.height), (7, 6, 4));
+
+        // Reusing the placement ID replaces the placement.
+        parser.advance(&mut term, b"\x1b[4;1H\x1b_Ga=p,i=1,p=7,C=1\x1b\\");
+        assert_eq!(writes.take(), ["\x1b_Gi=1,p=7;OK\x1b\\"]);
+        assert_eq!(term.placements().count(), 2);
+        assert_eq!(term.grid[Line(2)][Column(3)].graphic(), None);
+        assert!(term.grid[Line(3)][Column(1)].graphic().is_some());
+
+        // Missing images are reported.
+        parser.advance(&mut term, b"\x1b_Ga=p,i=2\x1b\\");
+        assert_eq!(writes.take(), ["\x1b_Gi=2;ENOENT:image not found\x1b\\"]);
+    }
+
+    #[test]
+    fn kitty_graphics_chunked_transmission() {
+        let size = TermSize::new(5, 4);
+        let writes = PtyWrites::default();
+        let mut term = Term::new(graphics_config(), &size, writes.clone());
+        let mut parser: Processor = Processor::new();
+
+        let (first, second) = KITTY_IMAGE.split_at(KITTY_IMAGE.len() - 16);
+        parser.advance(&mut term, format!("\x1b_Ga=t,I=3,m=1,{first}\x1b\\").as_bytes());
+        assert!(term.graphics().kitty.is_empty());
+        parser.advance(&mut term, format!("\x1b_Gm=0;{second}\x1b\\").as_bytes());
+        assert_eq!(writes.take(), ["\x1b_Gi=1,I=3;OK\x1b\\"]);
+        assert_eq!(term.graphics().kitty.len(), 1);
+
+        // Displaying requires the cell size.
+        parser.advance(&mut term, b"\x1b_Ga=p,I=3\x1b\\");
+        assert_eq!(writes.take(), ["\x1b_Gi=1,I=3;EINVAL:cell size is unknown\x1b\\"]);
+        assert_eq!(term.placements().count(), 0);
+    }
+
+    #[test]
+    fn kitty_graphics_query() {
+        let size = TermSize::new(5, 4);
+        let writes = PtyWrites::default();
+        let mut term = Term::new(graphics_config(), &size, writes.clone());
+        let mut parser: Processor = Processor::new();
+
+        parser.advance(&mut term, format!("\x1b_Ga=q,i=31,{KITTY_IMAGE}\x1b\\").as_bytes());
+        parser.advance(&mut term, b"\x1b_Ga=q,i=32,f=32,s=4,v=2;AAAA\x1b\\");
+        parser.advance(&mut term, b"\x1b_Ga=q,i=33,f=32,s=4,v=2,q=2;AAAA\x1b\\");
+        assert_eq!(writes.take(), [
+            "\x1b_Gi=31;OK\x1b\\",
+            "\x1b_Gi=32;ENODATA:insufficient image data\x1b\\",
+        ]);
+
+        // Queries never store images.
+        assert!(term.graphics().kitty.is_empty());
+    }
+
+    #[test]
+    fn kitty_graphics_delete() {
+        let size = TermSize::new(5, 4);
+        let mut term = Term::new(graphics_config(), &size, VoidListener);
+        let mut parser: Processor = Processor::new();
+        term.set_cell_size(2, 2);
+
+        parser.advance(&mut term, format!("\x1b_Ga=t,i=1,{KITTY_IMAGE}\x1b\\").as_bytes());
+        parser.advance(&mut term, format!("\x1b_Ga=t,i=2,{KITTY_IMAGE}\x1b\\").as_bytes());
+        parser.advance(&mut term, b"\x1b_Ga=p,i=1\x1b\\\r\n\x1b_Ga=p,i=2\x1b\\");
+        parser.advance(&mut term, b"\r\n\x1b_Ga=p,i=1,z=-1\x1b\\");
+        assert_eq!(term.placements().count(), 3);
+
+        // Delete by position, keeping the image data.
+        parser.advance(&mut term, b"\x1b_Ga=d,d=p,x=2,y=2\x1b\\");
+        assert_eq!(term.placements().count(), 2);
+        assert_eq!(term.graphics().kitty.len(), 2);
+
+        // Delete by z-index.
+        parser.advance(&mut term, b"\x1b_Ga=d,d=z,z=-1\x1b\\");
+        assert_eq!(term.placements().count(), 1);
+
+        // Delete by ID, freeing the image data.
+        parser.advance(&mut term, b"\x1b_Ga=d,d=I,i=1\x1b\\");
+        assert_eq!(term.placements().count(), 0);
+        assert_eq!(term.graphics().kitty.len(), 1);
+
+        // Delete all placements and free all images.
+        parser.advance(&mut term, b"\x1b_Ga=p,i=2\x1b\\\x1b_Ga=d,d=A\x1b\\");
+        assert_eq!(term.placements().count(), 0);
+        assert!(term.graphics().kitty.is_empty());
+    }
+
+    #[test]
+    fn kitty_graphics_scroll() {
+        let size = TermSize::new(5, 4);
+        let mut term = Term::new(graphics_config(), &size, VoidListener);
+        let mut parser: Processor = Processor::new();
+        term.set_cell_size(2, 2);
+
+        parser.advance(&mut term, format!("\x1b_Ga=t,i=1,{KITTY_IMAGE}\x1b\\").as_bytes());
+        parser.advance(&mut term, b"\x1b[2;1H\x1b_Ga=p,i=1\x1b\\\x1b[3;1H\x1b_Ga=p,i=1\x1b\\");
+
+        // Inserting a line moves placements below the cursor.
+        parser.advance(&mut term, b"\x1b[3H\x1b[L");
+        let points: Vec<_> = term.placements().map(|(point, _)| point).collect();
+        assert_eq!(points, [Point::new(Line(1), Column(0)), Point::new(Line(3), Column(0))]);
+
+        // Placements follow lines into the history.
+        parser.advance(&mut term, b"\x1b[4H\n\n");
+        let points: Vec<_> = term.placements().map(|(point, _)| point).collect();
+        assert_eq!(points, [Point::new(Line(-1), Column(0)), Point::new(Line(1), Column(0))]);
+
+        // Placements in the history are only deleted with history selectors.
+        parser.advance(&mut term, b"\x1b_Ga=d,d=y,y=2\x1b\\\x1b_Ga=d,d=x,x=1\x1b\\");
+        let points: Vec<_> = term.placements().map(|(point, _)| point).collect();
+        assert_eq!(points, [Point::new(Line(-1), Column(0))]);
+        parser.advance(&mut term, b"\x1b_Ga=d,d=a\x1b\\");
+        assert_eq!(term.placements().count(), 1);
+        parser.advance(&mut term, b"\x1b_Ga=d,d=i,i=1\x1b\\");
+        assert_eq!(term.placements().count(), 0);
+    }
+
+    #[test]
+    fn kitty_graphics_not_displayed() {
+        let size = TermSize::new(5, 4);
+        let writes = PtyWrites::default();
+        let mut term = Term::new(Config::default(), &size, writes.clone());
+        let mut parser: Processor = Processor::new();
+        term.set_cell_size(2, 2);
+
+        // Images are stored, but never reported as displayed.
+        parser.advance(&mut term, format!("\x1b_Ga=t,i=1,{KITTY_IMAGE}\x1b\\").as_bytes());
+        parser.advance(&mut term, format!("\x1b_Ga=q,i=2,{KITTY_IMAGE}\x1b\\").as_bytes());
+        parser.advance(&mut term, b"\x1b_Ga=p,i=1\x1b\\");
+        assert_eq!(writes.take(), [
+            "\x1b_Gi=1;OK\x1b\\",
+            "\x1b_Gi=2;ENOTSUP:graphics are not displayed\x1b\\",
+            "\x1b_Gi=1;ENOTSUP:graphics are not displayed\x1b\\",
+        ]);
+        assert_eq!(term.graphics().kitty.len(), 1);
+        assert_eq!(term.placements().count(), 0);
+    }
+
+    #[test]
+    fn blinking_text() {
+        let size = TermSize::new(5, 2);
+        let mut term = Term::new(Config::default(), &size, VoidListener);
+        let mut parser: Processor = Processor::new();
+
+        parser.advance(&mut term, b"\x1b[5ma\x1b[6mb\x1b[25mc");
+        assert!(term.grid[Line(0)][Column(0)].flags.contains(Flags::BLINK));
+        assert!(term.grid[Line(0)][Column(1)].flags.contains(Flags::BLINK));
+        assert!(!term.grid[Line(0)][Column(2)].flags.contains(Flags::BLINK));
+
+        // DECRARA toggles blinking within the rectangle.
+        parser.advance(&mut term, b"\x1b[1;2;1;3;5$t");
+        assert!(!term.grid[Line(0)][Column(1)].flags.contains(Flags::BLINK));
+        assert!(term.grid[Line(0)][Column(2)].flags.contains(Flags::BLINK));
+    }
+
+    #[test]
+    fn rectangle_attributes() {
+        let size = TermSize::new(5, 2);
+        let mut term = Term::new(Config::default(), &size, VoidListener);
+        let mut parser: Processor = Processor::new();
+
+        parser.advance(&mut term, b"\x1b[1;4mab");
+        let flags = Flags::BOLD | Flags::UNDERLINE;
+        assert!(term.grid[Line(0)][Column(0)].flags.contains(flags));
+
+        // Unsupported attributes don't reset the area.
+        parser.advance(&mut term, b"\x1b[1;1;1;2;3$r\x1b[1;1;1;2;3;6$r");
+        assert!(term.grid[Line(0)][Column(0)].flags.contains(flags));
+
+        // Missing attributes do.
+        parser.advance(&mut term, b"\x1b[1;1;1;1$r");
+        assert!(!term.grid[Line(0)][Column(0)].flags.intersects(flags));
+        assert!(term.grid[Line(0)][Column(1)].flags.contains(flags));
+    }
+
+    #[test]
+    fn overlined_text() {
+        let size = TermSize::new(5, 2);
+        let mut term = Term::new(Config::default(), &size, VoidListener);
+        let mut parser: Processor = Processor::new();
+
+        parser.advance(&mut term, b"\x1b[53ma\x1b[55mb\x1b[53mc\x1b[0md");
+        assert!(term.grid[Line(0)][Column(0)].flags.contains(Flags::OVERLINE));
+        assert!(!term.grid[Line(0)][Column(1)].flags.contains(Flags::OVERLINE));
+        assert!(term.grid[Line(0)][Column(2)].flags.contains(Flags::OVERLINE));
+        assert!(!term.grid[Line(0)][Column(3)].flags.contains(Flags::OVERLINE));
+    }
+
+    #[test]
+    fn termcap_request() {
+        let size = TermSize::new(5, 2);
+        let writes = PtyWrites::default();
+        let mut term = Term::new(Config::default(), &size, writes.clone());
+        let mut parser: Processor = Processor::new();
+
+        // Names are hex encoded, with one response for each requested capability.
+        parser.advance(&mut term, b"\x1bP+q544E;436F;5463\x1b\\");
+        assert_eq!(writes.take(), [
+            "\x1bP1+r544E=616C61637269747479\x1b\\",
+            "\x1bP1+r436F=323536\x1b\\",
+            "\x1bP1+r5463\x1b\\",
+        ]);
+
+        // String capabilities are reported with their escapes resolved.
+        parser.advance(&mut term, b"\x1bP+q626F6C64\x1b\\");
+        assert_eq!(writes.take(), ["\x1bP1+r626F6C64=1B5B316D\x1b\\"]);
+
+        // Unknown and malformed names are rejected.
+        parser.advance(&mut term, b"\x1bP+q78797A;7\x1b\\");
+        assert_eq!(writes.take(), ["\x1bP0+r78797A\x1b\\", "\x1bP0+r\x1b\\"]);
+    }
+
+    #[test]
+    fn status_request() {
+        let size = TermSize::new(10, 5);
+        let writes = PtyWrites::default();
+        let mut term = Term::new(Config::default(), &size, writes.clone());
+        let mut parser: Processor = Processor::new();
+
+        parser.advance(&mut term, b"\x1bP$qm\x1b\\");
+        assert_eq!(writes.take(), ["\x1bP1$r0m\x1b\\"]);
+
+        parser.advance(&mut term, b"\x1b[1;4:3;53;31;48;5;100;58;2;1;2;3m\x1bP$qm\x1b\\");
+        assert_eq!(writes.take(), ["\x1bP1$r0;1;4:3;53;31;48;5;100;58;2;1;2;3m\x1b\\"]);
+
+        parser.advance(&mut term, b"\x1b[2;4r\x1bP$qr\x1b\\");
+        assert_eq!(writes.take(), ["\x1bP1$r2;4r\x1b\\"]);
+
+        parser.advance(&mut term, b"\x1b[?69h\x1b[3;7s\x1bP$qs\x1b\\");
+        assert_eq!(writes.take(), ["\x1bP1$r3;7s\x1b\\"]);
+
+        parser.advance(&mut term, b"\x1b[6 q\x1bP$q q\x1b\\");
+        assert_eq!(writes.take(), ["\x1bP1$r6 q\x1b\\"]);
+
+        parser.advance(&mut term, b"\x1b[2*x\x1bP$q*x\x1b\\\x1bP$q\"p\x1b\\");
+        assert_eq!(writes.take(), ["\x1bP1$r2*x\x1b\\", "\x1bP1$r62;1\"p\x1b\\"]);
+
+        // Unsupported settings are rejected.
+        parser.advance(&mut term, b"\x1bP$qt\x1b\\");
+        assert_eq!(writes.take(), ["\x1bP0$r\x1b\\"]);
+    }
+
+    #[test]
+    fn soft_reset() {
+        let size = TermSize::new(10, 5);
+        let mut term = Term::new(Config::default(), &size, VoidListener);
+        let mut parser: Processor = Processor::new();
+
+        parser.advance(&mut term, b"\x1b[2;4r\x1b[?6h\x1b[4h\x1b[?25l\x1b[1;31m\x1b(0");
+        parser.advance(&mut term, b"\x1b7\x1b[2;3Ha\x1b[!p");
+
+        // Screen contents and cursor position are kept.
+        assert_eq!(term.grid[Line(2)][Column(2)].c, '▒');
+        assert_eq!(term.grid.cursor.point, Point::new(Line(2), Column(3)));
+
+        assert_eq!(term.scroll_region, Line(0)..Line(5));
+        assert!(!term.mode.intersects(TermMode::ORIGIN | TermMode::INSERT));
+        assert!(term.mode.contains(TermMode::SHOW_CURSOR));
+        assert_eq!(term.grid.cursor.template, Cell::default());
+        assert_eq!(term.grid.saved_cursor.point, Point::default());
+
+        parser.advance(&mut term, b"a");
+        assert_eq!(term.grid[Line(2)][Column(3)].c, 'a');
+    }
+
+    #[test]
+    fn report_modes() {
+        let size = TermSize::new(10, 5);
+        let writes = PtyWrites::default();
+        let mut term = Term::new(Config::default(), &size, writes.clone());
+        let mut parser: Processor = Processor::new();
+
+        parser.advance(&mut term, b"\x1b[?7$p\x1b[?3$p\x1b[?2$p\x1b[?1047$p\x1b[?9999$p");
+        parser.advance(&mut term, b"\x1b[4$p\x1b[12$p\x1b[2$p\x1b[99$p");
+        assert_eq!(writes.take(), [
+            "\x1b[?7;1$y",
+            "\x1b[?3;4$y",
+            "\x1b[?2;3$y",
+            "\x1b[?1047;4$y",
+            "\x1b[?9999;0$y",
+            "\x1b[4;2$y",
+            "\x1b[12;3$y",
+            "\x1b[2;4$y",
+            "\x1b[99;0$y",
+        ]);
+    }
+
+    #[test]
+    fn exclusive_mouse_modes() {
+        let size = TermSize::new(10, 5);
+        let writes = PtyWrites::default();
+        let mut term = Term::new(Config::default(), &size, writes.clone());
+        let mut parser: Processor = Processor::new();
+
+        // Encodings replace each other.
+        parser.advance(&mut term, b"\x1b[?1006h\x1b[?1016h");
+        assert!(term.mode.contains(TermMode::SGR_PIXEL_MOUSE));
+        assert!(!term.mode.contains(TermMode::SGR_MOUSE));
+        parser.advance(&mut term, b"\x1b[?1015h");
+        assert!(term.mode.contains(TermMode::URXVT_MOUSE));
+        assert!(!term.mode.contains(TermMode::SGR_PIXEL_MOUSE));
+
+        // X10 compatibility replaces other mouse reporting modes.
+        parser.advance(&mut term, b"\x1b[?1002h\x1b[?9h");
+        assert!(term.mode.contains(TermMode::X10_MOUSE));
+        assert!(!term.mode.contains(TermMode::MOUSE_DRAG));
+        assert!(term.mode.contains(TermMode::URXVT_MOUSE));
+
+        parser.advance(&mut term, b"\x1b[?9$p\x1b[?1015$p\x1b[?1016$p");
+        assert_eq!(writes.take(), ["\x1b[?9;1$y", "\x1b[?1015;1$y", "\x1b[?1016;2$y"]);
+    }
+
+    #[test]
+    fn rectangle_checksum() {
+        let size = TermSize::new(2, 2);
+        let writes = PtyWrites::default();
+        let mut term = Term::new(Config::default(), &size, writes.clone());
+        term.grid = mock_term("ab\r\ncd").grid;
+        let mut parser: Processor = Processor::new();
+
+        parser.advance(&mut term, b"\x1b[1;1*y\x1b[7;1;2;1;2;2*y");
+        assert_eq!(writes.take(), ["\x1bP1!~FE76\x1b\\", "\x1bP7!~FF39\x1b\\"]);
+
+        // Attributes are part of the checksum.
+        term.grid[Line(1)][Column(1)].flags.insert(Flags::BOLD | Flags::UNDERLINE);
+        parser.advance(&mut term, b"\x1b[7;1;2;1;2;2*y");
+        assert_eq!(writes.take(), ["\x1bP7!~FEA9\x1b\\"]);
+    }
+
+    #[test]
+    fn presentation_state_reports() {
+        let size = TermSize::new(20, 3);
+        let writes = PtyWrites::default();
+        let mut term = Term::new(Config::default(), &size, writes.clone());
+        let mut parser: Processor = Processor::new();
+
+        parser.advance(&mut term, b"\x1b[2;5H\x1b[1;7m\x1b[1\"q\x1b)0\x0e\x1b[1$w");
+        assert_eq!(writes.take(), ["\x1bP1$u2;5;1;I;A;@;1;2;@;B0BB\x1b\\"]);
+
+        parser.advance(&mut term, b"\x1b[3g\x1b[1;4H\x1bH\x1b[1;12H\x1bH\x1b[2$w");
+        assert_eq!(writes.take(), ["\x1bP2$u4/12\x1b\\"]);
+    }
+
+    #[test]
+    fn color_table_report() {
+        let size = TermSize::new(5, 1);
+        let writes = PtyWrites::default();
+        let mut term = Term::new(Config::default(), &size, writes.clone());
+        let mut parser: Processor = Processor::new();
+
+        parser.advance(&mut term, b"\x1b[2;2$u");
+        let report = writes.take();
+        assert_eq!(report.len(), 258);
+        assert_eq!(report[0], "\x1bP2$s");
+        assert_eq!(report[1], "0;2;0;0;100");
+        assert_eq!(report[256], "/255;2;100;0;100");
+        assert_eq!(report[257], "\x1b\\");
+
+        parser.advance(&mut term, b"\x1b[2$u");
+        let report = writes.take();
+        assert_eq!(report[1], "0;1;0;50;100");
+        assert_eq!(report[256], "/255;1;60;50;100");
+    }
+
+    #[test]
+    fn protected_cells() {
+        let size = TermSize::new(5, 2);
+        let writes = PtyWrites::default();
+        let mut term = Term::new(Config::default(), &size, writes.clone());
+        let mut parser: Processor = Processor::new();
+
+        // Protection survives SGR 0 and is reported through DECRQSS.
+        parser.advance(&mut term, b"a\x1b[1\"qb\x1b[0m\x1bP$q\"q\x1b\\\x1b[0\"qc");
+        parser.advance(&mut term, b"\r\n\x1bVd\x1bWe");
+        assert_eq!(writes.take(), ["\x1bP1$r1\"q\x1b\\"]);
+
+        // Selective erasure leaves protected cells alone.
+        parser.advance(&mut term, b"\x1b[1;1H\x1b[?K\x1b[2;1H\x1b[?1J\x1b[1;1;2;5${");
+        assert_eq!(term.grid[Line(0)][Column(0)].c, ' ');
+        assert_eq!(term.grid[Line(0)][Column(1)].c, 'b');
+        assert_eq!(term.grid[Line(0)][Column(2)].c, ' ');
+        assert_eq!(term.grid[Line(1)][Column(0)].c, 'd');
+        assert_eq!(term.grid[Line(1)][Column(1)].c, ' ');
+
+        // Regular erasure ignores protection.
+        parser.advance(&mut term, b"\x1b[2J");
+        assert_eq!(term.grid[Line(0)][Column(1)].c, ' ');
+        assert_eq!(term.grid[Line(1)][Column(0)].c, ' ');
+    }
+
+    #[test]
+    fn grapheme_clustering_insert() {
+        let size = TermSize::new(5, 1);
+        let writes = PtyWrites::default();
+        let mut term = Term::new(Config::default(), &size, writes.clone());
+        let mut parser: Processor = Processor::new();
+
+        parser.advance(&mut term, b"\x1b[?2027$p\x1b[?2027h\x1b[?2027$p");
+        assert_eq!(writes.take(), ["\x1b[?2027;2$y", "\x1b[?2027;1$y"]);
+
+        // Widened clusters shift the remaining text in insert mode.
+        parser.advance(&mut term, "ab\x1b[4h\x1b[G\u{2764}\u{fe0f}".as_bytes());
+        let line = &term.grid[Line(0)];
+        assert_eq!(line[Column(0)].c, '\u{2764}');
+        assert_eq!(line[Column(0)].zerowidth(), Some(&['\u{fe0f}'][..]));
+        assert!(line[Column(0)].flags.contains(Flags::WIDE_CHAR));
+        assert!(line[Column(1)].flags.contains(Flags::WIDE_CHAR_SPACER));
+        assert_eq!(line[Column(2)].c, 'a');
+        assert_eq!(line[Column(3)].c, 'b');
+        assert_eq!(term.grid.cursor.point.column, Column(2));
+    }
+
+    #[test]
+    fn grapheme_clustering_scripts() {
+        let size = TermSize::new(10, 1);
+        let mut term = Term::new(Config::default(), &size, VoidListener);
+        let mut parser: Processor = Processor::new();
+
+        // Spacing marks and conjoining jamo join their base, other text never does.
+        parser.advance(&mut term, "\x1b[?2027h\u{915}\u{93e}\u{1100}\u{ac00}ab\u{4e00}".as_bytes());
+        let line = &term.grid[Line(0)];
+        assert_eq!(line[Column(0)].zerowidth(), Some(&['\u{93e}'][..]));
+        assert_eq!(line[Column(2)].c, '\u{1100}');
+        assert_eq!(line[Column(2)].zerowidth(), Some(&['\u{ac00}'][..]));
+        assert_eq!(line[Column(4)].c, 'a');
+        assert_eq!(line[Column(5)].c, 'b');
+        assert_eq!(line[Column(6)].c, '\u{4e00}');
+        assert!(line[Column(4)..].iter().all(|cell| cell.zerowidth().is_none()));
+    }
+
     #[test]
     fn parse_cargo_version() {
         assert!(version_number(env!("CARGO_PKG_VERSION")) >= 10_01);
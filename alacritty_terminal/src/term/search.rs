@@ -10,7 +10,7 @@ use regex_automata::nfa::thompson::Config as ThompsonConfig;
 use regex_automata::util::syntax::Config as SyntaxConfig;
 use regex_automata::{Anchored, Input, MatchKind};
 
-use crate::grid::{BidirectionalIterator, Dimensions, GridIterator, Indexed};
+use crate::grid::{BidirectionalIterator, Dimensions, Indexed, ReadIterator};
 use crate::index::{Boundary, Column, Direction, Point, Side};
 use crate::term::Term;
 use crate::term::cell::{Cell, Flags};
@@ -283,8 +283,8 @@ impl<T> Term<T> {
 
         // Advance the iterator.
         let next = match regex.direction {
-            Direction::Right => GridIterator::next,
-            Direction::Left => GridIterator::prev,
+            Direction::Right => ReadIterator::next,
+            Direction::Left => ReadIterator::prev,
         };
 
         // Get start state for the DFA.
@@ -292,11 +292,12 @@ impl<T> Term<T> {
         let input = Input::new(&[]).anchored(regex_anchored);
         let mut state = regex.dfa.start_state_forward(&mut regex.cache, &input).unwrap();
 
-        let mut iter = self.grid.iter_from(start);
+        // History is read without unpacking it permanently, since searches can cover all of it.
+        let mut iter = self.grid.read_from(start);
         let mut regex_match = None;
         let mut done = false;
 
-        let mut cell = iter.cell();
+        let mut cell = iter.cell().clone();
         self.skip_fullwidth(&mut iter, &mut cell, regex.direction);
         let mut c = cell.c;
         let mut last_wrapped = iter.cell().flags.contains(Flags::WRAPLINE);
@@ -391,8 +392,8 @@ impl<T> Term<T> {
                     // Wrap around to other end of the scrollback buffer.
                     let line = topmost_line - point.line + screen_lines - 1;
                     let start = Point::new(line, last_column - point.column);
-                    iter = self.grid.iter_from(start);
-                    iter.cell()
+                    iter = self.grid.read_from(start);
+                    iter.cell().clone()
                 },
             };
 
@@ -433,10 +434,10 @@ impl<T> Term<T> {
     }
 
     /// Advance a grid iterator over fullwidth characters.
-    fn skip_fullwidth<'a>(
+    fn skip_fullwidth(
         &self,
-        iter: &'a mut GridIterator<'_, Cell>,
-        cell: &mut &'a Cell,
+        iter: &mut ReadIterator<'_, Cell>,
+        cell: &mut Cell,
         direction: Direction,
     ) {
         match direction {
@@ -460,7 +461,8 @@ impl<T> Term<T> {
                 }
 
                 let prev = iter.point().sub(self, Boundary::Grid, 1);
-                if self.grid[prev].flags.contains(Flags::LEADING_WIDE_CHAR_SPACER) {
+                let row = self.grid.read_row(prev.line);
+                if row[prev.column].flags.contains(Flags::LEADING_WIDE_CHAR_SPACER) {
                     iter.prev();
                 }
             },
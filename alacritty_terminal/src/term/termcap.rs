@@ -0,0 +1,126 @@
+//! Built-in terminfo capabilities reported through XTGETTCAP.
+
+use std::fmt::Write;
+
+/// Value of a terminfo capability.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum Capability {
+    Boolean,
+    Number(u32),
+    String(&'static str),
+}
+
+/// Capabilities which can be requested, by their terminfo or termcap name.
+///
+/// String capabilities are stored with their escapes already resolved, since that is the form in
+/// which they are reported.
+const CAPABILITIES: &[(&str, Capability)] = &[
+    // Terminal identification and colors.
+    ("TN", Capability::String("alacritty")),
+    ("name", Capability::String("alacritty")),
+    ("Co", Capability::Number(256)),
+    ("colors", Capability::Number(256)),
+    ("RGB", Capability::String("8/8/8")),
+    ("Tc", Capability::Boolean),
+    ("setrgbf", Capability::String("\x1b[38:2:%p1%d:%p2%d:%p3%dm")),
+    ("setrgbb", Capability::String("\x1b[48:2:%p1%d:%p2%d:%p3%dm")),
+    ("op", Capability::String("\x1b[39;49m")),
+    // Text attributes.
+    ("bold", Capability::String("\x1b[1m")),
+    ("dim", Capability::String("\x1b[2m")),
+    ("sitm", Capability::String("\x1b[3m")),
+    ("ritm", Capability::String("\x1b[23m")),
+    ("smul", Capability::String("\x1b[4m")),
+    ("rmul", Capability::String("\x1b[24m")),
+    ("Smulx", Capability::String("\x1b[4:%p1%dm")),
+    (
+        "Setulc",
+        Capability::String("\x1b[58:2::%p1%{65536}%/%d:%p1%{256}%/%{255}%&%d:%p1%{255}%&%dm"),
+    ),
+    ("rev", Capability::String("\x1b[7m")),
+    ("invis", Capability::String("\x1b[8m")),
+    ("smxx", Capability::String("\x1b[9m")),
+    ("rmxx", Capability::String("\x1b[29m")),
+    ("sgr0", Capability::String("\x1b(B\x1b[m")),
+    // Cursor.
+    ("civis", Capability::String("\x1b[?25l")),
+    ("cnorm", Capability::String("\x1b[?12l\x1b[?25h")),
+    ("Ss", Capability::String("\x1b[%p1%d q")),
+    ("Se", Capability::String("\x1b[0 q")),
+    ("Cs", Capability::String("\x1b]12;%p1%s\x07")),
+    ("Cr", Capability::String("\x1b]112\x07")),
+    // Terminal modes and features.
+    ("smcup", Capability::String("\x1b[?1049h\x1b[22;0;0t")),
+    ("rmcup", Capability::String("\x1b[?1049l\x1b[23;0;0t")),
+    ("Ms", Capability::String("\x1b]52;%p1%s;%p2%s\x07")),
+    ("Sync", Capability::String("\x1b[?2026%?%p1%{1}%-%tl%eh%;")),
+    ("BE", Capability::String("\x1b[?2004h")),
+    ("BD", Capability::String("\x1b[?2004l")),
+    ("PS", Capability::String("\x1b[200~")),
+    ("PE", Capability::String("\x1b[201~")),
+    ("XF", Capability::Boolean),
+    ("kxIN", Capability::String("\x1b[I")),
+    ("kxOUT", Capability::String("\x1b[O")),
+    // Keys.
+    ("kbs", Capability::String("\x7f")),
+    ("kcuu1", Capability::String("\x1bOA")),
+    ("kcud1", Capability::String("\x1bOB")),
+    ("kcuf1", Capability::String("\x1bOC")),
+    ("kcub1", Capability::String("\x1bOD")),
+    ("kri", Capability::String("\x1b[1;2A")),
+    ("kind", Capability::String("\x1b[1;2B")),
+    ("kRIT", Capability::String("\x1b[1;2C")),
+    ("kLFT", Capability::String("\x1b[1;2D")),
+    ("khome", Capability::String("\x1bOH")),
+    ("kend", Capability::String("\x1bOF")),
+    ("kich1", Capability::String("\x1b[2~")),
+    ("kdch1", Capability::String("\x1b[3~")),
+    ("kpp", Capability::String("\x1b[5~")),
+    ("knp", Capability::String("\x1b[6~")),
+    ("kcbt", Capability::String("\x1b[Z")),
+    ("kf1", Capability::String("\x1bOP")),
+    ("kf2", Capability::String("\x1bOQ")),
+    ("kf3", Capability::String("\x1bOR")),
+    ("kf4", Capability::String("\x1bOS")),
+    ("kf5", Capability::String("\x1b[15~")),
+    ("kf6", Capability::String("\x1b[17~")),
+    ("kf7", Capability::String("\x1b[18~")),
+    ("kf8", Capability::String("\x1b[19~")),
+    ("kf9", Capability::String("\x1b[20~")),
+    ("kf10", Capability::String("\x1b[21~")),
+    ("kf11", Capability::String("\x1b[23~")),
+    ("kf12", Capability::String("\x1b[24~")),
+];
+
+/// Look up a capability by name.
+pub fn lookup(name: &str) -> Option<Capability> {
+    CAPABILITIES.iter().find(|(capability, _)| *capability == name).map(|(_, value)| *value)
+}
+
+/// XTGETTCAP response for a capability.
+///
+/// Names which are not hex encoded properly are reported as `None`.
+pub fn response(name: Option<&str>) -> String {
+    let Some(name) = name else { return String::from("\x1bP0+r\x1b\\") };
+
+    let text = match lookup(name) {
+        Some(Capability::Boolean) => format!("1+r{}", hex_encode(name)),
+        Some(Capability::Number(value)) => {
+            format!("1+r{}={}", hex_encode(name), hex_encode(&value.to_string()))
+        },
+        Some(Capability::String(value)) => {
+            format!("1+r{}={}", hex_encode(name), hex_encode(value))
+        },
+        None => format!("0+r{}", hex_encode(name)),
+    };
+
+    format!("\x1bP{text}\x1b\\")
+}
+
+/// Encode text as uppercase hex digits.
+fn hex_encode(text: &str) -> String {
+    text.bytes().fold(String::with_capacity(text.len() * 2), |mut hex, byte| {
+        let _ = write!(hex, "{byte:02X}");
+        hex
+    })
+}
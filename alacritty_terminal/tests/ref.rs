@@ -9,10 +9,10 @@ use std::path::Path;
 use alacritty_terminal::event::{Event, EventListener};
 use alacritty_terminal::grid::{Dimensions, Grid};
 use alacritty_terminal::index::{Column, Line};
+use alacritty_terminal::parser::Processor;
 use alacritty_terminal::term::cell::Cell;
 use alacritty_terminal::term::test::TermSize;
 use alacritty_terminal::term::{Config, Term};
-use alacritty_terminal::vte::ansi;
 
 macro_rules! ref_tests {
     ($($name:ident)*) => {
@@ -39,6 +39,7 @@ ref_tests! {
     delete_lines
     erase_chars_reset
     fish_cc
+    grapheme_clusters
     grid_reset
     history
     hyperlinks
@@ -73,6 +74,13 @@ ref_tests! {
     erase_in_line
     scroll_in_region_up_preserves_history
     origin_goto
+    decslrm_scroll
+    decslrm_insert_delete
+    decslrm_origin_wrap
+    rect_copy_fill
+    rect_erase
+    rect_attributes
+    line_size
 }
 
 fn read_u8<P>(path: P) -> Vec<u8>
@@ -111,7 +119,7 @@ fn ref_test(dir: &Path) {
         Config { scrolling_history: ref_config.history_size as usize, ..Default::default() };
 
     let mut terminal = Term::new(options, &size, Mock);
-    let mut parser: ansi::Processor = ansi::Processor::new();
+    let mut parser: Processor = Processor::new();
 
     parser.advance(&mut terminal, &recording);
 